@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use seq2::{
+    eval::Evaluator,
+    parser::{Parser, MAX_PAREN_DEPTH},
+};
+
+fn criterion_benchmark(c: &mut Criterion) {
+    // one paren short of the limit, eg. `((1 + 1) + 1)`
+    let depth = MAX_PAREN_DEPTH - 1;
+    let nested = format!("{}1{}", "(".repeat(depth), " + 1)".repeat(depth));
+
+    // the whole way, from the source to the numbers
+    let mut group = c.benchmark_group("eval");
+    group.bench_function("small", |b| {
+        b.iter(|| seq2::parse(black_box("-1, {1..=3, s:2, m:+2}, (200 ^ 2 + 1)")))
+    });
+    group.bench_function("large range", |b| {
+        b.iter(|| seq2::parse(black_box("{0..=100_000, m:*3}")))
+    });
+    group.bench_function("nested math", |b| {
+        b.iter(|| seq2::parse(black_box(&nested)))
+    });
+    group.finish();
+
+    // a plain list of integers, through `seq2::parse` (and its shortcut) or the full pipeline
+    let ints = (0..1_000)
+        .map(|n| (n * 7919 % 20_001 - 10_000).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut group = c.benchmark_group("eval/1000 ints");
+    group.bench_function("fast path", |b| b.iter(|| seq2::parse(black_box(&ints))));
+    group.bench_function("full pipeline", |b| {
+        b.iter(|| {
+            let input = black_box(ints.as_str());
+            let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+            Evaluator::new(input.into()).eval(&nodes)
+        })
+    });
+    group.finish();
+
+    // eight large ranges, one after the other or spread over the rayon thread pool
+    #[cfg(feature = "parallel")]
+    {
+        let input = ["{0..1_000_000, m:(@ * 3 % 1_000)}"; 8].join(", ");
+        let nodes = Parser::from_source(&input).unwrap().parse().unwrap();
+        let evaluator = Evaluator::new(input.as_str().into());
+
+        let mut group = c.benchmark_group("eval/8 ranges");
+        group.sample_size(20);
+        group.bench_function("sequential", |b| {
+            b.iter(|| evaluator.eval(black_box(&nodes)))
+        });
+        group.bench_function("parallel", |b| {
+            b.iter(|| evaluator.par_eval(black_box(&nodes)))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use seq2::{lexer::Lexer, parser::Parser};
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut lexer = Lexer::new("{1..=20, s:1, m:*10-(200 ^ 5)}, -1, -200000000, -3, -2, -3, {1..=3, s:2, m:+2}, (200 ^ 2 + 1)");
+    let tokens = lexer.lex().unwrap();
+
+    let mut group = c.benchmark_group("parser");
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            let mut parser = Parser::new(lexer.source.clone(), black_box(&tokens));
+            let _ = parser.parse();
+        })
+    });
+    // many small items, where every step of the parser passes a `Result` along
+    let source = (0..500)
+        .map(|n| format!("{n}, (-{n} * 2), {{{n}..={n}}}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut lexer = Lexer::new(&source);
+    let tokens = lexer.lex().unwrap();
+    group.bench_function("parse_many_items", |b| {
+        b.iter(|| {
+            let mut parser = Parser::new(lexer.source.clone(), black_box(&tokens));
+            let _ = parser.parse();
+        })
+    });
+    // ~100k tokens of nested math and ranges, from the source to the AST
+    let source = (0..3_100)
+        .map(|n| format!("((({n} + 1) * 2) - max({n}, 3)), {{{n}..={n}, s:1, m:(@ * 2)}}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let tokens = Lexer::new(&source).lex().unwrap();
+    assert!(tokens.len() >= 100_000, "{} tokens", tokens.len());
+    group.bench_function("lex_and_parse_100k_tokens", |b| {
+        b.iter(|| Parser::from_source(black_box(&source)).unwrap().parse())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+//! Takes the ids to process as a seq2 expression, eg.
+//!
+//! ```sh
+//! cargo run --example ids --features clap -- --ids "{1..=10, s:2}, 42"
+//! ```
+
+use clap::Parser;
+use seq2::Seq2Values;
+
+#[derive(Debug, Parser)]
+struct Cli {
+    /// Ids to process, eg. "{1..=10, s:2}, 42"
+    #[arg(long)]
+    ids: Seq2Values,
+
+    /// Ids to leave out, with the parser given explicitly
+    #[arg(long, value_parser = seq2::clap_parser(), default_value = "")]
+    skip: Seq2Values,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    for id in cli.ids.iter().filter(|id| !cli.skip.contains(id)) {
+        println!("processing {id}");
+    }
+}
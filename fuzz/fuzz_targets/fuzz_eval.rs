@@ -0,0 +1,35 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seq2::{
+    eval::{EvalOptions, Evaluator},
+    lexer::Lexer,
+    parser::Parser,
+};
+
+/// Numbers taken from each input, ranges like `{MIN..MAX}` never end.
+const MAX_NUMBERS: usize = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    let Ok(tokens) = Lexer::new(&input).lex() else {
+        return;
+    };
+    let Ok(nodes) = Parser::new(input.as_ref().into(), &tokens).parse() else {
+        return;
+    };
+    // sorted ranges are generated in full before their first number comes out, the step
+    // budget keeps them short
+    let options = EvalOptions {
+        max_skipped: MAX_NUMBERS,
+        max_steps: 1_000_000,
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(input.as_ref().into(), options);
+    for number in evaluator.iter(&nodes).take(MAX_NUMBERS) {
+        if let Err(e) = number {
+            let _ = e.render_plain();
+            return;
+        }
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seq2::lexer::Lexer;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    if let Err(e) = Lexer::new(&input).lex() {
+        let _ = e.render_plain();
+    }
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seq2::{errors, lexer::Lexer, parser::Parser};
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    let Ok(tokens) = Lexer::new(&input).lex() else {
+        return;
+    };
+    if let Err(e) = Parser::new(input.as_ref().into(), &tokens).parse() {
+        let _ = e.render_plain();
+    }
+    if let Err(errors) = Parser::new(input.as_ref().into(), &tokens).parse_all_errors() {
+        let _ = errors::render_all(&errors);
+    }
+});
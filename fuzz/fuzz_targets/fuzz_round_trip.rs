@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seq2::{lexer::Lexer, parser::Node, parser::Parser};
+
+fn source(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// the source of the nodes has to parse back to the same nodes, which are compared through
+// their source again as the spans are made up
+fuzz_target!(|nodes: Vec<Node>| {
+    let input = source(&nodes);
+    let tokens = match Lexer::new(&input).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("{input:?} fails to lex\n{}", e.render_plain()),
+    };
+    let parsed = match Parser::new(input.as_str().into(), &tokens).parse() {
+        Ok(parsed) => parsed,
+        Err(e) => panic!("{input:?} fails to parse\n{}", e.render_plain()),
+    };
+    assert_eq!(source(&parsed), input);
+});
@@ -0,0 +1,42 @@
+/* C bindings of seq2, built with `cargo build --release --features ffi`. */
+#ifndef SEQ2_H
+#define SEQ2_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SEQ2_OK 0
+/* input, out or len was NULL */
+#define SEQ2_ERR_NULL_POINTER -1001
+/* input is not valid UTF-8 */
+#define SEQ2_ERR_INVALID_UTF8 -1002
+/* seq2 panicked, which is a bug */
+#define SEQ2_ERR_PANIC -1003
+
+/*
+ * Parses the NUL terminated input into a new buffer of *len numbers written to *out,
+ * an empty sequence is a NULL buffer of length 0. Free the buffer with seq2_free.
+ *
+ * Returns SEQ2_OK, or a negative code: the negated stable error code (eg. -112 for E0112)
+ * or one of the SEQ2_ERR_* codes above. *out and *len are left untouched on failure.
+ */
+int32_t seq2_parse(const char *input, int64_t **out, size_t *len);
+
+/* Frees a buffer of seq2_parse. NULL and buffers that were already freed are ignored. */
+void seq2_free(int64_t *buffer, size_t len);
+
+/*
+ * The error of the last failed seq2_parse on the calling thread, or NULL if it succeeded.
+ * Owned by seq2, valid until the next seq2_parse on the same thread.
+ */
+const char *seq2_last_error_message(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SEQ2_H */
@@ -0,0 +1,11 @@
+#!/usr/bin/env bash
+# Checks that the library builds without `std`, for a bare metal target that has no `std` at all.
+set -euo pipefail
+
+TARGET="${TARGET:-thumbv7em-none-eabihf}"
+
+cd "$(dirname "$0")/.."
+rustup target add "$TARGET"
+
+cargo check --lib --no-default-features --target "$TARGET"
+cargo check --lib --no-default-features --features aggregate,serde --target "$TARGET"
@@ -0,0 +1,192 @@
+//! [`Arbitrary`] for the spans and the nodes, the other tokens derive it. The nodes are
+//! ones the parser could have made: their source (see the `Display` of [`Node`]) parses
+//! back to them, spans aside.
+//!
+//! - the bounds of a range are numbers or math expressions, its step, `lo:` and `hi:`
+//!   numbers that can't fail the checks of the parser
+//! - only mutations have `@` in them
+//! - a zip doesn't have another zip on its right, and a repeated item is a single item
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use crate::{
+    parser::Node,
+    tokens::{Func, Op, SortOrder, Span, Token, TokenKind},
+};
+
+/// How deep math expressions go, so they stay short enough to be read.
+const MAX_MATH_DEPTH: usize = 4;
+
+/// Spans always have their `start` before their `end`.
+impl<'a> Arbitrary<'a> for Span {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let start = u32::arbitrary(u)?;
+        let end = u.int_in_range(start..=u32::MAX)?;
+        Ok(Span { start, end })
+    }
+}
+
+/// A top-level item, ie. a number, a math expression, a range, a repeated item or a zip
+/// of them.
+impl<'a> Arbitrary<'a> for Node {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut node = repeat(u)?;
+        // `~` is left associative, the zips pile up on the left
+        while u.int_in_range(0..=3)? == 3 {
+            node = Node::Zip {
+                span: Span::arbitrary(u)?,
+                left: Box::new(node),
+                right: Box::new(repeat(u)?),
+            };
+        }
+        Ok(node)
+    }
+}
+
+/// A single item, repeated or not.
+fn repeat(u: &mut Unstructured) -> Result<Node> {
+    let item = item(u)?;
+    match u.int_in_range(0..=3)? == 3 {
+        true => Ok(Node::Repeat {
+            span: Span::arbitrary(u)?,
+            item: Box::new(item),
+            count: u.int_in_range(1..=i64::MAX as u64)?,
+        }),
+        false => Ok(item),
+    }
+}
+
+fn item(u: &mut Unstructured) -> Result<Node> {
+    match u.int_in_range(0..=2)? {
+        0 => int(u),
+        1 => {
+            let negated = bool::arbitrary(u)?;
+            math(u, negated, false)
+        }
+        _ => range(u),
+    }
+}
+
+fn int(u: &mut Unstructured) -> Result<Node> {
+    Ok(Node::Int {
+        span: Span::arbitrary(u)?,
+        value: i64::arbitrary(u)?,
+    })
+}
+
+fn math(u: &mut Unstructured, negated: bool, mutation: bool) -> Result<Node> {
+    let mut rpn = vec![];
+    math_operand(u, &mut rpn, MAX_MATH_DEPTH, mutation)?;
+    Ok(Node::MathExpr {
+        negated,
+        span: Span::arbitrary(u)?,
+        rpn,
+    })
+}
+
+/// Pushes the RPN of an operand onto `rpn`, `depth` levels of operations deep at most.
+fn math_operand(
+    u: &mut Unstructured,
+    rpn: &mut Vec<Token>,
+    depth: usize,
+    mutation: bool,
+) -> Result<()> {
+    let kind = match u.int_in_range(0..=depth.min(4))? {
+        0 if mutation && u.int_in_range(0..=2)? == 2 => TokenKind::RngMutArg,
+        0 => TokenKind::Int {
+            value: i64::arbitrary(u)?,
+        },
+        1 => {
+            math_operand(u, rpn, depth - 1, mutation)?;
+            TokenKind::Math(*u.choose(&[Op::UnarySub, Op::UnaryAdd, Op::BitNot, Op::Factorial])?)
+        }
+        2 => {
+            let func = *u.choose(&[Func::Abs, Func::Min, Func::Max, Func::Gcd])?;
+            for _ in 0..func.arity() {
+                math_operand(u, rpn, depth - 1, mutation)?;
+            }
+            TokenKind::Func(func)
+        }
+        _ => {
+            math_operand(u, rpn, depth - 1, mutation)?;
+            math_operand(u, rpn, depth - 1, mutation)?;
+            let op = *u.choose(&[
+                Op::Add,
+                Op::Sub,
+                Op::Mul,
+                Op::Div,
+                Op::Pow,
+                Op::Mod,
+                Op::BitAnd,
+                Op::BitOr,
+                Op::Shl,
+                Op::Shr,
+            ])?;
+            TokenKind::Math(op)
+        }
+    };
+    rpn.push(Token::new(kind, Span::arbitrary(u)?));
+    Ok(())
+}
+
+fn range(u: &mut Unstructured) -> Result<Node> {
+    // a lone number in parenthesis is a number as a bound, see `Parser::parse_range_bound`
+    let bound = |u: &mut Unstructured| match math(u, false, false)? {
+        Node::MathExpr { rpn, .. } if rpn.len() == 1 => int(u),
+        node => Ok(node),
+    };
+    let start = bound(u)?;
+    let end = bound(u)?;
+
+    // a step is only given to ranges it goes the way of, see `Parser::check_step`
+    let step = match (&start, &end, u.int_in_range(0..=2)? == 2) {
+        (Node::Int { value: start, .. }, Node::Int { value: end, .. }, true) => {
+            let step = match start.cmp(end) {
+                core::cmp::Ordering::Less => u.int_in_range(1..=i64::MAX)?,
+                core::cmp::Ordering::Greater => u.int_in_range(i64::MIN..=-1)?,
+                core::cmp::Ordering::Equal => match bool::arbitrary(u)? {
+                    true => u.int_in_range(1..=i64::MAX)?,
+                    false => u.int_in_range(i64::MIN..=-1)?,
+                },
+            };
+            let span = Span::arbitrary(u)?;
+            Some(Box::new(Node::Int { span, value: step }))
+        }
+        _ => None,
+    };
+    let mutation = match u.int_in_range(0..=2)? == 2 {
+        true => Some(Box::new(math(u, false, true)?)),
+        false => None,
+    };
+    let mut low = Option::<i64>::arbitrary(u)?;
+    let mut high = Option::<i64>::arbitrary(u)?;
+    if let (Some(low), Some(high)) = (&mut low, &mut high) {
+        if low > high {
+            core::mem::swap(low, high);
+        }
+    }
+    let clamp = |u: &mut Unstructured, value: Option<i64>| -> Result<Option<Box<Node>>> {
+        Ok(match value {
+            Some(value) => Some(Box::new(Node::Int {
+                span: Span::arbitrary(u)?,
+                value,
+            })),
+            None => None,
+        })
+    };
+
+    Ok(Node::RangeExpr {
+        span: Span::arbitrary(u)?,
+        start: Box::new(start),
+        end: Box::new(end),
+        inclusive: bool::arbitrary(u)?,
+        step,
+        mutation,
+        low: clamp(u, low)?,
+        high: clamp(u, high)?,
+        unique: bool::arbitrary(u)?,
+        reverse: bool::arbitrary(u)?,
+        order: Option::<SortOrder>::arbitrary(u)?,
+    })
+}
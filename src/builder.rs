@@ -0,0 +1,206 @@
+//! Typed construction of seq2 expressions from Rust, instead of `format!`-ing them.
+//!
+//! ```
+//! use seq2::Seq2Builder;
+//!
+//! let (nodes, source) = Seq2Builder::new()
+//!     .int(5)
+//!     .range(|r| r.start(1).end_inclusive(10).step(2).mutate("*3"))
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(source, "5, {1..=10, s:2, m:*3}");
+//! assert_eq!(nodes.len(), 2);
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{
+    errors::Seq2Error,
+    parser::{Node, Parser},
+    tokens::SortOrder,
+};
+
+/// Builder of a comma separated list of items. It is displayed as the canonical source
+/// of the expression, eg. `5, {1..=10, s:2}`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Seq2Builder {
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Int(i64),
+    Math(String),
+    Range(RangeBuilder),
+}
+
+impl Seq2Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a number.
+    pub fn int(mut self, value: i64) -> Self {
+        self.items.push(Item::Int(value));
+        self
+    }
+
+    /// Adds a math expression, eg. `.math("2 ^ 10")` for `(2 ^ 10)`.
+    pub fn math(mut self, expr: impl Into<String>) -> Self {
+        self.items.push(Item::Math(expr.into()));
+        self
+    }
+
+    /// Adds the range set up by `range`.
+    pub fn range(mut self, range: impl FnOnce(RangeBuilder) -> RangeBuilder) -> Self {
+        self.items.push(Item::Range(range(RangeBuilder::default())));
+        self
+    }
+
+    /// Parses the canonical source of the expression, returning its nodes along with the
+    /// source their spans point into.
+    ///
+    /// The expression is checked like any other input, eg. a step going away from the end
+    /// of the range is a [`ParserError::StepDirectionMismatch`](crate::errors::ParserError)
+    /// and a literal too large for an `i64` in a math expression is a
+    /// [`LexicalError::NumberTooLarge`](crate::errors::LexicalError).
+    pub fn build(&self) -> Result<(Vec<Node>, String), Seq2Error> {
+        let source = self.to_string();
+        let nodes = Parser::from_source(&source)?.parse()?;
+        Ok((nodes, source))
+    }
+}
+
+impl fmt::Display for Seq2Builder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, item) in self.items.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            match item {
+                Item::Int(value) => write!(f, "{value}")?,
+                Item::Math(expr) => write!(f, "({expr})")?,
+                Item::Range(range) => write!(f, "{range}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder of a range `{<START>..<END>, s:<STEP>, m:<MUTATION>, u:, r:, o:<ORDER>, lo:<LOW>,
+/// hi:<HIGH>}`, see
+/// [`Seq2Builder::range`]. Left out arguments are left out of the source too, eg. the start
+/// defaults to `0`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RangeBuilder {
+    start: Option<i64>,
+    end: Option<(i64, bool)>,
+    step: Option<i64>,
+    mutation: Option<String>,
+    unique: bool,
+    reverse: bool,
+    order: Option<SortOrder>,
+    low: Option<i64>,
+    high: Option<i64>,
+}
+
+impl RangeBuilder {
+    pub fn start(mut self, start: i64) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Sets the end of the range, left out of the numbers.
+    pub fn end(mut self, end: i64) -> Self {
+        self.end = Some((end, false));
+        self
+    }
+
+    /// Sets the end of the range, included in the numbers.
+    pub fn end_inclusive(mut self, end: i64) -> Self {
+        self.end = Some((end, true));
+        self
+    }
+
+    pub fn step(mut self, step: i64) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Sets the mutation applied to every number, eg. `.mutate("*3")` or `.mutate("@ ^ 2")`.
+    pub fn mutate(mut self, mutation: impl Into<String>) -> Self {
+        self.mutation = Some(mutation.into());
+        self
+    }
+
+    /// Leaves out the numbers the range already generated.
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// Generates the numbers from the end of the range back to its start.
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Raises the (mutated) numbers below `low` to `low`.
+    pub fn low(mut self, low: i64) -> Self {
+        self.low = Some(low);
+        self
+    }
+
+    /// Lowers the (mutated) numbers above `high` to `high`.
+    pub fn high(mut self, high: i64) -> Self {
+        self.high = Some(high);
+        self
+    }
+}
+
+impl fmt::Display for RangeBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("{")?;
+        if let Some(start) = self.start {
+            write!(f, "{start}")?;
+        }
+        match self.end {
+            Some((end, true)) => write!(f, "..={end}")?,
+            Some((end, false)) => write!(f, "..{end}")?,
+            None => f.write_str("..")?,
+        }
+        if let Some(step) = self.step {
+            write!(f, ", s:{step}")?;
+        }
+        if let Some(mutation) = &self.mutation {
+            write!(f, ", m:{mutation}")?;
+        }
+        if self.unique {
+            f.write_str(", u:")?;
+        }
+        if self.reverse {
+            f.write_str(", r:")?;
+        }
+        match self.order {
+            Some(SortOrder::Asc) => f.write_str(", o:asc")?,
+            Some(SortOrder::Desc) => f.write_str(", o:desc")?,
+            None => {}
+        }
+        if let Some(low) = self.low {
+            write!(f, ", lo:{low}")?;
+        }
+        if let Some(high) = self.high {
+            write!(f, ", hi:{high}")?;
+        }
+        f.write_str("}")
+    }
+}
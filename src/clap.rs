@@ -0,0 +1,125 @@
+//! The numbers of an expression as a command line argument, for binaries built with clap.
+//!
+//! ```
+//! use clap::Parser;
+//! use seq2::Seq2Values;
+//!
+//! #[derive(Parser)]
+//! struct Cli {
+//!     #[arg(long)]
+//!     ids: Seq2Values,
+//! }
+//!
+//! let cli = Cli::parse_from(["app", "--ids", "{1..=10, s:2}, 42"]);
+//! assert_eq!(cli.ids, [1, 3, 5, 7, 9, 42]);
+//! ```
+//!
+//! Arguments of other types can still go through seq2 with
+//! `#[arg(value_parser = seq2::clap_parser())]`.
+
+use std::{error::Error, ffi::OsStr, fmt, ops::Deref, str::FromStr};
+
+use clap::{
+    builder::{TypedValueParser, ValueParserFactory},
+    error::ErrorKind,
+    Arg, Command,
+};
+
+use crate::errors::Seq2Error;
+
+/// Numbers an expression evaluates to, parsed with [`crate::parse`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Seq2Values(pub Vec<i64>);
+
+impl Deref for Seq2Values {
+    type Target = [i64];
+
+    fn deref(&self) -> &[i64] {
+        &self.0
+    }
+}
+
+impl<const N: usize> PartialEq<[i64; N]> for Seq2Values {
+    fn eq(&self, other: &[i64; N]) -> bool {
+        self.0 == other
+    }
+}
+
+impl From<Seq2Values> for Vec<i64> {
+    fn from(values: Seq2Values) -> Self {
+        values.0
+    }
+}
+
+impl IntoIterator for Seq2Values {
+    type Item = i64;
+    type IntoIter = std::vec::IntoIter<i64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl FromStr for Seq2Values {
+    type Err = Seq2ValuesError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        crate::parse(input).map(Seq2Values).map_err(Seq2ValuesError)
+    }
+}
+
+impl ValueParserFactory for Seq2Values {
+    type Parser = Seq2ValueParser;
+
+    fn value_parser() -> Self::Parser {
+        Seq2ValueParser
+    }
+}
+
+/// Error of parsing [`Seq2Values`], displayed as the rendering of the error without colors,
+/// so it reads well inside the error output of clap.
+#[derive(Debug)]
+pub struct Seq2ValuesError(pub Seq2Error);
+
+impl fmt::Display for Seq2ValuesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.render_plain().trim_end())
+    }
+}
+
+impl Error for Seq2ValuesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// [`TypedValueParser`] of [`Seq2Values`], see [`clap_parser`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Seq2ValueParser;
+
+impl TypedValueParser for Seq2ValueParser {
+    type Value = Seq2Values;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let arg = arg.map_or_else(|| "...".to_string(), ToString::to_string);
+        let Some(value) = value.to_str() else {
+            let message = format!("invalid UTF-8 was given to '{arg}'\n");
+            return Err(clap::Error::raw(ErrorKind::InvalidUtf8, message).with_cmd(cmd));
+        };
+        value.parse().map_err(|e: Seq2ValuesError| {
+            let message = format!("invalid value '{value}' for '{arg}':\n{e}\n");
+            clap::Error::raw(ErrorKind::ValueValidation, message).with_cmd(cmd)
+        })
+    }
+}
+
+/// Value parser for `#[arg(value_parser = seq2::clap_parser())]`, turning the argument into
+/// the [`Seq2Values`] of the expression.
+pub fn clap_parser() -> Seq2ValueParser {
+    Seq2ValueParser
+}
@@ -0,0 +1,274 @@
+//! Documents of named sequences, eg. a config file with a sequence on every line:
+//!
+//! ```text
+//! # ports to listen on
+//! ports = {8000..=8010}
+//! retries = 1, 2, 4, 8
+//! ```
+//!
+//! Every line is `name = <EXPR>`, blank lines and lines starting with a `#` are skipped.
+//! Names are made of ASCII letters, digits, `_`, `-` and `.`, and can only be given once.
+//! The expression is lexed and parsed on its own, up to the end of the line.
+//!
+//! ```
+//! let doc = "ports = {8000..=8002}\nretries = 1, 2, 4, 8";
+//! let sequences = seq2::document::parse(doc).unwrap();
+//! assert_eq!(sequences["ports"].eval().unwrap(), [8000, 8001, 8002]);
+//! assert_eq!(sequences["retries"].line, 2);
+//! ```
+
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec, vec::Vec};
+
+use crate::{
+    errors::{DocumentError, ErrorContext, EvalError, Seq2Error},
+    eval::Evaluator,
+    lexer::Lexer,
+    literal::{self, LiteralOptions},
+    parser::{Node, Parser},
+    tokens::Span,
+};
+
+/// A sequence of a document, parsed.
+///
+/// Evaluate it with [`Seq2::eval`], or with [`Seq2::collect`] and [`Seq2::collect_checked`]
+/// for other collections than a `Vec<i64>`, with [`Seq2::values_with_provenance`] for the
+/// items behind the numbers, or into the source of a list with [`Seq2::to_rust_literal`] and
+/// [`Seq2::to_literal`].
+#[derive(Debug, PartialEq)]
+pub struct Seq2 {
+    /// 1-based line of the document the sequence is on.
+    pub line: usize,
+    /// The expression, the spans of the nodes (and of the errors of evaluating them) point
+    /// into it rather than into the document.
+    pub source: Arc<str>,
+    pub nodes: Vec<Node>,
+}
+
+impl Seq2 {
+    /// Evaluates the sequence into its numbers.
+    pub fn eval(&self) -> Result<Vec<i64>, EvalError> {
+        Evaluator::new(self.source.clone()).eval(&self.nodes)
+    }
+
+    /// Evaluates the sequence into any collection of numbers, eg. a `HashSet<i64>`, which
+    /// keeps the first of the repeated numbers.
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let sequences = seq2::document::parse("sizes = 4, {1..=3}, 2").unwrap();
+    /// let sizes: BTreeSet<i64> = sequences["sizes"].collect().unwrap();
+    /// assert_eq!(sizes.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4]);
+    /// ```
+    pub fn collect<C: FromIterator<i64>>(&self) -> Result<C, EvalError> {
+        Evaluator::new(self.source.clone())
+            .iter(&self.nodes)
+            .collect()
+    }
+
+    /// Evaluates the sequence into any collection of `T`, converting every number. The first
+    /// number that doesn't fit in a `T` is an [`EvalError::OutOfRange`] pointing at the item
+    /// it came from.
+    ///
+    /// ```
+    /// let sequences = seq2::document::parse("ports = {8000..=8002}, -1").unwrap();
+    /// let error = sequences["ports"].collect_checked::<u16, Vec<_>>().unwrap_err();
+    /// assert_eq!(error.message(), "@ position 16-17 - -1 does not fit in a u16");
+    /// ```
+    pub fn collect_checked<T, C>(&self) -> Result<C, EvalError>
+    where
+        T: TryFrom<i64>,
+        C: FromIterator<T>,
+    {
+        let evaluator = Evaluator::new(self.source.clone());
+        let mut numbers = evaluator.iter(&self.nodes);
+        let mut error = None;
+        let collection = core::iter::from_fn(|| match numbers.next()? {
+            Ok(number) => match T::try_from(number) {
+                Ok(number) => Some(number),
+                Err(_) => {
+                    let ty = core::any::type_name::<T>();
+                    let ctx =
+                        ErrorContext::with(self.source.clone(), numbers.item_span(), (number, ty));
+                    error = Some(EvalError::OutOfRange(ctx));
+                    None
+                }
+            },
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        })
+        .collect();
+        match error {
+            Some(e) => Err(e),
+            None => Ok(collection),
+        }
+    }
+
+    /// Evaluates the sequence into its numbers, each with the span of the top-level item it
+    /// came from, eg. to find the part of the expression behind a wrong number.
+    ///
+    /// ```
+    /// use seq2::tokens::Span;
+    ///
+    /// let sequences = seq2::document::parse("ports = {80..=81}, 443").unwrap();
+    /// let ports = sequences["ports"].values_with_provenance().unwrap();
+    /// assert_eq!(
+    ///     ports,
+    ///     [(80, Span::new(0, 9)), (81, Span::new(0, 9)), (443, Span::new(11, 14))]
+    /// );
+    /// ```
+    pub fn values_with_provenance(&self) -> Result<Vec<(i64, Span)>, EvalError> {
+        Evaluator::new(self.source.clone())
+            .iter(&self.nodes)
+            .with_provenance()
+            .collect()
+    }
+
+    /// Evaluates the sequence into a Rust `vec![..]` literal, wrapped at 100 columns, eg. to
+    /// paste it into a test. See [`Seq2::to_literal`] for arrays and other languages.
+    ///
+    /// ```
+    /// let sequences = seq2::document::parse("odd = {1..8, s:2}").unwrap();
+    /// assert_eq!(sequences["odd"].to_rust_literal().unwrap(), "vec![1, 3, 5, 7]");
+    /// ```
+    pub fn to_rust_literal(&self) -> Result<String, EvalError> {
+        self.to_literal(&LiteralOptions::default())
+    }
+
+    /// Evaluates the sequence into a literal written with `options`.
+    pub fn to_literal(&self, options: &LiteralOptions) -> Result<String, EvalError> {
+        Ok(literal::to_literal(&self.eval()?, options))
+    }
+}
+
+/// Parses every line of `source`, stopping at the first error.
+pub fn parse(source: &str) -> Result<BTreeMap<String, Seq2>, DocumentError> {
+    let source: Arc<str> = Arc::from(source);
+    let mut sequences = BTreeMap::new();
+    let mut lines = BTreeMap::new();
+    for (line, span) in lines_of(&source) {
+        if let Some((name, seq2)) = parse_line(&source, line, span, &mut lines)? {
+            sequences.insert(name, seq2);
+        }
+    }
+    Ok(sequences)
+}
+
+/// Parses every line of `source` like [`parse`], but instead of stopping at the first error,
+/// skips the line and carries on, collecting every error on the way.
+///
+/// The sequences are the ones of the lines without errors. A name given twice is an error
+/// on the second line even if the first one failed to parse.
+pub fn parse_all_errors(source: &str) -> (BTreeMap<String, Seq2>, Vec<DocumentError>) {
+    let source: Arc<str> = Arc::from(source);
+    let mut sequences = BTreeMap::new();
+    let mut lines = BTreeMap::new();
+    let mut errors = vec![];
+    for (line, span) in lines_of(&source) {
+        match parse_line(&source, line, span, &mut lines) {
+            Ok(Some((name, seq2))) => {
+                sequences.insert(name, seq2);
+            }
+            Ok(None) => {}
+            Err(e) => errors.push(e),
+        }
+    }
+    (sequences, errors)
+}
+
+/// The 1-based number and the span of every line of `source`, without the line break.
+fn lines_of(source: &str) -> impl Iterator<Item = (usize, Span)> + '_ {
+    let mut start = 0;
+    source.split('\n').enumerate().map(move |(idx, text)| {
+        let end = start + text.strip_suffix('\r').unwrap_or(text).len();
+        let span = Span::new(start, end);
+        start += text.len() + 1;
+        (idx + 1, span)
+    })
+}
+
+/// Parses the line at `span`, `None` for blank lines and comments. `lines` has the line
+/// every name was first given on.
+fn parse_line(
+    source: &Arc<str>,
+    line: usize,
+    span: Span,
+    lines: &mut BTreeMap<String, usize>,
+) -> Result<Option<(String, Seq2)>, DocumentError> {
+    let text = &source[span.range()];
+    let trimmed = text.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return Ok(None);
+    }
+    let error_ctx = |start: usize, end: usize| {
+        let start = span.start as usize + start;
+        ErrorContext::new(source.clone(), Span::new(start, span.start as usize + end))
+    };
+
+    let name_start = text.len() - trimmed.len();
+    let name_len = trimmed
+        .find(|ch: char| !is_name_char(ch))
+        .unwrap_or(trimmed.len());
+    if name_len == 0 {
+        return Err(DocumentError::MissingName(error_ctx(
+            name_start,
+            text.trim_end().len(),
+        )));
+    }
+    let name_end = name_start + name_len;
+    let name = &text[name_start..name_end];
+
+    let rest = &text[name_end..];
+    let equal = name_end + (rest.len() - rest.trim_start().len());
+    if !text[equal..].starts_with('=') {
+        let next = text[equal..].chars().next().map_or(0, char::len_utf8);
+        return Err(DocumentError::MissingEqual(error_ctx(equal, equal + next)));
+    }
+
+    if let Some(first) = lines.get(name) {
+        return Err(DocumentError::DuplicateName(ErrorContext::with(
+            source.clone(),
+            Span::new(
+                span.start as usize + name_start,
+                span.start as usize + name_end,
+            ),
+            *first,
+        )));
+    }
+    lines.insert(name.into(), line);
+
+    let value = text[equal + 1..].trim_start();
+    let value_start = span.end as usize - value.len();
+    let expr_error = |e: Seq2Error| {
+        let error_span = e.span().unwrap_or(Span::new(0, 0));
+        DocumentError::Expr(ErrorContext::with(
+            source.clone(),
+            Span::new(
+                value_start + error_span.start as usize,
+                value_start + error_span.end as usize,
+            ),
+            e,
+        ))
+    };
+    let mut lexer = Lexer::new(value);
+    let tokens = lexer.lex().map_err(|e| expr_error(e.into()))?;
+    if tokens.is_empty() {
+        return Err(DocumentError::EmptyValue(error_ctx(equal, equal + 1)));
+    }
+    let nodes = Parser::new(lexer.source.clone(), &tokens)
+        .parse()
+        .map_err(|e| expr_error(e.into()))?;
+
+    let seq2 = Seq2 {
+        line,
+        source: lexer.source,
+        nodes,
+    };
+    Ok(Some((name.into(), seq2)))
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.')
+}
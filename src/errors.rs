@@ -1,123 +1,769 @@
-use std::fmt;
+//! Errors of the lexer, the parser and the evaluator.
+//!
+//! Every error variant has a stable code, returned by its `code()` method:
+//!
+//! | Code  | Error |
+//! |-------|-------|
+//! | E0001 | `LexicalError::InvalidToken` |
+//! | E0002 | `LexicalError::MissingColon` |
+//! | E0003 | `LexicalError::InvalidRange` |
+//! | E0004 | `LexicalError::UnexpectedEqual` |
+//! | E0005 | `LexicalError::MalformedNumber` |
+//! | E0006 | `LexicalError::MisplacedRngSyntax` |
+//! | E0007 | `LexicalError::NumberTooLarge` |
+//! | E0008 | `LexicalError::UnknownKeyword` |
+//! | E0009 | `LexicalError::UnknownFunc` |
+//! | E0010 | `LexicalError::UnknownRangeArg` |
+//! | E0011 | `LexicalError::InvalidSortOrder` |
+//! | E0012 | `LexicalError::InputTooLarge` |
+//! | E0013 | `LexicalError::NumberTooSmall` |
+//! | E0101 | `ParserError::AggregateOverflow` |
+//! | E0102 | `ParserError::EmptyParen` |
+//! | E0103 | `ParserError::IncompleteInt` |
+//! | E0104 | `ParserError::IncompleteMathExpr` |
+//! | E0105 | `ParserError::InvalidAggregate` |
+//! | E0106 | `ParserError::InvalidArgCount` |
+//! | E0107 | `ParserError::InvalidFlagValue` |
+//! | E0108 | `ParserError::InvalidInt` |
+//! | E0109 | `ParserError::InvalidMathOp` |
+//! | E0110 | `ParserError::InvalidMathExpr` |
+//! | E0111 | `ParserError::InvalidRangeExpr` |
+//! | E0112 | `ParserError::MissingComma` |
+//! | E0113 | `ParserError::MissingRangeEnd` |
+//! | E0114 | `ParserError::TooManyParen` |
+//! | E0115 | `ParserError::UnmatchedParen` |
+//! | E0116 | `ParserError::UnexpectedComma` |
+//! | E0117 | `ParserError::UnexpectedMathOp` |
+//! | E0118 | `ParserError::RangeOutsideBraces` |
+//! | E0119 | `ParserError::MisplacedRngSyntax` |
+//! | E0120 | `ParserError::MathOutsideParen` |
+//! | E0121 | `ParserError::ZeroStep` |
+//! | E0122 | `ParserError::StepDirectionMismatch` |
+//! | E0123 | `ParserError::DuplicateRangeArg` |
+//! | E0124 | `ParserError::ExpectedRangeArg` |
+//! | E0125 | `ParserError::NumberTooLarge` |
+//! | E0126 | `ParserError::NestedRange` |
+//! | E0127 | `ParserError::MissingRangeBounds` |
+//! | E0128 | `ParserError::MissingZipOperand` |
+//! | E0129 | `ParserError::InvalidRepeatCount` |
+//! | E0130 | `ParserError::NonPositiveRepeatCount` |
+//! | E0131 | `ParserError::MissingRepeatItem` |
+//! | E0132 | `ParserError::InvertedClamp` |
+//! | E0133 | `ParserError::ImplicitMultiplication` |
+//! | E0134 | `ParserError::ExpectedOperand` |
+//! | E0135 | `ParserError::ExpectedOperator` |
+//! | E0136 | `ParserError::TooManyTokens` |
+//! | E0137 | `ParserError::TooManyItems` |
+//! | E0138 | `ParserError::UnmatchedBrace` |
+//! | E0201 | `EvalError::DivisionByZero` |
+//! | E0202 | `EvalError::InvalidFactorial` |
+//! | E0203 | `EvalError::InvalidShift` |
+//! | E0204 | `EvalError::Overflow` |
+//! | E0205 | `EvalError::ZeroStep` |
+//! | E0206 | `EvalError::NonTerminatingRange` |
+//! | E0207 | `EvalError::BudgetExceeded` |
+//! | E0208 | `EvalError::InvertedClamp` |
+//! | E0209 | `EvalError::OutOfRange` |
+//! | E0301 | `Seq2Error::Io` (only with the `std` feature) |
+//! | E0401 | `DocumentError::MissingName` |
+//! | E0402 | `DocumentError::MissingEqual` |
+//! | E0403 | `DocumentError::EmptyValue` |
+//! | E0404 | `DocumentError::DuplicateName` |
+//!
+//! [`Seq2Error`] wraps the errors of all three stages, for callers that go from
+//! the source straight to the numbers (eg. [`crate::parse`]), and the I/O errors
+//! of writing the numbers out (eg. [`crate::write_to`]).
+//!
+//! [`DocumentError`] is an error of a document of named sequences (see [`crate::document`]),
+//! a line that isn't `name = <expr>` or an expression that failed to lex or parse, which
+//! keeps the code of its [`Seq2Error`].
+//!
+//! With the `miette` feature, all the errors implement [`miette::Diagnostic`] and can be
+//! turned into a [`miette::Report`] with `into_diagnostic()`.
+//!
+//! Without the `std` feature, errors display as their plain message, eg.
+//! `@ position 5 - Missing ',' between items`, and `render_plain()` is left out.
 
-use anstyle::{Color, Effects, RgbColor};
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, IsTerminal};
+
+#[cfg(feature = "std")]
+use anstyle::{Color, Effects, RgbColor, Style};
+#[cfg(feature = "std")]
 use indoc::formatdoc;
 
-use crate::tokens::Span;
+use crate::tokens::{Func, Span};
 
-const RED: RgbColor = RgbColor(235, 66, 66);
+#[cfg(feature = "std")]
+pub(crate) const RED: RgbColor = RgbColor(235, 66, 66);
+#[cfg(feature = "std")]
 const WHITE: RgbColor = RgbColor(255, 255, 255);
+#[cfg(feature = "std")]
 const CYAN: RgbColor = RgbColor(64, 224, 208);
+#[cfg(feature = "std")]
 const BLUE: RgbColor = RgbColor(66, 117, 235);
+#[cfg(feature = "std")]
+pub(crate) const YELLOW: RgbColor = RgbColor(235, 190, 66);
+
+/// Max number of chars of the offending line shown in the source excerpt,
+/// longer lines are cut down to a window around the error.
+#[cfg(feature = "std")]
+const EXCERPT_WIDTH: usize = 80;
 
 trait FancyError {
-    fn error_ctx(&self) -> (&Vec<char>, Span);
-    fn error_msg(&self) -> String;
+    fn error_ctx(&self) -> (&Arc<str>, Span);
+    fn error_msg(&self, color: bool) -> String;
+    fn error_hint(&self) -> Option<String>;
+
+    /// Corrected version of the offending source, for near-miss typos.
+    fn error_suggestion(&self) -> Option<&str> {
+        None
+    }
+
+    /// 1-based char positions of the first and last char of the error, for the messages.
+    fn position(&self) -> (usize, usize) {
+        let (input, span) = self.error_ctx();
+        let span = clamp_span(input, span);
+        let range = span.to_char_range(input);
+        (range.start + 1, range.end)
+    }
+
+    /// What the error displays as, the full report with the source excerpt with `std`,
+    /// the plain message without it.
+    fn render(&self) -> String {
+        #[cfg(feature = "std")]
+        return self.construct_error(use_color());
+        #[cfg(not(feature = "std"))]
+        return self.error_msg(false);
+    }
 
-    fn construct_error(&self) -> String {
+    #[cfg(feature = "std")]
+    fn construct_error(&self, color: bool) -> String {
         let (input, span) = self.error_ctx();
-        let msg = self.error_msg();
-        let red = RED.on_default() | Effects::BOLD;
-        let white_on_red = WHITE.on(Color::from(RED)) | Effects::BOLD;
-        let cyan = CYAN.on_default() | Effects::BOLD;
+        let hint = self.error_hint();
+        // the meme lives on for those who want it
+        #[cfg(feature = "fun")]
+        let hint = hint.or_else(|| Some("touch grass ;)".to_string()));
+        let report = Report {
+            label: "ERROR",
+            label_color: RED,
+            msg: self.error_msg(color),
+            hint,
+            suggestion: self.error_suggestion(),
+        };
+        report.render(input, span, color)
+    }
+}
+
+/// What goes into the box of a rendered error or warning.
+#[cfg(feature = "std")]
+pub(crate) struct Report<'a> {
+    pub label: &'a str,
+    pub label_color: RgbColor,
+    pub msg: String,
+    pub hint: Option<String>,
+    pub suggestion: Option<&'a str>,
+}
+
+#[cfg(feature = "std")]
+impl Report<'_> {
+    /// Renders the report with an excerpt of the line of `input` that `span` starts on.
+    pub fn render(self, input: &str, span: Span, color: bool) -> String {
+        let Report {
+            label,
+            label_color,
+            msg,
+            hint,
+            suggestion,
+        } = self;
+        let span = clamp_span(input, span);
+        let red = paint(color, label_color.on_default() | Effects::BOLD);
+        let white_on_red = paint(color, WHITE.on(Color::from(label_color)) | Effects::BOLD);
+        let cyan = paint(color, CYAN.on_default() | Effects::BOLD);
 
-        let before_err: String = input[0..(span.start - 1)].iter().collect();
-        let after_err: String = input[span.end..].iter().collect();
-        let err: String = input[(span.start - 1)..span.end].iter().collect();
+        // only show the line the error starts on, with a caret underneath the error
+        let ((line, column), _) = span.line_col(input);
+        let (start, end) = (span.start as usize, span.end as usize);
+        let line_start = input[..start].rfind('\n').map_or(0, |idx| idx + 1);
+        let line_end = input[start..]
+            .find('\n')
+            .map_or(input.len(), |idx| start + idx);
+        let err_end = end.min(line_end);
+
+        let text = |start, end| Span::new(start, end).text(input).unwrap_or_default();
+        let (before_err, err, after_err) = excerpt_window(
+            text(line_start, start),
+            text(start, err_end),
+            text(err_end, line_end),
+        );
+
+        // keep tabs so the caret lines up with the error
+        let indent: String = before_err
+            .chars()
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+        let caret = "^".repeat(err.chars().count().max(1));
+        // errors at the end of the input (or with an empty span) get a highlighted space as marker
+        let err = if err.is_empty() { " " } else { err.as_str() };
+        let mut notes = vec![];
+        if let Some(hint) = hint {
+            notes.push(format!("= {cyan}HINT{cyan:#}: {hint}"));
+        }
+        if let Some(suggestion) = suggestion {
+            notes.push(format!("= {cyan}SUGGESTION{cyan:#}: {suggestion}"));
+        }
+
+        // without any notes the caret line closes the box
+        let caret_prefix = if notes.is_empty() { "╰╴" } else { "│ " };
+        let last_note = notes.len().saturating_sub(1);
+        let notes: String = notes
+            .iter()
+            .enumerate()
+            .map(|(idx, note)| match idx == last_note {
+                true => format!("╰╴{note}\n"),
+                false => format!("├╴{note}\n"),
+            })
+            .collect();
 
         let error_msg = formatdoc! {"
-            ╭╴{red}ERROR{red:#}: {msg}
+            ╭╴{red}{label}{red:#}: {msg}
+            │ --> line {line}, column {column}
             │ 
             │ {before_err}{white_on_red}{err}{white_on_red:#}{after_err}
-            │
-            ╰╴= {cyan}HINT{cyan:#}: touch grass ;)
-        "};
+            {caret_prefix}{indent}{red}{caret}{red:#}
+            {notes}"};
         error_msg
     }
 }
 
+/// Cuts the line of an error down to `EXCERPT_WIDTH` chars centered on the error,
+/// with a `…` on each side that was cut.
+#[cfg(feature = "std")]
+fn excerpt_window(before: &str, err: &str, after: &str) -> (String, String, String) {
+    let before_len = before.chars().count();
+    let err_len = err.chars().count();
+    let after_len = after.chars().count();
+
+    if before_len + err_len + after_len <= EXCERPT_WIDTH {
+        return (before.to_string(), err.to_string(), after.to_string());
+    }
+
+    // an error wider than the window is cut at the end
+    if err_len >= EXCERPT_WIDTH {
+        let before = if before.is_empty() { "" } else { "…" };
+        let err = err.chars().take(EXCERPT_WIDTH).collect();
+        return (before.to_string(), err, "…".to_string());
+    }
+
+    // split the room left around the error, giving any unused room to the other side
+    let room = EXCERPT_WIDTH - err_len;
+    let mut keep_before = (room / 2).min(before_len);
+    let keep_after = (room - keep_before).min(after_len);
+    keep_before = (room - keep_after).min(before_len);
+
+    let before = match keep_before < before_len {
+        true => "…"
+            .chars()
+            .chain(before.chars().skip(before_len - keep_before))
+            .collect(),
+        false => before.to_string(),
+    };
+    let after = match keep_after < after_len {
+        true => after.chars().take(keep_after).chain("…".chars()).collect(),
+        false => after.to_string(),
+    };
+    (before, err.to_string(), after)
+}
+
+/// Colors are left out when `NO_COLOR` is set or stderr is not a terminal.
+#[cfg(feature = "std")]
+pub(crate) fn use_color() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && io::stderr().is_terminal()
+}
+
+#[cfg(feature = "std")]
+fn paint(color: bool, style: Style) -> Style {
+    match color {
+        true => style,
+        false => Style::new(),
+    }
+}
+
+/// Style of the position in the messages.
+#[cfg(feature = "std")]
+fn highlight(color: bool) -> Style {
+    paint(color, BLUE.on_default() | Effects::BOLD)
+}
+
+/// Stands in for [`anstyle::Style`] without `std`, where the messages are never colored.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy)]
+struct Style;
+
+#[cfg(not(feature = "std"))]
+impl fmt::Display for Style {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn highlight(_color: bool) -> Style {
+    Style
+}
+
+/// Renders a list of errors (eg. from [`crate::parser::Parser::parse_all_errors`]),
+/// separated by blank lines.
+pub fn render_all<E: fmt::Display>(errors: &[E]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Clamps `span` to the bounds of `input` (on char boundaries), so rendering an error
+/// with a degenerate span can't panic.
+fn clamp_span(input: &str, span: Span) -> Span {
+    let clamp = |offset: usize| {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    };
+    let start = clamp(span.start as usize);
+    Span::new(start, clamp(span.end as usize).max(start))
+}
+
+/// Builds a `miette` report of an error, with the plain message as the diagnostic message.
+#[cfg(feature = "miette")]
+fn into_report<E: FancyError>(error: &E, code: &'static str) -> miette::Report {
+    let (input, span) = error.error_ctx();
+    let span = clamp_span(input, span);
+    let mut diagnostic = miette::MietteDiagnostic::new(error.error_msg(false))
+        .with_code(code)
+        .with_label(miette::LabeledSpan::underline(span.range()));
+    if let Some(hint) = error.error_hint() {
+        diagnostic = diagnostic.with_help(hint);
+    }
+    miette::Report::new(diagnostic).with_source_code(input.clone())
+}
+
+#[cfg(feature = "miette")]
+fn labels<E: FancyError>(error: &E) -> Box<dyn Iterator<Item = miette::LabeledSpan> + '_> {
+    let (input, span) = error.error_ctx();
+    let span = clamp_span(input, span);
+    Box::new(core::iter::once(miette::LabeledSpan::new_with_span(
+        Some(error.error_msg(false)),
+        span.range(),
+    )))
+}
+
+/// Errors serialize to their code, plain message and span, eg.
+/// `{"code":"E0112","message":"@ position 5 - Missing ',' between items","span":{"start":4,"end":5}}`.
+/// The span of I/O errors is `null`.
+#[cfg(feature = "serde")]
+fn serialize_error<S: serde::Serializer>(
+    serializer: S,
+    code: &str,
+    message: &str,
+    span: Option<Span>,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+
+    let mut error = serializer.serialize_struct("Error", 3)?;
+    error.serialize_field("code", code)?;
+    error.serialize_field("message", message)?;
+    error.serialize_field("span", &span)?;
+    error.end()
+}
+
+/// How a result past `bound` is described in the messages.
+fn past(bound: Option<Bound>) -> &'static str {
+    match bound {
+        Some(Bound::Min) => "is smaller than MIN (-9_223_372_036_854_775_808)",
+        Some(Bound::Max) => "is larger than MAX (9_223_372_036_854_775_807)",
+        None => "does not fit in the range of i64",
+    }
+}
+
+fn slice(input: &str, span: Span) -> &str {
+    clamp_span(input, span).text(input).unwrap_or_default()
+}
+
+fn char_at(input: &str, offset: usize) -> char {
+    slice(input, Span::new(offset, input.len()))
+        .chars()
+        .next()
+        .unwrap_or_default()
+}
+
+fn char_before(input: &str, offset: usize) -> char {
+    slice(input, Span::new(0, offset))
+        .chars()
+        .next_back()
+        .unwrap_or_default()
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+
+/// The bound of i64 a number went past, see [`EvalError::Overflow`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// Below `MIN`, -9_223_372_036_854_775_808.
+    Min,
+    /// Above `MAX`, 9_223_372_036_854_775_807.
+    Max,
+}
+
+/// Source and location of an error, with any data specific to the error variant.
+///
+/// Variants hold it boxed, so an error is a single pointer wide and a `Result`
+/// carrying one stays small on the happy path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorContext<T = ()> {
+    pub source: Arc<str>,
+    pub span: Span,
+    pub extra: T,
+}
+
+impl ErrorContext {
+    pub fn new(source: Arc<str>, span: Span) -> Box<Self> {
+        Box::new(ErrorContext {
+            source,
+            span,
+            extra: (),
+        })
+    }
+}
+
+impl<T> ErrorContext<T> {
+    pub fn with(source: Arc<str>, span: Span, extra: T) -> Box<Self> {
+        Box::new(ErrorContext {
+            source,
+            span,
+            extra,
+        })
+    }
+}
+
+const _: () = assert!(core::mem::size_of::<LexicalError>() <= 16);
+const _: () = assert!(core::mem::size_of::<ParserError>() <= 16);
+const _: () = assert!(core::mem::size_of::<EvalError>() <= 16);
+const _: () = assert!(core::mem::size_of::<DocumentError>() <= 16);
+
 ////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug)]
 pub enum LexicalError {
-    InvalidToken(Vec<char>, Span),
-    MissingColon(Vec<char>, Span),
-    InvalidRange(Vec<char>, Span),
-    UnexpectedEqual(Vec<char>, Span),
-    MalformedNumber(Vec<char>, Span),
-    MisplacedRngSyntax(Vec<char>, Span),
-    NumberTooLarge(Vec<char>, Span),
+    InvalidToken(Box<ErrorContext>),
+    MissingColon(Box<ErrorContext<Option<String>>>),
+    InvalidRange(Box<ErrorContext<Option<String>>>),
+    UnexpectedEqual(Box<ErrorContext<Option<String>>>),
+    MalformedNumber(Box<ErrorContext>),
+    MisplacedRngSyntax(Box<ErrorContext>),
+    NumberTooLarge(Box<ErrorContext>),
+    UnknownKeyword(Box<ErrorContext>),
+    UnknownFunc(Box<ErrorContext>),
+    UnknownRangeArg(Box<ErrorContext>),
+    InvalidSortOrder(Box<ErrorContext>),
+    InputTooLarge(Box<ErrorContext>),
+    NumberTooSmall(Box<ErrorContext>),
+}
+
+impl LexicalError {
+    /// Renders the error without any colors, the error is underlined with `^` instead.
+    #[cfg(feature = "std")]
+    pub fn render_plain(&self) -> String {
+        self.construct_error(false)
+    }
+
+    /// Location of the error in the source.
+    pub fn span(&self) -> Span {
+        self.error_ctx().1
+    }
+
+    /// Description of the error, without colors or the source excerpt.
+    pub fn message(&self) -> String {
+        self.error_msg(false)
+    }
+
+    /// Suggestion on how to fix the error, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        self.error_hint()
+    }
+
+    /// Corrected version of the offending source, for near-miss typos like `...` or `s2`.
+    pub fn suggestion(&self) -> Option<&str> {
+        self.error_suggestion()
+    }
+
+    /// Stable code of the error variant, see the table in the module docs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexicalError::InvalidToken(..) => "E0001",
+            LexicalError::MissingColon(..) => "E0002",
+            LexicalError::InvalidRange(..) => "E0003",
+            LexicalError::UnexpectedEqual(..) => "E0004",
+            LexicalError::MalformedNumber(..) => "E0005",
+            LexicalError::MisplacedRngSyntax(..) => "E0006",
+            LexicalError::NumberTooLarge(..) => "E0007",
+            LexicalError::UnknownKeyword(..) => "E0008",
+            LexicalError::UnknownFunc(..) => "E0009",
+            LexicalError::UnknownRangeArg(..) => "E0010",
+            LexicalError::InvalidSortOrder(..) => "E0011",
+            LexicalError::InputTooLarge(..) => "E0012",
+            LexicalError::NumberTooSmall(..) => "E0013",
+        }
+    }
+
+    /// Converts the error into a [`miette::Report`], with the plain message as the
+    /// diagnostic message and the source attached.
+    #[cfg(feature = "miette")]
+    pub fn into_diagnostic(self) -> miette::Report {
+        into_report(&self, self.code())
+    }
+}
+
+impl core::error::Error for LexicalError {}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for LexicalError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(LexicalError::code(self)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error_hint()
+            .map(|hint| Box::new(hint) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(self.error_ctx().0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        Some(labels(self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for LexicalError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(serializer, self.code(), &self.message(), Some(self.span()))
+    }
 }
 
 impl fmt::Display for LexicalError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            LexicalError::InvalidToken(_, _)
-            | LexicalError::MissingColon(_, _)
-            | LexicalError::UnexpectedEqual(_, _)
-            | LexicalError::InvalidRange(_, _)
-            | LexicalError::MalformedNumber(_, _)
-            | LexicalError::MisplacedRngSyntax(_, _)
-            | LexicalError::NumberTooLarge(_, _) => write!(f, "{}", self.construct_error()),
+            LexicalError::InvalidToken(_)
+            | LexicalError::MissingColon(_)
+            | LexicalError::UnexpectedEqual(_)
+            | LexicalError::InvalidRange(_)
+            | LexicalError::MalformedNumber(_)
+            | LexicalError::MisplacedRngSyntax(_)
+            | LexicalError::NumberTooLarge(_)
+            | LexicalError::UnknownKeyword(_)
+            | LexicalError::UnknownFunc(_)
+            | LexicalError::UnknownRangeArg(_)
+            | LexicalError::InvalidSortOrder(_)
+            | LexicalError::InputTooLarge(_)
+            | LexicalError::NumberTooSmall(_) => f.write_str(&self.render()),
         }
     }
 }
 
 impl FancyError for LexicalError {
-    fn error_ctx(&self) -> (&Vec<char>, Span) {
+    fn error_ctx(&self) -> (&Arc<str>, Span) {
         match self {
-            LexicalError::InvalidToken(input, span)
-            | LexicalError::MissingColon(input, span)
-            | LexicalError::UnexpectedEqual(input, span)
-            | LexicalError::InvalidRange(input, span)
-            | LexicalError::MalformedNumber(input, span)
-            | LexicalError::MisplacedRngSyntax(input, span)
-            | LexicalError::NumberTooLarge(input, span) => (input, *span),
+            LexicalError::InvalidToken(ctx)
+            | LexicalError::MalformedNumber(ctx)
+            | LexicalError::MisplacedRngSyntax(ctx)
+            | LexicalError::NumberTooLarge(ctx)
+            | LexicalError::UnknownKeyword(ctx)
+            | LexicalError::UnknownFunc(ctx)
+            | LexicalError::UnknownRangeArg(ctx)
+            | LexicalError::InvalidSortOrder(ctx)
+            | LexicalError::InputTooLarge(ctx)
+            | LexicalError::NumberTooSmall(ctx) => (&ctx.source, ctx.span),
+            LexicalError::MissingColon(ctx)
+            | LexicalError::UnexpectedEqual(ctx)
+            | LexicalError::InvalidRange(ctx) => (&ctx.source, ctx.span),
         }
     }
 
-    fn error_msg(&self) -> String {
-        let blue = BLUE.on_default() | Effects::BOLD;
+    fn error_msg(&self, color: bool) -> String {
+        let blue = highlight(color);
+        let (start, end) = self.position();
 
         match self {
-            LexicalError::InvalidToken(_, span) => {
-                format!("{blue}@ position {}{blue:#} - Invalid token", span.start)
-            }
-            LexicalError::MissingColon(input, span) => {
+            LexicalError::InvalidToken(ctx) => match char_at(&ctx.source, ctx.span.start as usize) {
+                '!' => format!(
+                    "{blue}@ position {}{blue:#} - Invalid token, the factorial operator '!' can only be used in math expressions or range mutations",
+                    start
+                ),
+                _ => format!("{blue}@ position {}{blue:#} - Invalid token", start),
+            },
+            LexicalError::MissingColon(ctx) => {
+                let name = slice(&ctx.source, ctx.span);
                 format!(
-                    "{blue}@ position {}{blue:#} - Expected a trailing ':' after '{}'",
-                    span.start,
-                    input[span.start - 1],
+                    "{blue}@ position {}-{}{blue:#} - Expected a trailing ':' after '{}'",
+                    start, end, name,
                 )
             }
-            LexicalError::UnexpectedEqual(_, span) => {
-                format!("{blue}@ position {}{blue:#} - Unexpected '='", span.start)
+            LexicalError::UnexpectedEqual(..) => {
+                format!("{blue}@ position {}{blue:#} - Unexpected '='", start)
             }
-            LexicalError::InvalidRange(_, span) => {
+            LexicalError::InvalidRange(..) => {
                 format!(
                     "{blue}@ position {}-{}{blue:#} - Invalid range syntax",
-                    span.start, span.end
+                    start, end
                 )
             }
-            LexicalError::MalformedNumber(_, span) => {
+            LexicalError::MalformedNumber(..) => {
                 format!(
                     "{blue}@ position {}-{}{blue:#} - Malformed number",
-                    span.start, span.end
+                    start, end
                 )
             }
-            LexicalError::MisplacedRngSyntax(input, span) => {
+            LexicalError::MisplacedRngSyntax(ctx) => {
                 format!(
                     "{blue}@ position {}{blue:#} - Character '{}' can only be used when defining number ranges",
-                    span.start,
-                    input[span.start - 1],
+                    start,
+                    char_at(&ctx.source, ctx.span.start as usize),
                 )
             }
-            LexicalError::NumberTooLarge(_, span) => {
+            LexicalError::NumberTooLarge(..) => {
                 format!(
                     "{blue}@ position {}-{}{blue:#} - Number too large. Largest possible number is 9_223_372_036_854_775_807",
-                    span.start, span.end
+                    start, end
+                )
+            }
+            LexicalError::NumberTooSmall(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Number too small. Smallest possible number is -9_223_372_036_854_775_808",
+                    start, end
+                )
+            }
+            LexicalError::UnknownKeyword(ctx) => {
+                let keyword = slice(&ctx.source, ctx.span);
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Unknown keyword '{}'. Valid keywords are 'MAX', 'MIN', 's:'/'step:', 'm:'/'mut:', 'u:'/'unique:', 'r:'/'rev:', 'o:'/'order:', 'lo:'/'low:' and 'hi:'/'high:'",
+                    start, end, keyword
                 )
             }
+            LexicalError::UnknownFunc(ctx) => {
+                let name = slice(&ctx.source, ctx.span);
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Unknown function '{}'. Valid functions are {}",
+                    start,
+                    end,
+                    name,
+                    Func::NAMES.map(|name| format!("'{name}()'")).join(", ")
+                )
+            }
+            LexicalError::UnknownRangeArg(ctx) => {
+                let name = slice(&ctx.source, ctx.span);
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Unknown range argument '{}:'. Valid arguments are 's:'/'step:', 'm:'/'mut:', 'u:'/'unique:', 'r:'/'rev:', 'o:'/'order:', 'lo:'/'low:' and 'hi:'/'high:'",
+                    start, end, name
+                )
+            }
+            LexicalError::InvalidSortOrder(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Invalid sort order, expected 'asc' or 'desc'",
+                    start, end
+                )
+            }
+            LexicalError::InputTooLarge(ctx) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - Input too large. Inputs can be at most {} bytes long",
+                    start, ctx.span.start
+                )
+            }
+        }
+    }
+
+    fn error_hint(&self) -> Option<String> {
+        match self {
+            LexicalError::InvalidToken(ctx) => match char_at(&ctx.source, ctx.span.start as usize) {
+                '!' => Some("wrap the factorial in parenthesis, eg. '(5!)'".to_string()),
+                '<' | '>' => Some("bit shifts are written as '<<' and '>>'".to_string()),
+                _ => Some(
+                    "items can only be numbers, ranges '{..}' or math expressions '(..)'"
+                        .to_string(),
+                ),
+            },
+            LexicalError::MissingColon(ctx) => {
+                let name = slice(&ctx.source, ctx.span);
+                let value = match name {
+                    "s" | "step" => "<STEP>",
+                    "m" | "mut" => "<MUTATION>",
+                    "o" | "order" => "<ORDER>",
+                    _ => "",
+                };
+                Some(format!("range arguments are written as '{name}:{value}'"))
+            }
+            LexicalError::InvalidRange(..) => Some(
+                "use '..' for an exclusive range or '..=' for an inclusive range".to_string(),
+            ),
+            LexicalError::UnexpectedEqual(..) => Some(
+                "the '=' of an inclusive range goes after the dots, eg. '{1..=5}'".to_string(),
+            ),
+            LexicalError::MalformedNumber(..) => Some(
+                "'_' can only be used between two digits, eg. '1_000', and exponents need digits on both sides, eg. '1e6'"
+                    .to_string(),
+            ),
+            LexicalError::MisplacedRngSyntax(..) => Some(
+                "range syntax can only be used inside squiggly braces, eg. '{1..10, s:2}'"
+                    .to_string(),
+            ),
+            LexicalError::NumberTooLarge(..) => Some(
+                "split the value into smaller numbers or use 'MAX'".to_string(),
+            ),
+            LexicalError::NumberTooSmall(..) => Some(
+                "split the value into smaller numbers or use 'MIN'".to_string(),
+            ),
+            LexicalError::UnknownKeyword(ctx)
+                if slice(&ctx.source, ctx.span).eq_ignore_ascii_case("x") =>
+            {
+                Some("'x' only repeats items outside of math expressions and ranges, use '*' to multiply".to_string())
+            }
+            LexicalError::UnknownKeyword(ctx)
+                if Func::from_name(slice(&ctx.source, ctx.span)).is_some() =>
+            {
+                let name = slice(&ctx.source, ctx.span);
+                Some(format!("'{name}' is a function, call it with its arguments in parenthesis, eg. '{name}(..)'"))
+            }
+            LexicalError::UnknownKeyword(..)
+            | LexicalError::UnknownFunc(..)
+            | LexicalError::UnknownRangeArg(..) => None,
+            LexicalError::InvalidSortOrder(..) => {
+                Some("write 'o:asc' or 'o:desc'".to_string())
+            }
+            LexicalError::InputTooLarge(..) => {
+                Some("split the input into several smaller ones".to_string())
+            }
+        }
+    }
+
+    fn error_suggestion(&self) -> Option<&str> {
+        match self {
+            LexicalError::MissingColon(ctx)
+            | LexicalError::InvalidRange(ctx)
+            | LexicalError::UnexpectedEqual(ctx) => ctx.extra.as_deref(),
+            _ => None,
         }
     }
 }
@@ -126,117 +772,1210 @@ impl FancyError for LexicalError {
 
 #[derive(Debug)]
 pub enum ParserError {
-    EmptyParen(Vec<char>, Span),
-    IncompleteInt(Vec<char>, Span),
-    IncompleteMathExpr(Vec<char>, Span),
-    InvalidInt(Vec<char>, Span),
-    InvalidMathOp(Vec<char>, Span),
-    InvalidMathExpr(Vec<char>, Span),
-    TooManyParen(Vec<char>, Span),
-    UnmatchedParen(Vec<char>, Span),
-    UnexpectedComma(Vec<char>, Span),
-    UnexpectedMathOp(Vec<char>, Span),
+    /// The extra is the bound the aggregated number went past, if it is known.
+    AggregateOverflow(Box<ErrorContext<Option<Bound>>>),
+    DuplicateRangeArg(Box<ErrorContext<Span>>),
+    EmptyParen(Box<ErrorContext>),
+    /// The span is the single char where the value is missing, eg. the ` ` in `(1 + )`.
+    ExpectedOperand(Box<ErrorContext>),
+    /// The span is the single char where the operator is missing, eg. the ` ` in `(1 2)`.
+    ExpectedOperator(Box<ErrorContext>),
+    ExpectedRangeArg(Box<ErrorContext>),
+    IncompleteInt(Box<ErrorContext>),
+    IncompleteMathExpr(Box<ErrorContext>),
+    InvalidAggregate(Box<ErrorContext>),
+    InvalidArgCount(Box<ErrorContext>),
+    InvalidFlagValue(Box<ErrorContext>),
+    InvalidInt(Box<ErrorContext>),
+    InvalidMathOp(Box<ErrorContext>),
+    InvalidMathExpr(Box<ErrorContext>),
+    InvalidRangeExpr(Box<ErrorContext>),
+    InvalidRepeatCount(Box<ErrorContext>),
+    MathOutsideParen(Box<ErrorContext>),
+    MisplacedRngSyntax(Box<ErrorContext>),
+    MissingComma(Box<ErrorContext>),
+    MissingRangeBounds(Box<ErrorContext>),
+    MissingRangeEnd(Box<ErrorContext>),
+    MissingRepeatItem(Box<ErrorContext>),
+    MissingZipOperand(Box<ErrorContext>),
+    NestedRange(Box<ErrorContext>),
+    NonPositiveRepeatCount(Box<ErrorContext>),
+    NumberTooLarge(Box<ErrorContext>),
+    RangeOutsideBraces(Box<ErrorContext>),
+    StepDirectionMismatch(Box<ErrorContext<Span>>),
+    /// The span points at the value of `lo:`, the extra at the one of `hi:`.
+    InvertedClamp(Box<ErrorContext<Span>>),
+    /// The span covers both operands, the extra is the gap between them.
+    ImplicitMultiplication(Box<ErrorContext<Span>>),
+    TooManyParen(Box<ErrorContext<usize>>),
+    /// The span points at the first token over the limit, the extra is the limit.
+    TooManyTokens(Box<ErrorContext<usize>>),
+    /// The span points at the start of the first item over the limit, the extra is the limit.
+    TooManyItems(Box<ErrorContext<usize>>),
+    UnmatchedBrace(Box<ErrorContext>),
+    UnmatchedParen(Box<ErrorContext>),
+    UnexpectedComma(Box<ErrorContext>),
+    UnexpectedMathOp(Box<ErrorContext>),
+    ZeroStep(Box<ErrorContext>),
+}
+
+impl ParserError {
+    /// Renders the error without any colors, the error is underlined with `^` instead.
+    #[cfg(feature = "std")]
+    pub fn render_plain(&self) -> String {
+        self.construct_error(false)
+    }
+
+    /// Location of the error in the source.
+    pub fn span(&self) -> Span {
+        self.error_ctx().1
+    }
+
+    /// Description of the error, without colors or the source excerpt.
+    pub fn message(&self) -> String {
+        self.error_msg(false)
+    }
+
+    /// Suggestion on how to fix the error, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        self.error_hint()
+    }
+
+    /// Stable code of the error variant, see the table in the module docs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserError::AggregateOverflow(..) => "E0101",
+            ParserError::EmptyParen(..) => "E0102",
+            ParserError::IncompleteInt(..) => "E0103",
+            ParserError::IncompleteMathExpr(..) => "E0104",
+            ParserError::InvalidAggregate(..) => "E0105",
+            ParserError::InvalidArgCount(..) => "E0106",
+            ParserError::InvalidFlagValue(..) => "E0107",
+            ParserError::InvalidInt(..) => "E0108",
+            ParserError::InvalidMathOp(..) => "E0109",
+            ParserError::InvalidMathExpr(..) => "E0110",
+            ParserError::InvalidRangeExpr(..) => "E0111",
+            ParserError::MissingComma(..) => "E0112",
+            ParserError::MissingRangeEnd(..) => "E0113",
+            ParserError::TooManyParen(..) => "E0114",
+            ParserError::UnmatchedParen(..) => "E0115",
+            ParserError::UnexpectedComma(..) => "E0116",
+            ParserError::UnexpectedMathOp(..) => "E0117",
+            ParserError::RangeOutsideBraces(..) => "E0118",
+            ParserError::MisplacedRngSyntax(..) => "E0119",
+            ParserError::MathOutsideParen(..) => "E0120",
+            ParserError::ZeroStep(..) => "E0121",
+            ParserError::StepDirectionMismatch(..) => "E0122",
+            ParserError::DuplicateRangeArg(..) => "E0123",
+            ParserError::ExpectedRangeArg(..) => "E0124",
+            ParserError::NumberTooLarge(..) => "E0125",
+            ParserError::NestedRange(..) => "E0126",
+            ParserError::MissingRangeBounds(..) => "E0127",
+            ParserError::MissingZipOperand(..) => "E0128",
+            ParserError::InvalidRepeatCount(..) => "E0129",
+            ParserError::NonPositiveRepeatCount(..) => "E0130",
+            ParserError::MissingRepeatItem(..) => "E0131",
+            ParserError::InvertedClamp(..) => "E0132",
+            ParserError::ImplicitMultiplication(..) => "E0133",
+            ParserError::ExpectedOperand(..) => "E0134",
+            ParserError::ExpectedOperator(..) => "E0135",
+            ParserError::TooManyTokens(..) => "E0136",
+            ParserError::TooManyItems(..) => "E0137",
+            ParserError::UnmatchedBrace(..) => "E0138",
+        }
+    }
+
+    /// Converts the error into a [`miette::Report`], with the plain message as the
+    /// diagnostic message and the source attached.
+    #[cfg(feature = "miette")]
+    pub fn into_diagnostic(self) -> miette::Report {
+        into_report(&self, self.code())
+    }
+}
+
+impl core::error::Error for ParserError {}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for ParserError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(ParserError::code(self)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error_hint()
+            .map(|hint| Box::new(hint) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(self.error_ctx().0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        Some(labels(self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ParserError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(serializer, self.code(), &self.message(), Some(self.span()))
+    }
 }
 
 impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ParserError::EmptyParen(_, _)
-            | ParserError::IncompleteInt(_, _)
-            | ParserError::IncompleteMathExpr(_, _)
-            | ParserError::InvalidInt(_, _)
-            | ParserError::InvalidMathOp(_, _)
-            | ParserError::InvalidMathExpr(_, _)
-            | ParserError::TooManyParen(_, _)
-            | ParserError::UnmatchedParen(_, _)
-            | ParserError::UnexpectedComma(_, _)
-            | ParserError::UnexpectedMathOp(_, _) => {
-                write!(f, "{}", self.construct_error())
-            }
+            ParserError::AggregateOverflow(_)
+            | ParserError::DuplicateRangeArg(_)
+            | ParserError::EmptyParen(_)
+            | ParserError::ExpectedOperand(_)
+            | ParserError::ExpectedOperator(_)
+            | ParserError::ExpectedRangeArg(_)
+            | ParserError::IncompleteInt(_)
+            | ParserError::IncompleteMathExpr(_)
+            | ParserError::InvalidAggregate(_)
+            | ParserError::InvalidArgCount(_)
+            | ParserError::InvalidFlagValue(_)
+            | ParserError::InvalidInt(_)
+            | ParserError::InvalidMathOp(_)
+            | ParserError::InvalidMathExpr(_)
+            | ParserError::InvalidRangeExpr(_)
+            | ParserError::InvalidRepeatCount(_)
+            | ParserError::MathOutsideParen(_)
+            | ParserError::MisplacedRngSyntax(_)
+            | ParserError::MissingComma(_)
+            | ParserError::MissingRangeBounds(_)
+            | ParserError::MissingRangeEnd(_)
+            | ParserError::MissingRepeatItem(_)
+            | ParserError::MissingZipOperand(_)
+            | ParserError::NestedRange(_)
+            | ParserError::NonPositiveRepeatCount(_)
+            | ParserError::NumberTooLarge(_)
+            | ParserError::RangeOutsideBraces(_)
+            | ParserError::StepDirectionMismatch(_)
+            | ParserError::InvertedClamp(_)
+            | ParserError::ImplicitMultiplication(_)
+            | ParserError::TooManyParen(_)
+            | ParserError::TooManyTokens(_)
+            | ParserError::TooManyItems(_)
+            | ParserError::UnmatchedBrace(_)
+            | ParserError::UnmatchedParen(_)
+            | ParserError::UnexpectedComma(_)
+            | ParserError::UnexpectedMathOp(_)
+            | ParserError::ZeroStep(_) => f.write_str(&self.render()),
         }
     }
 }
 
 impl FancyError for ParserError {
-    fn error_ctx(&self) -> (&Vec<char>, Span) {
+    fn error_ctx(&self) -> (&Arc<str>, Span) {
         match self {
-            ParserError::EmptyParen(input, span)
-            | ParserError::IncompleteInt(input, span)
-            | ParserError::IncompleteMathExpr(input, span)
-            | ParserError::InvalidInt(input, span)
-            | ParserError::InvalidMathOp(input, span)
-            | ParserError::InvalidMathExpr(input, span)
-            | ParserError::TooManyParen(input, span)
-            | ParserError::UnmatchedParen(input, span)
-            | ParserError::UnexpectedComma(input, span)
-            | ParserError::UnexpectedMathOp(input, span) => (input, *span),
+            ParserError::AggregateOverflow(ctx) => (&ctx.source, ctx.span),
+            ParserError::EmptyParen(ctx)
+            | ParserError::ExpectedOperand(ctx)
+            | ParserError::ExpectedOperator(ctx)
+            | ParserError::ExpectedRangeArg(ctx)
+            | ParserError::IncompleteInt(ctx)
+            | ParserError::IncompleteMathExpr(ctx)
+            | ParserError::InvalidAggregate(ctx)
+            | ParserError::InvalidArgCount(ctx)
+            | ParserError::InvalidFlagValue(ctx)
+            | ParserError::InvalidInt(ctx)
+            | ParserError::InvalidMathOp(ctx)
+            | ParserError::InvalidMathExpr(ctx)
+            | ParserError::InvalidRangeExpr(ctx)
+            | ParserError::InvalidRepeatCount(ctx)
+            | ParserError::MathOutsideParen(ctx)
+            | ParserError::MisplacedRngSyntax(ctx)
+            | ParserError::MissingComma(ctx)
+            | ParserError::MissingRangeBounds(ctx)
+            | ParserError::MissingRangeEnd(ctx)
+            | ParserError::MissingRepeatItem(ctx)
+            | ParserError::MissingZipOperand(ctx)
+            | ParserError::NestedRange(ctx)
+            | ParserError::NonPositiveRepeatCount(ctx)
+            | ParserError::NumberTooLarge(ctx)
+            | ParserError::RangeOutsideBraces(ctx)
+            | ParserError::UnmatchedBrace(ctx)
+            | ParserError::UnmatchedParen(ctx)
+            | ParserError::UnexpectedComma(ctx)
+            | ParserError::UnexpectedMathOp(ctx)
+            | ParserError::ZeroStep(ctx) => (&ctx.source, ctx.span),
+            ParserError::DuplicateRangeArg(ctx)
+            | ParserError::StepDirectionMismatch(ctx)
+            | ParserError::InvertedClamp(ctx)
+            | ParserError::ImplicitMultiplication(ctx) => (&ctx.source, ctx.span),
+            ParserError::TooManyParen(ctx)
+            | ParserError::TooManyTokens(ctx)
+            | ParserError::TooManyItems(ctx) => (&ctx.source, ctx.span),
         }
     }
-    fn error_msg(&self) -> String {
-        let blue = BLUE.on_default() | Effects::BOLD;
+    fn error_msg(&self, color: bool) -> String {
+        let blue = highlight(color);
+        let (start, end) = self.position();
         match self {
-            ParserError::EmptyParen(_, span) => {
+            ParserError::AggregateOverflow(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Aggregated range {}",
+                    start,
+                    end,
+                    past(ctx.extra)
+                )
+            }
+            ParserError::InvalidAggregate(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Range cannot be aggregated as it fails to evaluate",
+                    start, end
+                )
+            }
+            ParserError::InvalidRangeExpr(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Invalid range expression, expected '{{<START>..<END>, s:<STEP>, m:<MUTATION>}}'",
+                    start, end
+                )
+            }
+            ParserError::MissingComma(..) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - Missing ',' between items",
+                    start
+                )
+            }
+            ParserError::MissingRangeEnd(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Range expression is missing an end bound, eg. '{{..10}}'",
+                    start, end
+                )
+            }
+            ParserError::EmptyParen(..) => {
                 format!(
                     "{blue}@ position {}-{}{blue:#} - Empty parenthesis",
-                    span.start, span.end
+                    start, end
                 )
             }
-            ParserError::UnexpectedComma(_, span) => {
-                format!("{blue}@ position {}{blue:#} - Unexpected comma", span.start)
+            ParserError::UnexpectedComma(..) => {
+                format!("{blue}@ position {}{blue:#} - Unexpected comma", start)
             }
-            ParserError::UnexpectedMathOp(input, span) => {
+            ParserError::UnexpectedMathOp(ctx) => {
+                let operator = slice(&ctx.source, ctx.span);
                 format!(
                     "{blue}@ position {}{blue:#} - Unexpected math operator '{}'",
-                    span.start,
-                    input[span.start - 1]
+                    start, operator
                 )
             }
-            ParserError::UnmatchedParen(_, span) => {
+            ParserError::UnmatchedBrace(..) => {
                 format!(
-                    "{blue}@ position {}{blue:#} - Unmatched parenthesis in math expression",
-                    span.start
+                    "{blue}@ position {}{blue:#} - Unmatched squiggly brace",
+                    start
                 )
             }
-            ParserError::IncompleteInt(input, span) => {
+            ParserError::UnmatchedParen(..) => format!(
+                "{blue}@ position {}{blue:#} - Unmatched parenthesis in math expression",
+                start
+            ),
+            ParserError::IncompleteInt(ctx) => {
                 format!(
                     "{blue}@ position {}{blue:#} - Expected a number after the math operator '{}'",
-                    span.start,
-                    input[span.start - 1]
+                    start,
+                    char_at(&ctx.source, ctx.span.start as usize)
                 )
             }
-            ParserError::IncompleteMathExpr(_, span) => {
+            ParserError::IncompleteMathExpr(..) => {
                 format!(
                     "{blue}@ position {}{blue:#} - Incomplete math expression",
-                    span.start
+                    start
                 )
             }
-            ParserError::InvalidMathExpr(_, span) => {
+            ParserError::InvalidMathExpr(..) => {
                 format!(
                     "{blue}@ position {}-{}{blue:#} - Invalid math expression",
-                    span.start, span.end
+                    start, end
+                )
+            }
+            ParserError::InvalidArgCount(ctx) => {
+                let name: String = slice(
+                    &ctx.source,
+                    Span::new(ctx.span.start as usize, ctx.source.len()),
+                )
+                .chars()
+                .take_while(|ch| *ch != '(')
+                .collect();
+                let arity = Func::from_name(&name).map_or(0, |func| func.arity());
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Function '{}()' expects {} argument{}",
+                    start,
+                    end,
+                    name,
+                    arity,
+                    if arity == 1 { "" } else { "s" }
+                )
+            }
+            ParserError::InvalidFlagValue(ctx) => {
+                let flag: String = slice(
+                    &ctx.source,
+                    Span::new(ctx.span.start as usize, ctx.source.len()),
+                )
+                .chars()
+                .take_while(|ch| *ch != ':')
+                .collect();
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Flag '{}:' expects no value, '0' or '1'",
+                    start, end, flag
                 )
             }
-            ParserError::InvalidInt(input, span) => {
+            ParserError::InvalidInt(ctx) => {
                 format!(
                     "{blue}@ position {}{blue:#} - Expected a number after the math operator '{}', found '{}'",
-                    span.start,
-                    input[span.start - 2],
-                    input[span.start - 1]
+                    start,
+                    char_before(&ctx.source, ctx.span.start as usize),
+                    char_at(&ctx.source, ctx.span.start as usize)
                 )
             }
-            ParserError::InvalidMathOp(input, span) => {
+            ParserError::InvalidMathOp(ctx) => {
                 format!(
                     "{blue}@ position {}{blue:#} - Expected a math operator, found '{}'",
-                    span.start,
-                    input[span.start - 1]
+                    start,
+                    char_at(&ctx.source, ctx.span.start as usize)
+                )
+            }
+            ParserError::TooManyParen(ctx) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - WE'RE IN TOO DEEP!!! Parenthesis can be nested at most {} levels deep",
+                    start, ctx.extra
                 )
             }
-            ParserError::TooManyParen(_, span) => {
+            ParserError::TooManyTokens(ctx) => {
                 format!(
-                    "{blue}@ position {}-{}{blue:#} - WE'RE IN TOO DEEP!!! Too many parenthesis!",
-                    span.start, span.end
+                    "{blue}@ position {}-{}{blue:#} - Input is too long, it can have at most {} tokens",
+                    start, end, ctx.extra
                 )
             }
+            ParserError::TooManyItems(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Too many items, there can be at most {}",
+                    start, end, ctx.extra
+                )
+            }
+            ParserError::RangeOutsideBraces(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Range syntax outside of squiggly braces",
+                    start, end
+                )
+            }
+            ParserError::MisplacedRngSyntax(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - '{}' can only be used when defining number ranges",
+                    start,
+                    end,
+                    slice(&ctx.source, ctx.span)
+                )
+            }
+            ParserError::MathOutsideParen(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Math expression outside of parenthesis",
+                    start, end
+                )
+            }
+            ParserError::ZeroStep(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Range step cannot be zero",
+                    start, end
+                )
+            }
+            ParserError::StepDirectionMismatch(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Step of '{}' never reaches the end of the range '{}'",
+                    start,
+                    end,
+                    slice(&ctx.source, ctx.span),
+                    slice(&ctx.source, ctx.extra)
+                )
+            }
+            ParserError::DuplicateRangeArg(ctx) => {
+                let first = clamp_span(&ctx.source, ctx.extra).to_char_range(&ctx.source);
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - '{}' was already given at position {}-{}",
+                    start,
+                    end,
+                    slice(&ctx.source, ctx.span),
+                    first.start + 1,
+                    first.end
+                )
+            }
+            ParserError::ExpectedRangeArg(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Expected a range argument, found '{}'",
+                    start,
+                    end,
+                    slice(&ctx.source, ctx.span)
+                )
+            }
+            ParserError::NumberTooLarge(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Number too large. Largest possible number is 9_223_372_036_854_775_807",
+                    start, end
+                )
+            }
+            ParserError::NestedRange(..) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - Ranges cannot be nested inside other ranges",
+                    start
+                )
+            }
+            ParserError::MissingRangeBounds(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Range expression is missing its bounds, eg. '{{1..10}}'",
+                    start, end
+                )
+            }
+            ParserError::MissingZipOperand(..) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - '~' needs an item on both sides",
+                    start
+                )
+            }
+            ParserError::InvalidRepeatCount(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - The count after 'x' must be a number",
+                    start, end
+                )
+            }
+            ParserError::NonPositiveRepeatCount(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - An item must be repeated at least once",
+                    start, end
+                )
+            }
+            ParserError::MissingRepeatItem(..) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - 'x' needs an item before it",
+                    start
+                )
+            }
+            ParserError::InvertedClamp(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - 'lo:{}' is above 'hi:{}'",
+                    start,
+                    end,
+                    slice(&ctx.source, ctx.span),
+                    slice(&ctx.source, ctx.extra)
+                )
+            }
+            ParserError::ExpectedOperand(..) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - Expected a number or a math expression",
+                    start
+                )
+            }
+            ParserError::ExpectedOperator(..) => {
+                format!(
+                    "{blue}@ position {}{blue:#} - Expected a math operator between the two values",
+                    start
+                )
+            }
+            ParserError::ImplicitMultiplication(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Missing operator, multiplication must be written with '*'",
+                    start, end
+                )
+            }
+        }
+    }
+
+    fn error_hint(&self) -> Option<String> {
+        match self {
+            ParserError::AggregateOverflow(..) => {
+                Some("aggregate a smaller range".to_string())
+            }
+            ParserError::EmptyParen(..) => Some(
+                "put a math expression inside the parenthesis, eg. '(1 + 2)', or remove them"
+                    .to_string(),
+            ),
+            ParserError::IncompleteInt(..) | ParserError::InvalidInt(..) => {
+                Some("a sign must be followed by a number, eg. '-5'".to_string())
+            }
+            ParserError::IncompleteMathExpr(..) => Some(
+                "every operator needs a value on both sides, eg. '(1 + 2)'".to_string(),
+            ),
+            ParserError::InvalidAggregate(..) | ParserError::InvalidArgCount(..) => None,
+            ParserError::InvalidFlagValue(ctx) => {
+                let flag: String = slice(&ctx.source, Span::new(ctx.span.start as usize, ctx.source.len()))
+                    .chars()
+                    .take_while(|ch| *ch != ':')
+                    .collect();
+                Some(format!(
+                    "write '{flag}:' or '{flag}:1' to turn the flag on, '{flag}:0' to turn it off"
+                ))
+            }
+            ParserError::InvalidMathOp(..) => Some(
+                "put an operator between the two values, eg. '(2 * 3)'".to_string(),
+            ),
+            ParserError::InvalidMathExpr(..) => Some(
+                "a math expression must reduce to a single number, ',' only separates function arguments"
+                    .to_string(),
+            ),
+            ParserError::InvalidRangeExpr(..) => Some(
+                "ranges are written as '{<START>..<END>, s:<STEP>, m:<MUTATION>, u:, r:, o:<ORDER>, lo:<LOW>, hi:<HIGH>}', with the arguments in any order"
+                    .to_string(),
+            ),
+            ParserError::MissingComma(..) => Some("separate the items with ','".to_string()),
+            ParserError::MissingRangeEnd(..) => {
+                Some("add an end bound after the dots, eg. '{1..10}'".to_string())
+            }
+            ParserError::TooManyParen(..) => {
+                Some("remove the redundant parenthesis".to_string())
+            }
+            ParserError::TooManyTokens(..) | ParserError::TooManyItems(..) => {
+                Some("split the input into smaller ones".to_string())
+            }
+            ParserError::UnmatchedBrace(..) => {
+                Some("add the missing squiggly brace or remove the extra one".to_string())
+            }
+            ParserError::UnmatchedParen(..) => {
+                Some("add the missing parenthesis or remove the extra one".to_string())
+            }
+            ParserError::UnexpectedComma(..) => Some("remove the extra ','".to_string()),
+            ParserError::UnexpectedMathOp(..) => Some(
+                "operators can only be used inside a math expression, eg. '(1 * 2)'".to_string(),
+            ),
+            ParserError::RangeOutsideBraces(..) => Some(
+                "wrap the range in squiggly braces, eg. '{1..10}'".to_string(),
+            ),
+            ParserError::MisplacedRngSyntax(..) => Some(
+                "range syntax can only be used inside squiggly braces, eg. '{1..10, s:2}'"
+                    .to_string(),
+            ),
+            ParserError::MathOutsideParen(..) => Some(
+                "wrap the math expression in parenthesis, eg. '(max(1, 2))'".to_string(),
+            ),
+            ParserError::ZeroStep(..) => {
+                Some("use a non-zero step, eg. 's:1' or 's:-1'".to_string())
+            }
+            ParserError::StepDirectionMismatch(ctx) => {
+                let step = slice(&ctx.source, ctx.span);
+                let flipped = match step.strip_prefix('-') {
+                    Some(magnitude) => magnitude.to_string(),
+                    None => format!("-{step}"),
+                };
+                match step.trim_start_matches(['-', '+']).chars().all(|ch| ch.is_ascii_digit() || ch == '_') {
+                    true => Some(format!("flip the sign of the step, eg. 's:{flipped}'")),
+                    false => Some("flip the sign of the step".to_string()),
+                }
+            }
+            ParserError::DuplicateRangeArg(..) => {
+                Some("each range argument can only be given once".to_string())
+            }
+            ParserError::ExpectedRangeArg(..) => Some(
+                "range arguments are 's:', 'm:', 'u:', 'r:', 'o:', 'lo:' and 'hi:', eg. '{1..10, s:2}'"
+                    .to_string(),
+            ),
+            ParserError::NumberTooLarge(..) => {
+                Some("'MIN' has no positive counterpart, use 'MAX' instead".to_string())
+            }
+            ParserError::NestedRange(..) => {
+                Some("close the outer range first, eg. '{1..5}, {2..3}'".to_string())
+            }
+            ParserError::MissingRangeBounds(..) => Some(
+                "start the range with its bounds, eg. '{<START>..<END>, s:<STEP>}'".to_string(),
+            ),
+            ParserError::MissingZipOperand(..) => Some(
+                "'~' interleaves the numbers of two items, eg. '{1..=3} ~ {10..=12}'".to_string(),
+            ),
+            ParserError::InvalidRepeatCount(..) => Some(
+                "write how many times to repeat the item as a plain number, eg. '{1..=3} x 2'"
+                    .to_string(),
+            ),
+            ParserError::NonPositiveRepeatCount(..) => {
+                Some("use a count of 1 or more, eg. '0 x 5'".to_string())
+            }
+            ParserError::MissingRepeatItem(..) => Some(
+                "'x' repeats the numbers of the item before it, eg. '{1..=3} x 2'".to_string(),
+            ),
+            ParserError::InvertedClamp(..) => {
+                Some("swap the values of 'lo:' and 'hi:'".to_string())
+            }
+            ParserError::ExpectedOperand(..) => Some(
+                "every operator needs a value on both sides, eg. '(1 + 2)'".to_string(),
+            ),
+            ParserError::ExpectedOperator(..) => Some(
+                "put an operator between the two values, eg. '(2 * 3)'".to_string(),
+            ),
+            ParserError::ImplicitMultiplication(ctx) => {
+                let lhs = slice(&ctx.source, Span::new(ctx.span.start as usize, ctx.extra.start as usize));
+                let rhs = slice(&ctx.source, Span::new(ctx.extra.end as usize, ctx.span.end as usize));
+                Some(format!("did you mean '{lhs} * {rhs}'?"))
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub enum EvalError {
+    DivisionByZero(Box<ErrorContext>),
+    InvalidFactorial(Box<ErrorContext>),
+    InvalidShift(Box<ErrorContext>),
+    /// A `u:` range dropped [`EvalOptions::max_skipped`](crate::eval::EvalOptions::max_skipped)
+    /// numbers in a row. Ranges without `u:` always reach their end, as the mutated numbers
+    /// never feed back into the steps, so `{1..=10, m:-5}`, `m:*0` and `m:*-1` don't get here.
+    NonTerminatingRange(Box<ErrorContext>),
+    /// The extra is the bound the result went past, if it is known.
+    Overflow(Box<ErrorContext<Option<Bound>>>),
+    ZeroStep(Box<ErrorContext>),
+    /// Ran out of [`EvalOptions::max_steps`](crate::eval::EvalOptions::max_steps) in the
+    /// item the span points at, the extra is the number of steps taken.
+    BudgetExceeded(Box<ErrorContext<u64>>),
+    InvertedClamp(Box<ErrorContext>),
+    /// A number doesn't fit in the type it is converted to, the span points at the item
+    /// that produced it, the extra is the number and the name of the type.
+    OutOfRange(Box<ErrorContext<(i64, &'static str)>>),
+}
+
+impl EvalError {
+    /// Renders the error without any colors, the error is underlined with `^` instead.
+    #[cfg(feature = "std")]
+    pub fn render_plain(&self) -> String {
+        self.construct_error(false)
+    }
+
+    /// Location of the error in the source.
+    pub fn span(&self) -> Span {
+        self.error_ctx().1
+    }
+
+    /// Description of the error, without colors or the source excerpt.
+    pub fn message(&self) -> String {
+        self.error_msg(false)
+    }
+
+    /// Suggestion on how to fix the error, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        self.error_hint()
+    }
+
+    /// Stable code of the error variant, see the table in the module docs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            EvalError::DivisionByZero(..) => "E0201",
+            EvalError::InvalidFactorial(..) => "E0202",
+            EvalError::InvalidShift(..) => "E0203",
+            EvalError::Overflow(..) => "E0204",
+            EvalError::ZeroStep(..) => "E0205",
+            EvalError::NonTerminatingRange(..) => "E0206",
+            EvalError::BudgetExceeded(..) => "E0207",
+            EvalError::InvertedClamp(..) => "E0208",
+            EvalError::OutOfRange(..) => "E0209",
+        }
+    }
+
+    /// Converts the error into a [`miette::Report`], with the plain message as the
+    /// diagnostic message and the source attached.
+    #[cfg(feature = "miette")]
+    pub fn into_diagnostic(self) -> miette::Report {
+        into_report(&self, self.code())
+    }
+}
+
+impl core::error::Error for EvalError {}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for EvalError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(EvalError::code(self)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error_hint()
+            .map(|hint| Box::new(hint) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(self.error_ctx().0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        Some(labels(self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for EvalError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(serializer, self.code(), &self.message(), Some(self.span()))
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero(_)
+            | EvalError::InvalidFactorial(_)
+            | EvalError::InvalidShift(_)
+            | EvalError::NonTerminatingRange(_)
+            | EvalError::Overflow(_)
+            | EvalError::ZeroStep(_)
+            | EvalError::BudgetExceeded(_)
+            | EvalError::InvertedClamp(_)
+            | EvalError::OutOfRange(_) => f.write_str(&self.render()),
+        }
+    }
+}
+
+impl FancyError for EvalError {
+    fn error_ctx(&self) -> (&Arc<str>, Span) {
+        match self {
+            EvalError::DivisionByZero(ctx)
+            | EvalError::InvalidFactorial(ctx)
+            | EvalError::InvalidShift(ctx)
+            | EvalError::NonTerminatingRange(ctx)
+            | EvalError::ZeroStep(ctx)
+            | EvalError::InvertedClamp(ctx) => (&ctx.source, ctx.span),
+            EvalError::Overflow(ctx) => (&ctx.source, ctx.span),
+            EvalError::BudgetExceeded(ctx) => (&ctx.source, ctx.span),
+            EvalError::OutOfRange(ctx) => (&ctx.source, ctx.span),
+        }
+    }
+
+    fn error_msg(&self, color: bool) -> String {
+        let blue = highlight(color);
+        let (start, end) = self.position();
+        match self {
+            EvalError::DivisionByZero(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Attempt to divide by zero",
+                    start, end
+                )
+            }
+            EvalError::InvalidFactorial(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Factorial of a negative number is undefined",
+                    start, end
+                )
+            }
+            EvalError::InvalidShift(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Shift amount must be between 0 and 63",
+                    start, end
+                )
+            }
+            EvalError::Overflow(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Result {}",
+                    start,
+                    end,
+                    past(ctx.extra)
+                )
+            }
+            EvalError::ZeroStep(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Range step cannot be zero",
+                    start, end
+                )
+            }
+            EvalError::NonTerminatingRange(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Mutation keeps repeating numbers the range already produced",
+                    start, end
+                )
+            }
+            EvalError::BudgetExceeded(ctx) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - Gave up after {} evaluation steps",
+                    start, end, ctx.extra
+                )
+            }
+            EvalError::InvertedClamp(..) => {
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - The value of 'lo:' is above the one of 'hi:'",
+                    start, end
+                )
+            }
+            EvalError::OutOfRange(ctx) => {
+                let (value, ty) = ctx.extra;
+                format!(
+                    "{blue}@ position {}-{}{blue:#} - {} does not fit in a {}",
+                    start, end, value, ty
+                )
+            }
+        }
+    }
+
+    fn error_hint(&self) -> Option<String> {
+        match self {
+            EvalError::DivisionByZero(..) | EvalError::InvalidFactorial(..) => None,
+            EvalError::InvalidShift(..) => Some("shift by an amount between 0 and 63".to_string()),
+            EvalError::Overflow(..) => Some("keep the values between 'MIN' and 'MAX'".to_string()),
+            EvalError::ZeroStep(..) => Some("use a non-zero step, eg. 's:1' or 's:-1'".to_string()),
+            EvalError::NonTerminatingRange(..) => {
+                Some("drop the 'u:' flag, or use a mutation that produces new numbers".to_string())
+            }
+            EvalError::BudgetExceeded(..) => {
+                Some("shrink the ranges, or allow more steps".to_string())
+            }
+            EvalError::InvertedClamp(..) => Some("swap the values of 'lo:' and 'hi:'".to_string()),
+            EvalError::OutOfRange(..) => {
+                Some("keep the numbers in range, eg. with 'lo:' and 'hi:'".to_string())
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+
+/// Error of any of the stages going from the source to the numbers,
+/// or of writing the numbers out (eg. [`crate::write_to`]).
+#[derive(Debug)]
+pub enum Seq2Error {
+    Lexical(LexicalError),
+    Parser(ParserError),
+    Eval(EvalError),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl Seq2Error {
+    /// Renders the error without any colors, the error is underlined with `^` instead.
+    /// I/O errors have no source to point at and render as their message.
+    #[cfg(feature = "std")]
+    pub fn render_plain(&self) -> String {
+        match self.fancy() {
+            Some(e) => e.construct_error(false),
+            None => self.message(),
+        }
+    }
+
+    /// Location of the error in the source, `None` for I/O errors.
+    pub fn span(&self) -> Option<Span> {
+        self.fancy().map(|e| e.error_ctx().1)
+    }
+
+    /// Description of the error, without colors or the source excerpt.
+    pub fn message(&self) -> String {
+        match (self, self.fancy()) {
+            (_, Some(e)) => e.error_msg(false),
+            #[cfg(feature = "std")]
+            (Seq2Error::Io(e), None) => e.to_string(),
+            (_, None) => unreachable!("only I/O errors have no source"),
+        }
+    }
+
+    /// Suggestion on how to fix the error, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        self.fancy()?.error_hint()
+    }
+
+    /// Corrected version of the offending source, for near-miss typos like `...` or `s2`.
+    pub fn suggestion(&self) -> Option<&str> {
+        self.fancy()?.error_suggestion()
+    }
+
+    /// Stable code of the error variant, see the table in the module docs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Seq2Error::Lexical(e) => e.code(),
+            Seq2Error::Parser(e) => e.code(),
+            Seq2Error::Eval(e) => e.code(),
+            #[cfg(feature = "std")]
+            Seq2Error::Io(_) => "E0301",
+        }
+    }
+
+    /// Converts the error into a [`miette::Report`], with the plain message as the
+    /// diagnostic message and the source attached.
+    ///
+    /// ```
+    /// fn main() -> miette::Result<()> {
+    ///     let numbers = match seq2::parse("1, {2..=4}, (5 * 2)") {
+    ///         Ok(numbers) => numbers,
+    ///         Err(e) => Err(e.into_diagnostic())?,
+    ///     };
+    ///     assert_eq!(numbers, [1, 2, 3, 4, 10]);
+    ///
+    ///     let report = seq2::parse("1, 2 3").unwrap_err().into_diagnostic();
+    ///     assert!(format!("{report:?}").contains("Missing ',' between items"));
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "miette")]
+    pub fn into_diagnostic(self) -> miette::Report {
+        match self {
+            Seq2Error::Lexical(e) => e.into_diagnostic(),
+            Seq2Error::Parser(e) => e.into_diagnostic(),
+            Seq2Error::Eval(e) => e.into_diagnostic(),
+            Seq2Error::Io(e) => {
+                miette::Report::new(miette::MietteDiagnostic::new(e.to_string()).with_code("E0301"))
+            }
+        }
+    }
+
+    /// The error of the stage that failed, unless writing the numbers out did.
+    fn fancy(&self) -> Option<&dyn FancyError> {
+        match self {
+            Seq2Error::Lexical(e) => Some(e),
+            Seq2Error::Parser(e) => Some(e),
+            Seq2Error::Eval(e) => Some(e),
+            #[cfg(feature = "std")]
+            Seq2Error::Io(_) => None,
+        }
+    }
+}
+
+impl From<LexicalError> for Seq2Error {
+    fn from(e: LexicalError) -> Self {
+        Seq2Error::Lexical(e)
+    }
+}
+
+impl From<ParserError> for Seq2Error {
+    fn from(e: ParserError) -> Self {
+        Seq2Error::Parser(e)
+    }
+}
+
+impl From<EvalError> for Seq2Error {
+    fn from(e: EvalError) -> Self {
+        Seq2Error::Eval(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Seq2Error {
+    fn from(e: io::Error) -> Self {
+        Seq2Error::Io(e)
+    }
+}
+
+impl core::error::Error for Seq2Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Seq2Error::Lexical(e) => Some(e),
+            Seq2Error::Parser(e) => Some(e),
+            Seq2Error::Eval(e) => Some(e),
+            #[cfg(feature = "std")]
+            Seq2Error::Io(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Seq2Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(Seq2Error::code(self)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.hint()
+            .map(|hint| Box::new(hint) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(self.fancy()?.error_ctx().0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let e = self.fancy()?;
+        let (input, span) = e.error_ctx();
+        let span = clamp_span(input, span);
+        Some(Box::new(core::iter::once(
+            miette::LabeledSpan::new_with_span(Some(e.error_msg(false)), span.range()),
+        )))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Seq2Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(serializer, self.code(), &self.message(), self.span())
+    }
+}
+
+impl fmt::Display for Seq2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Seq2Error::Lexical(e) => e.fmt(f),
+            Seq2Error::Parser(e) => e.fmt(f),
+            Seq2Error::Eval(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Seq2Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////
+
+/// Error of a line of a document of named sequences, see [`crate::document`].
+///
+/// The source is the whole document and the span is in it, so errors render with the line
+/// and column of the document.
+#[derive(Debug)]
+pub enum DocumentError {
+    /// The span is the line, which doesn't start with a name.
+    MissingName(Box<ErrorContext>),
+    /// The span is the char after the name, where the `=` should be.
+    MissingEqual(Box<ErrorContext>),
+    /// The span is the `=`.
+    EmptyValue(Box<ErrorContext>),
+    /// The span is the name, the extra is the line it was first given on.
+    DuplicateName(Box<ErrorContext<usize>>),
+    /// The expression failed to lex or parse, the span is the one of the error
+    /// moved into the document.
+    Expr(Box<ErrorContext<Seq2Error>>),
+}
+
+impl DocumentError {
+    /// Renders the error without any colors, the error is underlined with `^` instead.
+    #[cfg(feature = "std")]
+    pub fn render_plain(&self) -> String {
+        self.construct_error(false)
+    }
+
+    /// Location of the error in the document.
+    pub fn span(&self) -> Span {
+        self.error_ctx().1
+    }
+
+    /// 1-based line of the error in the document.
+    pub fn line(&self) -> usize {
+        let (source, span) = self.error_ctx();
+        clamp_span(source, span).line_col(source).0 .0
+    }
+
+    /// Description of the error, without colors or the source excerpt.
+    pub fn message(&self) -> String {
+        self.error_msg(false)
+    }
+
+    /// Suggestion on how to fix the error, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        self.error_hint()
+    }
+
+    /// Stable code of the error variant, see the table in the module docs. An error
+    /// of an expression has the code of the error of the expression.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DocumentError::MissingName(..) => "E0401",
+            DocumentError::MissingEqual(..) => "E0402",
+            DocumentError::EmptyValue(..) => "E0403",
+            DocumentError::DuplicateName(..) => "E0404",
+            DocumentError::Expr(ctx) => ctx.extra.code(),
+        }
+    }
+
+    /// Converts the error into a [`miette::Report`], with the plain message as the
+    /// diagnostic message and the document attached.
+    #[cfg(feature = "miette")]
+    pub fn into_diagnostic(self) -> miette::Report {
+        into_report(&self, self.code())
+    }
+}
+
+impl core::error::Error for DocumentError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            DocumentError::Expr(ctx) => Some(&ctx.extra),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for DocumentError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(DocumentError::code(self)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error_hint()
+            .map(|hint| Box::new(hint) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(self.error_ctx().0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        Some(labels(self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DocumentError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(serializer, self.code(), &self.message(), Some(self.span()))
+    }
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+impl FancyError for DocumentError {
+    fn error_ctx(&self) -> (&Arc<str>, Span) {
+        match self {
+            DocumentError::MissingName(ctx)
+            | DocumentError::MissingEqual(ctx)
+            | DocumentError::EmptyValue(ctx) => (&ctx.source, ctx.span),
+            DocumentError::DuplicateName(ctx) => (&ctx.source, ctx.span),
+            DocumentError::Expr(ctx) => (&ctx.source, ctx.span),
+        }
+    }
+
+    fn error_msg(&self, color: bool) -> String {
+        let blue = highlight(color);
+        let line = self.line();
+        let name = |source: &str, span: Span| slice(source, span).to_string();
+        match self {
+            DocumentError::MissingName(..) => {
+                format!("{blue}@ line {line}{blue:#} - Expected 'name = <EXPR>'")
+            }
+            DocumentError::MissingEqual(..) => {
+                format!("{blue}@ line {line}{blue:#} - Missing '=' after the name")
+            }
+            DocumentError::EmptyValue(..) => {
+                format!("{blue}@ line {line}{blue:#} - Missing expression after the '='")
+            }
+            DocumentError::DuplicateName(ctx) => {
+                format!(
+                    "{blue}@ line {line}{blue:#} - '{}' is already defined on line {}",
+                    name(&ctx.source, ctx.span),
+                    ctx.extra
+                )
+            }
+            DocumentError::Expr(ctx) => {
+                // the position in the message is the one in the expression, the line
+                // replaces it
+                let message = ctx.extra.message();
+                let message = message.split_once(" - ").map_or(&*message, |(_, msg)| msg);
+                format!("{blue}@ line {line}{blue:#} - {message}")
+            }
+        }
+    }
+
+    fn error_hint(&self) -> Option<String> {
+        match self {
+            DocumentError::MissingName(..) => {
+                Some("start the line with a name, eg. 'ports = {8000..=8010}'".to_string())
+            }
+            DocumentError::MissingEqual(..) => {
+                Some("put a '=' between the name and the expression".to_string())
+            }
+            DocumentError::EmptyValue(..) => {
+                Some("write the expression after the '=', or remove the line".to_string())
+            }
+            DocumentError::DuplicateName(..) => {
+                Some("rename one of them, or remove one of the lines".to_string())
+            }
+            DocumentError::Expr(ctx) => ctx.extra.hint(),
+        }
+    }
+
+    fn error_suggestion(&self) -> Option<&str> {
+        match self {
+            DocumentError::Expr(ctx) => ctx.extra.suggestion(),
+            _ => None,
         }
     }
 }
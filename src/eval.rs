@@ -0,0 +1,1389 @@
+// the random keys of `HashSet` come from `std`, a `BTreeSet` does the same job without them
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeSet as HashSet;
+use alloc::{sync::Arc, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{
+    errors::{Bound, ErrorContext, EvalError},
+    parser::Node,
+    tokens::{Aggregate, Func, Op, SortOrder, Span, Token, TokenKind},
+};
+
+type EvalResult = Result<i64, EvalError>;
+
+/// Default for [`EvalOptions::max_skipped`].
+pub const MAX_SKIPPED: usize = 10_000_000;
+
+/// Default for [`EvalOptions::max_steps`].
+pub const MAX_STEPS: u64 = 10_000_000_000;
+
+/// Estimated number of numbers below which [`Evaluator::par_eval`] evaluates sequentially,
+/// as handing the items out to other threads costs more than it saves.
+#[cfg(feature = "parallel")]
+pub const PARALLEL_THRESHOLD: usize = 100_000;
+
+/// How many numbers a range generates between checks of whether an earlier item failed.
+const ABORT_CHECK_INTERVAL: usize = 4096;
+
+/// Post-processing applied to the final vector of numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalOptions {
+    /// Drop repeated numbers from the whole output, keeping the first occurrence of each.
+    pub dedup_all: bool,
+    /// Sort the whole output, after dropping repeated numbers.
+    pub sort: Option<SortOrder>,
+    /// Max number of repeated numbers a `u:` range can skip in a row before giving up
+    /// with [`EvalError::NonTerminatingRange`], eg. `{1..MAX, m:*0, u:}` never produces
+    /// another number after its first.
+    pub max_skipped: usize,
+    /// Max number of steps an evaluation takes before giving up with
+    /// [`EvalError::BudgetExceeded`], counting every number a range goes through (skipped
+    /// ones included), every number an `x` repeats and every operation of the math
+    /// expressions, mutations included.
+    pub max_steps: u64,
+}
+
+/// How many numbers an evaluation produces, see [`Evaluator::len`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    /// Exactly this many numbers.
+    Exact(u128),
+    /// At most this many numbers, as `u:` or [`EvalOptions::dedup_all`] drop the numbers
+    /// a mutation repeats, which can't be known without generating them.
+    AtMost(u128),
+}
+
+impl Cardinality {
+    /// The number of numbers, if it is known exactly.
+    pub fn exact(self) -> Option<u128> {
+        match self {
+            Cardinality::Exact(len) => Some(len),
+            Cardinality::AtMost(_) => None,
+        }
+    }
+
+    /// The most numbers there can be.
+    pub fn upper_bound(self) -> u128 {
+        match self {
+            Cardinality::Exact(len) | Cardinality::AtMost(len) => len,
+        }
+    }
+}
+
+impl core::ops::Add for Cardinality {
+    type Output = Cardinality;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let len = self.upper_bound().saturating_add(rhs.upper_bound());
+        match (self, rhs) {
+            (Cardinality::Exact(_), Cardinality::Exact(_)) => Cardinality::Exact(len),
+            _ => Cardinality::AtMost(len),
+        }
+    }
+}
+
+impl core::ops::Mul<u64> for Cardinality {
+    type Output = Cardinality;
+
+    fn mul(self, rhs: u64) -> Self::Output {
+        let len = self.upper_bound().saturating_mul(u128::from(rhs));
+        match self {
+            Cardinality::Exact(_) => Cardinality::Exact(len),
+            Cardinality::AtMost(_) => Cardinality::AtMost(len),
+        }
+    }
+}
+
+/// How a number of the output was worked out, see [`Evaluator::explain`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Explanation {
+    pub value: i64,
+    /// 0-based position of the top-level item the number came from.
+    pub item: usize,
+    /// Span of that item.
+    pub span: Span,
+    /// How the range the number came from made it, `None` when the item isn't a range or
+    /// was generated in full.
+    pub range: Option<RangeStep>,
+}
+
+/// A number of a range on its way out, see [`Explanation::range`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeStep {
+    /// 0-based position of the number among the numbers of the range, in their order.
+    pub index: u128,
+    /// The number of the steps, before its mutation and clamp.
+    pub base: i64,
+    /// The span of the mutation and the number it makes of `base`.
+    pub mutation: Option<(Span, i64)>,
+}
+
+impl Default for EvalOptions {
+    fn default() -> Self {
+        Self {
+            dedup_all: false,
+            sort: None,
+            max_skipped: MAX_SKIPPED,
+            max_steps: MAX_STEPS,
+        }
+    }
+}
+
+/// Evaluates parsed nodes into numbers.
+///
+/// # Ordering
+///
+/// The output is the numbers of the top level items one after the other, in the order the
+/// items are written, and the numbers of a range in the order its step goes through them.
+/// Nothing is reordered or dropped but by the item asking for it (`u:`, `r:` and `o:` of a
+/// range, which only ever touch the numbers of that range, and `~` interleaving its two
+/// sides), or by [`EvalOptions::dedup_all`] and [`EvalOptions::sort`] for the whole output.
+/// A number appearing in several items, or several times in a mutated range, is kept every
+/// time, so the numbers can be looked up by their position.
+///
+/// [`Evaluator::iter`], [`Evaluator::nth`] and `Evaluator::par_eval` all go by this order.
+#[derive(Debug)]
+pub struct Evaluator {
+    source: Arc<str>,
+    options: EvalOptions,
+}
+
+impl Evaluator {
+    pub fn new(source: Arc<str>) -> Self {
+        Self::with_options(source, EvalOptions::default())
+    }
+
+    pub fn with_options(source: Arc<str>, options: EvalOptions) -> Self {
+        Self { source, options }
+    }
+
+    /// Evaluates the parsed nodes from left to right into the final vector of numbers.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "eval", level = "debug", skip_all, fields(nodes = nodes.len()))
+    )]
+    pub fn eval(&self, nodes: &[Node]) -> Result<Vec<i64>, EvalError> {
+        let mut numbers = self.iter(nodes).collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(order) = self.options.sort {
+            trace!(?order, "sorting the output");
+            sort(&mut numbers, order);
+        }
+
+        trace!(count = numbers.len(), "evaluated");
+        Ok(numbers)
+    }
+
+    /// Evaluates the parsed nodes like [`Evaluator::eval`], with every node expanded on the
+    /// rayon thread pool and the numbers concatenated in order, so the output (or the error)
+    /// is exactly the one of [`Evaluator::eval`].
+    ///
+    /// Inputs estimated to make fewer than [`PARALLEL_THRESHOLD`] numbers are evaluated
+    /// sequentially.
+    #[cfg(feature = "parallel")]
+    pub fn par_eval(&self, nodes: &[Node]) -> Result<Vec<i64>, EvalError> {
+        self.par_eval_with_threshold(nodes, PARALLEL_THRESHOLD)
+    }
+
+    #[cfg(feature = "parallel")]
+    pub(crate) fn par_eval_with_threshold(
+        &self,
+        nodes: &[Node],
+        threshold: usize,
+    ) -> Result<Vec<i64>, EvalError> {
+        let estimate = nodes
+            .iter()
+            .map(|node| self.estimate_len(node))
+            .fold(0, usize::saturating_add);
+        if nodes.len() < 2 || estimate < threshold {
+            return self.eval(nodes);
+        }
+
+        // index of the first node that failed, the nodes after it give up early as their
+        // numbers are never used, the ones before it keep going in case they fail first
+        let failed = AtomicUsize::new(usize::MAX);
+        let chunks = nodes
+            .par_iter()
+            .enumerate()
+            .map(|(idx, node)| {
+                let aborted = || failed.load(Ordering::Relaxed) < idx;
+                let (chunk, steps) = self.expand(node, 0, &aborted);
+                if chunk.is_err() || steps > self.options.max_steps {
+                    failed.fetch_min(idx, Ordering::Relaxed);
+                }
+                (chunk, steps)
+            })
+            .collect::<Vec<_>>();
+
+        let len = chunks
+            .iter()
+            .map(|(chunk, _)| chunk.as_ref().map_or(0, Vec::len));
+        let mut numbers = Vec::with_capacity(len.sum());
+        let mut seen = self.options.dedup_all.then(HashSet::new);
+        let mut steps: u64 = 0;
+        for (node, (chunk, chunk_steps)) in nodes.iter().zip(chunks) {
+            // the sequential evaluation runs out of steps in the node that crosses the budget
+            steps = steps.saturating_add(chunk_steps);
+            if steps > self.options.max_steps {
+                return Err(self.budget_exceeded(node.span()));
+            }
+            // the first error in order, as the sequential evaluation stops there
+            let chunk = chunk?;
+            match &mut seen {
+                Some(seen) => numbers.extend(chunk.into_iter().filter(|n| seen.insert(*n))),
+                None => numbers.extend(chunk),
+            }
+        }
+
+        if let Some(order) = self.options.sort {
+            sort(&mut numbers, order);
+        }
+
+        Ok(numbers)
+    }
+
+    /// The numbers of a single node, in order, or whatever was generated by the time
+    /// `aborted` returns `true`, along with the steps taken so far, counting from `steps`.
+    fn expand(
+        &self,
+        node: &Node,
+        steps: u64,
+        aborted: &dyn Fn() -> bool,
+    ) -> (Result<Vec<i64>, EvalError>, u64) {
+        let order = match node {
+            Node::Int { .. } | Node::MathExpr { .. } => {
+                let steps = steps.saturating_add(steps_of(node));
+                if steps > self.options.max_steps {
+                    return (Err(self.budget_exceeded(node.span())), steps);
+                }
+                return (self.eval_scalar(node).map(|n| vec![n]), steps);
+            }
+            Node::Zip { left, right, .. } => {
+                let (left, steps) = self.expand(left, steps, aborted);
+                let left = match left {
+                    Ok(left) => left,
+                    Err(e) => return (Err(e), steps),
+                };
+                let (right, steps) = self.expand(right, steps, aborted);
+                return (right.map(|right| interleave(left, right)), steps);
+            }
+            Node::Repeat { item, count, .. } => {
+                let (numbers, steps) = self.expand(item, steps, aborted);
+                let numbers = match numbers {
+                    Ok(numbers) => numbers,
+                    Err(e) => return (Err(e), steps),
+                };
+                // every copy after the first goes through the numbers again
+                let copies = (numbers.len() as u64).saturating_mul(count - 1);
+                let steps = steps.saturating_add(copies);
+                if steps > self.options.max_steps {
+                    return (Err(self.budget_exceeded(node.span())), steps);
+                }
+                let count = usize::try_from(*count).unwrap_or(usize::MAX);
+                return (Ok(numbers.repeat(count)), steps);
+            }
+            Node::RangeExpr { order, .. } => order,
+        };
+
+        let mut range = match self.range_iter(node) {
+            Ok(range) => range,
+            Err(e) => return (Err(e), steps),
+        };
+        // the range goes on from the steps taken so far
+        range.steps = range.steps.saturating_add(steps);
+        if range.steps > self.options.max_steps {
+            return (Err(self.budget_exceeded(node.span())), range.steps);
+        }
+        let mut numbers = vec![];
+        for (idx, number) in range.by_ref().enumerate() {
+            if idx % ABORT_CHECK_INTERVAL == 0 && aborted() {
+                return (Ok(numbers), range.steps);
+            }
+            match number {
+                Ok(number) => numbers.push(number),
+                Err(e) => return (Err(e), range.steps),
+            }
+        }
+        if let Some(order) = order {
+            sort(&mut numbers, *order);
+        }
+        (Ok(numbers), range.steps)
+    }
+
+    /// Rough number of numbers a node makes, ignoring `u:` and errors.
+    #[cfg(feature = "parallel")]
+    fn estimate_len(&self, node: &Node) -> usize {
+        match self.node_len(node) {
+            Ok(len) => usize::try_from(len.upper_bound()).unwrap_or(usize::MAX),
+            Err(_) => 1,
+        }
+    }
+
+    /// Counts the numbers the parsed nodes evaluate to without generating them, only the
+    /// bounds and steps of the ranges are evaluated.
+    ///
+    /// The count is the one of an evaluation that succeeds, errors that only come up while
+    /// generating the numbers (eg. an overflowing mutation, or running out of
+    /// [`EvalOptions::max_steps`]) are not looked for.
+    ///
+    /// ```
+    /// use seq2::{eval::{Cardinality, Evaluator}, parser::Parser};
+    ///
+    /// let input = "1, {0..=1e9, s:7}, {1..=5, m:/2, u:}";
+    /// let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    /// let len = Evaluator::new(input.into()).len(&nodes).unwrap();
+    /// assert_eq!(len, Cardinality::AtMost(1 + 142_857_143 + 5));
+    /// ```
+    pub fn len(&self, nodes: &[Node]) -> Result<Cardinality, EvalError> {
+        let mut len = Cardinality::Exact(0);
+        for node in nodes {
+            len = len + self.node_len(node)?;
+        }
+        match len {
+            Cardinality::Exact(len) if self.options.dedup_all && len > 1 => {
+                Ok(Cardinality::AtMost(len))
+            }
+            len => Ok(len),
+        }
+    }
+
+    /// Whether `value` is one of the numbers the parsed nodes evaluate to.
+    ///
+    /// Ranges without a mutation are answered from their bounds and step, without
+    /// generating anything. Ranges with a mutation are generated until `value` comes out,
+    /// each one keeping to [`EvalOptions::max_steps`] on its own. The items are looked at
+    /// from left to right, the ones after the first that contains `value` are left out.
+    ///
+    /// ```
+    /// use seq2::{eval::Evaluator, parser::Parser};
+    ///
+    /// let input = "{0..=1e9, s:7}, {1..=5, m:*3}";
+    /// let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    /// let evaluator = Evaluator::new(input.into());
+    /// assert!(evaluator.contains(&nodes, 4_900_007).unwrap());
+    /// assert!(evaluator.contains(&nodes, 12).unwrap());
+    /// assert!(!evaluator.contains(&nodes, 13).unwrap());
+    /// ```
+    pub fn contains(&self, nodes: &[Node], value: i64) -> Result<bool, EvalError> {
+        for node in nodes {
+            if self.node_contains(node, value)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn node_contains(&self, node: &Node, value: i64) -> Result<bool, EvalError> {
+        match node {
+            Node::Zip { left, right, .. } => {
+                return Ok(self.node_contains(left, value)? || self.node_contains(right, value)?);
+            }
+            Node::Repeat { item, .. } => return self.node_contains(item, value),
+            _ => {}
+        }
+
+        let Node::RangeExpr {
+            start,
+            end,
+            inclusive,
+            step,
+            mutation,
+            low,
+            high,
+            ..
+        } = node
+        else {
+            return Ok(self.eval_scalar(node)? == value);
+        };
+
+        if mutation.is_some() {
+            for number in self.range_iter(node)? {
+                if number? == value {
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+
+        let (start, end, step) = self.range_bounds(start, end, step.as_deref())?;
+        if let Some((low, high)) = self.clamp_bounds(low.as_deref(), high.as_deref())? {
+            let len = range_len(start, end, *inclusive, step);
+            if len == 0 || value < low || value > high {
+                return Ok(false);
+            }
+            // the numbers below `lo:` come out as `lo:`, the ones above `hi:` as `hi:`
+            let last = (i128::from(start) + i128::from(step) * (len - 1) as i128) as i64;
+            if (value == low && start.min(last) <= low)
+                || (value == high && start.max(last) >= high)
+            {
+                return Ok(true);
+            }
+        }
+        if !in_range(start, end, *inclusive, value) {
+            return Ok(false);
+        }
+        // a step going the other way leaves the range after the start, which is caught by
+        // the distance having the wrong sign
+        let distance = i128::from(value) - i128::from(start);
+        let step = i128::from(step);
+        Ok(distance % step == 0 && distance / step >= 0)
+    }
+
+    /// The number at `index` of the output, `None` past its end.
+    ///
+    /// Whole items are skipped by their [`len`](Evaluator::len), and the number of a range
+    /// is worked out from its start and step, with the mutation applied to it alone.
+    /// Only ranges whose order depends on the mutated numbers (`u:` or `o:` with a mutation)
+    /// and zips or repeats of a `u:` range that can't be counted exactly are generated,
+    /// along with the whole output when [`EvalOptions::dedup_all`] or [`EvalOptions::sort`]
+    /// is set. Like for [`len`](Evaluator::len), errors of the numbers
+    /// that aren't worked out are not looked for.
+    ///
+    /// ```
+    /// use seq2::{eval::Evaluator, parser::Parser};
+    ///
+    /// let input = "7, {0..=1e12, s:3, m:+1}";
+    /// let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    /// let evaluator = Evaluator::new(input.into());
+    /// assert_eq!(evaluator.nth(&nodes, 0).unwrap(), Some(7));
+    /// assert_eq!(evaluator.nth(&nodes, 1_000_000).unwrap(), Some(2_999_998));
+    /// assert_eq!(evaluator.nth(&nodes, 1_000_000_000_000).unwrap(), None);
+    /// ```
+    pub fn nth(&self, nodes: &[Node], index: u128) -> Result<Option<i64>, EvalError> {
+        Ok(self
+            .explain(nodes, index)?
+            .map(|explanation| explanation.value))
+    }
+
+    /// The number at `index` of the output like [`Evaluator::nth`], along with the item it
+    /// came from and, for a range, the steps it went through. Only as much is worked out as
+    /// for [`Evaluator::nth`].
+    ///
+    /// ```
+    /// use seq2::{eval::Evaluator, parser::Parser, tokens::Span};
+    ///
+    /// let input = "7, {0..=1e12, s:3, m:+1}";
+    /// let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    /// let explanation = Evaluator::new(input.into()).explain(&nodes, 3).unwrap().unwrap();
+    /// assert_eq!(explanation.value, 7);
+    /// assert_eq!(explanation.item, 1);
+    /// let range = explanation.range.unwrap();
+    /// assert_eq!((range.index, range.base), (2, 6));
+    /// assert_eq!(range.mutation, Some((Span::new(21, 23), 7)));
+    /// ```
+    pub fn explain(&self, nodes: &[Node], index: u128) -> Result<Option<Explanation>, EvalError> {
+        if self.options.dedup_all || self.options.sort.is_some() {
+            return self.explain_generated(nodes, index);
+        }
+
+        let mut index = index;
+        for (item, node) in nodes.iter().enumerate() {
+            let explanation = |value, range| Explanation {
+                value,
+                item,
+                span: node.span(),
+                range,
+            };
+            let len = self.node_len(node)?;
+            let generated = match node {
+                // clamping keeps the order of the numbers, but not them being unique
+                Node::RangeExpr {
+                    mutation,
+                    low,
+                    high,
+                    unique,
+                    order,
+                    ..
+                } => {
+                    let clamped = low.is_some() || high.is_some();
+                    (mutation.is_some() || clamped) && *unique
+                        || mutation.is_some() && order.is_some()
+                }
+                Node::Zip { .. } | Node::Repeat { .. } => len.exact().is_none(),
+                Node::Int { .. } | Node::MathExpr { .. } => false,
+            };
+
+            if generated {
+                let (numbers, _) = self.expand(node, 0, &|| false);
+                let numbers = numbers?;
+                match usize::try_from(index).ok().and_then(|idx| numbers.get(idx)) {
+                    Some(number) => return Ok(Some(explanation(*number, None))),
+                    None => index -= numbers.len() as u128,
+                }
+                continue;
+            }
+
+            let len = len.upper_bound();
+            if index >= len {
+                index -= len;
+                continue;
+            }
+            let value = match node {
+                Node::RangeExpr { .. } => {
+                    let (value, step) = self.range_nth(node, index, len)?;
+                    return Ok(Some(explanation(value, Some(step))));
+                }
+                Node::Zip { left, right, .. } => self.zip_nth(left, right, index)?,
+                // every copy of the item has the same numbers
+                Node::Repeat { item, count, .. } => {
+                    let index = index % (len / u128::from(*count));
+                    self.nth(core::slice::from_ref(&**item), index)?
+                }
+                Node::Int { .. } | Node::MathExpr { .. } => Some(self.eval_scalar(node)?),
+            };
+            return Ok(value.map(|value| explanation(value, None)));
+        }
+        Ok(None)
+    }
+
+    /// [`Evaluator::explain`] for an output made whole by [`EvalOptions::dedup_all`] or
+    /// [`EvalOptions::sort`], the numbers are generated along with the items they came from.
+    fn explain_generated(
+        &self,
+        nodes: &[Node],
+        index: u128,
+    ) -> Result<Option<Explanation>, EvalError> {
+        let mut numbers = self
+            .iter(nodes)
+            .with_provenance()
+            .collect::<Result<Vec<_>, _>>()?;
+        match self.options.sort {
+            Some(SortOrder::Asc) => numbers.sort_by_key(|&(number, _)| number),
+            Some(SortOrder::Desc) => numbers.sort_by(|(a, _), (b, _)| b.cmp(a)),
+            None => {}
+        }
+        let Some(&(value, span)) = usize::try_from(index).ok().and_then(|idx| numbers.get(idx))
+        else {
+            return Ok(None);
+        };
+        let item = nodes
+            .iter()
+            .position(|node| node.span() == span)
+            .expect("a number comes from one of the nodes");
+        Ok(Some(Explanation {
+            value,
+            item,
+            span,
+            range: None,
+        }))
+    }
+
+    /// The number at `index` of a zip whose sides have an exact length.
+    fn zip_nth(&self, left: &Node, right: &Node, index: u128) -> Result<Option<i64>, EvalError> {
+        let left_len = self.node_len(left)?.upper_bound();
+        let right_len = self.node_len(right)?.upper_bound();
+        // the sides take turns until the shorter one runs out
+        let turns = left_len.min(right_len);
+        let (side, index) = match index < 2 * turns {
+            true if index.is_multiple_of(2) => (left, index / 2),
+            true => (right, index / 2),
+            false if left_len > right_len => (left, index - turns),
+            false => (right, index - turns),
+        };
+        self.nth(core::slice::from_ref(side), index)
+    }
+
+    /// The number at `index` of a range of `len` numbers whose order doesn't depend on its
+    /// mutation, and how it was made.
+    fn range_nth(
+        &self,
+        node: &Node,
+        index: u128,
+        len: u128,
+    ) -> Result<(i64, RangeStep), EvalError> {
+        let Node::RangeExpr {
+            start,
+            end,
+            step,
+            mutation,
+            low,
+            high,
+            reverse,
+            order,
+            ..
+        } = node
+        else {
+            unreachable!("Expected a range expression, found {:?}", node)
+        };
+
+        let (start, end, step) = self.range_bounds(start, end, step.as_deref())?;
+        let clamp = self.clamp_bounds(low.as_deref(), high.as_deref())?;
+        // without a mutation, sorting either keeps the numbers or reverses them
+        let reverse = match order {
+            Some(SortOrder::Asc) => step < 0,
+            Some(SortOrder::Desc) => step > 0,
+            None => *reverse,
+        };
+        let position = match reverse {
+            true => len - 1 - index,
+            false => index,
+        };
+        // the number lies between the start and the end, so it fits
+        let value = (i128::from(start) + i128::from(step) * position as i128) as i64;
+        debug_assert!(in_range(start, end, true, value));
+
+        let mutation = match mutation.as_deref() {
+            Some(Node::MathExpr { span, rpn, .. }) => {
+                Some((*span, self.eval_mutation(rpn, value)?))
+            }
+            _ => None,
+        };
+        let mutated = mutation.map_or(value, |(_, mutated)| mutated);
+        let step = RangeStep {
+            index,
+            base: value,
+            mutation,
+        };
+        Ok((
+            clamp.map_or(mutated, |(low, high)| mutated.clamp(low, high)),
+            step,
+        ))
+    }
+
+    fn node_len(&self, node: &Node) -> Result<Cardinality, EvalError> {
+        match node {
+            Node::Zip { left, right, .. } => {
+                return Ok(self.node_len(left)? + self.node_len(right)?);
+            }
+            Node::Repeat { item, count, .. } => return Ok(self.node_len(item)? * *count),
+            _ => {}
+        }
+
+        let Node::RangeExpr {
+            start,
+            end,
+            inclusive,
+            step,
+            mutation,
+            low,
+            high,
+            unique,
+            ..
+        } = node
+        else {
+            return Ok(Cardinality::Exact(1));
+        };
+
+        let (start, end, step) = self.range_bounds(start, end, step.as_deref())?;
+        let len = range_len(start, end, *inclusive, step);
+        // `u:` drops the numbers a mutation or a clamp repeats
+        let repeats = mutation.is_some() || low.is_some() || high.is_some();
+        match *unique && repeats && len > 1 {
+            true => Ok(Cardinality::AtMost(len)),
+            false => Ok(Cardinality::Exact(len)),
+        }
+    }
+
+    /// Lazily evaluates the parsed nodes from left to right, one number at a time.
+    ///
+    /// Ranges with an `o:` argument, zips and repeats are generated in full before the first
+    /// of their numbers comes out. [`EvalOptions::sort`] is not applied, as that takes the whole output.
+    pub fn iter<'a>(&'a self, nodes: &'a [Node]) -> EvalIter<'a> {
+        EvalIter {
+            evaluator: self,
+            nodes: nodes.iter(),
+            range: None,
+            buffered: vec![].into_iter(),
+            seen: self.options.dedup_all.then(HashSet::new),
+            steps: 0,
+            failed: false,
+            item_span: Span::new(0, 0),
+        }
+    }
+
+    /// Evaluates a number or a math expression.
+    pub fn eval_scalar(&self, node: &Node) -> EvalResult {
+        match node {
+            Node::Int { value, .. } => Ok(*value),
+            Node::MathExpr { negated, span, rpn } => {
+                let value = self.eval_rpn(rpn)?;
+                match negated {
+                    // only `MIN` can't be negated
+                    true => value
+                        .checked_neg()
+                        .ok_or_else(|| self.overflow(*span, Bound::Max)),
+                    false => Ok(value),
+                }
+            }
+            Node::RangeExpr { .. } | Node::Zip { .. } | Node::Repeat { .. } => {
+                unreachable!("A range, a zip or a repeat cannot be evaluated to a single number")
+            }
+        }
+    }
+
+    /// Lazily generates the numbers of a range expression.
+    ///
+    /// The range keeps to [`EvalOptions::max_steps`] on its own, counting from the steps of
+    /// its start, end and step.
+    pub fn range_iter<'a>(&'a self, node: &'a Node) -> Result<RangeIter<'a>, EvalError> {
+        let Node::RangeExpr {
+            start,
+            end,
+            inclusive,
+            step,
+            mutation,
+            low,
+            high,
+            unique,
+            reverse,
+            ..
+        } = node
+        else {
+            unreachable!("Expected a range expression, found {:?}", node)
+        };
+
+        let (start, end, step) = self.range_bounds(start, end, step.as_deref())?;
+        let clamp = self.clamp_bounds(low.as_deref(), high.as_deref())?;
+        let mutation = match mutation.as_deref() {
+            Some(Node::MathExpr { span, rpn, .. }) => Some((*span, rpn.as_slice())),
+            _ => None,
+        };
+        let len = range_len(start, end, *inclusive, step);
+        trace!(
+            span = ?node.span(),
+            start,
+            end,
+            step,
+            inclusive,
+            len,
+            ?clamp,
+            unique,
+            reverse,
+            "range"
+        );
+
+        Ok(RangeIter {
+            evaluator: self,
+            span: node.span(),
+            start,
+            step,
+            front: 0,
+            back: len,
+            reverse: *reverse,
+            mutation,
+            clamp,
+            seen: unique.then(HashSet::new),
+            skipped: 0,
+            steps: range_steps(node),
+        })
+    }
+
+    /// Evaluates the start, end and step of a range, the step defaulting to `1` or `-1`.
+    fn range_bounds(
+        &self,
+        start: &Node,
+        end: &Node,
+        step: Option<&Node>,
+    ) -> Result<(i64, i64, i64), EvalError> {
+        let start = self.eval_scalar(start)?;
+        let end = self.eval_scalar(end)?;
+        let step = match step {
+            Some(step_node) => match self.eval_scalar(step_node)? {
+                0 => {
+                    return Err(EvalError::ZeroStep(ErrorContext::new(
+                        self.source.clone(),
+                        step_node.span(),
+                    )))
+                }
+                step => step,
+            },
+            None if start <= end => 1,
+            None => -1,
+        };
+        #[cfg(feature = "tracing")]
+        if step.unsigned_abs() > 1 && start.abs_diff(end) % step.unsigned_abs() != 0 {
+            trace!(start, end, step, "the last step is trimmed before the end");
+        }
+        Ok((start, end, step))
+    }
+
+    /// Evaluates the `lo:` and `hi:` of a range, `None` when it has neither. A missing one
+    /// leaves that side unbounded.
+    fn clamp_bounds(
+        &self,
+        low: Option<&Node>,
+        high: Option<&Node>,
+    ) -> Result<Option<(i64, i64)>, EvalError> {
+        if low.is_none() && high.is_none() {
+            return Ok(None);
+        }
+        let low_value = low.map_or(Ok(i64::MIN), |low| self.eval_scalar(low))?;
+        let high_value = high.map_or(Ok(i64::MAX), |high| self.eval_scalar(high))?;
+        match (low, high) {
+            (Some(low), Some(high)) if low_value > high_value => Err(EvalError::InvertedClamp(
+                ErrorContext::new(self.source.clone(), low.span().merge(high.span())),
+            )),
+            _ => Ok(Some((low_value, high_value))),
+        }
+    }
+
+    /// Reduces a range expression to a single number.
+    pub fn aggregate(&self, aggregate: Aggregate, node: &Node) -> EvalResult {
+        // the numbers are only made to be counted when a mutation could fail on one of them,
+        // or `u:` drops some of them
+        if let (
+            Aggregate::Count,
+            Node::RangeExpr {
+                mutation: None,
+                low,
+                high,
+                ..
+            },
+        ) = (aggregate, node)
+        {
+            self.clamp_bounds(low.as_deref(), high.as_deref())?;
+            if let Cardinality::Exact(len) = self.node_len(node)? {
+                return i64::try_from(len).map_err(|_| self.overflow(node.span(), Bound::Max));
+            }
+        }
+
+        let mut result: i64 = match aggregate {
+            Aggregate::Sum | Aggregate::Count => 0,
+            Aggregate::Prod => 1,
+        };
+
+        for number in self.range_iter(node)? {
+            let number = number?;
+            let (next, bound) = match aggregate {
+                Aggregate::Sum => (result.checked_add(number), sum_bound(number)),
+                Aggregate::Count => (result.checked_add(1), Bound::Max),
+                Aggregate::Prod => (result.checked_mul(number), product_bound(result, number)),
+            };
+            result = next.ok_or_else(|| self.overflow(node.span(), bound))?;
+        }
+
+        Ok(result)
+    }
+
+    /// Walks a math expression in reverse polish notation (as produced by the parser)
+    /// and reduces it to a single number.
+    pub fn eval_rpn(&self, rpn: &[Token]) -> EvalResult {
+        self.walk_rpn(rpn, None)
+    }
+
+    /// Applies a range mutation to `value`.
+    pub fn eval_mutation(&self, rpn: &[Token], value: i64) -> EvalResult {
+        self.walk_rpn(rpn, Some(value))
+    }
+
+    fn walk_rpn(&self, rpn: &[Token], mut_arg: Option<i64>) -> EvalResult {
+        let mut stack: Vec<i64> = vec![];
+
+        for token in rpn {
+            let value = match token.kind {
+                TokenKind::Int { value } => value,
+                TokenKind::RngMutArg => match mut_arg {
+                    Some(value) => value,
+                    None => unreachable!("'@' used outside of a range mutation"),
+                },
+                TokenKind::Math(op) if op.is_unary() => {
+                    let operand = self.pop(&mut stack, token.span)?;
+                    self.apply_unary_op(op, operand, token.span)?
+                }
+                TokenKind::Math(op) => {
+                    let rhs = self.pop(&mut stack, token.span)?;
+                    let lhs = self.pop(&mut stack, token.span)?;
+                    self.apply_op(op, lhs, rhs, token.span)?
+                }
+                TokenKind::Func(func) => {
+                    let mut args = vec![0; func.arity()];
+                    for arg in args.iter_mut().rev() {
+                        *arg = self.pop(&mut stack, token.span)?;
+                    }
+                    self.apply_func(func, &args, token.span)?
+                }
+                _ => unreachable!("Unexpected token in math expression: {:?}", token.kind),
+            };
+            stack.push(value);
+        }
+
+        match stack.as_slice() {
+            [value] => Ok(*value),
+            _ => unreachable!("Malformed math expression: {:?}", rpn),
+        }
+    }
+
+    fn pop(&self, stack: &mut Vec<i64>, span: Span) -> EvalResult {
+        match stack.pop() {
+            Some(value) => Ok(value),
+            None => unreachable!("Missing operand @ position {}", span.start),
+        }
+    }
+
+    fn apply_unary_op(&self, op: Op, operand: i64, span: Span) -> EvalResult {
+        let result = match op {
+            Op::UnaryAdd => Some(operand),
+            Op::UnarySub => operand.checked_neg(),
+            Op::BitNot => Some(!operand),
+            Op::Factorial => return self.factorial(operand, span),
+            _ => unreachable!(),
+        };
+        // only `-MIN` overflows
+        result.ok_or_else(|| self.overflow(span, Bound::Max))
+    }
+
+    fn apply_op(&self, op: Op, lhs: i64, rhs: i64, span: Span) -> EvalResult {
+        if matches!(op, Op::Div | Op::Mod) && rhs == 0 {
+            return Err(EvalError::DivisionByZero(ErrorContext::new(
+                self.source.clone(),
+                span,
+            )));
+        }
+
+        let result = match op {
+            Op::Add => lhs.checked_add(rhs),
+            Op::Sub => lhs.checked_sub(rhs),
+            Op::Mul => lhs.checked_mul(rhs),
+            Op::Div => lhs.checked_div(rhs),
+            // truncated like the division, so the remainder has the sign of `lhs`, and it
+            // always fits, even when the quotient of `MIN / -1` doesn't
+            Op::Mod => Some(lhs.wrapping_rem(rhs)),
+            Op::Pow => return self.pow(lhs, rhs, span),
+            Op::BitAnd => Some(lhs & rhs),
+            Op::BitOr => Some(lhs | rhs),
+            Op::Shl | Op::Shr => return self.shift(op, lhs, rhs, span),
+            Op::UnaryAdd | Op::UnarySub | Op::BitNot | Op::Factorial => unreachable!(),
+        };
+        let bound = match op {
+            Op::Add => sum_bound(rhs),
+            Op::Sub if rhs < 0 => Bound::Max,
+            Op::Sub => Bound::Min,
+            Op::Mul => product_bound(lhs, rhs),
+            // `MIN / -1`
+            _ => Bound::Max,
+        };
+        result.ok_or_else(|| self.overflow(span, bound))
+    }
+
+    /// Integer exponentiation, a negative exponent truncates the fraction towards zero.
+    fn pow(&self, base: i64, exp: i64, span: Span) -> EvalResult {
+        match (base, exp) {
+            (0, ..=-1) => Err(EvalError::DivisionByZero(ErrorContext::new(
+                self.source.clone(),
+                span,
+            ))),
+            (0, 1..) => Ok(0),
+            (1, _) => Ok(1),
+            (-1, _) => Ok(if exp % 2 == 0 { 1 } else { -1 }),
+            (_, ..=-1) => Ok(0),
+            _ => u32::try_from(exp)
+                .ok()
+                .and_then(|exp| base.checked_pow(exp))
+                .ok_or_else(|| {
+                    // an odd power keeps the sign of the base
+                    let bound = match exp % 2 != 0 && base < 0 {
+                        true => Bound::Min,
+                        false => Bound::Max,
+                    };
+                    self.overflow(span, bound)
+                }),
+        }
+    }
+
+    fn factorial(&self, operand: i64, span: Span) -> EvalResult {
+        if operand < 0 {
+            return Err(EvalError::InvalidFactorial(ErrorContext::new(
+                self.source.clone(),
+                span,
+            )));
+        }
+
+        let mut result: i64 = 1;
+        for factor in 2..=operand {
+            result = result
+                .checked_mul(factor)
+                .ok_or_else(|| self.overflow(span, Bound::Max))?;
+        }
+        Ok(result)
+    }
+
+    /// Bit shift, `>>` is an arithmetic shift so the sign of `lhs` is kept.
+    fn shift(&self, op: Op, lhs: i64, rhs: i64, span: Span) -> EvalResult {
+        let amount = match u32::try_from(rhs) {
+            Ok(amount @ 0..=63) => amount,
+            _ => {
+                return Err(EvalError::InvalidShift(ErrorContext::new(
+                    self.source.clone(),
+                    span,
+                )))
+            }
+        };
+
+        match op {
+            Op::Shl => Ok(lhs << amount),
+            Op::Shr => Ok(lhs >> amount),
+            _ => unreachable!(),
+        }
+    }
+
+    fn apply_func(&self, func: Func, args: &[i64], span: Span) -> EvalResult {
+        let result = match (func, args) {
+            (Func::Abs, [value]) => value.checked_abs(),
+            (Func::Min, [a, b]) => Some(*a.min(b)),
+            (Func::Max, [a, b]) => Some(*a.max(b)),
+            (Func::Gcd, [a, b]) => {
+                let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                i64::try_from(a).ok()
+            }
+            _ => unreachable!("Wrong number of arguments for {}()", func.name()),
+        };
+        // `abs(MIN)` and `gcd(MIN, MIN)` are the only ones, both `-MIN`
+        result.ok_or_else(|| self.overflow(span, Bound::Max))
+    }
+
+    fn overflow(&self, span: Span, bound: Bound) -> EvalError {
+        EvalError::Overflow(ErrorContext::with(self.source.clone(), span, Some(bound)))
+    }
+
+    fn budget_exceeded(&self, span: Span) -> EvalError {
+        let steps = self.options.max_steps;
+        EvalError::BudgetExceeded(ErrorContext::with(self.source.clone(), span, steps))
+    }
+}
+
+/// Steps of evaluating a number or a math expression, one per operation.
+fn steps_of(node: &Node) -> u64 {
+    match node {
+        Node::MathExpr { rpn, .. } => rpn.len() as u64,
+        _ => 0,
+    }
+}
+
+/// Steps of evaluating the start, end and step of a range, before it generates anything.
+fn range_steps(node: &Node) -> u64 {
+    node.children()
+        .filter(|child| !matches!(child, Node::MathExpr { rpn, .. } if is_mutation(rpn)))
+        .map(steps_of)
+        .sum()
+}
+
+fn is_mutation(rpn: &[Token]) -> bool {
+    rpn.iter().any(|token| token.kind == TokenKind::RngMutArg)
+}
+
+/// Number of steps from `start` that lie between `start` and `end`.
+fn range_len(start: i64, end: i64, inclusive: bool, step: i64) -> u128 {
+    match start.cmp(&end) {
+        core::cmp::Ordering::Equal => u128::from(inclusive),
+        // a step going the other way leaves the range after the start
+        ordering if ordering.is_lt() != (step > 0) => 1,
+        _ => {
+            let (span, step) = (start.abs_diff(end), step.unsigned_abs());
+            // the steps that fit, one less when the last lands on an excluded end
+            let lands_on_end = span % step == 0;
+            u128::from(span / step) + u128::from(inclusive || !lands_on_end)
+        }
+    }
+}
+
+/// Whether `value` lies between `start` and `end`, `end` itself only for inclusive ranges.
+fn in_range(start: i64, end: i64, inclusive: bool, value: i64) -> bool {
+    let (low, high) = match start <= end {
+        true => (start, end),
+        false => (end, start),
+    };
+
+    match inclusive || value != end {
+        true => (low..=high).contains(&value),
+        false => false,
+    }
+}
+
+/// The numbers of `left` and `right` taking turns, the rest of the longer one after them.
+fn interleave(left: Vec<i64>, right: Vec<i64>) -> Vec<i64> {
+    let mut numbers = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter(), right.into_iter());
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return numbers,
+            (left, right) => numbers.extend(left.into_iter().chain(right)),
+        }
+    }
+}
+
+/// The bound a sum goes past when it overflows by adding `rhs`.
+fn sum_bound(rhs: i64) -> Bound {
+    match rhs < 0 {
+        true => Bound::Min,
+        false => Bound::Max,
+    }
+}
+
+/// The bound the product of `lhs` and `rhs` goes past when it overflows.
+fn product_bound(lhs: i64, rhs: i64) -> Bound {
+    match (lhs < 0) != (rhs < 0) {
+        true => Bound::Min,
+        false => Bound::Max,
+    }
+}
+
+fn sort(numbers: &mut [i64], order: SortOrder) {
+    match order {
+        SortOrder::Asc => numbers.sort_unstable(),
+        SortOrder::Desc => numbers.sort_unstable_by(|a, b| b.cmp(a)),
+    }
+}
+
+/// Iterator over the numbers of a range expression.
+///
+/// Numbers are generated from `start` in increments of `step` for as long as they lie
+/// between `start` and `end`, `end` itself only being produced by inclusive ranges.
+/// `r:` ranges are generated from the last of these numbers back to `start`.
+/// The mutation is applied to each generated number on its way out, followed by the clamp
+/// of `lo:` and `hi:`, and does not affect the numbers generated after it.
+/// For `u:` ranges, numbers already emitted by the range are skipped, erroring once more
+/// than [`EvalOptions::max_skipped`] of them come in a row.
+///
+/// Taking numbers from the back with [`DoubleEndedIterator::next_back`] generates them from
+/// the other end, in which case `u:` skips the numbers already emitted from either end.
+#[derive(Debug)]
+pub struct RangeIter<'a> {
+    evaluator: &'a Evaluator,
+    span: Span,
+    start: i64,
+    step: i64,
+    front: u128, // position of the next number from the front, in steps from `start`
+    back: u128,  // position after the next number from the back
+    reverse: bool,
+    mutation: Option<(Span, &'a [Token])>,
+    clamp: Option<(i64, i64)>, // `lo:` and `hi:`
+    seen: Option<HashSet<i64>>,
+    skipped: usize, // numbers skipped in a row by `u:`
+    steps: u64,
+}
+
+impl RangeIter<'_> {
+    fn take_front(&mut self) -> Option<EvalResult> {
+        while self.front < self.back {
+            let position = self.front;
+            self.front += 1;
+            if let Some(number) = self.number_at(position) {
+                return Some(number);
+            }
+        }
+        None
+    }
+
+    fn take_back(&mut self) -> Option<EvalResult> {
+        while self.front < self.back {
+            self.back -= 1;
+            if let Some(number) = self.number_at(self.back) {
+                return Some(number);
+            }
+        }
+        None
+    }
+
+    /// The mutated number at `position` steps from `start`, `None` when `u:` skips it.
+    fn number_at(&mut self, position: u128) -> Option<EvalResult> {
+        // numbers lie between the start and the end, so they fit
+        let value = (i128::from(self.start) + i128::from(self.step) * position as i128) as i64;
+
+        let mutation_steps = self.mutation.map_or(0, |(_, rpn)| rpn.len() as u64);
+        self.steps = self.steps.saturating_add(1 + mutation_steps);
+        if self.steps > self.evaluator.options.max_steps {
+            self.back = self.front;
+            return Some(Err(self.evaluator.budget_exceeded(self.span)));
+        }
+
+        let mutated = match self.mutation {
+            Some((_, rpn)) => match self.evaluator.eval_mutation(rpn, value) {
+                Ok(mutated) => mutated,
+                Err(e) => {
+                    trace!(position, value, "mutation failed");
+                    return Some(Err(e));
+                }
+            },
+            None => value,
+        };
+        let clamped = self
+            .clamp
+            .map_or(mutated, |(low, high)| mutated.clamp(low, high));
+        trace!(position, value, mutated, clamped, "number");
+        let value = clamped;
+
+        if let Some(seen) = &mut self.seen {
+            if !seen.insert(value) {
+                self.skipped += 1;
+                trace!(value, skipped = self.skipped, "skipped by u:");
+                if self.skipped > self.evaluator.options.max_skipped {
+                    self.back = self.front;
+                    // the numbers are repeated by the mutation, or else by the clamp
+                    let span = self.mutation.map_or(self.span, |(span, _)| span);
+                    let source = self.evaluator.source.clone();
+                    return Some(Err(EvalError::NonTerminatingRange(ErrorContext::new(
+                        source, span,
+                    ))));
+                }
+                return None;
+            }
+            self.skipped = 0;
+        }
+        Some(Ok(value))
+    }
+}
+
+impl Iterator for RangeIter<'_> {
+    type Item = EvalResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reverse {
+            true => self.take_back(),
+            false => self.take_front(),
+        }
+    }
+}
+
+impl DoubleEndedIterator for RangeIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.reverse {
+            true => self.take_front(),
+            false => self.take_back(),
+        }
+    }
+}
+
+/// Iterator over the numbers of all the parsed nodes, see [`Evaluator::iter`].
+///
+/// Stops after the first error.
+#[derive(Debug)]
+pub struct EvalIter<'a> {
+    evaluator: &'a Evaluator,
+    nodes: core::slice::Iter<'a, Node>,
+    range: Option<RangeIter<'a>>,
+    buffered: alloc::vec::IntoIter<i64>, // numbers of a sorted range, a zip or a repeat still to come out
+    seen: Option<HashSet<i64>>,
+    steps: u64,
+    failed: bool,
+    item_span: Span,
+}
+
+impl<'a> EvalIter<'a> {
+    /// Span of the top-level item the last number came from, eg. to point at the item
+    /// behind a number that turns out to be wrong.
+    pub fn item_span(&self) -> Span {
+        self.item_span
+    }
+
+    /// Pairs every number with the span of the top-level item it came from, see
+    /// [`EvalIter::item_span`].
+    ///
+    /// ```
+    /// use seq2::{eval::Evaluator, parser::Parser, tokens::Span};
+    ///
+    /// let mut parser = Parser::from_source("7, {1..3}").unwrap();
+    /// let nodes = parser.parse().unwrap();
+    /// let evaluator = Evaluator::new(parser.source().clone());
+    /// let numbers = evaluator.iter(&nodes).with_provenance();
+    /// assert_eq!(
+    ///     numbers.collect::<Result<Vec<_>, _>>().unwrap(),
+    ///     [(7, Span::new(0, 1)), (1, Span::new(3, 9)), (2, Span::new(3, 9))]
+    /// );
+    /// ```
+    pub fn with_provenance(self) -> WithProvenance<'a> {
+        WithProvenance { numbers: self }
+    }
+
+    fn next_number(&mut self) -> Option<EvalResult> {
+        loop {
+            if let Some(number) = self.buffered.next() {
+                return Some(Ok(number));
+            }
+            if let Some(range) = &mut self.range {
+                let number = range.next();
+                self.steps = range.steps;
+                match number {
+                    Some(number) => return Some(number),
+                    None => self.range = None,
+                }
+            }
+
+            let node = self.nodes.next()?;
+            self.item_span = node.span();
+            match node {
+                Node::Int { value, .. } => return Some(Ok(*value)),
+                Node::MathExpr { .. } => {
+                    self.steps = self.steps.saturating_add(steps_of(node));
+                    if self.steps > self.evaluator.options.max_steps {
+                        return Some(Err(self.evaluator.budget_exceeded(node.span())));
+                    }
+                    return Some(self.evaluator.eval_scalar(node));
+                }
+                Node::RangeExpr { order: None, .. } => {
+                    let mut range = match self.evaluator.range_iter(node) {
+                        Ok(range) => range,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    // the range goes on from the steps taken so far
+                    range.steps = range.steps.saturating_add(self.steps);
+                    if range.steps > self.evaluator.options.max_steps {
+                        return Some(Err(self.evaluator.budget_exceeded(node.span())));
+                    }
+                    self.range = Some(range);
+                }
+                // sorted ranges, zips and repeats are generated in full
+                Node::RangeExpr { .. } | Node::Zip { .. } | Node::Repeat { .. } => {
+                    let (numbers, steps) = self.evaluator.expand(node, self.steps, &|| false);
+                    self.steps = steps;
+                    match numbers {
+                        Ok(numbers) => self.buffered = numbers.into_iter(),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for EvalIter<'_> {
+    type Item = EvalResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        loop {
+            let number = match self.next_number()? {
+                Ok(number) => number,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            };
+
+            if let Some(seen) = &mut self.seen {
+                if !seen.insert(number) {
+                    continue;
+                }
+            }
+            return Some(Ok(number));
+        }
+    }
+}
+
+/// Iterator over the numbers of all the parsed nodes and the spans of the items they came
+/// from, see [`EvalIter::with_provenance`].
+#[derive(Debug)]
+pub struct WithProvenance<'a> {
+    numbers: EvalIter<'a>,
+}
+
+impl Iterator for WithProvenance<'_> {
+    type Item = Result<(i64, Span), EvalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let number = self.numbers.next()?;
+        Some(number.map(|number| (number, self.numbers.item_span())))
+    }
+}
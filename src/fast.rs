@@ -0,0 +1,85 @@
+//! Fast path of [`crate::parse`] for the most common input, a plain list of integers like
+//! `-3, 17, 42, 9`, which skips the tokens, the AST and the evaluator.
+
+use alloc::vec::Vec;
+
+/// Bytes a plain list of integers can be made of.
+const PLAIN: [bool; 256] = {
+    let mut plain = [false; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        plain[byte] =
+            matches!(byte as u8, b'0'..=b'9' | b'-' | b'+' | b'_' | b',') || is_space(byte as u8);
+        byte += 1;
+    }
+    plain
+};
+
+/// The numbers of `input` if it is nothing but integers separated by commas, `None` when it
+/// is anything else. Errors give `None` as well, for the full pipeline to report them with
+/// the same error and span as always.
+pub(crate) fn parse_int_list(input: &str) -> Option<Vec<i64>> {
+    let bytes = input.as_bytes();
+    if !bytes.iter().all(|byte| PLAIN[*byte as usize]) {
+        return None;
+    }
+    let items = bytes.iter().filter(|byte| **byte == b',').count() + 1;
+
+    let mut numbers = Vec::with_capacity(items);
+    let mut idx = skip_space(bytes, 0);
+
+    while idx < bytes.len() {
+        // any number of signs, like the parser folds them into the number
+        let mut negative = false;
+        while let Some(sign @ (b'+' | b'-')) = bytes.get(idx) {
+            negative ^= *sign == b'-';
+            idx = skip_space(bytes, idx + 1);
+        }
+
+        // `_` only between two digits
+        let start = idx;
+        let mut magnitude: u64 = 0;
+        while let Some(&byte) = bytes.get(idx) {
+            match byte {
+                b'0'..=b'9' => {
+                    magnitude = magnitude
+                        .checked_mul(10)?
+                        .checked_add(u64::from(byte - b'0'))?
+                }
+                b'_' if idx > start
+                    && bytes[idx - 1].is_ascii_digit()
+                    && bytes.get(idx + 1).is_some_and(u8::is_ascii_digit) => {}
+                _ => break,
+            }
+            idx += 1;
+        }
+        if idx == start {
+            return None;
+        }
+
+        // `MIN` is left to the lexer, its magnitude doesn't fit an i64
+        let value = i64::try_from(magnitude).ok()?;
+        numbers.push(if negative { -value } else { value });
+
+        idx = skip_space(bytes, idx);
+        match bytes.get(idx) {
+            Some(b',') => idx = skip_space(bytes, idx + 1),
+            Some(_) => return None,
+            None => break,
+        }
+    }
+
+    Some(numbers)
+}
+
+/// The ASCII chars that `char::is_whitespace` is true for.
+const fn is_space(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | b'\x0b' | b'\x0c')
+}
+
+fn skip_space(bytes: &[u8], mut idx: usize) -> usize {
+    while bytes.get(idx).is_some_and(|byte| is_space(*byte)) {
+        idx += 1;
+    }
+    idx
+}
@@ -0,0 +1,123 @@
+//! C bindings, see `include/seq2.h` for the declarations.
+//!
+//! [`seq2_parse`] returns `0` on success and the negated stable error code on failure,
+//! eg. `-112` for `E0112` (see the table in [`crate::errors`]). Failures that only
+//! exist at the C boundary have codes of their own, starting at `-1001`.
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    ffi::{c_char, CStr, CString},
+    panic, ptr,
+    sync::Mutex,
+};
+
+pub const SEQ2_OK: i32 = 0;
+/// `input`, `out` or `len` was `NULL`.
+pub const SEQ2_ERR_NULL_POINTER: i32 = -1001;
+/// `input` is not valid UTF-8.
+pub const SEQ2_ERR_INVALID_UTF8: i32 = -1002;
+/// seq2 panicked, which is a bug.
+pub const SEQ2_ERR_PANIC: i32 = -1003;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Buffers handed out by [`seq2_parse`] and not freed yet, so freeing one twice
+/// (or freeing a pointer seq2 never handed out) does nothing.
+static BUFFERS: Mutex<Option<HashSet<usize>>> = Mutex::new(None);
+
+fn set_last_error(message: String) {
+    // the messages come from C strings, which can't have an inner NUL
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Parses the NUL terminated `input` and stores the numbers in a new buffer, written to
+/// `*out` with its length in `*len`. An empty sequence is a `NULL` buffer of length `0`.
+///
+/// Returns [`SEQ2_OK`], or a negative code with the error available from
+/// [`seq2_last_error_message`]. `*out` and `*len` are left untouched on failure.
+///
+/// # Safety
+///
+/// `input` must be `NULL` or point to a NUL terminated string, `out` and `len` must be
+/// `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn seq2_parse(
+    input: *const c_char,
+    out: *mut *mut i64,
+    len: *mut usize,
+) -> i32 {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    if input.is_null() || out.is_null() || len.is_null() {
+        set_last_error("input, out and len must not be NULL".to_string());
+        return SEQ2_ERR_NULL_POINTER;
+    }
+    let input = match CStr::from_ptr(input).to_str() {
+        Ok(input) => input,
+        Err(e) => {
+            set_last_error(format!("input is not valid UTF-8: {e}"));
+            return SEQ2_ERR_INVALID_UTF8;
+        }
+    };
+
+    let numbers = match panic::catch_unwind(|| crate::parse(input)) {
+        Ok(Ok(numbers)) => numbers,
+        Ok(Err(e)) => {
+            set_last_error(e.render_plain());
+            return -e.code()[1..].parse::<i32>().unwrap_or(0);
+        }
+        Err(_) => {
+            set_last_error("seq2 panicked while parsing the input".to_string());
+            return SEQ2_ERR_PANIC;
+        }
+    };
+
+    let (buffer, count) = match numbers.len() {
+        0 => (ptr::null_mut(), 0),
+        count => {
+            let buffer = Box::into_raw(numbers.into_boxed_slice()) as *mut i64;
+            let mut buffers = BUFFERS.lock().unwrap_or_else(|e| e.into_inner());
+            buffers
+                .get_or_insert_with(HashSet::new)
+                .insert(buffer as usize);
+            (buffer, count)
+        }
+    };
+    *out = buffer;
+    *len = count;
+    SEQ2_OK
+}
+
+/// Frees a buffer returned by [`seq2_parse`]. `NULL`, buffers that were already freed and
+/// pointers that didn't come from [`seq2_parse`] are ignored.
+///
+/// # Safety
+///
+/// `len` must be the length [`seq2_parse`] returned along with `buffer`.
+#[no_mangle]
+pub unsafe extern "C" fn seq2_free(buffer: *mut i64, len: usize) {
+    if buffer.is_null() {
+        return;
+    }
+    let mut buffers = BUFFERS.lock().unwrap_or_else(|e| e.into_inner());
+    if buffers
+        .get_or_insert_with(HashSet::new)
+        .remove(&(buffer as usize))
+    {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, len)));
+    }
+}
+
+/// The error of the last failed [`seq2_parse`] on the calling thread, rendered without
+/// colors, or `NULL` if the last call succeeded. The string is owned by seq2 and stays
+/// valid until the next [`seq2_parse`] on the same thread.
+#[no_mangle]
+pub extern "C" fn seq2_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
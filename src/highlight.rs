@@ -0,0 +1,89 @@
+//! Classification of the input into semantic tokens, for syntax highlighting in editors.
+//!
+//! ```
+//! use seq2::{highlight, tokens::Span, TokenClass};
+//!
+//! assert_eq!(
+//!     highlight("{1..5}, $"),
+//!     [
+//!         (Span::new(0, 1), TokenClass::Brace),
+//!         (Span::new(1, 2), TokenClass::Number),
+//!         (Span::new(2, 4), TokenClass::RangeOperator),
+//!         (Span::new(4, 5), TokenClass::Number),
+//!         (Span::new(5, 6), TokenClass::Brace),
+//!         (Span::new(6, 7), TokenClass::Comma),
+//!         (Span::new(8, 9), TokenClass::Error),
+//!     ]
+//! );
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::{
+    lexer::Lexer,
+    tokens::{Span, TokenKind},
+};
+
+/// What a highlighted piece of the input is.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TokenClass {
+    /// Integers and the `@` of range mutations
+    Number,
+    /// Math operators, functions and aggregates, eg. `*`, `max` or `sum`
+    Operator,
+    /// `..` and `..=`
+    RangeOperator,
+    /// Range arguments, eg. `s:` or `o:desc`
+    RangeArgKeyword,
+    Brace,
+    Paren,
+    Comma,
+    /// Anything the lexer chokes on
+    Error,
+}
+
+impl TokenClass {
+    /// Class of a token of the given kind, `None` for trivia.
+    pub fn of(kind: TokenKind) -> Option<Self> {
+        match kind {
+            TokenKind::Int { .. } | TokenKind::RngMutArg => Some(TokenClass::Number),
+            TokenKind::Math(_)
+            | TokenKind::Func(_)
+            | TokenKind::Aggregate(_)
+            | TokenKind::Zip
+            | TokenKind::Repeat => Some(TokenClass::Operator),
+            TokenKind::RngInclusive | TokenKind::RngExclusive => Some(TokenClass::RangeOperator),
+            TokenKind::RngStep
+            | TokenKind::RngMutation
+            | TokenKind::RngUnique
+            | TokenKind::RngReverse
+            | TokenKind::RngLow
+            | TokenKind::RngHigh
+            | TokenKind::RngOrder(_) => Some(TokenClass::RangeArgKeyword),
+            TokenKind::LSquiggly | TokenKind::RSquiggly => Some(TokenClass::Brace),
+            TokenKind::LParen | TokenKind::RParen => Some(TokenClass::Paren),
+            TokenKind::Comma => Some(TokenClass::Comma),
+            TokenKind::Whitespace | TokenKind::Comment => None,
+        }
+    }
+}
+
+/// Classifies the tokens of `input`, in the order they appear. Whitespace and comments
+/// are left out.
+///
+/// Lexical errors don't stop the scan, whatever they point at is classified as
+/// [`TokenClass::Error`] and the rest of the input is still highlighted. The input is only
+/// lexed, so eg. `1 2` is highlighted as two numbers despite the missing comma.
+pub fn highlight(input: &str) -> Vec<(Span, TokenClass)> {
+    let (tokens, errors) = Lexer::new(input).lex_all_errors();
+
+    let mut classes = tokens
+        .iter()
+        .filter_map(|token| Some((token.span, TokenClass::of(token.kind)?)))
+        .chain(errors.iter().map(|e| (e.span(), TokenClass::Error)))
+        .collect::<Vec<_>>();
+    classes.sort_by_key(|(span, _)| span.start);
+    classes
+}
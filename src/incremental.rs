@@ -0,0 +1,335 @@
+//! Re-parsing of an input as it is being edited, eg. by an editor or a language server.
+//!
+//! A [`Seq2Document`] keeps the tokens and the nodes of its source. An edit only re-lexes and
+//! re-parses the items it touches, the items after it are kept with their spans moved.
+//!
+//! ```
+//! use seq2::{tokens::Span, Seq2Document};
+//!
+//! let mut doc = Seq2Document::new("1, {2..=4}, (3 * 4)");
+//! doc.apply_edit(Span::new(4, 5), "10");
+//! assert_eq!(doc.source(), "1, {10..=4}, (3 * 4)");
+//! assert_eq!(doc.nodes().count(), 3);
+//! assert!(doc.errors().is_empty());
+//! ```
+
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    errors::Seq2Error,
+    lexer::{Lexer, MAX_INPUT_LEN},
+    parser::{Node, Parser},
+    tokens::{Span, Token, TokenKind},
+};
+
+/// An input with its tokens and nodes, kept up to date with [`Seq2Document::apply_edit`].
+///
+/// The items (the parts between the commas outside of any braces/parenthesis) are parsed on
+/// their own, so an item that fails to parse doesn't keep the others from being parsed.
+#[derive(Debug)]
+pub struct Seq2Document {
+    source: Arc<str>,
+    /// Tokens of the source, whitespace and comments left out. Empty if it failed to lex.
+    tokens: Vec<Token>,
+    items: Vec<Item>,
+    lexed: bool,
+}
+
+#[derive(Debug)]
+struct Item {
+    /// Range of the tokens of the item, its comma included.
+    tokens: Range<usize>,
+    /// Whether the item ends with a comma, only the last item can be without one.
+    closed: bool,
+    /// `None` if the item failed to parse.
+    nodes: Option<Vec<Node>>,
+}
+
+impl Seq2Document {
+    pub fn new(source: &str) -> Self {
+        let mut lexer = Lexer::new(source);
+        let source = lexer.source.clone();
+        match lexer.lex() {
+            Ok(tokens) => {
+                let items = parse_items(&source, &tokens, 0);
+                Self {
+                    source,
+                    tokens,
+                    items,
+                    lexed: true,
+                }
+            }
+            Err(_) => Self {
+                source,
+                tokens: vec![],
+                items: vec![],
+                lexed: false,
+            },
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Nodes of the items that parsed, in the order of the source.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.items
+            .iter()
+            .filter_map(|item| item.nodes.as_deref())
+            .flatten()
+    }
+
+    /// The lexical error of the source, or the parser errors of the items that failed to parse.
+    /// Empty if the whole source parsed.
+    pub fn errors(&self) -> Vec<Seq2Error> {
+        if !self.lexed {
+            return Lexer::new(&self.source)
+                .lex()
+                .err()
+                .into_iter()
+                .map(Seq2Error::from)
+                .collect();
+        }
+        self.items
+            .iter()
+            .filter(|item| item.nodes.is_none())
+            .filter_map(|item| {
+                Parser::new(self.source.clone(), &self.tokens[item.tokens.clone()])
+                    .parse()
+                    .err()
+            })
+            .map(Seq2Error::from)
+            .collect()
+    }
+
+    /// Replaces the `range` of the source with `replacement`, leaving the document as
+    /// [`Seq2Document::new`] would make it for the new source.
+    ///
+    /// Only the items the edit falls in are lexed and parsed again, along with the ones after
+    /// them up to where the lexer is back outside of any braces/parenthesis and comment.
+    /// The whole source is done over if it failed to lex, before or after the edit.
+    ///
+    /// # Panics
+    /// If `range` is out of bounds of the source or doesn't fall on char boundaries.
+    pub fn apply_edit(&mut self, range: Span, replacement: &str) {
+        let Range { start, end } = range.range();
+        let mut source =
+            String::with_capacity(self.source.len() - (end - start) + replacement.len());
+        source.push_str(&self.source[..start]);
+        source.push_str(replacement);
+        source.push_str(&self.source[end..]);
+
+        if !self.lexed || source.len() > MAX_INPUT_LEN {
+            *self = Self::new(&source);
+            return;
+        }
+        let delta = replacement.len() as isize - (end - start) as isize;
+
+        // the items before the edit are left as they are, the edited ones start
+        // right after the last comma before it
+        let first = self
+            .items
+            .iter()
+            .take_while(|item| {
+                self.comma(item)
+                    .is_some_and(|comma| comma.end as usize <= start)
+            })
+            .count();
+        let lex_start = match first {
+            0 => 0,
+            _ => self
+                .comma(&self.items[first - 1])
+                .map_or(0, |comma| comma.end as usize),
+        };
+
+        // up to the first comma after the edit, or past it if the lexer isn't done with
+        // a parenthesis or a comment by then (`None` for the end of the source)
+        let mut last = first
+            + self.items[first..]
+                .iter()
+                .position(|item| {
+                    self.comma(item)
+                        .is_some_and(|comma| comma.start as usize >= end)
+                })
+                .unwrap_or(self.items.len() - first);
+        let (tokens, lex_end) = loop {
+            let lex_end = self
+                .items
+                .get(last)
+                .and_then(|item| self.comma(item))
+                .map(|comma| shift(comma.start as usize, delta));
+            let text = &source[lex_start..lex_end.unwrap_or(source.len())];
+            let Ok(tokens) = Lexer::new(text).lex() else {
+                *self = Self::new(&source);
+                return;
+            };
+            if lex_end.is_none() || at_rest(text, &tokens) {
+                break (tokens, lex_end);
+            }
+            last += 1;
+        };
+
+        let source: Arc<str> = Arc::from(source);
+        let old_start = self
+            .items
+            .get(first)
+            .map_or(self.tokens.len(), |item| item.tokens.start);
+        let old_end = match lex_end {
+            Some(_) => self.items[last].tokens.end - 1,
+            None => self.tokens.len(),
+        };
+        let kept = self.tokens.split_off(old_end);
+        self.tokens.truncate(old_start);
+        self.tokens.extend(tokens.into_iter().map(|mut token| {
+            token.span = Span::new(
+                token.span.start as usize + lex_start,
+                token.span.end as usize + lex_start,
+            );
+            token
+        }));
+        let new_end = self.tokens.len();
+        self.tokens.extend(kept.into_iter().map(|mut token| {
+            shift_span(&mut token.span, delta);
+            token
+        }));
+
+        // the comma the lexing stopped at ends the last of the new items
+        let new_items_end = match lex_end {
+            Some(_) => new_end + 1,
+            None => new_end,
+        };
+        let token_delta = new_end as isize - old_end as isize;
+        let kept = self.items.split_off((last + 1).min(self.items.len()));
+        self.items.truncate(first);
+        self.items.extend(parse_items(
+            &source,
+            &self.tokens[old_start..new_items_end],
+            old_start,
+        ));
+        self.items.extend(kept.into_iter().map(|mut item| {
+            item.tokens =
+                shift(item.tokens.start, token_delta)..shift(item.tokens.end, token_delta);
+            if let Some(nodes) = &mut item.nodes {
+                nodes.iter_mut().for_each(|node| shift_node(node, delta));
+            }
+            item
+        }));
+        self.source = source;
+    }
+
+    /// The comma ending the item.
+    fn comma(&self, item: &Item) -> Option<Span> {
+        match item.closed {
+            true => Some(self.tokens[item.tokens.end - 1].span),
+            false => None,
+        }
+    }
+}
+
+/// Splits `tokens` into items at the commas outside of any braces/parenthesis (counted the way
+/// the lexer does) and parses each of them, `offset` is the index of the first token.
+fn parse_items(source: &Arc<str>, tokens: &[Token], offset: usize) -> Vec<Item> {
+    let mut items = vec![];
+    let mut item_start = 0;
+    let mut depths = (0, 0);
+
+    for (idx, token) in tokens.iter().enumerate() {
+        track_depths(&mut depths, token.kind);
+        let closed = token.kind == TokenKind::Comma && depths == (0, 0);
+        if closed || idx == tokens.len() - 1 {
+            let nodes = Parser::new(source.clone(), &tokens[item_start..=idx])
+                .parse()
+                .ok();
+            items.push(Item {
+                tokens: offset + item_start..offset + idx + 1,
+                closed,
+                nodes,
+            });
+            item_start = idx + 1;
+        }
+    }
+    items
+}
+
+/// Whether the lexer is outside of any braces/parenthesis and comment at the end of `text`,
+/// ie. the comma right after it is lexed the same as when `text` is lexed on its own.
+fn at_rest(text: &str, tokens: &[Token]) -> bool {
+    let mut depths = (0, 0);
+    tokens
+        .iter()
+        .for_each(|token| track_depths(&mut depths, token.kind));
+    let last_line = text.rfind('\n').map_or(text, |idx| &text[idx..]);
+    depths == (0, 0) && !last_line.contains('#')
+}
+
+/// How deep the lexer is in braces and in parenthesis after `kind`.
+fn track_depths((squiggly, paren): &mut (usize, usize), kind: TokenKind) {
+    match kind {
+        TokenKind::LSquiggly => *squiggly += 1,
+        TokenKind::RSquiggly => *squiggly = squiggly.saturating_sub(1),
+        TokenKind::LParen => *paren += 1,
+        TokenKind::RParen => *paren = paren.saturating_sub(1),
+        _ => {}
+    }
+}
+
+fn shift(offset: usize, delta: isize) -> usize {
+    offset.wrapping_add_signed(delta)
+}
+
+fn shift_span(span: &mut Span, delta: isize) {
+    *span = Span::new(
+        shift(span.start as usize, delta),
+        shift(span.end as usize, delta),
+    );
+}
+
+/// Moves the spans of `node` and of everything inside it by `delta` bytes.
+fn shift_node(node: &mut Node, delta: isize) {
+    match node {
+        Node::Int { span, .. } => shift_span(span, delta),
+        Node::MathExpr { span, rpn, .. } => {
+            shift_span(span, delta);
+            rpn.iter_mut()
+                .for_each(|token| shift_span(&mut token.span, delta));
+        }
+        Node::RangeExpr {
+            span,
+            start,
+            end,
+            step,
+            mutation,
+            low,
+            high,
+            ..
+        } => {
+            shift_span(span, delta);
+            [
+                Some(start),
+                Some(end),
+                step.as_mut(),
+                mutation.as_mut(),
+                low.as_mut(),
+                high.as_mut(),
+            ]
+            .into_iter()
+            .flatten()
+            .for_each(|node| shift_node(node, delta));
+        }
+        Node::Zip { span, left, right } => {
+            shift_span(span, delta);
+            shift_node(left, delta);
+            shift_node(right, delta);
+        }
+        Node::Repeat { span, item, .. } => {
+            shift_span(span, delta);
+            shift_node(item, delta);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Checks of the span bookkeeping of the lexer and the parser, run on everything they return
+//! in debug builds, and in release builds with the `strict-invariants` feature, eg. to fuzz
+//! with optimizations. A broken invariant is a bug of seq2 and panics with what went wrong.
+//!
+//! - spans never end before they start, and are on char boundaries of the input
+//! - the tokens (and the errors of the lexer) don't go past the end of the input, and the
+//!   tokens come in the order of the input without overlapping
+//! - a node is within the tokens it was parsed from, and covers its children and the
+//!   tokens of its math expression
+
+use crate::{
+    parser::Node,
+    tokens::{Span, Token},
+};
+
+/// Whether the checks are compiled in.
+const ENABLED: bool = cfg!(any(debug_assertions, feature = "strict-invariants"));
+
+#[track_caller]
+fn check_span(span: Span, what: &dyn core::fmt::Debug) {
+    assert!(
+        span.start <= span.end,
+        "invariant broken: {what:?} ends before it starts at {span}"
+    );
+}
+
+/// Spans of the tokens and the errors of the lexer, see the [module docs](self).
+#[track_caller]
+pub(crate) fn check_lexed(source: &str, tokens: &[Token], errors: &[Span]) {
+    if !ENABLED {
+        return;
+    }
+    let mut prev_end = 0;
+    for token in tokens {
+        check_span(token.span, token);
+        assert!(
+            token.span.start >= prev_end,
+            "invariant broken: {token:?} starts before the end of the token before it ({prev_end})"
+        );
+        assert!(
+            token.text(source).is_some(),
+            "invariant broken: {token:?} isn't within the {} bytes of {source:?}",
+            source.len()
+        );
+        prev_end = token.span.end;
+    }
+    for &span in errors {
+        check_span(span, &"the error");
+        assert!(
+            span.end as usize <= source.len(),
+            "invariant broken: an error at {span} is past the {} bytes of {source:?}",
+            source.len()
+        );
+    }
+}
+
+/// Spans of the nodes parsed from `tokens`, see the [module docs](self).
+#[track_caller]
+pub(crate) fn check_parsed(tokens: &[Token], nodes: &[Node]) {
+    if !ENABLED {
+        return;
+    }
+    let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+        assert!(nodes.is_empty(), "invariant broken: nodes without tokens");
+        return;
+    };
+    let bounds = first.span.merge(last.span);
+    for node in nodes {
+        check_node(node, bounds);
+    }
+}
+
+#[track_caller]
+fn check_node(node: &Node, outer: Span) {
+    let span = node.span();
+    check_span(span, node);
+    assert!(
+        outer.start <= span.start && span.end <= outer.end,
+        "invariant broken: {node:?} isn't within {outer}"
+    );
+    if let Node::MathExpr { rpn, .. } = node {
+        for token in rpn {
+            check_span(token.span, token);
+            assert!(
+                span.start <= token.span.start && token.span.end <= span.end,
+                "invariant broken: {token:?} isn't within the math expression at {span}"
+            );
+        }
+    }
+    for child in node.children() {
+        check_node(child, span);
+    }
+}
@@ -1,99 +1,335 @@
-use std::{iter::Peekable, num::IntErrorKind, str::Chars};
+use alloc::{
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{iter::Peekable, num::IntErrorKind, str::Chars};
 
+#[cfg(feature = "aggregate")]
+use crate::tokens::Aggregate;
 use crate::{
-    errors::LexicalError,
-    tokens::{Op, Span, Token, TokenKind},
+    errors::{ErrorContext, LexicalError},
+    invariants,
+    tokens::{Func, Op, SortOrder, Span, Token, TokenKind},
 };
 
+/// Longest input (in bytes) the lexer takes, so every offset fits the `u32`s of a [`Span`].
+pub const MAX_INPUT_LEN: usize = u32::MAX as usize;
+
 type LexResult = Result<Vec<Token>, LexicalError>;
 type TokenResult = Result<Token, LexicalError>;
 
 #[derive(Debug)]
 pub struct Lexer<'a> {
-    pub input_chars: Vec<char>,
+    pub source: Arc<str>,
     input: Peekable<Chars<'a>>,
-    position: usize,
+    position: usize, // byte offset of the next char
     ch: char,
-    in_squiggly: bool,
+    squiggly_depth: usize,
+    paren_depth: usize,
+    max_input_len: usize,
+    trivia: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Self {
-            input_chars: input.chars().collect::<Vec<char>>(),
+            source: Arc::from(input),
             input: input.chars().peekable(),
-            position: 1,
+            position: 0,
             ch: '\0',
-            in_squiggly: false,
+            squiggly_depth: 0,
+            paren_depth: 0,
+            max_input_len: MAX_INPUT_LEN,
+            trivia: false,
         }
     }
 
+    /// Whether to emit whitespace and comments as [`TokenKind::Whitespace`] and
+    /// [`TokenKind::Comment`] tokens, off by default.
+    ///
+    /// With trivia, the spans of the tokens cover the whole input, so tools like formatters
+    /// can reproduce it exactly. [`Parser`](crate::parser::Parser) skips over trivia.
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
+
+    /// Sets how long (in bytes) the input can be, [`MAX_INPUT_LEN`] by default and at most.
+    pub fn with_max_input_len(mut self, max_input_len: usize) -> Self {
+        self.max_input_len = max_input_len.min(MAX_INPUT_LEN);
+        self
+    }
+
+    /// The tokens along with the text of the source they cover, see [`Token::text`].
+    pub fn tokens_with_source<'t>(
+        &'t self,
+        tokens: &'t [Token],
+    ) -> impl Iterator<Item = (&'t Token, &'t str)> {
+        let source: &'t str = &self.source;
+        tokens
+            .iter()
+            .map(move |token| (token, token.text(source).unwrap_or_default()))
+    }
+
+    /// One line per token with its span, its kind and the text it covers, in aligned columns,
+    /// eg. for bug reports about the lexer.
+    ///
+    /// ```
+    /// use seq2::lexer::Lexer;
+    ///
+    /// let mut lexer = Lexer::new("{1..3}");
+    /// let tokens = lexer.lex().unwrap();
+    /// assert_eq!(
+    ///     lexer.dump(&tokens),
+    ///     "0..1  LSquiggly         \"{\"\n\
+    ///      1..2  Int { value: 1 }  \"1\"\n\
+    ///      2..4  RngExclusive      \"..\"\n\
+    ///      4..5  Int { value: 3 }  \"3\"\n\
+    ///      5..6  RSquiggly         \"}\"\n"
+    /// );
+    /// ```
+    pub fn dump(&self, tokens: &[Token]) -> String {
+        let rows = self
+            .tokens_with_source(tokens)
+            .map(|(token, text)| {
+                let span = format!("{}..{}", token.span.start, token.span.end);
+                (span, format!("{:?}", token.kind), text)
+            })
+            .collect::<Vec<_>>();
+        let span_width = rows.iter().map(|(span, ..)| span.len()).max().unwrap_or(0);
+        let kind_width = rows
+            .iter()
+            .map(|(_, kind, _)| kind.len())
+            .max()
+            .unwrap_or(0);
+        rows.iter()
+            .map(|(span, kind, text)| format!("{span:span_width$}  {kind:kind_width$}  {text:?}\n"))
+            .collect()
+    }
+
+    /// The tokens as a JSON array of their kind, span and text, in the format of the
+    /// `Serialize` of [`Token`] with the text added.
+    ///
+    /// ```
+    /// use seq2::lexer::Lexer;
+    ///
+    /// let mut lexer = Lexer::new("7");
+    /// let tokens = lexer.lex().unwrap();
+    /// assert_eq!(
+    ///     lexer.dump_json(&tokens),
+    ///     r#"[{"kind":{"int":{"value":7}},"span":{"start":0,"end":1},"text":"7"}]"#
+    /// );
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn dump_json(&self, tokens: &[Token]) -> String {
+        #[derive(serde::Serialize)]
+        struct TokenWithSource<'t> {
+            kind: TokenKind,
+            span: Span,
+            text: &'t str,
+        }
+
+        let tokens = self
+            .tokens_with_source(tokens)
+            .map(|(token, text)| TokenWithSource {
+                kind: token.kind,
+                span: token.span,
+                text,
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&tokens).expect("tokens serialize to JSON")
+    }
+
     fn advance(&mut self) {
-        self.input.next();
-        self.position += 1;
+        if let Some(ch) = self.input.next() {
+            self.position += ch.len_utf8();
+        }
     }
 
+    /// Span of the char at the cursor.
+    fn char_span(&self) -> Span {
+        Span::new(self.position, self.position + self.ch.len_utf8())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "lex", level = "debug", skip_all, fields(len = self.source.len()))
+    )]
     pub fn lex(&mut self) -> LexResult {
+        self.check_input_len()?;
+
         let mut tokens = vec![];
+        while self
+            .lex_token(&mut tokens)
+            .inspect_err(|e| invariants::check_lexed(&self.source, &[], &[e.span()]))?
+        {}
+        invariants::check_lexed(&self.source, &tokens, &[]);
 
-        while let Some(ch) = self.input.peek() {
-            self.ch = *ch;
+        // the tokens are only final once the whole input is lexed, eg. `-` and `5` make `-5`
+        #[cfg(feature = "tracing")]
+        for token in &tokens {
+            trace!(kind = ?token.kind, span = ?token.span, "token");
+        }
+        trace!(count = tokens.len(), "lexed");
+        Ok(tokens)
+    }
 
-            match *ch {
-                ' ' => {
+    /// Lexes the input like [`Lexer::lex`], but instead of stopping at the first error,
+    /// skips past the offending chars and carries on, collecting every error on the way.
+    ///
+    /// The tokens are whatever could be lexed around the errors, eg. for highlighting
+    /// the input while it's being written.
+    pub fn lex_all_errors(&mut self) -> (Vec<Token>, Vec<LexicalError>) {
+        if let Err(e) = self.check_input_len() {
+            return (vec![], vec![e]);
+        }
+
+        let mut tokens = vec![];
+        let mut errors = vec![];
+        loop {
+            let start = self.position;
+            match self.lex_token(&mut tokens) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    // always move forward, by at least the char the error started at
+                    let end = (e.span().end as usize).max(start + 1);
+                    while self.position < end && self.input.peek().is_some() {
+                        self.advance();
+                    }
+                    errors.push(e);
+                }
+            }
+        }
+
+        let spans = errors.iter().map(LexicalError::span).collect::<Vec<_>>();
+        invariants::check_lexed(&self.source, &tokens, &spans);
+        (tokens, errors)
+    }
+
+    fn check_input_len(&self) -> Result<(), LexicalError> {
+        // point right past the last byte that fits
+        if self.source.len() > self.max_input_len {
+            return Err(LexicalError::InputTooLarge(ErrorContext::new(
+                self.source.clone(),
+                Span::new(self.max_input_len, self.max_input_len),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Lexes the token at the cursor into `tokens`, returning whether there was one.
+    fn lex_token(&mut self, tokens: &mut Vec<Token>) -> Result<bool, LexicalError> {
+        let Some(ch) = self.input.peek() else {
+            return Ok(false);
+        };
+        self.ch = *ch;
+
+        match *ch {
+            ch if ch.is_whitespace() => {
+                let start = self.position;
+                while self.input.peek().is_some_and(|ch| ch.is_whitespace()) {
                     self.advance();
                 }
-                ',' => {
+                if self.trivia {
                     tokens.push(Token::new(
-                        TokenKind::Comma,
-                        Span::new(self.position, self.position),
+                        TokenKind::Whitespace,
+                        Span::new(start, self.position),
                     ));
-                    self.advance();
                 }
-                '@' => {
-                    if !self.in_squiggly {
-                        return Err(LexicalError::MisplacedRngSyntax(
-                            self.input_chars.clone(),
-                            Span::new(self.position, self.position),
-                        ));
-                    }
+            }
+            '#' => {
+                let start = self.position;
+                self.skip_comment();
+                if self.trivia {
                     tokens.push(Token::new(
-                        TokenKind::RngMutArg,
-                        Span::new(self.position, self.position),
+                        TokenKind::Comment,
+                        Span::new(start, self.position),
                     ));
-                    self.advance();
                 }
-                '0'..='9' => {
-                    let number = self.tokenize_numbers()?;
-                    tokens.push(number);
-                }
-                '.' => {
-                    let range = self.tokenize_range()?;
-                    tokens.push(range);
-                }
-                's' | 'm' => {
-                    let range_arg = self.tokenize_range_arg()?;
-                    tokens.push(range_arg);
-                }
-                '+' | '-' | '*' | '/' | '^' | '%' => {
-                    let operator = self.tokenize_operator();
-                    tokens.push(operator);
+            }
+            ',' => {
+                tokens.push(Token::new(TokenKind::Comma, self.char_span()));
+                self.advance();
+            }
+            '@' => {
+                if self.squiggly_depth == 0 {
+                    return Err(LexicalError::MisplacedRngSyntax(ErrorContext::new(
+                        self.source.clone(),
+                        self.char_span(),
+                    )));
                 }
-                '(' | ')' | '{' | '}' => {
-                    let paren = self.tokenize_parenteses();
-                    tokens.push(paren);
+                tokens.push(Token::new(TokenKind::RngMutArg, self.char_span()));
+                self.advance();
+            }
+            '0'..='9' | '_' => {
+                let minus = unary_minus(tokens, self.position);
+                let number = self.tokenize_numbers(minus)?;
+                // the '-' of `-9_223_372_036_854_775_808` is folded into the number
+                if minus.is_some_and(|minus| number.span.start == minus.start) {
+                    tokens.pop();
                 }
-                '\0' => break,
-                _ => {
-                    return Err(LexicalError::InvalidToken(
-                        self.input_chars.clone(),
-                        Span::new(self.position, self.position),
-                    ));
+                tokens.push(number);
+            }
+            '.' => {
+                let range = self.tokenize_range()?;
+                tokens.push(range);
+            }
+            // misordered inclusive range, eg. `1=..5`
+            '=' if self.source[self.position..].starts_with("=.") => {
+                let range = self.tokenize_range()?;
+                tokens.push(range);
+            }
+            'a'..='z' | 'A'..='Z' => {
+                let keyword = self.tokenize_keyword()?;
+                tokens.push(keyword);
+            }
+            '+' | '-' | '*' | '/' | '^' | '%' | '&' | '|' | '~' => {
+                let operator = self.tokenize_operator();
+                tokens.push(operator);
+            }
+            '!' => {
+                // factorials only make sense in math expressions or range mutations
+                if self.paren_depth == 0 && self.squiggly_depth == 0 {
+                    return Err(LexicalError::InvalidToken(ErrorContext::new(
+                        self.source.clone(),
+                        self.char_span(),
+                    )));
                 }
+                let operator = self.tokenize_operator();
+                tokens.push(operator);
+            }
+            '<' | '>' => {
+                let shift = self.tokenize_shift()?;
+                tokens.push(shift);
+            }
+            '(' | ')' | '{' | '}' => {
+                let paren = self.tokenize_parenteses();
+                tokens.push(paren);
+            }
+            '\0' => return Ok(false),
+            _ => {
+                return Err(LexicalError::InvalidToken(ErrorContext::new(
+                    self.source.clone(),
+                    self.char_span(),
+                )));
             }
         }
 
-        Ok(tokens)
+        Ok(true)
+    }
+
+    /// Discards everything from a `#` up to (not including) the next newline.
+    fn skip_comment(&mut self) {
+        while let Some(ch) = self.input.peek() {
+            if *ch == '\n' {
+                break;
+            }
+            self.advance();
+        }
     }
 
     fn tokenize_parenteses(&mut self) -> Token {
@@ -105,13 +341,15 @@ impl<'a> Lexer<'a> {
             '}' => TokenKind::RSquiggly,
             _ => unreachable!(),
         };
-        if kind == TokenKind::LSquiggly {
-            self.in_squiggly = true;
-        } else if kind == TokenKind::RSquiggly {
-            self.in_squiggly = false;
+        match kind {
+            TokenKind::LSquiggly => self.squiggly_depth += 1,
+            TokenKind::RSquiggly => self.squiggly_depth = self.squiggly_depth.saturating_sub(1),
+            TokenKind::LParen => self.paren_depth += 1,
+            TokenKind::RParen => self.paren_depth = self.paren_depth.saturating_sub(1),
+            _ => {}
         }
         self.advance();
-        Token::new(kind, Span::new(current_pos, current_pos))
+        Token::new(kind, Span::new(current_pos, current_pos + 1))
     }
 
     fn tokenize_operator(&mut self) -> Token {
@@ -123,10 +361,37 @@ impl<'a> Lexer<'a> {
             '/' => TokenKind::Math(Op::Div),
             '^' => TokenKind::Math(Op::Pow),
             '%' => TokenKind::Math(Op::Mod),
+            '&' => TokenKind::Math(Op::BitAnd),
+            '|' => TokenKind::Math(Op::BitOr),
+            // outside of math expressions and ranges, `~` interleaves two items
+            '~' if self.paren_depth == 0 && self.squiggly_depth == 0 => TokenKind::Zip,
+            '~' => TokenKind::Math(Op::BitNot),
+            '!' => TokenKind::Math(Op::Factorial),
             _ => unreachable!(),
         };
         self.advance();
-        Token::new(kind, Span::new(current_pos, current_pos))
+        Token::new(kind, Span::new(current_pos, current_pos + 1))
+    }
+
+    fn tokenize_shift(&mut self) -> TokenResult {
+        let start_pos = self.position;
+        self.advance();
+
+        match self.input.peek() {
+            Some(ch) if *ch == self.ch => {
+                let kind = match self.ch {
+                    '<' => TokenKind::Math(Op::Shl),
+                    '>' => TokenKind::Math(Op::Shr),
+                    _ => unreachable!(),
+                };
+                self.advance();
+                Ok(Token::new(kind, Span::new(start_pos, self.position)))
+            }
+            _ => Err(LexicalError::InvalidToken(ErrorContext::new(
+                self.source.clone(),
+                Span::new(start_pos, start_pos + 1),
+            ))),
+        }
     }
 
     fn tokenize_range(&mut self) -> TokenResult {
@@ -139,10 +404,11 @@ impl<'a> Lexer<'a> {
             match *ch {
                 '.' => {
                     if prev_ch == '=' {
-                        return Err(LexicalError::UnexpectedEqual(
-                            self.input_chars.clone(),
-                            Span::new(start_pos, self.position),
-                        ));
+                        return Err(LexicalError::UnexpectedEqual(ErrorContext::with(
+                            self.source.clone(),
+                            Span::new(start_pos, self.position + 1),
+                            Some("did you mean '..='?".to_string()),
+                        )));
                     }
 
                     dot_count += 1;
@@ -160,10 +426,16 @@ impl<'a> Lexer<'a> {
         }
 
         if dot_count != 2 {
-            return Err(LexicalError::InvalidRange(
-                self.input_chars.clone(),
-                Span::new(start_pos, self.position - 1),
-            ));
+            let suggestion = match (dot_count, inclusive) {
+                (3, true) => Some("did you mean '..='?".to_string()),
+                (3, false) => Some("did you mean '..=' or '..'?".to_string()),
+                _ => None,
+            };
+            return Err(LexicalError::InvalidRange(ErrorContext::with(
+                self.source.clone(),
+                Span::new(start_pos, self.position),
+                suggestion,
+            )));
         }
 
         let kind = match inclusive {
@@ -171,60 +443,296 @@ impl<'a> Lexer<'a> {
             false => TokenKind::RngExclusive,
         };
 
-        Ok(Token::new(kind, Span::new(start_pos, self.position - 1)))
+        Ok(Token::new(kind, Span::new(start_pos, self.position)))
     }
 
-    fn tokenize_range_arg(&mut self) -> TokenResult {
+    fn tokenize_keyword(&mut self) -> TokenResult {
         let start_pos = self.position;
-        self.advance();
+        let mut keyword = String::new();
 
-        if !self.in_squiggly {
-            return Err(LexicalError::MisplacedRngSyntax(
-                self.input_chars.clone(),
-                Span::new(start_pos, self.position - 1),
-            ));
+        while let Some(ch @ ('a'..='z' | 'A'..='Z')) = self.input.peek() {
+            keyword.push(*ch);
+            self.advance();
         }
 
-        if let Some(':') = self.input.peek() {
-            let kind = match self.ch {
-                's' => TokenKind::RngStep,
-                'm' => TokenKind::RngMutation,
-                _ => unreachable!(),
+        let span = Span::new(start_pos, self.position);
+
+        // repeating an item, eg. `0 x 5`, only between items as it could be taken for a
+        // name inside math expressions and ranges
+        if keyword.eq_ignore_ascii_case("x") && self.paren_depth == 0 && self.squiggly_depth == 0 {
+            return Ok(Token::new(TokenKind::Repeat, span));
+        }
+
+        // function calls, eg. `abs(-5)` or `abs (-5)`. A name followed by a `(` is always a
+        // call, so `max(1, 2)` is the function while `MAX` on its own is the constant
+        if self.source[self.position..].trim_start().starts_with('(') {
+            return match Func::from_name(&keyword) {
+                Some(func) => Ok(Token::new(TokenKind::Func(func), span)),
+                None => Err(LexicalError::UnknownFunc(ErrorContext::new(
+                    self.source.clone(),
+                    span,
+                ))),
             };
+        }
+
+        // aggregates, eg. `sum{1..=10}`
+        #[cfg(feature = "aggregate")]
+        if let Some('{') = self.input.peek() {
+            if let Some(aggregate) = Aggregate::from_name(&keyword) {
+                return Ok(Token::new(TokenKind::Aggregate(aggregate), span));
+            }
+        }
+
+        // range arguments, eg. `s:2`, `step:2` or the pretty-formatted `s : 2`
+        let colon_follows = self.source[self.position..].trim_start().starts_with(':');
+        if let Some(kind) = range_arg(&keyword).filter(|_| colon_follows) {
+            while self.input.peek().is_some_and(|ch| ch.is_whitespace()) {
+                self.advance();
+            }
+            return self.tokenize_range_arg(kind, start_pos);
+        }
+        if let Some(':') = self.input.peek() {
+            return Err(LexicalError::UnknownRangeArg(ErrorContext::new(
+                self.source.clone(),
+                span,
+            )));
+        }
+
+        match keyword.as_str() {
+            _ if range_arg(&keyword).is_some() => match self.squiggly_depth {
+                0 => Err(LexicalError::MisplacedRngSyntax(ErrorContext::new(
+                    self.source.clone(),
+                    span,
+                ))),
+                _ => {
+                    // the value that should follow the colon, eg. `2` for `s2`
+                    let value: String = self.source[self.position..]
+                        .chars()
+                        .take_while(|ch| !matches!(ch, ',' | '}' | '\n'))
+                        .collect();
+                    Err(LexicalError::MissingColon(ErrorContext::with(
+                        self.source.clone(),
+                        span,
+                        Some(format!("did you mean '{keyword}:{}'?", value.trim())),
+                    )))
+                }
+            },
+            _ if keyword.eq_ignore_ascii_case("max") => {
+                Ok(Token::new(TokenKind::Int { value: i64::MAX }, span))
+            }
+            _ if keyword.eq_ignore_ascii_case("min") => {
+                Ok(Token::new(TokenKind::Int { value: i64::MIN }, span))
+            }
+            _ => Err(LexicalError::UnknownKeyword(ErrorContext::new(
+                self.source.clone(),
+                span,
+            ))),
+        }
+    }
+
+    fn tokenize_range_arg(&mut self, kind: TokenKind, start_pos: usize) -> TokenResult {
+        if self.squiggly_depth == 0 {
+            return Err(LexicalError::MisplacedRngSyntax(ErrorContext::new(
+                self.source.clone(),
+                Span::new(start_pos, self.position),
+            )));
+        }
+
+        // eat the ':'
+        self.advance();
+
+        if let TokenKind::RngOrder(_) = kind {
+            return self.tokenize_sort_order(start_pos);
+        }
+        Ok(Token::new(kind, Span::new(start_pos, self.position)))
+    }
+
+    /// Reads the `asc`/`desc` value of an `o:` argument into a single token spanning both.
+    fn tokenize_sort_order(&mut self, start_pos: usize) -> TokenResult {
+        while let Some(' ' | '\t') = self.input.peek() {
+            self.advance();
+        }
+
+        let value_pos = self.position;
+        let mut value = String::new();
+        while let Some(ch @ ('a'..='z' | 'A'..='Z')) = self.input.peek() {
+            value.push(*ch);
             self.advance();
-            Ok(Token::new(kind, Span::new(start_pos, self.position - 1)))
-        } else {
-            Err(LexicalError::MissingColon(
-                self.input_chars.clone(),
-                Span::new(start_pos, self.position - 1),
-            ))
+        }
+
+        match SortOrder::from_name(&value) {
+            Some(order) => Ok(Token::new(
+                TokenKind::RngOrder(order),
+                Span::new(start_pos, self.position),
+            )),
+            None => Err(LexicalError::InvalidSortOrder(ErrorContext::new(
+                self.source.clone(),
+                Span::new(value_pos, self.position.max(value_pos + 1)),
+            ))),
         }
     }
 
-    fn tokenize_numbers(&mut self) -> TokenResult {
+    /// `minus` is the span of a `-` negating the number, which is folded into the token when
+    /// the number is `i64::MIN`, as its magnitude doesn't fit i64 on its own. A negated number
+    /// below `i64::MIN` is too small rather than too large.
+    fn tokenize_numbers(&mut self, minus: Option<Span>) -> TokenResult {
         let mut number = String::new();
         let start_pos = self.position;
 
-        while let Some(ch @ ('0'..='9' | '_')) = self.input.peek() {
-            if *ch != '_' {
-                number.push(*ch);
-            }
-            self.advance();
+        self.scan_digits(&mut number)?;
+
+        // scientific notation (eg. `1e6`, `2E3`)
+        if let Some('e' | 'E') = self.input.peek() {
+            return self.tokenize_exponent(number, start_pos, minus);
         }
 
         match number.parse::<i64>() {
             Ok(val) => Ok(Token::new(
                 TokenKind::Int { value: val },
-                Span::new(start_pos, self.position - 1),
+                Span::new(start_pos, self.position),
             )),
-            Err(e) if e.kind() == &IntErrorKind::PosOverflow => Err(LexicalError::NumberTooLarge(
-                self.input_chars.clone(),
-                Span::new(start_pos, self.position - 1),
+            Err(e) if e.kind() == &IntErrorKind::PosOverflow => match minus {
+                Some(minus) if number == i64::MIN.unsigned_abs().to_string() => Ok(Token::new(
+                    TokenKind::Int { value: i64::MIN },
+                    Span::new(minus.start as usize, self.position),
+                )),
+                _ => Err(self.out_of_range(start_pos, minus)),
+            },
+            Err(_) => Err(LexicalError::MalformedNumber(ErrorContext::new(
+                self.source.clone(),
+                Span::new(start_pos, self.position),
+            ))),
+        }
+    }
+
+    /// Collects a run of digits into `digits`, skipping `_` separators.
+    /// Like rust, an underscore is only allowed between two digits, so leading,
+    /// trailing and doubled underscores are reported with the span of the offending `_`.
+    fn scan_digits(&mut self, digits: &mut String) -> Result<(), LexicalError> {
+        let mut prev_ch = None;
+
+        while let Some(ch @ ('0'..='9' | '_')) = self.input.peek() {
+            if *ch == '_' {
+                if !matches!(prev_ch, Some('0'..='9')) {
+                    return Err(LexicalError::MalformedNumber(ErrorContext::new(
+                        self.source.clone(),
+                        Span::new(self.position, self.position + 1),
+                    )));
+                }
+            } else {
+                digits.push(*ch);
+            }
+            prev_ch = Some(*ch);
+            self.advance();
+        }
+
+        if prev_ch == Some('_') {
+            return Err(LexicalError::MalformedNumber(ErrorContext::new(
+                self.source.clone(),
+                Span::new(self.position - 1, self.position),
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Expands `<MANTISSA>e<EXPONENT>` into a single integer token.
+    /// Signs and decimal points are not allowed in either part, a negating `minus` is folded
+    /// in like for [`Lexer::tokenize_numbers`].
+    fn tokenize_exponent(
+        &mut self,
+        mantissa: String,
+        start_pos: usize,
+        minus: Option<Span>,
+    ) -> TokenResult {
+        let mut exponent = String::new();
+
+        // eat the 'e'/'E'
+        self.advance();
+
+        self.scan_digits(&mut exponent)?;
+
+        let span = Span::new(start_pos, self.position);
+
+        if mantissa.is_empty() || exponent.is_empty() {
+            return Err(LexicalError::MalformedNumber(ErrorContext::new(
+                self.source.clone(),
+                span,
+            )));
+        }
+
+        let out_of_range = || self.out_of_range(start_pos, minus);
+
+        // the magnitude of `MIN` doesn't fit i64, it is worked out in i128
+        let mantissa = mantissa.parse::<u64>().map_err(|_| out_of_range())?;
+        let exponent = exponent.parse::<u32>().map_err(|_| out_of_range())?;
+
+        let magnitude = 10_i128
+            .checked_pow(exponent)
+            .and_then(|pow| i128::from(mantissa).checked_mul(pow));
+        match (magnitude, minus) {
+            // 0e<HUGE> is still 0
+            _ if mantissa == 0 => Ok(Token::new(TokenKind::Int { value: 0 }, span)),
+            (Some(magnitude), Some(minus)) if magnitude == i128::from(i64::MIN).abs() => Ok(
+                Token::new(TokenKind::Int { value: i64::MIN }, minus.merge(span)),
+            ),
+            (Some(magnitude), _) => match i64::try_from(magnitude) {
+                Ok(value) => Ok(Token::new(TokenKind::Int { value }, span)),
+                Err(_) => Err(out_of_range()),
+            },
+            (None, _) => Err(out_of_range()),
+        }
+    }
+
+    /// The error of a number from `start_pos` to the cursor that doesn't fit i64, too small
+    /// when it is negated by `minus`. The span of a too small number starts at the `-`.
+    fn out_of_range(&self, start_pos: usize, minus: Option<Span>) -> LexicalError {
+        match minus {
+            Some(minus) => LexicalError::NumberTooSmall(ErrorContext::new(
+                self.source.clone(),
+                Span::new(minus.start as usize, self.position),
             )),
-            Err(_) => Err(LexicalError::MalformedNumber(
-                self.input_chars.clone(),
-                Span::new(start_pos, self.position - 1),
+            None => LexicalError::NumberTooLarge(ErrorContext::new(
+                self.source.clone(),
+                Span::new(start_pos, self.position),
             )),
         }
     }
 }
+
+/// Span of the `-` right before `position`, if it negates the number that follows
+/// rather than subtracting it from the operand before it.
+fn unary_minus(tokens: &[Token], position: usize) -> Option<Span> {
+    let mut tokens = tokens.iter().rev().filter(|token| !token.kind.is_trivia());
+    let minus = tokens.next()?;
+    let before = tokens.next().map(|token| token.kind);
+    if minus.kind != TokenKind::Math(Op::Sub) || minus.span.end as usize != position {
+        return None;
+    }
+
+    match before {
+        Some(
+            TokenKind::Int { .. }
+            | TokenKind::RParen
+            | TokenKind::RSquiggly
+            | TokenKind::RngMutArg
+            | TokenKind::Math(Op::Factorial),
+        ) => None,
+        _ => Some(minus.span),
+    }
+}
+
+/// Maps the name of a range argument (short or long form) to its token.
+fn range_arg(name: &str) -> Option<TokenKind> {
+    match name {
+        "s" | "step" => Some(TokenKind::RngStep),
+        "m" | "mut" => Some(TokenKind::RngMutation),
+        "u" | "unique" => Some(TokenKind::RngUnique),
+        "r" | "rev" => Some(TokenKind::RngReverse),
+        // the actual order is read by `tokenize_sort_order`
+        "o" | "order" => Some(TokenKind::RngOrder(SortOrder::Asc)),
+        "lo" | "low" => Some(TokenKind::RngLow),
+        "hi" | "high" => Some(TokenKind::RngHigh),
+        _ => None,
+    }
+}
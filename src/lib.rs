@@ -11,6 +11,12 @@
 //! ### Single numbers
 //! Single number are can be any positive or negative number that can fit i64
 //!
+//! Large round numbers can be written in scientific notation as `<MANTISSA>e<EXPONENT>`
+//! (eg. `1e6`, `2E3`). Both parts must be plain digits, without signs or decimal points.
+//!
+//! The bounds of i64 are available as the (case-insensitive) keywords `MAX` and `MIN`
+//! and can be used anywhere a number can (eg. `{MAX-5..=MAX}`).
+//!
 //! ### Range of numbers
 //! A range specification must be encapsulated in squiggly braces `{}` and must adhere the following syntax:
 //! - For exclusive ranges:
@@ -24,6 +30,9 @@
 //!    - `{<START>..=<END>, m:<MUTATION>}`
 //!    - `{<START>..=<END>, s:<STEP>, m:<MUTATION>}`
 //!
+//! The arguments after the `END` can be given in any order, but each one only once,
+//! eg. `{1..10, m:*2, s:3}` is the same as `{1..10, s:3, m:*2}`.
+//!
 //! #### `<START>`, `<END>`:
 //! Any positive or negative number that can fit i64.
 //! If the `END` is smaller than the `START`, the parser will assume
@@ -33,11 +42,35 @@
 //!   - `{3..=1}` will be parsed to `3, 2, 1`
 //!   - `{-3..=-6}` will be parsed to `-3, -4, -5, -6`
 //!
+//! The `START` can be left out, in which case the range starts at `0`.
+//! The `END` is always required.
+//!
+//! i.e.
+//!   - `{..3}` will be parsed to `0, 1, 2`
+//!   - `{..=-2}` will be parsed to `0, -1, -2`
+//!
+//! An `END` starting with a `+` is relative to the `START`, it is the `START` plus the number
+//! after the `+`. A `-` is still the sign of an absolute `END`, count down with `+-`.
+//!
+//! i.e.
+//!   - `{100..+5}` will be parsed to `100, 101, 102, 103, 104`
+//!   - `{100..=+-2}` will be parsed to `100, 99, 98`
+//!
+//! With `..` the `END` itself is never produced, with `..=` it is produced whenever a `STEP`
+//! lands on it. This holds for ranges in both directions, and the bounds are checked before
+//! any `MUTATION` is applied.
+//!
+//! i.e.
+//!   - `{1..4}` will be parsed to `1, 2, 3`
+//!   - `{4..1}` will be parsed to `4, 3, 2`
+//!   - `{1..10, s:3}` will be parsed to `1, 4, 7`
+//!   - `{5..5}` will be parsed to nothing, while `{5..=5}` will be parsed to `5`
+//!
 //! #### `s:<STEP>` (_Optional argument_):
 //! The increment or decrement between each number in the range.
-//! Value must be prefixed with `s:`.
+//! Value must be prefixed with `s:` (or the long form `step:`).
 //! If no `STEP` is  specified, the default `step` is 1 or -1.
-//! `STEP` must respect the `START` and `END` of the range.
+//! `STEP` must respect the `START` and `END` of the range, otherwise parsing fails.
 //! Meaning if the `START` is smaller than the `END`, the `STEP` must be positive
 //! and if the `START` is greater than the `END`, the `STEP` must be negative.
 //!
@@ -50,17 +83,65 @@
 //!
 //! #### `m:<MUTATION>` (_Optional argument_):
 //! The mutation (an arithmetic operation) to be applied to each number in the range.
-//! Value must be prefixed with `m:`.
+//! Value must be prefixed with `m:` (or the long form `mut:`).
 //! If not specified, not mutations will be applied.
 //!
 //! The `MUTATION` is applied after each `STEP` increment/decrement of the range
 //! and must written as an arithmetic operation that assumes the number to be mutated
-//! will be on the lhs of the operation.
+//! will be on the lhs of the operation. The mutated numbers never feed back into the steps,
+//! so a range always stops at its `END`, whatever the mutation (eg. `m:-5`, `m:*0` or `m:*-1`).
+//!
+//! i.e.
+//! ```
+//! assert_eq!(seq2::parse("{1..=5, m:+2}").unwrap(), [3, 4, 5, 6, 7]);
+//! assert_eq!(seq2::parse("{5..=1, s:-2, m:-2}").unwrap(), [3, 1, -1]);
+//! // the steps stop at `1`, as `-1` is past the `END`, before the mutation
+//! assert_eq!(seq2::parse("{5..=0, s:-2, m:-2}").unwrap(), [3, 1, -1]);
+//! ```
+//!
+//! #### `u:` (_Optional flag_):
+//! Drops the numbers the range has already produced, keeping the first occurrence of each.
+//! Can be written as `u:`, `u:1` (on) or `u:0` (off), or with the long form `unique:`.
+//!
+//! i.e.
+//!   - `{1..=20, m:/5, u:}` will be parsed to `0, 1, 2, 3, 4`
+//!
+//! A range that keeps repeating itself, eg. `{1..MAX, m:*0, u:}`, stops with an error once
+//! 10 million numbers in a row have been dropped, see [`eval::EvalOptions::max_skipped`].
+//! This is the only way a range can get stuck, as a mutation alone can't keep it from reaching
+//! its `END`.
+//!
+//! To drop repeated numbers from the whole output instead, evaluate with
+//! [`eval::EvalOptions::dedup_all`] turned on.
+//!
+//! #### `r:` (_Optional flag_):
+//! Generates the numbers of the range from the last one back to the `START`, without sorting them.
+//! Any `MUTATION` and `u:` are applied in that order, so `u:` keeps the last occurrence of each number.
+//! Can be written as `r:`, `r:1` (on) or `r:0` (off), or with the long form `rev:`.
+//!
+//! i.e.
+//!   - `{1..=5, r:}` will be parsed to `5, 4, 3, 2, 1`
+//!   - `{1..10, s:3, r:}` will be parsed to `7, 4, 1`, the reverse of `{1..10, s:3}`
+//!
+//! #### `lo:<LOW>`, `hi:<HIGH>` (_Optional arguments_):
+//! Clamps the numbers produced by the range, after any `MUTATION` is applied: numbers below `LOW`
+//! come out as `LOW` and numbers above `HIGH` as `HIGH`. Either one can be given on its own.
+//! `u:` and `o:` see the clamped numbers. Can also be written with the long forms `low:` and `high:`.
+//! `LOW` can't be above `HIGH`.
+//!
+//! i.e.
+//!   - `{0..=10, m:*3, hi:20}` will be parsed to `0, 3, 6, 9, 12, 15, 18, 20, 20, 20, 20`
+//!   - `{0..=5, lo:2, hi:4}` will be parsed to `2, 2, 2, 3, 4, 4`
+//!   - `{0..=5, lo:2, hi:4, u:}` will be parsed to `2, 3, 4`
+//!
+//! #### `o:<ORDER>` (_Optional argument_):
+//! Sorts the numbers produced by the range, after any `MUTATION` and `u:` are applied.
+//! `ORDER` is either `asc` or `desc`. Value must be prefixed with `o:` (or the long form `order:`).
 //!
 //! i.e.
-//!   - `{1..=5, m:+2}` will be parsed to `3, 5, 7`
-//!   - `{5..=1, s:-2, m:-2}` will be parsed to `3, 1, -1`
-//!   - `{5..=0, s:-2, m:-2}`
+//!   - `{1..=3, m:*-1, o:asc}` will be parsed to `-3, -2, -1`
+//!
+//! To sort the whole output instead, evaluate with [`eval::EvalOptions::sort`] set.
 //!
 //! ### Basic arithmetic operations
 //! Basic arithmetic operations can be applied to any number or range of numbers.
@@ -69,33 +150,277 @@
 //! - Addition `+`
 //! - Subtraction `-`
 //! - Multiplication `*`
-//! - Division `/`
+//! - Division `/`, truncated towards zero, eg. `(-7 / 2)` is `-3`
+//! - Remainder `%` of the truncated division, which has the sign of the left operand,
+//!   eg. `(-7 % 3)` is `-1` and `(7 % -3)` is `1`
 //! - Exponentiation `^`
+//! - Bitwise AND `&`, OR `|` and NOT `~`
+//! - Bit shifts `<<` and `>>` (arithmetic), the shift amount must be between 0 and 63
+//! - Factorial `!` (postfix), eg. `(5!)` is `120`. The operand must not be negative
 //! > Note: Any floating point number will be truncated to an integer.
 //!
+//! Operators are applied in the following order (highest precedence first):
+//!
+//! | Precedence | Operators          | Associativity |
+//! |------------|--------------------|---------------|
+//! | 1          | `!` (postfix)      | left          |
+//! | 2          | `^`                | right         |
+//! | 3          | `~`, unary `-`/`+` | right         |
+//! | 4          | `*`, `/`, `%`      | left          |
+//! | 5          | `+`, `-`           | left          |
+//! | 6          | `<<`, `>>`         | left          |
+//! | 7          | `&`                | left          |
+//! | 8          | `\|`               | left          |
+//!
+//! So `(2 ^ 3 ^ 2)` is `2 ^ (3 ^ 2)`, ie. `512`, and `(-2 ^ 2)` is `-(2 ^ 2)`, ie. `-4`.
+//! A negative exponent truncates the result towards zero, eg. `(2 ^ -3)` is `0`.
+//! The unary `-` and `+` can be put before parenthesis and function calls too, eg. `(3 * -(1 + 2))` is `-9`
+//! and `-(2 + 3)` is `-5`.
+//!
+//! The following built-in functions can be called inside a math expression,
+//! with their arguments separated by commas:
+//! - `abs(<N>)` - absolute value of `N`
+//! - `min(<A>, <B>)` - smaller of `A` and `B`
+//! - `max(<A>, <B>)` - larger of `A` and `B`
+//! - `gcd(<A>, <B>)` - greatest common divisor of `A` and `B`
+//!
+//! i.e.
+//!   - `"(abs(-5) * 2)"` will be parsed to `10`
+//!   - `"(gcd(12, max(8, 18 / 2)))"` will be parsed to `3`
+//!
+//! A name followed by a `(` is always a call, spaces between them or not, so `(max (1, 2))` is `2`,
+//! while `MAX` without a `(` is the constant.
+//!
+//! A range can also be reduced to a single number inside a math expression
+//! (or as a range bound) by prefixing it with an aggregate (_requires the default `aggregate` feature_):
+//! - `sum{..}` - sum of the numbers in the range
+//! - `count{..}` - amount of numbers in the range
+//! - `prod{..}` - product of the numbers in the range
+//!
+//! i.e.
+//!   - `"(sum{1..=10} * 2)"` will be parsed to `110`
+//!   - `"{0..count{0..100, s:7}, s:5}"` will be parsed to `0, 5, 10`
+//!
 //! The operations can be applied set the `START` or `END` of a number range.
 //!
 //! i.e.
 //!   - `"(1 + 2 - 3)"` will be parsed to `0`
 //!   - `"(-2^3 - (3 * 100 / 20))"` will be parsed to `-23`
-//!   - `"{(1 - (10 ^ 2))..-108, s:3, m:*-1}"` will be parsed to `99, 102, 105`
+//!   - `"{(1 - (10 ^ 2))..-108, s:-3, m:*-1}"` will be parsed to `99, 102, 105`
 //!     > **Breakdown of the above example:**
 //!     > 1. `1 - (10 ^ 2)` will be calculated to `-99` (range start)
-//!     > 2. From `-99`, the number will decrement as specified by the step `s:3`
-//!          and then mutated by `m:*-1`. (`-99*-1`, `-102*-1`, etc.)
+//!     > 2. From `-99`, the number will decrement as specified by the step `s:-3`
+//!     >    and then mutated by `m:*-1`. (`-99*-1`, `-102*-1`, etc.)
 //!     > 3. Stops generating new numbers once `-108` is reached.
 //!
 //! ## Chaining all the syntaxes
 //! All the syntaxes can be chained together to create complex number vectors.
 //! The parser will parse the string from left to right and apply the operations in the order they are found.
+//! The numbers come out in that same order: the items one after the other, and the numbers of each range in
+//! the order its step goes through them. Repeated numbers are kept wherever they appear, unless asked
+//! otherwise with `u:`, or with [`EvalOptions::dedup_all`](eval::EvalOptions::dedup_all) for the whole
+//! output, so a number can be looked up by its position.
+//!
+//! i.e.
+//! ```
+//! assert_eq!(
+//!     seq2::parse("-1, -2, -3, {1..=3, s:2, m:+2}, (200 ^ 2 + 1)").unwrap(),
+//!     [-1, -2, -3, 3, 5, 40001]
+//! );
+//! assert_eq!(seq2::parse("{1..=3}, 2, {3..=1}").unwrap(), [1, 2, 3, 2, 3, 2, 1]);
+//! ```
+//!
+//! ## Zipping
+//! Two items joined by `~` have their numbers interleaved, taking one number from each side in turn.
+//! Once the shorter side runs out, the rest of the longer side follows.
+//! A single number or an arithmetic operation counts as a side with one number.
+//! `~` binds tighter than `,` and chains from left to right.
+//!
+//! i.e.
+//!   - `"{1..=3} ~ {10..=12}"` will be parsed to `1, 10, 2, 11, 3, 12`
+//!   - `"{1..=5} ~ {10..=11}, 0"` will be parsed to `1, 10, 2, 11, 3, 4, 5, 0`
+//!   - `"{1..=2} ~ 5 ~ {7..=9}"` will be parsed to `1, 7, 5, 8, 2, 9`
+//!
+//! Inside an arithmetic operation or a range `~` is still the bitwise NOT.
+//!
+//! ## Repeating
+//! An item followed by `x` (or `X`) and a number has its numbers repeated that many times.
+//! The count has to be a plain number of at least 1, and `x` binds tighter than `~`.
+//!
+//! i.e.
+//!   - `"0 x 5"` will be parsed to `0, 0, 0, 0, 0`
+//!   - `"{1..=3} x 2"` will be parsed to `1, 2, 3, 1, 2, 3`
+//!   - `"1 x 2 ~ {5..=7}"` will be parsed to `1, 5, 1, 6, 7`
+//!
+//! `x` can't be used inside an arithmetic operation or a range, use `*` to multiply.
+//!
+//! ## Whitespace and comments
+//! Spaces, tabs and newlines between items are ignored, so a sequence can be split over several lines.
+//! The same goes for the inside of a range, including the space before the colon of an argument.
+//! Everything from a `#` to the end of the line is a comment and is ignored as well.
 //!
 //! i.e.
-//!   - `"-1, -2, -3, {1..=3, s:2, m:+2}, (200 ^ 2 + 1)"` will be parsed to `-1, -2, -3, 3, 5, 7, 400001`
+//!   - `"{1..=12}  # months"` will be parsed to `1, 2, ..., 12`
+//!   - `"{ 1 ..= 10 , s : 3 }"` will be parsed to `1, 4, 7, 10`
+//!
+//! ## Tracing
+//! With the `tracing` feature the lexing, the parsing and the evaluation run in `lex`, `parse`
+//! and `eval` spans, with `TRACE` events for the tokens, the nodes and how each range is expanded.
+//! Without the feature none of it is compiled.
+//!
+//! ```
+//! tracing_subscriber::fmt()
+//!     .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+//!     .init();
+//!
+//! // RUST_LOG=seq2=trace prints, along with the tokens and the other numbers,
+//! // TRACE parse{tokens=14}: seq2::parser: node span=Span { start: 3, end: 21 } node={0..5, s:2, m:(@ * 10)}
+//! // TRACE eval{nodes=2}: seq2::eval: number position=1 value=2 mutated=20 clamped=20
+//! seq2::parse("1, {0..5, s:2, m:*10}").unwrap();
+//! ```
+//!
+//! ## `no_std`
+//! Without the default `std` feature the crate only needs `core` and `alloc`. The lexer, the parser
+//! and the evaluator work the same, but errors display as their plain message, without the source
+//! excerpt, and [`write_to`] is left out.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+// the `cdylib` needs a panic handler and an allocator, take the ones of `std` where there is one
+#[cfg(all(not(any(feature = "std", test)), not(target_os = "none")))]
+extern crate std;
+
+/// `tracing::trace!` with the `tracing` feature, without it the event and its fields
+/// aren't compiled at all.
+#[cfg(feature = "tracing")]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        tracing::trace!($($arg)*)
+    };
+}
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+pub mod builder;
+#[cfg(feature = "clap")]
+pub mod clap;
+pub mod document;
 pub mod errors;
+pub mod eval;
+mod fast;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod highlight;
+pub mod incremental;
+mod invariants;
 pub mod lexer;
-mod parser;
-mod tokens;
+pub mod literal;
+pub mod parser;
+pub mod tokens;
+pub mod validate;
+pub mod visit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use crate::builder::Seq2Builder;
+#[cfg(feature = "clap")]
+pub use crate::clap::{clap_parser, Seq2Values};
+pub use crate::highlight::{highlight, TokenClass};
+pub use crate::incremental::Seq2Document;
+pub use crate::validate::{validate, validate_with, Diagnostic, Severity};
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{BufWriter, Write};
+
+use errors::Seq2Error;
+use eval::{EvalOptions, Evaluator};
+use parser::{Parser, ParserOptions};
+
+/// Lexes, parses and evaluates `input` into the final vector of numbers.
+///
+/// Plain lists of integers (eg. `-3, 17, 42, 9`) take a shortcut straight to the numbers.
+///
+/// ```
+/// assert_eq!(seq2::parse("-1, {1..=3, s:2, m:+2}").unwrap(), [-1, 3, 5]);
+/// ```
+pub fn parse(input: &str) -> Result<Vec<i64>, Seq2Error> {
+    parse_with(input, ParserOptions::default())
+}
+
+/// [`parse`] with limits on the size of the input and on the steps its evaluation takes, eg.
+/// for a public facing service.
+///
+/// ```
+/// use seq2::parser::ParserOptions;
+///
+/// let options = ParserOptions {
+///     max_items: 2,
+///     ..Default::default()
+/// };
+/// assert_eq!(seq2::parse_with("1, {2..=4}", options).unwrap(), [1, 2, 3, 4]);
+/// assert_eq!(seq2::parse_with("1, 2, 3", options).unwrap_err().code(), "E0137");
+/// ```
+pub fn parse_with(input: &str, options: ParserOptions) -> Result<Vec<i64>, Seq2Error> {
+    // too long inputs are refused by the lexer, and the shortcut doesn't count
+    // the tokens or the items
+    let limited = options.max_tokens != usize::MAX || options.max_items != usize::MAX;
+    if input.len() > lexer::MAX_INPUT_LEN || limited {
+        return parse_slow(input, options);
+    }
+    match fast::parse_int_list(input) {
+        Some(numbers) => Ok(numbers),
+        None => parse_slow(input, options),
+    }
+}
+
+/// [`parse_with`] without the shortcut for plain lists of integers.
+pub(crate) fn parse_slow(input: &str, options: ParserOptions) -> Result<Vec<i64>, Seq2Error> {
+    let mut parser = Parser::from_source_with_options(input, options)?;
+    let nodes = parser.parse()?;
+    let options = EvalOptions {
+        max_steps: options.max_steps,
+        ..Default::default()
+    };
+    Ok(Evaluator::with_options(parser.source().clone(), options).eval(&nodes)?)
+}
+
+/// Lexes, parses and evaluates `input`, writing the numbers to `out` as they are generated,
+/// with `sep` between them. Returns how many numbers were written.
+///
+/// The numbers are never collected, so even huge ranges only take as much memory as the
+/// buffer in front of `out`, which is flushed whenever it fills up and once at the end.
+/// The numbers written before an error stay written.
+///
+/// ```
+/// let mut out = vec![];
+/// assert_eq!(seq2::write_to("{1..=3}, (2 * 4)", &mut out, b"\n").unwrap(), 4);
+/// assert_eq!(out, b"1\n2\n3\n8");
+/// ```
+#[cfg(feature = "std")]
+pub fn write_to(input: &str, out: &mut impl Write, sep: &[u8]) -> Result<u64, Seq2Error> {
+    let mut parser = Parser::from_source(input)?;
+    let nodes = parser.parse()?;
+    let evaluator = Evaluator::new(parser.source().clone());
+
+    let mut out = BufWriter::new(out);
+    let mut count = 0;
+    for number in evaluator.iter(&nodes) {
+        let number = number?;
+        if count > 0 {
+            out.write_all(sep)?;
+        }
+        write!(out, "{number}")?;
+        count += 1;
+    }
+    out.flush()?;
+    Ok(count)
+}
 
 #[cfg(test)]
 mod tests;
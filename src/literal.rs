@@ -0,0 +1,170 @@
+//! Rendering of numbers as a list literal of a programming language, eg. to paste a sequence
+//! into a test fixture.
+//!
+//! ```
+//! use seq2::literal::{to_literal, Language, LiteralOptions};
+//!
+//! let numbers = seq2::parse("{1..8, s:2}").unwrap();
+//! assert_eq!(to_literal(&numbers, &LiteralOptions::default()), "vec![1, 3, 5, 7]");
+//!
+//! let python = LiteralOptions {
+//!     language: Language::Python,
+//!     ..Default::default()
+//! };
+//! assert_eq!(to_literal(&numbers, &python), "[1, 3, 5, 7]");
+//! ```
+//!
+//! A literal too wide for [`LiteralOptions::max_width`] is broken into indented lines, with
+//! as many numbers on each line as fit and a trailing separator where the language allows it:
+//!
+//! ```
+//! use seq2::literal::{to_literal, LiteralOptions};
+//!
+//! let options = LiteralOptions {
+//!     max_width: Some(16),
+//!     ..Default::default()
+//! };
+//! let numbers = seq2::parse("{1..=6, m:* 10}").unwrap();
+//! assert_eq!(
+//!     to_literal(&numbers, &options),
+//!     "vec![\n    10, 20, 30,\n    40, 50, 60,\n]"
+//! );
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Default of [`LiteralOptions::max_width`], the line width rustfmt goes with.
+pub const MAX_WIDTH: usize = 100;
+
+/// Indentation of the lines of a wrapped literal.
+const INDENT: &str = "    ";
+
+/// The language of a literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    /// `vec![1, 2, 3]`, or an array `[1, 2, 3]`, see [`LiteralOptions::rust_vec`]
+    #[default]
+    Rust,
+    /// A list `[1, 2, 3]`
+    Python,
+    /// An array `[1,2,3]`, like `seq2 --json` prints it, never wrapped
+    Json,
+    /// Comma separated numbers `1,2,3`, like `seq2 --csv` prints them, never wrapped
+    Csv,
+}
+
+/// How a literal is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiteralOptions {
+    pub language: Language,
+    /// For Rust, a `vec![..]` rather than an array `[..]`.
+    pub rust_vec: bool,
+    /// Number of columns the lines of the literal fit in, a line still gets a number when
+    /// the number alone doesn't fit. `None` keeps the literal on a single line.
+    pub max_width: Option<usize>,
+}
+
+impl Default for LiteralOptions {
+    fn default() -> Self {
+        Self {
+            language: Language::Rust,
+            rust_vec: true,
+            max_width: Some(MAX_WIDTH),
+        }
+    }
+}
+
+/// The punctuation of a literal.
+struct Syntax {
+    open: &'static str,
+    close: &'static str,
+    separator: &'static str,
+    /// Whether the last item of a wrapped literal can be followed by a separator.
+    trailing_separator: bool,
+    wraps: bool,
+}
+
+impl LiteralOptions {
+    fn syntax(&self) -> Syntax {
+        let (open, close, separator) = match (self.language, self.rust_vec) {
+            (Language::Rust, true) => ("vec![", "]", ", "),
+            (Language::Rust, false) | (Language::Python, _) => ("[", "]", ", "),
+            (Language::Json, _) => ("[", "]", ","),
+            (Language::Csv, _) => ("", "", ","),
+        };
+        Syntax {
+            open,
+            close,
+            separator,
+            trailing_separator: matches!(self.language, Language::Rust | Language::Python),
+            wraps: matches!(self.language, Language::Rust | Language::Python),
+        }
+    }
+}
+
+impl Language {
+    /// The text of `number` in the language. Integers are written the same in all of them,
+    /// values that need escaping would be escaped here.
+    fn item(self, number: i64) -> String {
+        number.to_string()
+    }
+}
+
+/// Renders `numbers` as a literal, see the [module docs](self).
+pub fn to_literal(numbers: &[i64], options: &LiteralOptions) -> String {
+    let items = numbers
+        .iter()
+        .map(|&number| options.language.item(number))
+        .collect::<Vec<_>>();
+    let syntax = options.syntax();
+    wrap(&items, &syntax, options.max_width.filter(|_| syntax.wraps))
+}
+
+/// Puts `items` between the brackets of `syntax`, on a single line if it fits in `max_width`
+/// columns, otherwise with a line for the opening bracket, as many items as fit on each of
+/// the following lines, and a line for the closing bracket.
+fn wrap(items: &[String], syntax: &Syntax, max_width: Option<usize>) -> String {
+    let one_line = items.join(syntax.separator);
+    let width = syntax.open.len() + one_line.len() + syntax.close.len();
+    let max_width = match max_width {
+        Some(max_width) if width > max_width && !items.is_empty() => max_width,
+        _ => return [syntax.open, &one_line, syntax.close].concat(),
+    };
+
+    // a line ends with the separator, trimmed of its space
+    let line_end = syntax.separator.trim_end();
+    let mut literal = String::from(syntax.open);
+    let mut line = String::new();
+    for (idx, item) in items.iter().enumerate() {
+        let last = idx + 1 == items.len();
+        let end = match last && !syntax.trailing_separator {
+            true => "",
+            false => line_end,
+        };
+        let fits = INDENT.len() + line.len() + syntax.separator.len() + item.len() + end.len()
+            <= max_width;
+        if line.is_empty() || !fits {
+            if !line.is_empty() {
+                literal.push_str(line_end);
+            }
+            literal.push('\n');
+            literal.push_str(INDENT);
+            line.clear();
+        } else {
+            line.push_str(syntax.separator);
+            literal.push_str(syntax.separator);
+        }
+        line.push_str(item);
+        literal.push_str(item);
+    }
+    literal.push_str(match syntax.trailing_separator {
+        true => line_end,
+        false => "",
+    });
+    literal.push('\n');
+    literal.push_str(syntax.close);
+    literal
+}
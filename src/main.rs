@@ -1,3 +1,801 @@
-fn main() {
-    println!("Hello, world!");
+use std::{
+    fs,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+    sync::Arc,
+};
+
+use clap::{error::ErrorKind, ArgGroup, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use seq2::{
+    document,
+    errors::{self, ErrorContext, EvalError, Seq2Error},
+    eval::{Cardinality, EvalOptions, Evaluator, Explanation, MAX_STEPS},
+    lexer::Lexer,
+    literal::{self, Language, LiteralOptions},
+    parser::{Node, Parser as Seq2Parser, ParserOptions},
+    tokens::Span,
+    validate,
+};
+
+/// Parses a string of comma separated numbers, ranges and math expressions
+/// and prints the numbers it evaluates to.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
+#[command(group(ArgGroup::new("layout").args(["delimiter", "one_per_line", "json", "csv"])))]
+#[command(group(ArgGroup::new("style").args(["equal_width", "number_format"]).conflicts_with("json")))]
+#[command(group(
+    ArgGroup::new("summary")
+        .args(["sum", "count", "min", "max", "product"])
+        .multiple(true)
+        .conflicts_with_all(["layout", "style"])
+))]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The expression to evaluate, multiple arguments are joined with commas
+    /// (eg. `seq2 "{1..=10, s:2}" "(3 * 4)"`). Put expressions starting
+    /// with a '-' after a `--`, eg. `seq2 -- -1`. A single `-` reads the expression from stdin
+    #[arg(
+        required_unless_present_any = ["file", "compat", "get"],
+        conflicts_with_all = ["file", "compat", "get"]
+    )]
+    expr: Vec<String>,
+
+    /// Read the expression from a file
+    #[arg(short, long, value_name = "PATH")]
+    file: Option<PathBuf>,
+
+    /// Read the file as a document of `name = <EXPR>` lines and evaluate the sequence NAME,
+    /// eg. `seq2 --file specs.conf --get ports`
+    #[arg(long, value_name = "NAME", requires = "file", conflicts_with = "check")]
+    get: Option<String>,
+
+    /// Generate the numbers like GNU seq does instead of evaluating an expression,
+    /// from `[FIRST [INCR]] LAST`, eg. `seq2 --compat 1 2 10`
+    #[arg(
+        long,
+        num_args = 1..=3,
+        value_name = "NUM",
+        allow_negative_numbers = true,
+        conflicts_with_all = ["file", "check"]
+    )]
+    compat: Option<Vec<i64>>,
+
+    /// Separator printed between the numbers [default: a space, a newline with --compat]
+    #[arg(short, long, value_name = "SEP")]
+    delimiter: Option<String>,
+
+    /// Print every number on its own line
+    #[arg(short = '1', long)]
+    one_per_line: bool,
+
+    /// Print the numbers as a JSON array
+    #[arg(long)]
+    json: bool,
+
+    /// Print the numbers on a single line, separated by commas without spaces
+    #[arg(long)]
+    csv: bool,
+
+    /// Pad the numbers with leading zeros to the width of the widest one.
+    /// All the numbers are generated before the first one is printed
+    #[arg(short = 'w', long)]
+    equal_width: bool,
+
+    /// Print every number with a printf-style format, eg. `img_%03d.png`.
+    /// Supports %d, %x, %o and %b with an optional width, padded with spaces or with zeros (`%04x`).
+    /// `rust` and `python` print the numbers as a list literal of the language instead,
+    /// `json` and `csv` are the same as --json and --csv
+    #[arg(long = "format", value_name = "FMT")]
+    number_format: Option<FormatArg>,
+
+    /// With --format rust or python, wrap the literal to fit in COLS columns,
+    /// 0 keeps it on a single line
+    #[arg(long, value_name = "COLS", default_value_t = literal::MAX_WIDTH)]
+    line_width: usize,
+
+    /// Print the sum of the numbers instead of the numbers
+    #[arg(long, help_heading = "Summary")]
+    sum: bool,
+
+    /// Print how many numbers there are instead of the numbers
+    #[arg(long, help_heading = "Summary")]
+    count: bool,
+
+    /// Print the smallest number instead of the numbers
+    #[arg(long, help_heading = "Summary")]
+    min: bool,
+
+    /// Print the largest number instead of the numbers
+    #[arg(long, help_heading = "Summary")]
+    max: bool,
+
+    /// Print the product of the numbers instead of the numbers
+    #[arg(long, help_heading = "Summary")]
+    product: bool,
+
+    /// Give up once the evaluation, or the one of an aggregate, takes more than N steps,
+    /// counting every number a range goes through and every math operation
+    #[arg(long, value_name = "N", default_value_t = MAX_STEPS)]
+    max_steps: u64,
+
+    /// Only check that the expression is valid, without evaluating it
+    #[arg(long, conflicts_with_all = ["layout", "style", "summary"])]
+    check: bool,
+
+    /// With --check, print the syntax tree of the expression
+    #[arg(short, long, requires = "check")]
+    verbose: bool,
+
+    /// Print the tokens of the expression, with their span, kind and text, instead of
+    /// evaluating it. With --json (needs the `serde` feature), print them as a JSON array
+    #[arg(
+        long,
+        conflicts_with_all = ["delimiter", "one_per_line", "csv", "style", "summary", "check", "compat", "get"]
+    )]
+    dump_tokens: bool,
+
+    /// Print how the Nth number of the sequence (counting from 1) was worked out instead of
+    /// the numbers, eg. `seq2 --explain 2 "{1..=5, s:2, m:*3}"`
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["layout", "style", "summary", "check", "dump_tokens"]
+    )]
+    explain: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the completion script for a shell
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// How the numbers are laid out on stdout.
+struct Format<'a> {
+    prefix: &'a str,
+    separator: &'a str,
+    suffix: &'a str,
+    number: NumberFormat,
+    equal_width: bool,
+}
+
+/// A `--format`: the language of a literal, or a printf-style format for every number.
+#[derive(Debug, Clone)]
+enum FormatArg {
+    Literal(Language),
+    Number(NumberFormat),
+}
+
+impl FromStr for FormatArg {
+    type Err = String;
+
+    fn from_str(fmt: &str) -> Result<Self, Self::Err> {
+        match fmt {
+            "rust" => Ok(FormatArg::Literal(Language::Rust)),
+            "python" => Ok(FormatArg::Literal(Language::Python)),
+            "json" => Ok(FormatArg::Literal(Language::Json)),
+            "csv" => Ok(FormatArg::Literal(Language::Csv)),
+            _ => fmt.parse().map(FormatArg::Number),
+        }
+    }
+}
+
+/// A `--format` string: text around a single `%d`, `%x`, `%o` or `%b`,
+/// with an optional width and `0` flag, eg. `img_%03d.png`.
+#[derive(Debug, Clone, Default)]
+struct NumberFormat {
+    prefix: String,
+    suffix: String,
+    zero_pad: bool,
+    width: usize,
+    radix: Radix,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+enum Radix {
+    #[default]
+    Dec,
+    Hex,
+    Oct,
+    Bin,
+}
+
+impl FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(fmt: &str) -> Result<Self, Self::Err> {
+        let mut format = NumberFormat::default();
+        let mut conversion = None;
+        let mut chars = fmt.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            let text = match conversion {
+                Some(_) => &mut format.suffix,
+                None => &mut format.prefix,
+            };
+            if ch != '%' {
+                text.push(ch);
+                continue;
+            }
+            if chars.next_if_eq(&'%').is_some() {
+                text.push('%');
+                continue;
+            }
+            if conversion.is_some() {
+                return Err("only a single '%' conversion is allowed".into());
+            }
+
+            format.zero_pad = chars.next_if_eq(&'0').is_some();
+            let mut width = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                width.push(digit);
+            }
+            format.width = match width.is_empty() {
+                true => 0,
+                false => width
+                    .parse()
+                    .map_err(|_| format!("width '{width}' is too large"))?,
+            };
+            format.radix = match chars.next() {
+                Some('d') => Radix::Dec,
+                Some('x') => Radix::Hex,
+                Some('o') => Radix::Oct,
+                Some('b') => Radix::Bin,
+                Some(ch) => {
+                    return Err(format!(
+                        "unknown conversion '%{ch}', expected one of %d, %x, %o or %b"
+                    ))
+                }
+                None => return Err("the format ends with an incomplete '%' conversion".into()),
+            };
+            conversion = Some(());
+        }
+
+        match conversion {
+            Some(()) => Ok(format),
+            None => Err("the format needs a '%d', '%x', '%o' or '%b' conversion".into()),
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Writes `number` in the format, negative numbers are written as a '-'
+    /// followed by the digits of their magnitude, the zeros go between the two.
+    fn write(&self, out: &mut impl Write, number: i64) -> io::Result<()> {
+        let magnitude = number.unsigned_abs();
+        let digits = match self.radix {
+            Radix::Dec => magnitude.to_string(),
+            Radix::Hex => format!("{magnitude:x}"),
+            Radix::Oct => format!("{magnitude:o}"),
+            Radix::Bin => format!("{magnitude:b}"),
+        };
+        let sign = if number < 0 { "-" } else { "" };
+        let padding = self.width.saturating_sub(sign.len() + digits.len());
+
+        out.write_all(self.prefix.as_bytes())?;
+        match self.zero_pad {
+            true => write!(out, "{sign}{:0>padding$}{digits}", "")?,
+            false => write!(out, "{:padding$}{sign}{digits}", "")?,
+        }
+        out.write_all(self.suffix.as_bytes())
+    }
+}
+
+impl Cli {
+    /// The expression, from the arguments, stdin or a file.
+    fn input(&self) -> io::Result<String> {
+        let mut input = match (&self.file, self.expr.as_slice()) {
+            (Some(path), _) => fs::read_to_string(path).map_err(|e| {
+                io::Error::new(e.kind(), format!("cannot read '{}': {e}", path.display()))
+            })?,
+            (None, [arg]) if arg == "-" => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                input
+            }
+            (None, args) => return Ok(args.join(", ")),
+        };
+
+        // editors like to end files with a newline
+        if input.ends_with('\n') {
+            input.pop();
+            if input.ends_with('\r') {
+                input.pop();
+            }
+        }
+        Ok(input)
+    }
+
+    fn summary(&self) -> Summary {
+        Summary {
+            sum: self.sum.then_some(0),
+            count: self.count.then_some(0),
+            min: self.min.then_some(None),
+            max: self.max.then_some(None),
+            product: self.product.then_some(1),
+        }
+    }
+
+    /// The language of `--format`, if it is one.
+    fn language(&self) -> Option<Language> {
+        match self.number_format {
+            Some(FormatArg::Literal(language)) => Some(language),
+            _ => None,
+        }
+    }
+
+    /// The options of a `--format rust` or `--format python` literal.
+    fn literal(&self) -> Option<LiteralOptions> {
+        let language = self.language()?;
+        matches!(language, Language::Rust | Language::Python).then(|| LiteralOptions {
+            language,
+            rust_vec: true,
+            max_width: Some(self.line_width).filter(|&width| width > 0),
+        })
+    }
+
+    fn format(&self) -> Format<'_> {
+        let json = self.json || self.language() == Some(Language::Json);
+        let csv = self.csv || self.language() == Some(Language::Csv);
+        let (prefix, separator, suffix) = match (self.one_per_line, json, csv) {
+            (true, _, _) => ("", "\n", "\n"),
+            (_, true, _) => ("[", ",", "]\n"),
+            (_, _, true) => ("", ",", "\n"),
+            _ => match (&self.delimiter, &self.compat) {
+                (Some(delimiter), _) => ("", delimiter.as_str(), "\n"),
+                // one per line, like GNU seq
+                (None, Some(_)) => ("", "\n", "\n"),
+                (None, None) => ("", " ", "\n"),
+            },
+        };
+        Format {
+            prefix,
+            separator,
+            suffix,
+            number: match &self.number_format {
+                Some(FormatArg::Number(format)) => format.clone(),
+                _ => NumberFormat::default(),
+            },
+            equal_width: self.equal_width,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Some(Command::Completions { shell }) = cli.command {
+        clap_complete::generate(shell, &mut Cli::command(), "seq2", &mut io::stdout());
+        return ExitCode::SUCCESS;
+    }
+
+    // a language is a layout of its own, which clap doesn't know when it parses `--format`
+    if cli.language().is_some() && (cli.delimiter.is_some() || cli.one_per_line || cli.csv) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "a language '--format' cannot be used with '--delimiter', '--one-per-line' or '--csv'",
+            )
+            .exit();
+    }
+
+    let output = match (
+        cli.sum || cli.count || cli.min || cli.max || cli.product,
+        cli.literal(),
+        cli.explain,
+    ) {
+        (_, _, Some(number)) => Output::Explain(number),
+        (true, _, _) => Output::Summary(cli.summary()),
+        (false, Some(options), _) => Output::Literal(options),
+        (false, None, _) => Output::Numbers(cli.format()),
+    };
+
+    if let Some(args) = &cli.compat {
+        let result = compat(args)
+            .and_then(|(source, nodes)| run(source, &nodes, output, cli.max_steps))
+            .map_err(compat_error);
+        return exit_code(result);
+    }
+
+    let input = match cli.input() {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("seq2: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if cli.check {
+        return check(&input, cli.verbose, cli.max_steps);
+    }
+
+    if cli.dump_tokens {
+        return dump_tokens(&input, cli.json);
+    }
+
+    // clap makes sure there is a file along with `--get`
+    if let (Some(name), Some(path)) = (&cli.get, &cli.file) {
+        let sequences = match document::parse(&input) {
+            Ok(sequences) => sequences,
+            Err(e) => {
+                eprint!("{e}");
+                return ExitCode::from(1);
+            }
+        };
+        let Some(seq2) = sequences.get(name) else {
+            eprintln!("seq2: no sequence named '{name}' in '{}'", path.display());
+            return ExitCode::FAILURE;
+        };
+        return exit_code(run(seq2.source.clone(), &seq2.nodes, output, cli.max_steps));
+    }
+
+    let result = parse(&input, cli.max_steps)
+        .and_then(|(source, nodes)| run(source, &nodes, output, cli.max_steps));
+    exit_code(result)
+}
+
+/// Prints the error, if there is one, and picks the exit code for it.
+fn exit_code(result: Result<(), Seq2Error>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // eg. the other end of a pipe went away
+        Err(Seq2Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(Seq2Error::Io(e)) => {
+            eprintln!("seq2: {e}");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprint!("{e}");
+            match e {
+                Seq2Error::Eval(_) => ExitCode::from(2),
+                _ => ExitCode::from(1),
+            }
+        }
+    }
+}
+
+/// What is written to stdout.
+enum Output<'a> {
+    Numbers(Format<'a>),
+    /// A list literal of a language, printed once all the numbers are there.
+    Literal(LiteralOptions),
+    Summary(Summary),
+    /// How the Nth number (counting from 1) was worked out, without generating the others.
+    Explain(u64),
+}
+
+/// Running totals of the requested summary values, `None` when not requested.
+struct Summary {
+    sum: Option<i64>,
+    count: Option<i64>,
+    min: Option<Option<i64>>,
+    max: Option<Option<i64>>,
+    product: Option<i64>,
+}
+
+impl Summary {
+    /// Adds `number` to the totals, returns `None` if the sum or product overflows.
+    fn add(&mut self, number: i64) -> Option<()> {
+        if let Some(sum) = &mut self.sum {
+            *sum = sum.checked_add(number)?;
+        }
+        if let Some(count) = &mut self.count {
+            *count += 1;
+        }
+        if let Some(min) = &mut self.min {
+            *min = Some(min.map_or(number, |min| min.min(number)));
+        }
+        if let Some(max) = &mut self.max {
+            *max = Some(max.map_or(number, |max| max.max(number)));
+        }
+        if let Some(product) = &mut self.product {
+            *product = product.checked_mul(number)?;
+        }
+        Some(())
+    }
+
+    /// Values with their labels, in the order the flags are documented.
+    /// The min and max of an empty sequence are printed as an empty string.
+    fn values(&self) -> Vec<(&'static str, String)> {
+        let show = |n: &Option<i64>| n.map(|n| n.to_string()).unwrap_or_default();
+        [
+            ("sum", self.sum.as_ref().map(|n| n.to_string())),
+            ("count", self.count.as_ref().map(|n| n.to_string())),
+            ("min", self.min.as_ref().map(show)),
+            ("max", self.max.as_ref().map(show)),
+            ("product", self.product.as_ref().map(|n| n.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| Some((label, value?)))
+        .collect()
+    }
+}
+
+/// Lexes and parses `input`, returning the source the spans of the nodes point into.
+fn parse(input: &str, max_steps: u64) -> Result<(Arc<str>, Vec<Node>), Seq2Error> {
+    let options = ParserOptions {
+        max_steps,
+        ..Default::default()
+    };
+    let mut parser = Seq2Parser::from_source_with_options(input, options)?;
+    let nodes = parser.parse()?;
+    Ok((parser.source().clone(), nodes))
+}
+
+/// Builds the range GNU seq would generate for `[FIRST [INCR]] LAST`, along with
+/// the equivalent seq2 expression (eg. `{1..=10, s:2}`) for the spans to point into.
+///
+/// Like GNU seq, nothing is generated when `INCR` goes away from `LAST`, and an `INCR` of
+/// zero is refused.
+fn compat(args: &[i64]) -> Result<(Arc<str>, Vec<Node>), Seq2Error> {
+    let (first, incr, last) = match *args {
+        [last] => (1, 1, last),
+        [first, last] => (first, 1, last),
+        [first, incr, last] => (first, incr, last),
+        _ => unreachable!("clap allows 1 to 3 arguments"),
+    };
+    if incr == 0 {
+        let message = "invalid zero INCR value: '0'";
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message).into());
+    }
+    let source: Arc<str> = format!("{{{first}..={last}, s:{incr}}}").into();
+
+    if (incr > 0 && first > last) || (incr < 0 && first < last) {
+        return Ok((source, vec![]));
+    }
+
+    let int = |value: i64, start: usize| Node::Int {
+        span: Span::new(start, start + value.to_string().len()),
+        value,
+    };
+    let start = int(first, 1);
+    let end = int(last, start.span().end as usize + 3);
+    let step = int(incr, end.span().end as usize + 4);
+    let range = Node::RangeExpr {
+        span: Span::new(0, source.len()),
+        start: Box::new(start),
+        end: Box::new(end),
+        inclusive: true,
+        step: Some(Box::new(step)),
+        mutation: None,
+        low: None,
+        high: None,
+        unique: false,
+        reverse: false,
+        order: None,
+    };
+    Ok((source, vec![range]))
+}
+
+/// Words an error of `--compat` in terms of its arguments, as the expression the spans point
+/// into was never typed.
+fn compat_error(e: Seq2Error) -> Seq2Error {
+    match e {
+        Seq2Error::Eval(EvalError::BudgetExceeded(ctx)) => {
+            let message = format!(
+                "gave up after {} steps generating the numbers from FIRST to LAST, allow more with '--max-steps'",
+                ctx.extra
+            );
+            io::Error::other(message).into()
+        }
+        e => e,
+    }
+}
+
+/// Evaluates `nodes`, writing the numbers or their summary to stdout as they are generated.
+fn run(source: Arc<str>, nodes: &[Node], output: Output, max_steps: u64) -> Result<(), Seq2Error> {
+    let options = EvalOptions {
+        max_steps,
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(source.clone(), options);
+    let mut numbers: Box<dyn Iterator<Item = Result<i64, EvalError>>> =
+        Box::new(evaluator.iter(nodes));
+    let mut out = BufWriter::new(io::stdout().lock());
+
+    match output {
+        Output::Numbers(mut format) => {
+            if format.equal_width {
+                // the width is only known once all the numbers are there
+                let all = numbers.collect::<Result<Vec<_>, _>>()?;
+                format.number.zero_pad = true;
+                format.number.width = all.iter().map(|n| n.to_string().len()).max().unwrap_or(0);
+                numbers = Box::new(all.into_iter().map(Ok));
+            }
+
+            out.write_all(format.prefix.as_bytes())?;
+            let mut empty = true;
+            for number in numbers {
+                let number = number?;
+                if !empty {
+                    out.write_all(format.separator.as_bytes())?;
+                }
+                format.number.write(&mut out, number)?;
+                empty = false;
+            }
+            // an empty sequence prints nothing at all, unless it has brackets around it
+            if !empty || !format.prefix.is_empty() {
+                out.write_all(format.suffix.as_bytes())?;
+            }
+        }
+        Output::Literal(options) => {
+            let all = numbers.collect::<Result<Vec<_>, _>>()?;
+            writeln!(out, "{}", literal::to_literal(&all, &options))?;
+        }
+        Output::Summary(mut summary) => {
+            for number in numbers {
+                if summary.add(number?).is_none() {
+                    // the overflow comes from the whole sequence, not a single value
+                    let span = Span::new(0, source.len());
+                    let ctx = ErrorContext::with(source, span, None);
+                    return Err(EvalError::Overflow(ctx).into());
+                }
+            }
+            match summary.values().as_slice() {
+                [(_, value)] => writeln!(out, "{value}")?,
+                values => {
+                    for (label, value) in values {
+                        writeln!(out, "{label}: {value}")?;
+                    }
+                }
+            }
+        }
+        Output::Explain(number) => match evaluator.explain(nodes, u128::from(number - 1))? {
+            Some(explanation) => {
+                out.write_all(explain(&source, number, &explanation).as_bytes())?
+            }
+            None => {
+                let len = match evaluator.len(nodes)? {
+                    Cardinality::Exact(len) => format!("{len}"),
+                    Cardinality::AtMost(len) => format!("at most {len}"),
+                };
+                let message = format!("there is no number {number}, the sequence has {len}");
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message).into());
+            }
+        },
+    }
+    Ok(out.flush()?)
+}
+
+/// Describes how the `number`th number of the sequence was worked out, eg.
+///
+/// ```text
+/// number 2 = 9
+///   item 1 @ position 1-18: {1..=5, s:2, m:*3}
+///   number 2 of the range: 3
+///   mutation *3: 3 -> 9
+/// ```
+fn explain(source: &str, number: u64, explanation: &Explanation) -> String {
+    let text = |span: Span| span.text(source).unwrap_or_default();
+    let position = |span: Span| {
+        let range = span.to_char_range(source);
+        match range.len() {
+            0 | 1 => format!("{}", range.start + 1),
+            _ => format!("{}-{}", range.start + 1, range.end),
+        }
+    };
+
+    let Explanation {
+        value,
+        item,
+        span,
+        range,
+    } = explanation;
+    let mut lines = vec![
+        format!("number {number} = {value}"),
+        format!(
+            "  item {} @ position {}: {}",
+            item + 1,
+            position(*span),
+            text(*span)
+        ),
+    ];
+    if let Some(range) = range {
+        lines.push(format!(
+            "  number {} of the range: {}",
+            range.index + 1,
+            range.base
+        ));
+        let mut last = range.base;
+        if let Some((span, mutated)) = range.mutation {
+            lines.push(format!("  mutation {}: {last} -> {mutated}", text(span)));
+            last = mutated;
+        }
+        if last != *value {
+            lines.push(format!("  clamp: {last} -> {value}"));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Lexes `input`, printing its tokens as they are dumped by the library.
+fn dump_tokens(input: &str, json: bool) -> ExitCode {
+    let mut lexer = Lexer::new(input);
+    let tokens = match lexer.lex() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            eprint!("{e}");
+            return ExitCode::from(1);
+        }
+    };
+    let dump = match json {
+        #[cfg(feature = "serde")]
+        true => lexer.dump_json(&tokens) + "\n",
+        #[cfg(not(feature = "serde"))]
+        true => {
+            eprintln!("seq2: --dump-tokens --json needs seq2 built with the `serde` feature");
+            return ExitCode::FAILURE;
+        }
+        false => lexer.dump(&tokens),
+    };
+    if let Err(e) = io::stdout().lock().write_all(dump.as_bytes()) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("seq2: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Lexes and parses `input`, printing every syntax error, or the warnings and, when `verbose`,
+/// the syntax tree.
+fn check(input: &str, verbose: bool, max_steps: u64) -> ExitCode {
+    let mut lexer = Lexer::new(input);
+    let tokens = match lexer.lex() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            eprint!("{e}");
+            return ExitCode::from(1);
+        }
+    };
+    let options = ParserOptions {
+        max_steps,
+        ..Default::default()
+    };
+    let nodes = match tokens.is_empty() {
+        true => vec![],
+        false => match Seq2Parser::with_options(lexer.source.clone(), &tokens, options)
+            .parse_all_errors()
+        {
+            Ok(nodes) => nodes,
+            Err(errors) => {
+                eprint!("{}", errors::render_all(&errors));
+                return ExitCode::from(1);
+            }
+        },
+    };
+
+    let warnings = validate::warnings(input, &nodes)
+        .iter()
+        .map(|warning| warning.render(input))
+        .collect::<Vec<_>>();
+    if !warnings.is_empty() {
+        eprint!("{}", warnings.join("\n"));
+    }
+
+    if verbose {
+        let tree = nodes
+            .iter()
+            .map(|node| node.tree_string(input))
+            .collect::<String>();
+        if let Err(e) = io::stdout().lock().write_all(tree.as_bytes()) {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                eprintln!("seq2: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
 }
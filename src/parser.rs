@@ -1,8 +1,22 @@
-use std::{iter::Peekable, slice::Iter};
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::fmt;
 
+#[cfg(feature = "aggregate")]
+use crate::{errors::EvalError, eval::EvalOptions, tokens::Aggregate};
 use crate::{
-    errors::ParserError,
-    tokens::{Op, Span, Token, TokenKind},
+    errors::{ErrorContext, LexicalError, ParserError},
+    eval::{Evaluator, MAX_STEPS},
+    invariants,
+    lexer::Lexer,
+    tokens::{Func, Op, SortOrder, Span, Token, TokenKind},
 };
 
 /// NOICE!
@@ -23,7 +37,38 @@ use crate::{
 /// ⣿⡿⠃⠀⠐⠶⣿⡿⢻⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣞⢻⣿⣿⣿⣿⡇
 pub const MAX_PAREN_DEPTH: usize = 69;
 
+/// Limits on the size of the input the parser takes on, see [`Parser::with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// How deep parenthesis can be nested, function calls included, before giving up
+    /// with [`ParserError::TooManyParen`].
+    pub max_paren_depth: usize,
+    /// Max number of tokens, whitespace and comments left out, before giving up with
+    /// [`ParserError::TooManyTokens`].
+    pub max_tokens: usize,
+    /// Max number of top-level items, ie. the comma separated ones, before giving up
+    /// with [`ParserError::TooManyItems`]. Zipped items count as a single one.
+    pub max_items: usize,
+    /// Max number of steps the evaluation of each aggregate takes before giving up with
+    /// [`ParserError::InvalidAggregate`], see
+    /// [`EvalOptions::max_steps`](crate::eval::EvalOptions::max_steps).
+    pub max_steps: u64,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_paren_depth: MAX_PAREN_DEPTH,
+            max_tokens: usize::MAX,
+            max_items: usize::MAX,
+            max_steps: MAX_STEPS,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
 pub enum Node {
     Int {
         span: Span,
@@ -38,114 +83,855 @@ pub enum Node {
         span: Span,
         start: Box<Node>,
         end: Box<Node>,
+        inclusive: bool,
         step: Option<Box<Node>>,
         mutation: Option<Box<Node>>,
+        low: Option<Box<Node>>,
+        high: Option<Box<Node>>,
+        unique: bool,
+        reverse: bool,
+        order: Option<SortOrder>,
+    },
+    /// Items joined by `~`, their numbers interleaved, eg. `{1..=3} ~ {10..=12}`.
+    Zip {
+        span: Span,
+        left: Box<Node>,
+        right: Box<Node>,
     },
+    /// An item followed by `x` and how many times its numbers are repeated, eg. `{1..=3} x 2`.
+    Repeat {
+        span: Span,
+        item: Box<Node>,
+        count: u64,
+    },
+}
+
+impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Int { span, .. }
+            | Node::MathExpr { span, .. }
+            | Node::RangeExpr { span, .. }
+            | Node::Zip { span, .. }
+            | Node::Repeat { span, .. } => *span,
+        }
+    }
+
+    /// Text of `source` the node covers, see [`Span::text`].
+    pub fn text<'s>(&self, source: &'s str) -> Option<&'s str> {
+        self.span().text(source)
+    }
+
+    /// The nodes directly inside this one, ie. the start, end, step, mutation, `lo:` and `hi:`
+    /// of a range, in that order, the two sides of a zip or the repeated item. Numbers and math
+    /// expressions have none.
+    pub fn children(&self) -> impl Iterator<Item = &Node> {
+        let (start, end, step, mutation, low, high) = match self {
+            Node::RangeExpr {
+                start,
+                end,
+                step,
+                mutation,
+                low,
+                high,
+                ..
+            } => (
+                Some(start),
+                Some(end),
+                step.as_ref(),
+                mutation.as_ref(),
+                low.as_ref(),
+                high.as_ref(),
+            ),
+            Node::Zip { left, right, .. } => (Some(left), Some(right), None, None, None, None),
+            Node::Repeat { item, .. } => (Some(item), None, None, None, None, None),
+            Node::Int { .. } | Node::MathExpr { .. } => (None, None, None, None, None, None),
+        };
+        [start, end, step, mutation, low, high]
+            .into_iter()
+            .flatten()
+            .map(|node| &**node)
+    }
+
+    /// Renders the node and everything inside it as a tree, one line per node or argument,
+    /// eg. for `{1..=10 ^ 2, s:3}`
+    ///
+    /// ```text
+    /// RangeExpr [0..17]
+    /// ├─ start: Int(1) [1..2]
+    /// ├─ end: MathExpr(10 ^ 2 → 100) [5..11]
+    /// ├─ inclusive: true
+    /// └─ step: Int(3) [15..16]
+    /// ```
+    ///
+    /// `source` is the input the node was parsed from, math expressions are shown with their
+    /// text and, unless they mutate `@`, their value.
+    pub fn tree_string(&self, source: &str) -> String {
+        let mut tree = String::new();
+        let evaluator = Evaluator::new(source.into());
+        write_tree(&mut tree, self, source, &evaluator, "");
+        tree
+    }
+}
+
+/// Writes the node as source that parses back to the same node, spans aside, eg.
+/// `{(1 + 2)..=10, s:2, m:((@ * 3) + 1)}`. Math expressions are written out of their RPN,
+/// every operation in parenthesis.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Int { value, .. } => write!(f, "{value}"),
+            Node::MathExpr { negated, rpn, .. } => {
+                if *negated {
+                    f.write_str("-")?;
+                }
+                match rpn_to_infix(rpn) {
+                    (infix, Operand::Group) => f.write_str(&infix),
+                    (infix, _) => write!(f, "({infix})"),
+                }
+            }
+            Node::RangeExpr {
+                start,
+                end,
+                inclusive,
+                step,
+                mutation,
+                low,
+                high,
+                unique,
+                reverse,
+                order,
+                ..
+            } => {
+                let dots = if *inclusive { "..=" } else { ".." };
+                write!(f, "{{{start}{dots}{end}")?;
+                let args = [("s", step), ("m", mutation), ("lo", low), ("hi", high)];
+                for (name, arg) in args {
+                    if let Some(arg) = arg {
+                        write!(f, ", {name}:{arg}")?;
+                    }
+                }
+                if *unique {
+                    f.write_str(", u:")?;
+                }
+                if *reverse {
+                    f.write_str(", r:")?;
+                }
+                if let Some(order) = order {
+                    write!(f, ", o:{}", order.name())?;
+                }
+                f.write_str("}")
+            }
+            Node::Zip { left, right, .. } => write!(f, "{left} ~ {right}"),
+            Node::Repeat { item, count, .. } => write!(f, "{item} x {count}"),
+        }
+    }
+}
+
+/// What an operand written out of RPN looks like, to know when it needs parenthesis.
+#[derive(Clone, Copy, PartialEq)]
+enum Operand {
+    /// A number that isn't negative or its factorial, eg. `5!`, a `-` in front would be
+    /// taken as its sign
+    Number,
+    /// An operation in parenthesis, eg. `(1 + 2)`
+    Group,
+    /// A function call or `@`, eg. `max(1, 2)`
+    Atom,
+    /// A negative number or a prefix operation, eg. `-(5)`
+    Signed,
+}
+
+/// Writes the `rpn` of a math expression back in infix. Operations are put in parenthesis,
+/// and so are the operands a `-` in front of them would change, eg. `-(5)` instead of `-5`
+/// which is the number `-5`.
+fn rpn_to_infix(rpn: &[Token]) -> (String, Operand) {
+    let mut stack: Vec<(String, Operand)> = vec![];
+    // RPN of the parser always has the operands its operators need
+    let pop = |stack: &mut Vec<(String, Operand)>| {
+        stack
+            .pop()
+            .unwrap_or_else(|| (String::new(), Operand::Number))
+    };
+
+    for token in rpn {
+        let operand = match token.kind {
+            TokenKind::Int { value } if value < 0 => (value.to_string(), Operand::Signed),
+            TokenKind::Int { value } => (value.to_string(), Operand::Number),
+            TokenKind::RngMutArg => ("@".to_string(), Operand::Atom),
+            TokenKind::Math(op) if op.is_prefix() => match pop(&mut stack) {
+                (text, Operand::Group | Operand::Atom) => {
+                    (format!("{}{text}", op.symbol()), Operand::Signed)
+                }
+                (text, _) => (format!("{}({text})", op.symbol()), Operand::Signed),
+            },
+            TokenKind::Math(op) if op.is_postfix() => match pop(&mut stack) {
+                (text, Operand::Signed) => (format!("({text}){}", op.symbol()), Operand::Number),
+                (text, _) => (format!("{text}{}", op.symbol()), Operand::Number),
+            },
+            TokenKind::Math(op) => {
+                let rhs = parenthesize(pop(&mut stack));
+                let lhs = parenthesize(pop(&mut stack));
+                (format!("({lhs} {} {rhs})", op.symbol()), Operand::Group)
+            }
+            TokenKind::Func(func) => {
+                let mut args = (0..func.arity())
+                    .map(|_| pop(&mut stack).0)
+                    .collect::<Vec<_>>();
+                args.reverse();
+                (
+                    format!("{}({})", func.name(), args.join(", ")),
+                    Operand::Atom,
+                )
+            }
+            _ => continue,
+        };
+        stack.push(operand);
+    }
+    pop(&mut stack)
+}
+
+/// `-5` or `-(5)` as an operand of a binary operation, in parenthesis.
+fn parenthesize((text, operand): (String, Operand)) -> String {
+    match operand {
+        Operand::Signed => format!("({text})"),
+        _ => text,
+    }
+}
+
+/// Appends `node` to `tree`, with `indent` in front of the lines of its arguments.
+fn write_tree(tree: &mut String, node: &Node, source: &str, evaluator: &Evaluator, indent: &str) {
+    let mut args: Vec<(&str, Result<String, &Node>)> = vec![];
+
+    match node {
+        Node::Int { span, value } => tree.push_str(&format!("Int({value}) [{span}]\n")),
+        Node::MathExpr { span, rpn, .. } => {
+            let text = strip_parens(node.text(source).unwrap_or_default());
+            let value = match rpn.iter().any(|token| token.kind == TokenKind::RngMutArg) {
+                true => String::new(),
+                false => match evaluator.eval_scalar(node) {
+                    Ok(value) => format!(" → {value}"),
+                    Err(e) => format!(" → error {}", e.code()),
+                },
+            };
+            tree.push_str(&format!("MathExpr({text}{value}) [{span}]\n"));
+        }
+        Node::RangeExpr {
+            span,
+            start,
+            end,
+            inclusive,
+            step,
+            mutation,
+            low,
+            high,
+            unique,
+            reverse,
+            order,
+        } => {
+            tree.push_str(&format!("RangeExpr [{span}]\n"));
+            args.push(("start", Err(start)));
+            args.push(("end", Err(end)));
+            args.push(("inclusive", Ok(inclusive.to_string())));
+            if let Some(step) = step {
+                args.push(("step", Err(step)));
+            }
+            if let Some(mutation) = mutation {
+                args.push(("mutation", Err(mutation)));
+            }
+            if let Some(low) = low {
+                args.push(("low", Err(low)));
+            }
+            if let Some(high) = high {
+                args.push(("high", Err(high)));
+            }
+            if *unique {
+                args.push(("unique", Ok(unique.to_string())));
+            }
+            if *reverse {
+                args.push(("reverse", Ok(reverse.to_string())));
+            }
+            if let Some(order) = order {
+                args.push(("order", Ok(format!("{order:?}").to_lowercase())));
+            }
+        }
+        Node::Zip { span, left, right } => {
+            tree.push_str(&format!("Zip [{span}]\n"));
+            args.push(("left", Err(left)));
+            args.push(("right", Err(right)));
+        }
+        Node::Repeat { span, item, count } => {
+            tree.push_str(&format!("Repeat [{span}]\n"));
+            args.push(("item", Err(item)));
+            args.push(("count", Ok(count.to_string())));
+        }
+    }
+
+    let last = args.len().saturating_sub(1);
+    for (idx, (name, value)) in args.into_iter().enumerate() {
+        let (branch, child_indent) = match idx == last {
+            true => ("└─", "   "),
+            false => ("├─", "│  "),
+        };
+        tree.push_str(&format!("{indent}{branch} {name}: "));
+        match value {
+            Ok(value) => tree.push_str(&format!("{value}\n")),
+            Err(child) => {
+                let indent = format!("{indent}{child_indent}");
+                write_tree(tree, child, source, evaluator, &indent);
+            }
+        }
+    }
+}
+
+/// `text` without the parenthesis around all of it, eg. `1 + 2` for `(1 + 2)`
+/// but `(1) + (2)` as it is.
+fn strip_parens(text: &str) -> &str {
+    let Some(inner) = text
+        .strip_prefix('(')
+        .and_then(|text| text.strip_suffix(')'))
+    else {
+        return text;
+    };
+    let mut depth: usize = 0;
+    for ch in inner.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' if depth == 0 => return text,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    inner
 }
 
 #[derive(Debug)]
 pub struct Parser<'a> {
-    input_chars: Vec<char>,
-    tokens: Peekable<Iter<'a, Token>>,
-    position: usize,
+    source: Arc<str>,
+    all_tokens: Cow<'a, [Token]>, // without trivia
+    position: usize,              // index of the next token in `all_tokens`
     current_token: Token,
-    in_squiggly: bool,
-    in_paren: bool,
     paren_depth: usize,
+    options: ParserOptions,
+    in_mutation: bool,
+}
+
+impl Parser<'static> {
+    /// Lexes `source` into a parser owning the tokens, for when the tokens themselves aren't
+    /// needed. The source the spans point into is [`Parser::source`].
+    ///
+    /// ```
+    /// use seq2::{eval::Evaluator, parser::Parser};
+    ///
+    /// let mut parser = Parser::from_source("1, {2..=4}").unwrap();
+    /// let nodes = parser.parse().unwrap();
+    /// let numbers = Evaluator::new(parser.source().clone()).eval(&nodes).unwrap();
+    /// assert_eq!(numbers, [1, 2, 3, 4]);
+    /// ```
+    pub fn from_source(source: &str) -> Result<Self, LexicalError> {
+        Self::from_source_with_options(source, ParserOptions::default())
+    }
+
+    /// [`Parser::from_source`] with limits on the size of the input.
+    pub fn from_source_with_options(
+        source: &str,
+        options: ParserOptions,
+    ) -> Result<Self, LexicalError> {
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.lex()?;
+        Ok(Self::with_tokens(lexer.source, Cow::Owned(tokens), options))
+    }
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(input_chars: Vec<char>, tokens: &'a [Token]) -> Self {
+    /// Makes a parser of tokens lexed beforehand, see [`Parser::from_source`] to lex and parse
+    /// in one go. `source` is the input the spans of the tokens point into.
+    ///
+    /// Whitespace and comments (see [`Lexer::with_trivia`](crate::lexer::Lexer::with_trivia))
+    /// are dropped from `tokens`, they have no say in the parsing.
+    pub fn new(source: Arc<str>, tokens: &'a [Token]) -> Self {
+        Self::with_options(source, tokens, ParserOptions::default())
+    }
+
+    pub fn with_options(source: Arc<str>, tokens: &'a [Token], options: ParserOptions) -> Self {
+        Self::with_tokens(source, Cow::Borrowed(tokens), options)
+    }
+
+    fn with_tokens(source: Arc<str>, tokens: Cow<'a, [Token]>, options: ParserOptions) -> Self {
+        let tokens = match tokens.iter().any(|token| token.kind.is_trivia()) {
+            true => Cow::Owned(
+                tokens
+                    .iter()
+                    .filter(|token| !token.kind.is_trivia())
+                    .copied()
+                    .collect(),
+            ),
+            false => tokens,
+        };
+
         Self {
-            input_chars,
-            tokens: tokens.iter().peekable(),
+            source,
             position: 0,
-            current_token: tokens[0],
-            in_squiggly: false,
-            in_paren: false,
+            // without any tokens the parse loop ends before reading it
+            current_token: tokens
+                .first()
+                .copied()
+                .unwrap_or(Token::new(TokenKind::Comma, Span::new(0, 0))),
+            all_tokens: tokens,
             paren_depth: 0,
+            options,
+            in_mutation: false,
         }
     }
 
-    fn advance(&mut self) {
-        self.tokens.next();
-        self.position += 1;
+    /// The input the spans of the tokens and of the parsed nodes point into.
+    pub fn source(&self) -> &Arc<str> {
+        &self.source
     }
 
-    fn prev_token(&self) -> Option<&Token> {
-        self.tokens.clone().nth(self.position - 1)
+    /// Sets how deep parenthesis can be nested, [`MAX_PAREN_DEPTH`] by default.
+    pub fn with_max_paren_depth(mut self, max_paren_depth: usize) -> Self {
+        self.options.max_paren_depth = max_paren_depth;
+        self
     }
 
-    fn advance_past_comma(&mut self) -> Result<(), ParserError> {
-        let mut comma_count: u8 = 0;
+    pub(crate) fn advance(&mut self) {
+        if self.position < self.all_tokens.len() {
+            self.position += 1;
+        }
+    }
+
+    /// The token under the cursor.
+    fn peek(&self) -> Option<Token> {
+        self.peek_nth(0)
+    }
+
+    /// The last token the cursor moved past.
+    pub(crate) fn prev_token(&self) -> Option<Token> {
+        self.all_tokens.get(self.position.checked_sub(1)?).copied()
+    }
+
+    /// The token `n` places after the cursor, `peek_nth(0)` being the token under it.
+    pub(crate) fn peek_nth(&self, n: usize) -> Option<Token> {
+        self.all_tokens.get(self.position + n).copied()
+    }
+
+    /// The first token after the run of `+` and `-` signs under the cursor.
+    fn peek_past_signs(&self) -> Option<Token> {
+        (0..)
+            .map_while(|n| self.peek_nth(n))
+            .find(|token| !matches!(token.kind, TokenKind::Math(Op::Add | Op::Sub)))
+    }
 
+    /// Span of the operand starting under the cursor, up to the matching `)` or `}` when
+    /// it opens a group, calls a function or aggregates a range, eg. `abs(-2)`.
+    fn peek_operand_span(&self) -> Span {
+        let first = self.current_token.span;
+        let mut depth = 0;
+
+        for token in (0..).map_while(|n| self.peek_nth(n)) {
+            match token.kind {
+                TokenKind::LParen | TokenKind::LSquiggly => depth += 1,
+                TokenKind::RParen | TokenKind::RSquiggly if depth == 1 => {
+                    return first.merge(token.span)
+                }
+                TokenKind::RParen | TokenKind::RSquiggly => depth -= 1,
+                TokenKind::Func(_) | TokenKind::Aggregate(_) if depth == 0 => {}
+                _ if depth == 0 => return first,
+                _ => {}
+            }
+        }
+        // the group is never closed
+        first
+    }
+
+    /// The single char right after the last token the cursor moved past, where a
+    /// missing operand or operator should go.
+    fn gap_after_prev(&self) -> Span {
+        let end = self.consumed_end();
+        Span::new(end, end + 1)
+    }
+
+    /// End of the last token the cursor moved past.
+    fn consumed_end(&self) -> usize {
+        self.prev_token().map_or(0, |token| token.span.end as usize)
+    }
+
+    fn advance_past_comma(&mut self) -> Result<(), ParserError> {
         self.advance();
+        self.skip_comma()
+    }
 
-        while let Some(token) = self.tokens.peek() {
+    /// Moves past the comma separating two items, erroring if there is none.
+    /// A single trailing comma is allowed at the end of the input, eg. `1, 2, 3,`
+    /// The cursor is left on a `~` or an `x` right after the item instead.
+    fn skip_comma(&mut self) -> Result<(), ParserError> {
+        let mut comma_count: u8 = 0;
+
+        while let Some(token) = self.peek() {
             match token.kind {
                 TokenKind::Comma => {
-                    self.current_token = **token;
+                    self.current_token = token;
                     self.advance();
                     comma_count += 1;
 
                     if comma_count > 1 {
-                        return Err(ParserError::UnexpectedComma(
-                            self.input_chars.clone(),
+                        return Err(ParserError::UnexpectedComma(ErrorContext::new(
+                            self.source.clone(),
                             self.current_token.span,
-                        ));
+                        )));
                     }
                 }
                 _ => break,
             }
         }
 
-        self.current_token = match self.tokens.peek() {
-            Some(token) => **token,
+        self.current_token = match self.peek() {
+            Some(token) => token,
             None => return Ok(()),
         };
 
+        // the item is zipped with the next one or repeated, see `parse_t`
+        if comma_count == 0 && matches!(self.current_token.kind, TokenKind::Zip | TokenKind::Repeat)
+        {
+            return Ok(());
+        }
+
+        // items must be separated by a comma, eg. `1 2` or `(1+2)(3+4)`
+        // (a stray operator like in `1 * 2` is reported as such by `parse_t`)
+        let stray_op = matches!(
+            self.current_token.kind,
+            TokenKind::Math(op) if !matches!(op, Op::Add | Op::Sub)
+        );
+        if comma_count == 0 {
+            match self.current_token.kind {
+                TokenKind::RngExclusive | TokenKind::RngInclusive => {
+                    return Err(ParserError::RangeOutsideBraces(ErrorContext::new(
+                        self.source.clone(),
+                        self.current_token.span,
+                    )));
+                }
+                // closing a range or a parenthesis twice, eg. `{1..5}}`
+                TokenKind::RSquiggly => {
+                    return Err(ParserError::UnmatchedBrace(ErrorContext::new(
+                        self.source.clone(),
+                        self.current_token.span,
+                    )));
+                }
+                TokenKind::RParen => {
+                    return Err(ParserError::UnmatchedParen(ErrorContext::new(
+                        self.source.clone(),
+                        self.current_token.span,
+                    )));
+                }
+                _ => {}
+            }
+        }
+        if comma_count == 0 && !stray_op {
+            let consumed_end = self.consumed_end();
+            let next_len = self.source[consumed_end..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+            return Err(ParserError::MissingComma(ErrorContext::new(
+                self.source.clone(),
+                Span::new(consumed_end, consumed_end + next_len),
+            )));
+        }
+
         Ok(())
     }
 
-    fn check_unmatched_paren(&self) -> Result<(), ParserError> {
+    /// Checks that the parenthesis of the math expression starting at the token `from` are
+    /// balanced, pointing at the specific `(` or `)` without a partner.
+    ///
+    /// The expression ends at the first `,` outside of any parenthesis, or at the first token
+    /// that can't be part of a math expression, eg. the `..` after a range bound. Reaching
+    /// the end of the expression inside a parenthesis means that one is never closed.
+    fn check_unmatched_paren(&self, from: usize) -> Result<(), ParserError> {
         let mut stack = vec![];
+        let mut squiggly_depth = 0;
 
-        for token in self.tokens.clone() {
+        for token in &self.all_tokens[from..] {
             match token.kind {
+                // skip over the ranges of aggregates
+                TokenKind::LSquiggly => squiggly_depth += 1,
+                TokenKind::RSquiggly if squiggly_depth > 0 => squiggly_depth -= 1,
+                _ if squiggly_depth > 0 => {}
+
                 TokenKind::LParen => stack.push(token.span),
                 TokenKind::RParen => {
                     if stack.pop().is_none() {
-                        return Err(ParserError::UnmatchedParen(
-                            self.input_chars.clone(),
+                        return Err(ParserError::UnmatchedParen(ErrorContext::new(
+                            self.source.clone(),
                             token.span,
-                        ));
+                        )));
                     }
                 }
-                TokenKind::Math(_) | TokenKind::Int { .. } => {}
+                TokenKind::Comma if stack.is_empty() => break,
+                TokenKind::Math(_)
+                | TokenKind::Int { .. }
+                | TokenKind::Func(_)
+                | TokenKind::Aggregate(_)
+                | TokenKind::RngMutArg
+                | TokenKind::Comma => {}
                 _ => break,
             }
         }
 
         if let Some(span) = stack.pop() {
-            return Err(ParserError::UnmatchedParen(self.input_chars.clone(), span));
+            return Err(ParserError::UnmatchedParen(ErrorContext::new(
+                self.source.clone(),
+                span,
+            )));
         }
 
         Ok(())
     }
 
+    /// Swaps `error` for the unmatched parenthesis of the math expression starting at the
+    /// token `from`, if there is one. The expression is only checked once parsing it failed,
+    /// but a missing partner still takes precedence over whatever error it caused.
+    fn paren_error(&self, from: usize, error: ParserError) -> ParserError {
+        self.check_unmatched_paren(from).err().unwrap_or(error)
+    }
+
+    /// Checks the braces of the range starting at the current `{`. Ranges can't be nested,
+    /// apart from the ranges of aggregates, and have to be closed.
+    /// Returns the span of the closing `}`.
+    fn check_range_braces(&self) -> Result<Span, ParserError> {
+        let mut aggregate_depth = 0;
+        let mut prev_kind = self.current_token.kind;
+
+        for token in &self.all_tokens[self.position + 1..] {
+            match token.kind {
+                TokenKind::LSquiggly
+                    if aggregate_depth > 0 || matches!(prev_kind, TokenKind::Aggregate(_)) =>
+                {
+                    aggregate_depth += 1
+                }
+                TokenKind::LSquiggly => {
+                    return Err(ParserError::NestedRange(ErrorContext::new(
+                        self.source.clone(),
+                        token.span,
+                    )));
+                }
+                TokenKind::RSquiggly if aggregate_depth > 0 => aggregate_depth -= 1,
+                TokenKind::RSquiggly => return Ok(token.span),
+                _ => {}
+            }
+            prev_kind = token.kind;
+        }
+
+        Err(ParserError::UnmatchedBrace(ErrorContext::new(
+            self.source.clone(),
+            self.current_token.span,
+        )))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "parse", level = "debug", skip_all, fields(tokens = self.all_tokens.len()))
+    )]
     pub fn parse(&mut self) -> Result<Vec<Node>, ParserError> {
+        self.check_token_count()?;
         let mut nodes = vec![];
 
-        while let Some(token) = self.tokens.peek() {
-            self.current_token = **token;
+        while let Some(token) = self.peek() {
+            self.current_token = token;
+            self.check_item_count(nodes.len())?;
             let node = self.parse_t()?;
+            trace!(span = ?node.span(), node = %node, "node");
             nodes.push(node);
         }
 
+        invariants::check_parsed(&self.all_tokens, &nodes);
+        trace!(count = nodes.len(), "parsed");
         Ok(nodes)
     }
 
+    /// Parses the input like [`Parser::parse`], but instead of stopping at the first error,
+    /// skips to the next top-level comma and carries on, collecting every error on the way.
+    pub fn parse_all_errors(&mut self) -> Result<Vec<Node>, Vec<ParserError>> {
+        self.check_token_count().map_err(|e| vec![e])?;
+        let mut nodes = vec![];
+        let mut errors = vec![];
+
+        while let Some(token) = self.peek() {
+            self.current_token = token;
+            let item_start = self.position;
+
+            // the items past the limit are not looked at
+            if let Err(e) = self.check_item_count(nodes.len() + errors.len()) {
+                errors.push(e);
+                break;
+            }
+
+            match self.parse_t() {
+                Ok(node) => nodes.push(node),
+                Err(e) => {
+                    self.position = item_start;
+                    self.recover(e.span().start as usize);
+                    errors.push(e);
+                }
+            }
+        }
+
+        invariants::check_parsed(&self.all_tokens, &nodes);
+        match errors.is_empty() {
+            true => Ok(nodes),
+            false => Err(errors),
+        }
+    }
+
+    /// Errors if there are more tokens than [`ParserOptions::max_tokens`], pointing at the
+    /// first one over the limit.
+    fn check_token_count(&self) -> Result<(), ParserError> {
+        match self.all_tokens.get(self.options.max_tokens) {
+            Some(token) => Err(ParserError::TooManyTokens(ErrorContext::with(
+                self.source.clone(),
+                token.span,
+                self.options.max_tokens,
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Errors if the item starting at the cursor goes over [`ParserOptions::max_items`],
+    /// with `count` items before it.
+    fn check_item_count(&self, count: usize) -> Result<(), ParserError> {
+        match count < self.options.max_items {
+            true => Ok(()),
+            false => Err(ParserError::TooManyItems(ErrorContext::with(
+                self.source.clone(),
+                self.current_token.span,
+                self.options.max_items,
+            ))),
+        }
+    }
+
+    /// Moves the cursor past the first comma outside of any braces/parenthesis that comes
+    /// at or after `offset`, so parsing can resume with the next item.
+    fn recover(&mut self, offset: usize) {
+        let mut depth: usize = 0;
+        self.paren_depth = 0;
+        self.in_mutation = false;
+
+        while let Some(token) = self.peek() {
+            self.advance();
+
+            match token.kind {
+                TokenKind::LParen | TokenKind::LSquiggly => depth += 1,
+                TokenKind::RParen | TokenKind::RSquiggly => depth = depth.saturating_sub(1),
+                TokenKind::Comma if depth == 0 && token.span.start as usize >= offset => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses an item and the items zipped with it, eg. `{1..=3} ~ 7`.
     fn parse_t(&mut self) -> Result<Node, ParserError> {
+        let mut node = self.parse_repeat()?;
+
+        // the item stops before a `~`, which binds tighter than the comma
+        while let Some(zip) = self.peek().filter(|token| token.kind == TokenKind::Zip) {
+            if self.prev_token().map(|token| token.kind) == Some(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+            self.current_token = match self.peek() {
+                Some(token) if !matches!(token.kind, TokenKind::Comma | TokenKind::Zip) => token,
+                _ => {
+                    return Err(ParserError::MissingZipOperand(ErrorContext::new(
+                        self.source.clone(),
+                        zip.span,
+                    )))
+                }
+            };
+
+            let right = self.parse_repeat()?;
+            node = Node::Zip {
+                span: node.span().merge(right.span()),
+                left: Box::new(node),
+                right: Box::new(right),
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Parses an item and the `x` repetitions after it, eg. `{1..=3} x 2`.
+    /// An `x` binds tighter than a `~`, so `1 x 2 ~ 3` zips `1, 1` with `3`.
+    fn parse_repeat(&mut self) -> Result<Node, ParserError> {
+        let mut node = self.parse_item()?;
+
+        while let Some(repeat) = self.peek().filter(|token| token.kind == TokenKind::Repeat) {
+            if self.prev_token().map(|token| token.kind) == Some(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+
+            let count = self.parse_repeat_count(repeat.span)?;
+            node = Node::Repeat {
+                span: node.span().merge(count.span),
+                item: Box::new(node),
+                count: match count.kind {
+                    TokenKind::Int { value } => value as u64,
+                    _ => unreachable!(),
+                },
+            };
+            self.advance_past_comma()?;
+        }
+
+        Ok(node)
+    }
+
+    /// Parses the count after the `x` at `repeat`, which has to be a positive number
+    /// literal. The cursor is left on the number token.
+    fn parse_repeat_count(&mut self, repeat: Span) -> Result<Token, ParserError> {
+        let source = self.source.clone();
+        let invalid_count =
+            move |span| ParserError::InvalidRepeatCount(ErrorContext::new(source.clone(), span));
+
+        self.current_token = match self.peek() {
+            Some(token)
+                if matches!(
+                    token.kind,
+                    TokenKind::Int { .. } | TokenKind::Math(Op::Add | Op::Sub)
+                ) =>
+            {
+                token
+            }
+            // a math expression or a range as the count, eg. `0 x (2 + 3)`
+            Some(token)
+                if !matches!(
+                    token.kind,
+                    TokenKind::Comma | TokenKind::Zip | TokenKind::Repeat
+                ) =>
+            {
+                return Err(invalid_count(token.span))
+            }
+            // no count at all, eg. `0 x, 1`
+            _ => return Err(invalid_count(repeat)),
+        };
+
+        let count = self.parse_signed_int().map_err(|e| match e {
+            ParserError::InvalidInt(ctx) | ParserError::IncompleteInt(ctx) => {
+                invalid_count(ctx.span)
+            }
+            e => e,
+        })?;
+        match count.kind {
+            TokenKind::Int { value } if value > 0 => Ok(count),
+            _ => Err(ParserError::NonPositiveRepeatCount(ErrorContext::new(
+                self.source.clone(),
+                count.span,
+            ))),
+        }
+    }
+
+    /// Parses a single number, math expression or range, along with the comma after it.
+    fn parse_item(&mut self) -> Result<Node, ParserError> {
         match self.current_token.kind {
             TokenKind::Int { .. } => {
                 let int_node = self.parser_int()?;
@@ -153,21 +939,44 @@ impl<'a> Parser<'a> {
             }
 
             // Error if the first token is a comma
-            TokenKind::Comma => Err(ParserError::UnexpectedComma(
-                self.input_chars.clone(),
+            TokenKind::Comma => Err(ParserError::UnexpectedComma(ErrorContext::new(
+                self.source.clone(),
                 self.current_token.span,
-            )),
+            ))),
+
+            // Zipping without an item before the `~`, eg. `1, ~ 2`
+            TokenKind::Zip => Err(ParserError::MissingZipOperand(ErrorContext::new(
+                self.source.clone(),
+                self.current_token.span,
+            ))),
+
+            // Repeating without an item before the `x`, eg. `1, x 2`
+            TokenKind::Repeat => Err(ParserError::MissingRepeatItem(ErrorContext::new(
+                self.source.clone(),
+                self.current_token.span,
+            ))),
 
-            // Singular negative/positive numbers
+            // Singular negative/positive numbers, or math expressions, eg. `-(2 + 3)`
             TokenKind::Math(op) => match op {
+                Op::Add | Op::Sub
+                    if matches!(
+                        self.peek_past_signs(),
+                        Some(Token {
+                            kind: TokenKind::LParen,
+                            ..
+                        })
+                    ) =>
+                {
+                    self.parse_signed_math_expr()
+                }
                 Op::Add | Op::Sub => {
                     let int_node = self.parser_int()?;
                     Ok(int_node)
                 }
-                _ => Err(ParserError::UnexpectedMathOp(
-                    self.input_chars.clone(),
+                _ => Err(ParserError::UnexpectedMathOp(ErrorContext::new(
+                    self.source.clone(),
                     self.current_token.span,
-                )),
+                ))),
             },
 
             // Math expressions
@@ -176,23 +985,78 @@ impl<'a> Parser<'a> {
                 Ok(expr_node)
             }
 
-            _ => {
-                todo!("Unexpected token: {:?}", self.current_token.kind)
+            // Range expressions
+            TokenKind::LSquiggly => {
+                let range_node = self.parse_range_expr()?;
+                Ok(range_node)
             }
+
+            // Closing a parenthesis or a range that was never opened
+            TokenKind::RParen => Err(ParserError::UnmatchedParen(ErrorContext::new(
+                self.source.clone(),
+                self.current_token.span,
+            ))),
+            TokenKind::RSquiggly => Err(ParserError::UnmatchedBrace(ErrorContext::new(
+                self.source.clone(),
+                self.current_token.span,
+            ))),
+
+            // Range syntax without the braces, eg. `..5`
+            TokenKind::RngExclusive | TokenKind::RngInclusive => {
+                Err(ParserError::RangeOutsideBraces(ErrorContext::new(
+                    self.source.clone(),
+                    self.current_token.span,
+                )))
+            }
+            TokenKind::RngStep
+            | TokenKind::RngMutation
+            | TokenKind::RngUnique
+            | TokenKind::RngReverse
+            | TokenKind::RngLow
+            | TokenKind::RngHigh
+            | TokenKind::RngOrder(_)
+            | TokenKind::RngMutArg => Err(ParserError::MisplacedRngSyntax(ErrorContext::new(
+                self.source.clone(),
+                self.current_token.span,
+            ))),
+
+            // Math without the parenthesis, eg. `max(1, 2)`
+            TokenKind::Func(_) | TokenKind::Aggregate(_) => Err(ParserError::MathOutsideParen(
+                ErrorContext::new(self.source.clone(), self.current_token.span),
+            )),
+
+            // `Parser::new` drops all trivia
+            TokenKind::Whitespace | TokenKind::Comment => unreachable!(),
         }
     }
 
     fn parser_int(&mut self) -> Result<Node, ParserError> {
+        let int_token = self.parse_signed_int()?;
+        self.advance_past_comma()?;
+        Ok(Node::Int {
+            span: int_token.span,
+            value: match int_token.kind {
+                TokenKind::Int { value } => value,
+                _ => unreachable!(),
+            },
+        })
+    }
+
+    /// Eats all the '-' and '+' tokens before a number and folds them into the number.
+    /// The cursor is left on the number token, the returned token spans the signs too.
+    fn parse_signed_int(&mut self) -> Result<Token, ParserError> {
         let mut minus_count = 0;
-        let span_start = self.current_token.span.start;
+        let first_sign = self.current_token.span;
 
         // eat all '-' and '+' tokens before number
-        while let Some(token) = self.tokens.peek() {
+        while let Some(token) = self.peek() {
             match token.kind {
                 TokenKind::Math(Op::Add) => {
+                    self.current_token = token;
                     self.advance();
                 }
                 TokenKind::Math(Op::Sub) => {
+                    self.current_token = token;
                     self.advance();
                     minus_count += 1;
                 }
@@ -204,180 +1068,908 @@ impl<'a> Parser<'a> {
 
         // update the current token
         // returns error if there is no next token
-        self.current_token = match self.tokens.peek() {
-            Some(token) => **token,
+        self.current_token = match self.peek() {
+            Some(token) => token,
             None => {
-                return Err(ParserError::IncompleteInt(
-                    self.input_chars.clone(),
+                return Err(ParserError::IncompleteInt(ErrorContext::new(
+                    self.source.clone(),
                     self.current_token.span,
-                ));
+                )));
             }
         };
 
         match self.current_token.kind {
             TokenKind::Int { value: val } => {
-                let int_node = match is_negative {
-                    true => Node::Int {
-                        span: Span::new(span_start, self.current_token.span.end),
-                        value: -val,
-                    },
-                    false => Node::Int {
-                        span: Span::new(span_start, self.current_token.span.end),
-                        value: val,
-                    },
+                let span = first_sign.merge(self.current_token.span);
+                // `-MIN` has no positive counterpart
+                let value = match is_negative {
+                    true => val.checked_neg().ok_or_else(|| {
+                        ParserError::NumberTooLarge(ErrorContext::new(self.source.clone(), span))
+                    })?,
+                    false => val,
                 };
-                self.advance_past_comma()?;
-                Ok(int_node)
+                Ok(Token::new(TokenKind::Int { value }, span))
             }
-            _ => Err(ParserError::InvalidInt(
-                self.input_chars.clone(),
+            _ => Err(ParserError::InvalidInt(ErrorContext::new(
+                self.source.clone(),
                 self.current_token.span,
-            )),
+            ))),
         }
     }
 
-    // TODO: Switch to use shunting yard algorithm
     fn parse_math_expr(&mut self) -> Result<Node, ParserError> {
-        self.check_unmatched_paren()?;
-        self.in_paren = true;
+        let start = self.position;
+        let node = self
+            .parse_math_group()
+            .map_err(|e| self.paren_error(start, e))?;
+
+        // `infix_to_postfix` already moved the cursor past the closing parenthesis
+        self.skip_comma().map_err(|e| self.paren_error(start, e))?;
+
+        // a stray operator after the expression fails the next item, eg. `(1 + 2) * (3`
+        let comma = self.prev_token().map(|token| token.kind) == Some(TokenKind::Comma);
+        if !comma && self.peek_nth(0).is_some() {
+            self.check_unmatched_paren(start)?;
+        }
+
+        Ok(node)
+    }
+
+    /// Parses a math expression preceded by signs, eg. `-(2 + 3)`. The signs are folded
+    /// into the `negated` flag of the expression.
+    fn parse_signed_math_expr(&mut self) -> Result<Node, ParserError> {
+        let first_sign = self.current_token.span;
+        let mut negated = false;
+
+        while let TokenKind::Math(op @ (Op::Add | Op::Sub)) = self.current_token.kind {
+            negated ^= op == Op::Sub;
+            self.advance();
+            // `peek_past_signs` found the `(` after the signs
+            self.current_token = self.peek().unwrap();
+        }
+
+        match self.parse_math_expr()? {
+            Node::MathExpr { span, rpn, .. } => Ok(Node::MathExpr {
+                negated,
+                span: first_sign.merge(span),
+                rpn,
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Parses a parenthesised math expression, leaving the cursor after the closing `)`.
+    fn parse_math_group(&mut self) -> Result<Node, ParserError> {
+        let open_paren = self.current_token.span;
+        let mut output_queue = vec![];
+
+        let arg_count = self.infix_to_postfix(open_paren, &mut output_queue, false)?;
+        let span = open_paren.merge(self.current_token.span);
+
+        if arg_count != 1 {
+            return Err(ParserError::InvalidMathExpr(ErrorContext::new(
+                self.source.clone(),
+                span,
+            )));
+        }
+
+        Ok(Node::MathExpr {
+            negated: false,
+            span,
+            rpn: output_queue,
+        })
+    }
+
+    fn parse_range_expr(&mut self) -> Result<Node, ParserError> {
+        let range_node = self.parse_range()?;
+        self.advance_past_comma()?;
+        Ok(range_node)
+    }
+
+    /// Parses `{<START>..<END>, s:<STEP>, m:<MUTATION>, u:, r:, o:<ORDER>, lo:<LOW>, hi:<HIGH>}`,
+    /// leaving the cursor on the closing `}`.
+    /// `<START>` can be left out, in which case it defaults to `0`.
+    fn parse_range(&mut self) -> Result<Node, ParserError> {
+        let close_brace = self.check_range_braces()?;
+
+        let open_brace = self.current_token.span;
+
+        // eat the '{'
+        self.advance();
+        self.expect_token(open_brace)?;
+
+        // nothing but arguments, eg. `{}` or `{s:2}`
+        if let TokenKind::RSquiggly
+        | TokenKind::Comma
+        | TokenKind::RngStep
+        | TokenKind::RngMutation
+        | TokenKind::RngUnique
+        | TokenKind::RngReverse
+        | TokenKind::RngLow
+        | TokenKind::RngHigh
+        | TokenKind::RngOrder(_) = self.current_token.kind
+        {
+            return Err(ParserError::MissingRangeBounds(ErrorContext::new(
+                self.source.clone(),
+                open_brace.merge(close_brace),
+            )));
+        }
+
+        // open-ended start, eg. `{..10}` is `{0..10}`
+        let start = match self.current_token.kind {
+            TokenKind::RngExclusive | TokenKind::RngInclusive => Node::Int {
+                span: self.current_token.span,
+                value: 0,
+            },
+            _ => self.parse_range_bound(open_brace)?,
+        };
+
+        let inclusive = match self.current_token.kind {
+            TokenKind::RngInclusive => true,
+            TokenKind::RngExclusive => false,
+            _ => return Err(self.invalid_range_expr(open_brace)),
+        };
+        let dots = self.current_token.span;
+        self.advance();
+        self.expect_token(open_brace)?;
+
+        if let TokenKind::RSquiggly | TokenKind::Comma = self.current_token.kind {
+            // `{..}` has neither bound
+            return Err(match start.span() == dots {
+                true => {
+                    ParserError::MissingRangeBounds(ErrorContext::new(self.source.clone(), dots))
+                }
+                false => ParserError::MissingRangeEnd(ErrorContext::new(self.source.clone(), dots)),
+            });
+        }
+
+        // a `+` right after the dots makes the end relative to the start, eg. `{100..+5}`
+        let end = match self.current_token.kind {
+            TokenKind::Math(Op::Add) => {
+                let plus = self.current_token;
+                self.advance();
+                self.expect_token(open_brace)?;
+                let offset = self.parse_range_bound(open_brace)?;
+                self.relative_end(&start, plus, offset)
+            }
+            _ => self.parse_range_bound(open_brace)?,
+        };
+        let mut step = None;
+        let mut mutation = None;
+        let mut unique = false;
+        let mut reverse = false;
+        let mut order = None;
+        let mut low = None;
+        let mut high = None;
+        // arguments can come in any order, but each only once
+        let mut seen_args: [Option<Span>; 7] = [None; 7];
+
+        while self.current_token.kind == TokenKind::Comma {
+            self.advance();
+            self.expect_token(open_brace)?;
+
+            let arg = match self.current_token.kind {
+                TokenKind::RngStep => 0,
+                TokenKind::RngMutation => 1,
+                TokenKind::RngUnique => 2,
+                TokenKind::RngReverse => 3,
+                TokenKind::RngOrder(_) => 4,
+                TokenKind::RngLow => 5,
+                TokenKind::RngHigh => 6,
+                // trailing comma, eg. `{1..5, m:*2,}`
+                TokenKind::RSquiggly => break,
+                _ => {
+                    return Err(ParserError::ExpectedRangeArg(ErrorContext::new(
+                        self.source.clone(),
+                        self.current_token.span,
+                    )))
+                }
+            };
+            if let Some(first) = seen_args[arg] {
+                return Err(ParserError::DuplicateRangeArg(ErrorContext::with(
+                    self.source.clone(),
+                    self.current_token.span,
+                    first,
+                )));
+            }
+            seen_args[arg] = Some(self.current_token.span);
+
+            let arg_token = self.current_token;
+            self.advance();
+            self.expect_token(open_brace)?;
+
+            match arg_token.kind {
+                TokenKind::RngStep => step = Some(Box::new(self.parse_range_bound(open_brace)?)),
+                TokenKind::RngMutation => {
+                    mutation = Some(Box::new(self.parse_mutation()?));
+                    self.expect_token(open_brace)?;
+                }
+                TokenKind::RngUnique => unique = self.parse_flag(arg_token, open_brace)?,
+                TokenKind::RngReverse => reverse = self.parse_flag(arg_token, open_brace)?,
+                TokenKind::RngOrder(sort_order) => order = Some(sort_order),
+                TokenKind::RngLow => low = Some(Box::new(self.parse_range_bound(open_brace)?)),
+                TokenKind::RngHigh => high = Some(Box::new(self.parse_range_bound(open_brace)?)),
+                _ => unreachable!(),
+            }
+        }
+
+        if self.current_token.kind == TokenKind::RSquiggly {
+            if let Some(step) = &step {
+                self.check_step(&start, &end, step)?;
+            }
+            if let (Some(low), Some(high)) = (&low, &high) {
+                self.check_clamp(low, high)?;
+            }
+        }
+
+        match self.current_token.kind {
+            TokenKind::RSquiggly => Ok(Node::RangeExpr {
+                span: open_brace.merge(self.current_token.span),
+                start: Box::new(start),
+                end: Box::new(end),
+                inclusive,
+                step,
+                mutation,
+                low,
+                high,
+                unique,
+                reverse,
+                order,
+            }),
+            _ => Err(self.invalid_range_expr(open_brace)),
+        }
+    }
+
+    /// The absolute end of a range whose end is `offset` past its `start`, spanning the `plus`
+    /// and the offset. A start or an offset that fails to evaluate, or a sum that overflows,
+    /// is left for the evaluator to report, as the math expression `start + offset` spanning
+    /// both.
+    fn relative_end(&self, start: &Node, plus: Token, offset: Node) -> Node {
+        let span = plus.span.merge(offset.span());
+        let evaluator = Evaluator::new(self.source.clone());
+        if let (Ok(start), Ok(offset)) =
+            (evaluator.eval_scalar(start), evaluator.eval_scalar(&offset))
+        {
+            if let Some(value) = start.checked_add(offset) {
+                return Node::Int { span, value };
+            }
+        }
+
+        let rpn = |node: &Node| match node {
+            Node::Int { span, value } => vec![Token::new(TokenKind::Int { value: *value }, *span)],
+            Node::MathExpr { rpn, .. } => rpn.clone(),
+            _ => unreachable!("range bounds are numbers or math expressions"),
+        };
+        let mut sum = rpn(start);
+        sum.extend(rpn(&offset));
+        sum.push(Token::new(TokenKind::Math(Op::Add), plus.span));
+        Node::MathExpr {
+            negated: false,
+            span: start.span().merge(span),
+            rpn: sum,
+        }
+    }
+
+    /// Catches steps that would never reach the end of the range, eg. `{1..=10, s:0}` or `{10..=1, s:2}`.
+    /// Bounds that fail to evaluate are left for the evaluator to report.
+    fn check_step(&self, start: &Node, end: &Node, step: &Node) -> Result<(), ParserError> {
+        let evaluator = Evaluator::new(self.source.clone());
+        let (Ok(start_value), Ok(end_value), Ok(step_value)) = (
+            evaluator.eval_scalar(start),
+            evaluator.eval_scalar(end),
+            evaluator.eval_scalar(step),
+        ) else {
+            return Ok(());
+        };
+
+        if step_value == 0 {
+            return Err(ParserError::ZeroStep(ErrorContext::new(
+                self.source.clone(),
+                step.span(),
+            )));
+        }
+        if start_value != end_value && (step_value > 0) != (start_value < end_value) {
+            return Err(ParserError::StepDirectionMismatch(ErrorContext::with(
+                self.source.clone(),
+                step.span(),
+                start.span().merge(end.span()),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Catches a `lo:` above the `hi:` of a range, eg. `{1..10, lo:5, hi:2}`.
+    /// Values that fail to evaluate are left for the evaluator to report.
+    fn check_clamp(&self, low: &Node, high: &Node) -> Result<(), ParserError> {
+        let evaluator = Evaluator::new(self.source.clone());
+        match (evaluator.eval_scalar(low), evaluator.eval_scalar(high)) {
+            (Ok(low_value), Ok(high_value)) if low_value > high_value => {
+                Err(ParserError::InvertedClamp(ErrorContext::with(
+                    self.source.clone(),
+                    low.span(),
+                    high.span(),
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Parses the start, end, step, `lo:` or `hi:` of a range, leaving the cursor on the token after the value.
+    /// Math expressions don't need to be enclosed in parenthesis here, eg. `{MAX-5..=MAX}`.
+    fn parse_range_bound(&mut self, open_brace: Span) -> Result<Node, ParserError> {
+        let bound_start = self.current_token.span;
+        let mut output_queue = vec![];
+
+        if let TokenKind::RngExclusive | TokenKind::RngInclusive | TokenKind::RSquiggly =
+            self.current_token.kind
+        {
+            return Err(self.invalid_range_expr(open_brace));
+        }
+
+        let from = self.position;
+        self.infix_to_postfix(bound_start, &mut output_queue, true)
+            .map_err(|e| self.paren_error(from, e))?;
+        let span = Span::new(bound_start.start as usize, self.consumed_end());
+        self.expect_token(open_brace)?;
+
+        match output_queue.as_slice() {
+            [Token {
+                kind: TokenKind::Int { value },
+                ..
+            }] => Ok(Node::Int {
+                span,
+                value: *value,
+            }),
+            _ => Ok(Node::MathExpr {
+                negated: false,
+                span,
+                rpn: output_queue,
+            }),
+        }
+    }
+
+    /// Parses the optional value of a flag like `u:` or `r:`, which is either nothing, `0` or `1`,
+    /// leaving the cursor on the token after it.
+    fn parse_flag(&mut self, flag: Token, open_brace: Span) -> Result<bool, ParserError> {
+        match self.current_token.kind {
+            TokenKind::Comma | TokenKind::RSquiggly => Ok(true),
+            TokenKind::Int {
+                value: value @ (0 | 1),
+            } => {
+                self.advance();
+                self.expect_token(open_brace)?;
+                Ok(value == 1)
+            }
+            _ => Err(ParserError::InvalidFlagValue(ErrorContext::new(
+                self.source.clone(),
+                flag.span.merge(self.current_token.span),
+            ))),
+        }
+    }
+
+    /// Whether the current token is `-9223372036854775808` written out, rather than `MIN`.
+    fn is_negative_min_literal(&self) -> bool {
+        self.current_token.kind == (TokenKind::Int { value: i64::MIN })
+            && self
+                .current_token
+                .text(&self.source)
+                .is_some_and(|text| text.starts_with('-'))
+    }
+
+    /// Parses the math expression of a range mutation. If the expression starts with an
+    /// operator, the number to be mutated (`@`) is assumed to be the lhs of the operation.
+    fn parse_mutation(&mut self) -> Result<Node, ParserError> {
+        let first = self.current_token.span;
+        let mut output_queue = vec![];
+
+        let from = self.position;
+        self.in_mutation = true;
+        let result = self.infix_to_postfix(first, &mut output_queue, true);
+        self.in_mutation = false;
+        result.map_err(|e| self.paren_error(from, e))?;
+
+        Ok(Node::MathExpr {
+            negated: false,
+            span: Span::new(first.start as usize, self.consumed_end()),
+            rpn: output_queue,
+        })
+    }
 
-        let span_start = self.current_token.span.start;
-        let mut start_of_expr = true;
-        let mut paren_depth = 0;
-        let mut ouput_queue = vec![];
-        let mut operator_stack = vec![];
+    /// Parses `sum{..}`, `count{..}` or `prod{..}` and folds it into a single number,
+    /// leaving the cursor after the closing `}`.
+    #[cfg(feature = "aggregate")]
+    fn parse_aggregate(&mut self, aggregate: Aggregate) -> Result<Token, ParserError> {
+        let keyword = self.current_token.span;
 
-        // Advance the cursor
+        // eat the keyword, the lexer guarantees it is followed by a '{'
         self.advance();
+        self.expect_token(keyword)?;
 
-        // while let Some(token) = self.tokens.peek() {
-        //     self.current_token = **token;
-
-        //     match token.kind {
-        //         TokenKind::LParen => {
-        //             self.advance();
-        //             paren_depth += 1;
-        //             if paren_depth > 1 {
-        //                 operator_stack.push(self.current_token);
-        //                 start_of_expr = true;
-        //             }
-        //         }
-
-        //         // Singular negative/positive numbers at the start of the expression/parenthesis
-        //         TokenKind::Math(op) if start_of_expr => match op {
-        //             Op::Add | Op::Sub => {
-        //                 let int_token = match self.parser_int()? {
-        //                     Node::Int { value, .. } => TokenKind::Int { value },
-        //                     _ => unreachable!(),
-        //                 };
-        //                 ouput_queue.push(int_token);
-        //             }
-        //             _ => {
-        //                 return Err(ParserError::UnexpectedMathOp(
-        //                     self.input_chars.clone(),
-        //                     self.current_token.span,
-        //                 ))
-        //             }
-        //         },
-
-        //         TokenKind::Math(_) if !start_of_expr => {
-        //             operator_stack.push(self.current_token);
-        //         }
-        //         _ => {
-        //             let node = self.parse_t()?;
-        //         }
-
-        //         TokenKind::RParen => {
-        //             self.advance();
-        //             paren_depth -= 1;
-        //         }
-        //     }
-
-        //     start_of_expr = false;
-        // }
-
-        self.infix_to_postfix(span_start, &mut ouput_queue, &mut operator_stack)?;
-
-        todo!("Implement shunting yard algorithm")
-    }
-
-    // A recursive infix to postfix translator based on shunting yard algorithm
+        let in_mutation = self.in_mutation;
+        self.in_mutation = false;
+        let range_node = self.parse_range();
+        self.in_mutation = in_mutation;
+        let range_node = range_node?;
+
+        let span = keyword.merge(self.current_token.span);
+        self.advance();
+
+        let options = EvalOptions {
+            max_steps: self.options.max_steps,
+            ..Default::default()
+        };
+        let evaluator = Evaluator::with_options(self.source.clone(), options);
+        match evaluator.aggregate(aggregate, &range_node) {
+            Ok(value) => Ok(Token::new(TokenKind::Int { value }, span)),
+            Err(EvalError::Overflow(ctx)) => Err(ParserError::AggregateOverflow(
+                ErrorContext::with(self.source.clone(), span, ctx.extra),
+            )),
+            Err(
+                EvalError::DivisionByZero(ctx)
+                | EvalError::InvalidFactorial(ctx)
+                | EvalError::InvalidShift(ctx)
+                | EvalError::NonTerminatingRange(ctx)
+                | EvalError::ZeroStep(ctx)
+                | EvalError::InvertedClamp(ctx),
+            ) => Err(ParserError::InvalidAggregate(ErrorContext::new(
+                self.source.clone(),
+                ctx.span,
+            ))),
+            Err(EvalError::BudgetExceeded(ctx)) => Err(ParserError::InvalidAggregate(
+                ErrorContext::new(self.source.clone(), ctx.span),
+            )),
+            Err(EvalError::OutOfRange(..)) => {
+                unreachable!("Aggregates don't convert the numbers")
+            }
+        }
+    }
+
+    /// Syncs the current token with the cursor, erroring if the range expression
+    /// starting at `open_brace` ends abruptly.
+    fn expect_token(&mut self, open_brace: Span) -> Result<(), ParserError> {
+        match self.peek() {
+            Some(token) => {
+                self.current_token = token;
+                Ok(())
+            }
+            None => Err(ParserError::InvalidRangeExpr(ErrorContext::new(
+                self.source.clone(),
+                open_brace.merge(self.current_token.span),
+            ))),
+        }
+    }
+
+    fn invalid_range_expr(&self, open_brace: Span) -> ParserError {
+        ParserError::InvalidRangeExpr(ErrorContext::new(
+            self.source.clone(),
+            open_brace.merge(self.current_token.span),
+        ))
+    }
+
+    /// An infix to postfix translator based on shunting yard algorithm.
+    ///
+    /// Translates the parenthesis group starting at the current `(` token up to and
+    /// including the matching `)`. Nested groups don't recurse, their `(` is pushed onto
+    /// the operator stack as a sentinel and everything above it is popped at the `)`.
+    /// Returns the number of comma separated arguments found in the group, so function
+    /// calls can check their arity.
+    ///
+    /// A `bare` expression (ie. a range mutation) has no enclosing parenthesis and ends
+    /// at the first top-level `,` or `}`, which is left for the caller.
     fn infix_to_postfix(
         &mut self,
-        start: usize,
-        ouput_queue: &mut Vec<Token>,
-        operator_stack: &mut Vec<Token>,
-    ) -> Result<(), ParserError> {
-        self.paren_depth += 1;
-        self.advance();
-        let mut token_count = 0; // keeps track of tokens in parenthesis
-        let mut is_start = true; // whether position of the cursor is at the start of a new number of nested maths epxr. (For parsing unary operators)
+        start: Span,
+        output_queue: &mut Vec<Token>,
+        bare: bool,
+    ) -> Result<usize, ParserError> {
+        let mut operator_stack: Vec<Token> = vec![];
+        let mut groups: Vec<Group> = vec![]; // the open parenthesis, innermost last
+        let mut bare_arg_count = 1;
+        let mut is_start = true; // whether the cursor is at the start of the group or of a function argument
+        let mut expect_operand = true; // whether the next token must be a number or nested math expr
+        let mut last_operand = start; // the operand before the cursor, eg. `(1 + 2)` or `5!`
 
-        if self.paren_depth > MAX_PAREN_DEPTH {
-            return Err(ParserError::TooManyParen(
-                self.input_chars.clone(),
-                Span::new(start, self.current_token.span.end),
-            ));
+        if bare {
+            // implicit lhs of a mutation, eg. `m:*2` is `@ * 2`
+            // (`~` can only be a prefix, eg. `m:~@`)
+            if let (true, TokenKind::Math(op)) = (self.in_mutation, self.current_token.kind) {
+                if !op.is_prefix() {
+                    output_queue.push(Token::new(
+                        TokenKind::RngMutArg,
+                        Span::new(start.start as usize, start.start as usize + 1),
+                    ));
+                    is_start = false;
+                    expect_operand = false;
+                }
+            }
+            // the lexer folds the `-` of `m:-9223372036854775808` into `MIN`, as the number
+            // doesn't fit i64 on its own, but it is still `@ - 9223372036854775808`, ie. `@ + MIN`
+            if self.in_mutation && self.is_negative_min_literal() {
+                let minus = Span::new(start.start as usize, start.start as usize + 1);
+                output_queue.push(Token::new(TokenKind::RngMutArg, minus));
+                operator_stack.push(Token::new(TokenKind::Math(Op::Add), minus));
+                is_start = false;
+            }
+        } else {
+            self.open_group(&mut groups, &mut operator_stack, None)?;
         }
 
-        while let Some(token) = self.tokens.peek() {
-            self.current_token = **token;
+        while let Some(token) = self.peek() {
+            self.current_token = token;
 
             match self.current_token.kind {
-                // End of math expression
-                TokenKind::RParen => {
+                // End of a bare math expression
+                TokenKind::Comma
+                | TokenKind::RSquiggly
+                | TokenKind::RngExclusive
+                | TokenKind::RngInclusive
+                | TokenKind::RngStep
+                | TokenKind::RngMutation
+                | TokenKind::RngUnique
+                | TokenKind::RngReverse
+                | TokenKind::RngLow
+                | TokenKind::RngHigh
+                | TokenKind::RngOrder(_)
+                    if groups.is_empty() =>
+                {
+                    break
+                }
+
+                // End of a parenthesis group
+                TokenKind::RParen if !groups.is_empty() => {
+                    let group = groups.pop().unwrap();
+
+                    if is_start && group.arg_count == 1 {
+                        return Err(ParserError::EmptyParen(ErrorContext::new(
+                            self.source.clone(),
+                            group.open_paren.span.merge(self.current_token.span),
+                        )));
+                    }
+                    if expect_operand {
+                        return Err(ParserError::ExpectedOperand(ErrorContext::new(
+                            self.source.clone(),
+                            self.gap_after_prev(),
+                        )));
+                    }
+
+                    while let Some(operator) = operator_stack.pop() {
+                        if operator.kind == TokenKind::LParen {
+                            break;
+                        }
+                        output_queue.push(operator);
+                    }
+
                     self.advance();
                     self.paren_depth -= 1;
-                    if self.paren_depth > 0 {
-                        todo!("Pop the opertator stack")
+                    last_operand = group.open_paren.span.merge(self.current_token.span);
+
+                    match group.func {
+                        Some((func, call_start)) => {
+                            let call_span = call_start.merge(self.current_token.span);
+                            last_operand = call_span;
+                            if group.arg_count != func.arity() {
+                                return Err(ParserError::InvalidArgCount(ErrorContext::new(
+                                    self.source.clone(),
+                                    call_span,
+                                )));
+                            }
+                            output_queue.push(Token::new(TokenKind::Func(func), call_span));
+                        }
+                        // the outermost group, its caller checks the argument count
+                        None if !bare && groups.is_empty() => return Ok(group.arg_count),
+                        None if group.arg_count != 1 => {
+                            return Err(ParserError::InvalidMathExpr(ErrorContext::new(
+                                self.source.clone(),
+                                start.merge(self.current_token.span),
+                            )));
+                        }
+                        None => {}
+                    }
+                    expect_operand = false;
+                }
+
+                // Function argument separator
+                TokenKind::Comma => {
+                    if expect_operand {
+                        return Err(ParserError::UnexpectedComma(ErrorContext::new(
+                            self.source.clone(),
+                            self.current_token.span,
+                        )));
+                    }
+
+                    while let Some(operator) = operator_stack.last() {
+                        if operator.kind == TokenKind::LParen {
+                            break;
+                        }
+                        output_queue.push(operator_stack.pop().unwrap());
+                    }
+
+                    self.advance();
+                    match groups.last_mut() {
+                        Some(group) => group.arg_count += 1,
+                        None => bare_arg_count += 1,
+                    }
+                    is_start = true;
+                    expect_operand = true;
+                    continue;
+                }
+
+                // Operands following an operand
+                TokenKind::LParen
+                | TokenKind::Int { .. }
+                | TokenKind::Func(_)
+                | TokenKind::Aggregate(_)
+                | TokenKind::RngMutArg
+                    if !expect_operand =>
+                {
+                    // multiplication written like in math notation, eg. `2(3 + 4)` or `(1 + 2)3`,
+                    // but not two numbers in a row like `(1 2)`
+                    let after_group = matches!(
+                        self.prev_token(),
+                        Some(Token {
+                            kind: TokenKind::RParen,
+                            ..
+                        })
+                    );
+                    if after_group || !matches!(self.current_token.kind, TokenKind::Int { .. }) {
+                        let operand = self.peek_operand_span();
+                        return Err(ParserError::ImplicitMultiplication(ErrorContext::with(
+                            self.source.clone(),
+                            last_operand.merge(operand),
+                            Span::new(last_operand.end as usize, operand.start as usize),
+                        )));
                     }
-                    break;
+                    return Err(ParserError::ExpectedOperator(ErrorContext::new(
+                        self.source.clone(),
+                        self.gap_after_prev(),
+                    )));
                 }
 
                 // Nested math expression
-                TokenKind::LParen => self.infix_to_postfix(start, ouput_queue, operator_stack)?,
+                TokenKind::LParen => {
+                    self.open_group(&mut groups, &mut operator_stack, None)?;
+                    is_start = true;
+                    continue;
+                }
+
+                // Function calls
+                TokenKind::Func(func) => {
+                    let call_start = self.current_token.span;
+
+                    match self.peek_nth(1) {
+                        Some(
+                            token @ Token {
+                                kind: TokenKind::LParen,
+                                ..
+                            },
+                        ) => {
+                            self.advance();
+                            self.current_token = token;
+                        }
+                        _ => {
+                            return Err(ParserError::IncompleteMathExpr(ErrorContext::new(
+                                self.source.clone(),
+                                start.merge(self.current_token.span),
+                            )))
+                        }
+                    }
+
+                    self.open_group(&mut groups, &mut operator_stack, Some((func, call_start)))?;
+                    is_start = true;
+                    continue;
+                }
+
+                // Aggregated ranges
+                #[cfg(feature = "aggregate")]
+                TokenKind::Aggregate(aggregate) => {
+                    let int_token = self.parse_aggregate(aggregate)?;
+                    output_queue.push(int_token);
+                    last_operand = int_token.span;
+                    expect_operand = false;
+                }
 
                 // Numbers
-                TokenKind::Int { .. } => {}
+                TokenKind::Int { .. } => {
+                    output_queue.push(self.current_token);
+                    last_operand = self.current_token.span;
+                    self.advance();
+                    expect_operand = false;
+                }
+
+                // The number being mutated
+                TokenKind::RngMutArg if self.in_mutation => {
+                    output_queue.push(self.current_token);
+                    last_operand = self.current_token.span;
+                    self.advance();
+                    expect_operand = false;
+                }
 
                 // Singular negative/positive numbers at the start of the expression/parenthesis
-                TokenKind::Math(op) if is_start => match op {
-                    Op::Add | Op::Sub => {
-                        let int_token = match self.parser_int()? {
-                            Node::Int { value, span } => Token::new(TokenKind::Int { value }, span),
-                            _ => unreachable!(),
+                // or after another operator
+                TokenKind::Math(op) if expect_operand => match op {
+                    // Prefix operators
+                    Op::BitNot => {
+                        operator_stack.push(self.current_token);
+                        self.advance();
+                    }
+                    // Signs of a group, a function call, `@` or `~`, eg. `-(2 + 3)` or `-abs(@)`
+                    Op::Add | Op::Sub
+                        if matches!(
+                            self.peek_past_signs().map(|token| token.kind),
+                            Some(
+                                TokenKind::LParen
+                                    | TokenKind::Func(_)
+                                    | TokenKind::Aggregate(_)
+                                    | TokenKind::RngMutArg
+                                    | TokenKind::Math(Op::BitNot)
+                            )
+                        ) =>
+                    {
+                        let unary = match op {
+                            Op::Sub => Op::UnarySub,
+                            _ => Op::UnaryAdd,
                         };
-                        ouput_queue.push(int_token);
+                        operator_stack
+                            .push(Token::new(TokenKind::Math(unary), self.current_token.span));
+                        self.advance();
                     }
+                    // Signs with nothing to apply to, eg. `(1 + + )`
+                    Op::Add | Op::Sub
+                        if !matches!(
+                            self.peek_past_signs(),
+                            Some(Token {
+                                kind: TokenKind::Int { .. },
+                                ..
+                            })
+                        ) =>
+                    {
+                        while let Some(TokenKind::Math(Op::Add | Op::Sub)) =
+                            self.peek().map(|token| token.kind)
+                        {
+                            self.advance();
+                        }
+                        return Err(ParserError::ExpectedOperand(ErrorContext::new(
+                            self.source.clone(),
+                            self.gap_after_prev(),
+                        )));
+                    }
+                    Op::Add | Op::Sub => {
+                        let int_token = self.parse_signed_int()?;
+                        let literal = self.current_token;
+                        self.advance();
+
+                        // `-2^2` is `-(2^2)`, so the sign is applied after the power
+                        match self.peek() {
+                            Some(Token {
+                                kind: TokenKind::Math(Op::Pow),
+                                ..
+                            }) if int_token.kind != literal.kind => {
+                                output_queue.push(literal);
+                                operator_stack.push(Token::new(
+                                    TokenKind::Math(Op::UnarySub),
+                                    Span::new(
+                                        int_token.span.start as usize,
+                                        literal.span.start as usize,
+                                    ),
+                                ));
+                            }
+                            _ => output_queue.push(int_token),
+                        }
+                        last_operand = int_token.span;
+                        expect_operand = false;
+                    }
+                    // Binary operators without a lhs, eg. `(* 3)`
                     _ => {
-                        return Err(ParserError::UnexpectedMathOp(
-                            self.input_chars.clone(),
-                            self.current_token.span,
-                        ))
+                        return Err(ParserError::ExpectedOperand(ErrorContext::new(
+                            self.source.clone(),
+                            self.gap_after_prev(),
+                        )))
                     }
                 },
 
-                // Math operators + negated numbers/nested math expression
-                TokenKind::Math(op) => {}
+                // Postfix operators apply straight away as nothing binds tighter
+                TokenKind::Math(op) if op.is_postfix() => {
+                    output_queue.push(self.current_token);
+                    last_operand = last_operand.merge(self.current_token.span);
+                    self.advance();
+                }
+
+                // Prefix operators between two operands, eg. `(6 ~ 9)`
+                TokenKind::Math(op) if op.is_prefix() => {
+                    return Err(ParserError::InvalidMathOp(ErrorContext::new(
+                        self.source.clone(),
+                        self.current_token.span,
+                    )));
+                }
+
+                // Math operators, the `(` sentinels stop the popping
+                TokenKind::Math(op) => {
+                    while let Some(Token {
+                        kind: TokenKind::Math(top),
+                        ..
+                    }) = operator_stack.last()
+                    {
+                        if top.precedence() > op.precedence()
+                            || (top.precedence() == op.precedence()
+                                && op.associativity() == Op::LEFT_ASSOC)
+                        {
+                            output_queue.push(operator_stack.pop().unwrap());
+                        } else {
+                            break;
+                        }
+                    }
+                    operator_stack.push(self.current_token);
+                    self.advance();
+                    expect_operand = true;
+                }
 
                 // Any other token is invalid syntax
                 _ => {
-                    return Err(ParserError::IncompleteMathExpr(
-                        self.input_chars.clone(),
-                        Span::new(start, token.span.end),
-                    ))
+                    return Err(ParserError::IncompleteMathExpr(ErrorContext::new(
+                        self.source.clone(),
+                        start.merge(token.span),
+                    )))
                 }
             }
             is_start = false;
         }
 
-        if token_count == 0 {
-            return Err(ParserError::EmptyParen(
-                self.input_chars.clone(),
-                Span::new(start, self.current_token.span.end + 1),
-            ));
+        if let Some(group) = groups.first() {
+            return Err(ParserError::UnmatchedParen(ErrorContext::new(
+                self.source.clone(),
+                group.open_paren.span,
+            )));
+        }
+
+        if expect_operand {
+            return Err(ParserError::ExpectedOperand(ErrorContext::new(
+                self.source.clone(),
+                self.gap_after_prev(),
+            )));
+        }
+
+        while let Some(operator) = operator_stack.pop() {
+            output_queue.push(operator);
+        }
+
+        Ok(bare_arg_count)
+    }
+
+    /// Moves the cursor past the `(` under it, pushing it onto the operator stack as
+    /// the sentinel of a new group. Errors if the group goes over the depth limit.
+    fn open_group(
+        &mut self,
+        groups: &mut Vec<Group>,
+        operator_stack: &mut Vec<Token>,
+        func: Option<(Func, Span)>,
+    ) -> Result<(), ParserError> {
+        let open_paren = self.current_token;
+        self.paren_depth += 1;
+        self.advance();
+
+        // point at the paren that went over the limit
+        if self.paren_depth > self.options.max_paren_depth {
+            return Err(ParserError::TooManyParen(ErrorContext::with(
+                self.source.clone(),
+                open_paren.span,
+                self.options.max_paren_depth,
+            )));
         }
 
+        operator_stack.push(open_paren);
+        groups.push(Group {
+            open_paren,
+            func,
+            arg_count: 1,
+        });
         Ok(())
     }
 }
+
+/// An open parenthesis group of [`Parser::infix_to_postfix`].
+#[derive(Debug)]
+struct Group {
+    open_paren: Token,
+    func: Option<(Func, Span)>, // the function being called and the span of its name
+    arg_count: usize,
+}
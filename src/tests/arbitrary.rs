@@ -0,0 +1,42 @@
+use arbitrary::{Arbitrary, Unstructured};
+use pretty_assertions::assert_eq;
+
+use super::rng::Rng;
+use crate::{lexer::Lexer, parser::Node, parser::Parser, tokens::Span};
+
+fn source(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[test]
+fn test_span_is_ordered() {
+    let mut rng = Rng(0xa5b1_7a8e);
+    for _ in 0..100 {
+        let bytes = (0..8).map(|_| rng.next() as u8).collect::<Vec<_>>();
+        let span = Span::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert!(span.start <= span.end, "{span:?}");
+    }
+}
+
+/// The source of arbitrary nodes parses back to the same nodes.
+#[test]
+fn test_display_round_trip() {
+    let mut rng = Rng(0x5eed_0867);
+    for _ in 0..500 {
+        let bytes = (0..rng.below(512))
+            .map(|_| rng.next() as u8)
+            .collect::<Vec<_>>();
+        let nodes = Vec::<Node>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        let input = source(&nodes);
+
+        let tokens = Lexer::new(&input).lex().expect(&input);
+        let parsed = Parser::new(input.as_str().into(), &tokens)
+            .parse()
+            .expect(&input);
+        assert_eq!(source(&parsed), input);
+    }
+}
@@ -0,0 +1,119 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    errors::{LexicalError, ParserError, Seq2Error},
+    eval::Evaluator,
+    lexer::Lexer,
+    parser::{Node, Parser},
+    tokens::SortOrder,
+    Seq2Builder,
+};
+
+use super::parser::assert_nodes_eq;
+
+fn parse(input: &str) -> Vec<Node> {
+    let tokens = Lexer::new(input).lex().unwrap();
+    Parser::new(input.into(), &tokens).parse().unwrap()
+}
+
+#[test]
+fn test_builder() {
+    let builder = Seq2Builder::new()
+        .int(5)
+        .int(i64::MIN)
+        .math("2 ^ 10")
+        .range(|r| r.start(1).end_inclusive(10).step(2).mutate("*3"))
+        .range(|r| r.end(5).unique().order(SortOrder::Desc))
+        .range(|r| r.start(-3).end(3).mutate("@ % 2").unique())
+        .range(|r| r.start(1).end(10).step(3).reverse());
+    let (nodes, source) = builder.build().unwrap();
+    assert_eq!(
+        source,
+        "5, -9223372036854775808, (2 ^ 10), {1..=10, s:2, m:*3}, {..5, u:, o:desc}, {-3..3, m:@ % 2, u:}, {1..10, s:3, r:}"
+    );
+    assert_eq!(source, builder.to_string());
+
+    // the canonical source parses back to the same nodes
+    assert_nodes_eq(&source, &nodes, &parse(&source));
+    let numbers = Evaluator::new(source.as_str().into()).eval(&nodes).unwrap();
+    assert_eq!(
+        numbers,
+        [
+            5,
+            i64::MIN,
+            1024,
+            3,
+            9,
+            15,
+            21,
+            27,
+            4,
+            3,
+            2,
+            1,
+            0,
+            -1,
+            0,
+            1,
+            7,
+            4,
+            1
+        ]
+    );
+
+    assert_eq!(Seq2Builder::new().build().unwrap(), (vec![], String::new()));
+}
+
+#[test]
+fn test_builder_clamp() {
+    let builder = Seq2Builder::new()
+        .range(|r| r.start(1).end_inclusive(5).mutate("*3").low(4).high(10))
+        .range(|r| r.end(3).high(1));
+    let (nodes, source) = builder.build().unwrap();
+    assert_eq!(source, "{1..=5, m:*3, lo:4, hi:10}, {..3, hi:1}");
+    assert_nodes_eq(&source, &nodes, &parse(&source));
+    let numbers = Evaluator::new(source.as_str().into()).eval(&nodes).unwrap();
+    assert_eq!(numbers, [4, 6, 9, 10, 10, 0, 1, 1]);
+
+    let error = Seq2Builder::new()
+        .range(|r| r.end(3).low(2).high(1))
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Seq2Error::Parser(ParserError::InvertedClamp(..))
+    ));
+}
+
+#[test]
+fn test_builder_errors() {
+    // validated like any other input
+    let error = Seq2Builder::new()
+        .range(|r| r.start(1).end(10).step(-1))
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Seq2Error::Parser(ParserError::StepDirectionMismatch(..))
+    ));
+
+    let error = Seq2Builder::new()
+        .math("9223372036854775808 - 1")
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Seq2Error::Lexical(LexicalError::NumberTooLarge(..))
+    ));
+
+    let error = Seq2Builder::new()
+        .range(|r| r.start(1))
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Seq2Error::Parser(ParserError::MissingRangeEnd(..))
+    ));
+    // the spans point into the canonical source
+    assert_eq!(error.span().unwrap().range(), 2..4);
+}
@@ -0,0 +1,70 @@
+use pretty_assertions::assert_eq;
+
+use clap::{error::ErrorKind, Arg, Command};
+
+use crate::{
+    clap::Seq2ValuesError,
+    clap_parser,
+    errors::{ParserError, Seq2Error},
+    Seq2Values,
+};
+
+fn command() -> Command {
+    Command::new("app")
+        .arg(Arg::new("ids").long("ids").value_parser(clap_parser()))
+        .arg(
+            Arg::new("skip")
+                .long("skip")
+                .value_parser(clap::value_parser!(Seq2Values)),
+        )
+}
+
+#[test]
+fn test_from_str() {
+    let values: Seq2Values = "{1..=10, s:2}, 42".parse().unwrap();
+    assert_eq!(values, [1, 3, 5, 7, 9, 42]);
+    assert_eq!(Vec::from(values), [1, 3, 5, 7, 9, 42]);
+    assert_eq!("".parse::<Seq2Values>().unwrap(), Seq2Values::default());
+
+    let error = "1, 2 3".parse::<Seq2Values>().unwrap_err();
+    assert!(matches!(
+        error,
+        Seq2ValuesError(Seq2Error::Parser(ParserError::MissingComma(..)))
+    ));
+    // the plain rendering, without the trailing newline
+    assert_eq!(error.to_string(), error.0.render_plain().trim_end());
+    assert!(!error.to_string().contains('\x1b'));
+}
+
+#[test]
+fn test_value_parser() {
+    let matches = command()
+        .try_get_matches_from(["app", "--ids", "{1..=10, s:2}, 42", "--skip", "(3 * 3)"])
+        .unwrap();
+    assert_eq!(
+        matches.get_one::<Seq2Values>("ids").unwrap(),
+        &[1, 3, 5, 7, 9, 42]
+    );
+    assert_eq!(matches.get_one::<Seq2Values>("skip").unwrap(), &[9]);
+}
+
+#[test]
+fn test_value_parser_errors() {
+    let error = command()
+        .try_get_matches_from(["app", "--ids", "1, 2 3"])
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ValueValidation);
+    let rendered = error.render().to_string();
+    println!("{rendered}");
+    assert!(rendered.contains("invalid value '1, 2 3' for '--ids <ids>'"));
+    assert!(rendered.contains("@ position 5 - Missing ',' between items"));
+    assert!(rendered.contains("│ 1, 2 3"));
+    assert!(!rendered.contains('\x1b'));
+
+    // `value_parser!` picks up the same parser
+    let error = command()
+        .try_get_matches_from(["app", "--skip", "(MAX + 1)"])
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ValueValidation);
+    assert!(error.to_string().contains("for '--skip <skip>'"));
+}
@@ -0,0 +1,404 @@
+//! Differential testing of the evaluator against a slow but obviously correct reference
+//! interpreter, which evaluates the source text directly with `i128` math.
+
+use alloc::collections::BTreeSet;
+
+use proptest::prelude::*;
+
+use super::props::{int, math_expr, range};
+use crate::{eval::Evaluator, parser::Parser};
+
+/// Codes of the evaluation errors the reference tells apart, see [`crate::errors::EvalError`].
+const DIVISION_BY_ZERO: &str = "E0201";
+const INVALID_FACTORIAL: &str = "E0202";
+const INVALID_SHIFT: &str = "E0203";
+const OVERFLOW: &str = "E0204";
+const ZERO_STEP: &str = "E0205";
+
+type RefResult = Result<i128, &'static str>;
+
+/// Binary operators with their precedence, all of them are left associative.
+/// `^` is right associative and binds tighter than the prefix operators, see [`Reference::power`].
+const BINARY_OPS: [(&str, u8); 9] = [
+    ("<<", 3),
+    (">>", 3),
+    ("|", 1),
+    ("&", 2),
+    ("+", 4),
+    ("-", 4),
+    ("*", 5),
+    ("/", 5),
+    ("%", 5),
+];
+
+/// Every intermediate value has to fit an `i64`.
+fn fit(value: i128) -> RefResult {
+    match i64::try_from(value) {
+        Ok(_) => Ok(value),
+        Err(_) => Err(OVERFLOW),
+    }
+}
+
+fn binary(op: &str, lhs: i128, rhs: i128) -> RefResult {
+    match op {
+        "+" => fit(lhs + rhs),
+        "-" => fit(lhs - rhs),
+        "*" => fit(lhs * rhs),
+        "/" | "%" if rhs == 0 => Err(DIVISION_BY_ZERO),
+        // both truncate towards zero, like the math on `i64`
+        "/" => fit(lhs / rhs),
+        "%" => fit(lhs % rhs),
+        "^" => pow(lhs, rhs),
+        "&" => Ok(lhs & rhs),
+        "|" => Ok(lhs | rhs),
+        "<<" | ">>" if !(0..64).contains(&rhs) => Err(INVALID_SHIFT),
+        // bits shifted past the 64th are lost
+        "<<" => Ok(i128::from((lhs << rhs) as i64)),
+        ">>" => Ok(lhs >> rhs),
+        _ => unreachable!("Unknown operator {op}"),
+    }
+}
+
+fn pow(base: i128, exp: i128) -> RefResult {
+    if exp < 0 {
+        // 1 / base^-exp, truncated towards zero
+        return match base {
+            0 => Err(DIVISION_BY_ZERO),
+            1 => Ok(1),
+            -1 => Ok(if exp % 2 == 0 { 1 } else { -1 }),
+            _ => Ok(0),
+        };
+    }
+
+    // past 64 multiplications any base but 0, 1 and -1 has overflowed, and those only
+    // depend on whether the exponent is even
+    let exp = if exp > 64 { 64 + exp % 2 } else { exp };
+    let mut result = 1;
+    for _ in 0..exp {
+        result = fit(result * base)?;
+    }
+    Ok(result)
+}
+
+fn factorial(value: i128) -> RefResult {
+    if value < 0 {
+        return Err(INVALID_FACTORIAL);
+    }
+    let mut result = 1;
+    for factor in 2..=value {
+        result = fit(result * factor)?;
+    }
+    Ok(result)
+}
+
+fn func(name: &str, args: &[i128]) -> RefResult {
+    match (name, args) {
+        ("abs", [value]) => fit(value.abs()),
+        ("min", [a, b]) => Ok(*a.min(b)),
+        ("max", [a, b]) => Ok(*a.max(b)),
+        ("gcd", [a, b]) => {
+            let (mut a, mut b) = (a.abs(), b.abs());
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            fit(a)
+        }
+        _ => unreachable!("Unknown function {name}"),
+    }
+}
+
+/// Reference interpreter, walking the source text one char at a time.
+struct Reference<'a> {
+    rest: &'a str,
+    /// Value of `@`, while evaluating a range mutation.
+    at: Option<i128>,
+}
+
+impl<'a> Reference<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            rest: input,
+            at: None,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(text) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, text: &str) {
+        assert!(self.eat(text), "Expected {text:?} at {:?}", self.rest);
+    }
+
+    /// Takes the chars for as long as `pred` holds.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let len = self.rest.find(|ch| !pred(ch)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        taken
+    }
+
+    /// Evaluates all the items into their numbers.
+    fn eval(&mut self) -> Result<Vec<i64>, &'static str> {
+        let mut numbers = vec![];
+        loop {
+            if self.eat("{") {
+                numbers.extend(self.range()?);
+            } else {
+                numbers.push(self.expr(0)? as i64);
+            }
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.skip_whitespace();
+        assert!(self.rest.is_empty(), "Leftover input {:?}", self.rest);
+        Ok(numbers)
+    }
+
+    /// Evaluates the binary operators with at least `min_precedence`.
+    fn expr(&mut self, min_precedence: u8) -> RefResult {
+        let mut lhs = self.prefix()?;
+        loop {
+            self.skip_whitespace();
+            let Some((op, precedence)) = BINARY_OPS
+                .into_iter()
+                .find(|(op, _)| self.rest.starts_with(op))
+            else {
+                break;
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.rest = &self.rest[op.len()..];
+            let rhs = self.expr(precedence + 1)?;
+            lhs = binary(op, lhs, rhs)?;
+        }
+        Ok(lhs)
+    }
+
+    /// Prefix operators bind tighter than the other binary ones, but looser than `^` and `!`.
+    fn prefix(&mut self) -> RefResult {
+        self.skip_whitespace();
+        if self.rest.starts_with("-9223372036854775808") {
+            return self.power();
+        }
+        if self.eat("-") {
+            return fit(-self.prefix()?);
+        }
+        if self.eat("+") {
+            return self.prefix();
+        }
+        if self.eat("~") {
+            return Ok(!self.prefix()?);
+        }
+        self.power()
+    }
+
+    /// `^` is right associative, and its exponent can have prefix operators, eg. `2^-3`.
+    fn power(&mut self) -> RefResult {
+        // the lexer reads `-9223372036854775808` as a single number
+        let base = match self.rest.strip_prefix("-9223372036854775808") {
+            Some(rest) => {
+                self.rest = rest;
+                self.postfix(i128::from(i64::MIN))?
+            }
+            None => {
+                let value = self.primary()?;
+                self.postfix(value)?
+            }
+        };
+        self.skip_whitespace();
+        match self.eat("^") {
+            true => pow(base, self.prefix()?),
+            false => Ok(base),
+        }
+    }
+
+    fn postfix(&mut self, mut value: i128) -> RefResult {
+        while self.eat("!") {
+            value = factorial(value)?;
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> RefResult {
+        self.skip_whitespace();
+        if self.eat("(") {
+            let value = self.expr(0)?;
+            self.expect(")");
+            return Ok(value);
+        }
+        if self.eat("@") {
+            return Ok(self.at.expect("'@' outside of a mutation"));
+        }
+
+        let word = self.take_while(|ch| ch.is_ascii_alphabetic());
+        match word {
+            "MAX" => return Ok(i128::from(i64::MAX)),
+            "MIN" => return Ok(i128::from(i64::MIN)),
+            "" => {}
+            name => {
+                self.expect("(");
+                let mut args = vec![self.expr(0)?];
+                while self.eat(",") {
+                    args.push(self.expr(0)?);
+                }
+                self.expect(")");
+                return func(name, &args);
+            }
+        }
+
+        let digits = self.take_while(|ch| ch.is_ascii_digit());
+        let mut value: i128 = digits.parse().expect("Expected a number");
+        if self.rest.starts_with('e') {
+            self.rest = &self.rest[1..];
+            let exponent: u32 = self.take_while(|ch| ch.is_ascii_digit()).parse().unwrap();
+            for _ in 0..exponent {
+                value *= 10;
+            }
+        }
+        fit(value)
+    }
+
+    /// Evaluates a range, after its `{`.
+    fn range(&mut self) -> Result<Vec<i64>, &'static str> {
+        let start = self.expr(0)?;
+        let inclusive = match self.eat("..=") {
+            true => true,
+            false => {
+                self.expect("..");
+                false
+            }
+        };
+        let end = self.expr(0)?;
+
+        let (mut step, mut mutation, mut unique, mut reverse, mut order) =
+            (None, None, false, false, None);
+        let (mut clamp_low, mut clamp_high) = (i128::from(i64::MIN), i128::from(i64::MAX));
+        while self.eat(",") {
+            if self.eat("lo:") {
+                clamp_low = self.expr(0)?;
+            } else if self.eat("hi:") {
+                clamp_high = self.expr(0)?;
+            } else if self.eat("s:") {
+                step = Some(self.expr(0)?);
+            } else if self.eat("m:") {
+                mutation = Some(self.take_while(|ch| !matches!(ch, ',' | '}')));
+            } else if self.eat("u:") {
+                unique = true;
+            } else if self.eat("r:") {
+                reverse = true;
+            } else {
+                self.expect("o:");
+                order = Some(self.take_while(|ch| ch.is_ascii_alphabetic()));
+            }
+        }
+        self.expect("}");
+
+        let step = match step {
+            Some(0) => return Err(ZERO_STEP),
+            Some(step) => step,
+            None if start <= end => 1,
+            None => -1,
+        };
+        let (low, high) = (start.min(end), start.max(end));
+
+        let mut values = vec![];
+        let mut value = start;
+        while low <= value && value <= high && (inclusive || value != end) {
+            values.push(value);
+            value += step;
+        }
+        if reverse {
+            values.reverse();
+        }
+
+        let mut numbers = vec![];
+        let mut seen = BTreeSet::new();
+        for value in values {
+            let number = match mutation {
+                Some(mutation) => mutate(mutation, value)?,
+                None => value,
+            };
+            let number = number.clamp(clamp_low, clamp_high);
+            if !unique || seen.insert(number) {
+                numbers.push(number as i64);
+            }
+        }
+
+        match order {
+            Some("asc") => numbers.sort(),
+            Some("desc") => numbers.sort_by(|a, b| b.cmp(a)),
+            _ => {}
+        }
+        Ok(numbers)
+    }
+}
+
+/// Applies a mutation like `*3` or `@ ^ 2` to `value`.
+fn mutate(mutation: &str, value: i128) -> RefResult {
+    let mutation = mutation.trim();
+    let source = match mutation.starts_with(|ch: char| "+-*/%^&|<>".contains(ch)) {
+        true => format!("@ {mutation}"),
+        false => mutation.to_string(),
+    };
+    let mut reference = Reference::new(&source);
+    reference.at = Some(value);
+    let value = reference.expr(0)?;
+    reference.skip_whitespace();
+    assert!(reference.rest.is_empty(), "Leftover mutation {source:?}");
+    Ok(value)
+}
+
+/// Operators chained without parenthesis in between, to go through the precedence rules.
+fn chain() -> impl Strategy<Value = String> {
+    let op = prop::sample::select(vec!["+", "-", "*", "/", "%", "^", "&", "|", "<<", ">>"]);
+    let operand = prop_oneof![
+        4 => int(),
+        1 => math_expr(),
+        1 => int().prop_map(|value| format!("~{value}")),
+        1 => math_expr().prop_map(|expr| format!("-{expr}")),
+        1 => (-3i64..25).prop_map(|value| format!("({value})!")),
+        1 => int().prop_map(|value| format!("abs({value})")),
+        1 => (int(), int()).prop_map(|(a, b)| format!("gcd({a}, {b})")),
+        1 => (int(), int()).prop_map(|(a, b)| format!("min({a}, {b})")),
+    ];
+    (operand.clone(), prop::collection::vec((op, operand), 1..5)).prop_map(|(first, rest)| {
+        let rest: String = rest
+            .iter()
+            .map(|(op, operand)| format!(" {op} {operand}"))
+            .collect();
+        format!("({first}{rest})")
+    })
+}
+
+fn differential_input() -> impl Strategy<Value = String> {
+    let negated = math_expr().prop_map(|expr| format!("-{expr}"));
+    let item = prop_oneof![int(), math_expr(), negated, chain(), range()];
+    prop::collection::vec(item, 1..6).prop_map(|items| items.join(", "))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(3_000))]
+
+    #[test]
+    fn evaluator_matches_reference(input in differential_input()) {
+        let nodes = Parser::from_source(&input).unwrap().parse().unwrap();
+        let numbers = Evaluator::new(input.as_str().into()).eval(&nodes);
+
+        let expected = Reference::new(&input).eval();
+        prop_assert_eq!(numbers.map_err(|e| e.code()), expected, "{:?}", input);
+    }
+}
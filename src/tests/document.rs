@@ -0,0 +1,125 @@
+use pretty_assertions::assert_eq;
+use std::collections::HashSet;
+
+use crate::{
+    document::{parse, parse_all_errors},
+    tokens::Span,
+};
+
+#[test]
+fn test_document() {
+    let source =
+        "# ports to listen on\nports = {8000..=8002}\r\n\n  retries=1, 2, 4, 8  # backoff\n";
+    let sequences = parse(source).unwrap();
+    assert_eq!(sequences.keys().collect::<Vec<_>>(), ["ports", "retries"]);
+    assert_eq!(sequences["ports"].line, 2);
+    assert_eq!(sequences["ports"].eval().unwrap(), [8000, 8001, 8002]);
+    assert_eq!(sequences["retries"].line, 4);
+    assert_eq!(sequences["retries"].eval().unwrap(), [1, 2, 4, 8]);
+
+    assert!(parse("").unwrap().is_empty());
+}
+
+#[test]
+fn test_error_recovery() {
+    let source = "ports = {8000..=8002}\nretries = 1, 2 4, 8\nsizes = {1..=3} x 2\n";
+    let error = parse(source).unwrap_err();
+    assert_eq!(error.code(), "E0112");
+    assert_eq!(error.line(), 2);
+    // the span of the error is moved from the expression into the document
+    assert_eq!(error.span(), Span::new(36, 37));
+    assert_eq!(error.message(), "@ line 2 - Missing ',' between items");
+
+    let (sequences, errors) = parse_all_errors(source);
+    assert_eq!(sequences.keys().collect::<Vec<_>>(), ["ports", "sizes"]);
+    assert_eq!(sequences["ports"].eval().unwrap(), [8000, 8001, 8002]);
+    assert_eq!(sequences["sizes"].eval().unwrap(), [1, 2, 3, 1, 2, 3]);
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.code(), e.line()))
+            .collect::<Vec<_>>(),
+        [("E0112", 2)]
+    );
+}
+
+#[test]
+fn test_document_errors() {
+    let cases = [
+        ("E0401", "= 1, 2", Span::new(0, 6)),
+        ("E0401", "  {1..=3}  ", Span::new(2, 9)),
+        ("E0402", "ports {1..=3}", Span::new(6, 7)),
+        ("E0402", "ports", Span::new(5, 5)),
+        ("E0403", "ports =", Span::new(6, 7)),
+        ("E0403", "ports = # todo", Span::new(6, 7)),
+        // the errors of the expression keep their code
+        ("E0001", "ports = 1, $", Span::new(11, 12)),
+        ("E0113", "ports = {1..}", Span::new(10, 12)),
+    ];
+    for (code, source, span) in cases {
+        let error = parse(source).unwrap_err();
+        assert_eq!((error.code(), error.span()), (code, span), "{source:?}");
+    }
+
+    let source = "ports = 1\nretries = 3\nports = 2";
+    let (sequences, errors) = parse_all_errors(source);
+    assert_eq!(sequences["ports"].eval().unwrap(), [1]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code(), "E0404");
+    assert_eq!(errors[0].span().text(source), Some("ports"));
+    assert_eq!(
+        errors[0].message(),
+        "@ line 3 - 'ports' is already defined on line 1"
+    );
+}
+
+#[test]
+fn test_collect() {
+    let sequences = parse("ids = 3, {1..=5}, 3, (2 * 2)\nsizes = 10, {250..=260, s:5}, 1").unwrap();
+
+    // the repeated numbers are left out by the set
+    let ids: HashSet<i64> = sequences["ids"].collect().unwrap();
+    assert_eq!(ids, HashSet::from([1, 2, 3, 4, 5]));
+    let ids: Vec<u32> = sequences["ids"].collect_checked().unwrap();
+    assert_eq!(ids, [3, 1, 2, 3, 4, 5, 3, 4]);
+
+    // 260 doesn't fit in a `u8`, the error points at the range it came from
+    let error = sequences["sizes"]
+        .collect_checked::<u8, Vec<_>>()
+        .unwrap_err();
+    assert_eq!(error.code(), "E0209");
+    assert_eq!(
+        error.span().text(&sequences["sizes"].source),
+        Some("{250..=260, s:5}")
+    );
+    assert_eq!(
+        error.message(),
+        "@ position 5-20 - 260 does not fit in a u8"
+    );
+
+    // the spans point into the expression of the line, like those of the errors
+    let sequences = parse("mixed = {1..=2}, (3 * 3), {5..=4, m:-1}").unwrap();
+    let mixed = sequences["mixed"].values_with_provenance().unwrap();
+    let spans = mixed
+        .iter()
+        .map(|(number, span)| (*number, span.text(&sequences["mixed"].source).unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        [
+            (1, "{1..=2}"),
+            (2, "{1..=2}"),
+            (9, "(3 * 3)"),
+            (4, "{5..=4, m:-1}"),
+            (3, "{5..=4, m:-1}"),
+        ]
+    );
+    assert_eq!(mixed[2].1, Span::new(9, 16));
+
+    // the errors of the evaluation come first
+    let sequences = parse("overflow = (MAX + 1), -1").unwrap();
+    let error = sequences["overflow"]
+        .collect_checked::<u8, Vec<_>>()
+        .unwrap_err();
+    assert_eq!(error.code(), "E0204");
+}
@@ -0,0 +1,459 @@
+#[cfg(feature = "std")]
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+use std::sync::Arc;
+
+#[cfg(feature = "std")]
+use crate::errors::Bound;
+use crate::{
+    errors::{DocumentError, ErrorContext, EvalError, LexicalError, ParserError, Seq2Error},
+    lexer::Lexer,
+    parser::Parser,
+    tokens::Span,
+};
+
+#[test]
+fn test_line_col() {
+    let input = "1, 2,\n{1..=3},\t(4 * 5)\n6";
+    assert_eq!(Span::new(0, 1).line_col(input), ((1, 1), (1, 1)));
+    assert_eq!(Span::new(6, 13).line_col(input), ((2, 1), (2, 7)));
+    assert_eq!(Span::new(15, 22).line_col(input), ((2, 10), (2, 16)));
+    assert_eq!(Span::new(3, 8).line_col(input), ((1, 4), (2, 2)));
+    assert_eq!(Span::new(23, 24).line_col(input), ((3, 1), (3, 1)));
+}
+
+#[cfg(feature = "std")]
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => {
+                chars.by_ref().find(|ch| *ch == 'm');
+            }
+            _ => plain.push(ch),
+        }
+    }
+    plain
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_render_line() {
+    // (input, offending line, caret line, location)
+    let cases = [
+        ("1, $\n2,\n3", "│ 1, ", "│    ^", "line 1, column 4"),
+        ("1,\n2, $,\n3", "│ 2, ", "│    ^", "line 2, column 4"),
+        ("1,\n2,\n\t3, $$", "│ \t3, ", "│ \t   ^", "line 3, column 5"),
+    ];
+
+    for (input, line, caret, location) in cases {
+        let tokens = Lexer::new(input).lex();
+        if let Err(err @ LexicalError::InvalidToken(..)) = tokens {
+            let rendered = strip_ansi(&err.to_string());
+            println!("{rendered}");
+            assert!(rendered.contains(location), "input: {input:?}");
+            let lines = rendered.lines().collect::<Vec<_>>();
+            assert!(lines[3].starts_with(line), "input: {input:?}");
+            assert!(lines[4].starts_with(caret), "input: {input:?}");
+            // the other lines of the input are not shown
+            assert_eq!(lines.len(), 6, "input: {input:?}");
+        } else {
+            panic!("Expected InvalidToken error for {input:?}");
+        }
+    }
+}
+
+#[test]
+fn test_degenerate_spans() {
+    let spans = [
+        Span::new(0, 0),
+        Span::new(4, 4),
+        Span::new(4, 10),
+        Span::new(20, 30),
+        Span::new(3, 1),
+        Span::new(1, 2),
+    ];
+
+    for span in spans {
+        for input in ["1, 2", "é, ü", ""] {
+            let errors = [
+                LexicalError::InvalidToken(ErrorContext::new(Arc::from(input), span)).to_string(),
+                LexicalError::UnknownKeyword(ErrorContext::new(Arc::from(input), span)).to_string(),
+                ParserError::IncompleteInt(ErrorContext::new(Arc::from(input), span)).to_string(),
+                ParserError::InvalidInt(ErrorContext::new(Arc::from(input), span)).to_string(),
+                ParserError::InvalidArgCount(ErrorContext::new(Arc::from(input), span)).to_string(),
+                EvalError::Overflow(ErrorContext::with(Arc::from(input), span, None)).to_string(),
+            ];
+            for error in errors {
+                #[cfg(feature = "std")]
+                assert!(error.contains("ERROR"), "input: {input:?}, span: {span:?}");
+                assert!(
+                    error.contains("@ position"),
+                    "input: {input:?}, span: {span:?}"
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    {
+        // errors at the end of the input are marked with a highlighted space
+        let input = "1, -";
+        let error =
+            ParserError::IncompleteInt(ErrorContext::new(Arc::from(input), Span::new(4, 5)));
+        let rendered = strip_ansi(&error.to_string());
+        println!("{rendered}");
+        assert!(rendered.contains("│ 1, - \n"));
+        assert!(rendered.contains("│     ^\n"));
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_render_plain() {
+    let input = "1, (2 * 3, 4";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let error = Parser::new(input.into(), &tokens).parse().unwrap_err();
+    let rendered = error.render_plain();
+    println!("{rendered}");
+    assert!(!rendered.contains("\x1b["));
+    assert_eq!(
+        rendered.lines().collect::<Vec<_>>()[3..5],
+        ["│ 1, (2 * 3, 4", "│    ^"]
+    );
+
+    let error = Lexer::new("{1..5, foo:2}").lex().unwrap_err();
+    assert!(!error.render_plain().contains("\x1b["));
+    // no hint, so the caret line closes the box
+    #[cfg(not(feature = "fun"))]
+    assert!(error.render_plain().contains("╰╴       ^^^"));
+
+    let error = EvalError::Overflow(ErrorContext::with(
+        Arc::from("(MAX + 1)"),
+        Span::new(5, 6),
+        Some(Bound::Max),
+    ));
+    assert!(!error.render_plain().contains("\x1b["));
+}
+
+#[test]
+fn test_error_codes() {
+    let src: Arc<str> = Arc::from("1, 2");
+    let span = Span::new(0, 1);
+    #[rustfmt::skip]
+    let codes = [
+        LexicalError::InvalidToken(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::MissingColon(ErrorContext::with(src.clone(), span, None)).code(),
+        LexicalError::InvalidRange(ErrorContext::with(src.clone(), span, None)).code(),
+        LexicalError::UnexpectedEqual(ErrorContext::with(src.clone(), span, None)).code(),
+        LexicalError::MalformedNumber(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::MisplacedRngSyntax(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::NumberTooLarge(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::UnknownKeyword(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::UnknownFunc(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::UnknownRangeArg(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::InvalidSortOrder(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::InputTooLarge(ErrorContext::new(src.clone(), span)).code(),
+        LexicalError::NumberTooSmall(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::AggregateOverflow(ErrorContext::with(src.clone(), span, None)).code(),
+        ParserError::EmptyParen(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::IncompleteInt(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::IncompleteMathExpr(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidAggregate(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidArgCount(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidFlagValue(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidInt(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidMathOp(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidMathExpr(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidRangeExpr(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::MissingComma(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::MissingRangeEnd(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::TooManyParen(ErrorContext::with(src.clone(), span, 69)).code(),
+        ParserError::TooManyTokens(ErrorContext::with(src.clone(), span, 69)).code(),
+        ParserError::TooManyItems(ErrorContext::with(src.clone(), span, 69)).code(),
+        ParserError::UnmatchedBrace(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::UnmatchedParen(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::UnexpectedComma(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::UnexpectedMathOp(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::RangeOutsideBraces(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::MisplacedRngSyntax(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::MathOutsideParen(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::ZeroStep(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::StepDirectionMismatch(ErrorContext::with(src.clone(), span, span)).code(),
+        ParserError::DuplicateRangeArg(ErrorContext::with(src.clone(), span, span)).code(),
+        ParserError::ExpectedRangeArg(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::NumberTooLarge(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::NestedRange(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::MissingRangeBounds(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::MissingZipOperand(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvalidRepeatCount(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::NonPositiveRepeatCount(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::MissingRepeatItem(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::InvertedClamp(ErrorContext::with(src.clone(), span, span)).code(),
+        ParserError::ImplicitMultiplication(ErrorContext::with(src.clone(), span, span)).code(),
+        ParserError::ExpectedOperand(ErrorContext::new(src.clone(), span)).code(),
+        ParserError::ExpectedOperator(ErrorContext::new(src.clone(), span)).code(),
+        EvalError::DivisionByZero(ErrorContext::new(src.clone(), span)).code(),
+        EvalError::InvalidFactorial(ErrorContext::new(src.clone(), span)).code(),
+        EvalError::InvalidShift(ErrorContext::new(src.clone(), span)).code(),
+        EvalError::Overflow(ErrorContext::with(src.clone(), span, None)).code(),
+        EvalError::ZeroStep(ErrorContext::new(src.clone(), span)).code(),
+        EvalError::NonTerminatingRange(ErrorContext::new(src.clone(), span)).code(),
+        EvalError::BudgetExceeded(ErrorContext::with(src.clone(), span, 0)).code(),
+        EvalError::InvertedClamp(ErrorContext::new(src.clone(), span)).code(),
+        EvalError::OutOfRange(ErrorContext::with(src.clone(), span, (-1, "u8"))).code(),
+        DocumentError::MissingName(ErrorContext::new(src.clone(), span)).code(),
+        DocumentError::MissingEqual(ErrorContext::new(src.clone(), span)).code(),
+        DocumentError::EmptyValue(ErrorContext::new(src.clone(), span)).code(),
+        DocumentError::DuplicateName(ErrorContext::with(src.clone(), span, 1)).code(),
+    ];
+
+    let unique = codes.iter().collect::<std::collections::HashSet<_>>();
+    assert_eq!(unique.len(), codes.len());
+    assert!(codes
+        .iter()
+        .all(|code| code.len() == 5 && code.starts_with('E')));
+}
+
+#[test]
+fn test_error_accessors() {
+    let input = "1, 2 3";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let error = Parser::new(input.into(), &tokens).parse().unwrap_err();
+    assert_eq!(error.code(), "E0112");
+    assert_eq!(error.span(), Span::new(4, 5));
+    assert_eq!(error.message(), "@ position 5 - Missing ',' between items");
+}
+
+#[cfg(not(feature = "std"))]
+#[test]
+fn test_display_without_std() {
+    // just the message, there is no excerpt of the source without `std`
+    let error = crate::parse("1, 2 3").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "@ position 5 - Missing ',' between items"
+    );
+    let error = Lexer::new("{1..5, foo:2}").lex().unwrap_err();
+    assert_eq!(error.to_string(), error.message());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_seq2_error() {
+    assert_eq!(crate::parse("1, {3..=1}, (2 ^ 3)").unwrap(), [1, 3, 2, 1, 8]);
+    assert!(crate::parse("  # nothing here").unwrap().is_empty());
+
+    let error = crate::parse("1, $").unwrap_err();
+    assert!(matches!(error, Seq2Error::Lexical(LexicalError::InvalidToken(..))));
+    assert_eq!(error.code(), "E0001");
+    assert_eq!(error.span(), Some(Span::new(3, 4)));
+
+    let error = crate::parse("1, 2 3").unwrap_err();
+    assert!(matches!(error, Seq2Error::Parser(ParserError::MissingComma(..))));
+    assert_eq!(error.message(), "@ position 5 - Missing ',' between items");
+
+    let error = crate::parse("(MAX + 1)").unwrap_err();
+    assert!(matches!(error, Seq2Error::Eval(EvalError::Overflow(..))));
+    #[cfg(feature = "std")]
+    assert!(error.render_plain().contains("│      ^"));
+}
+
+#[test]
+fn test_hints() {
+    let hint = |input: &str| crate::parse(input).unwrap_err().hint();
+    assert_eq!(
+        hint("{1...5}").unwrap(),
+        "use '..' for an exclusive range or '..=' for an inclusive range"
+    );
+    assert_eq!(
+        hint("{1..5, s 2}").unwrap(),
+        "range arguments are written as 's:<STEP>'"
+    );
+    assert_eq!(
+        hint("{1..5, u:2}").unwrap(),
+        "write 'u:' or 'u:1' to turn the flag on, 'u:0' to turn it off"
+    );
+    assert_eq!(hint("{1..5, foo:2}"), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_rendered_hints() {
+    let rendered = crate::parse("99999999999999999999")
+        .unwrap_err()
+        .render_plain();
+    println!("{rendered}");
+    assert!(rendered.ends_with("╰╴= HINT: split the value into smaller numbers or use 'MAX'\n"));
+
+    let rendered = crate::parse("(foo(1))").unwrap_err().render_plain();
+    println!("{rendered}");
+    #[cfg(not(feature = "fun"))]
+    assert!(!rendered.contains("HINT"));
+    #[cfg(feature = "fun")]
+    assert!(rendered.ends_with("╰╴= HINT: touch grass ;)\n"));
+    assert!(rendered.lines().last().unwrap().starts_with("╰╴"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_suggestions() {
+    let render = |input: &str| crate::parse(input).unwrap_err().render_plain();
+
+    assert_eq!(
+        render("{1...5}"),
+        indoc! {"
+            ╭╴ERROR: @ position 3-5 - Invalid range syntax
+            │ --> line 1, column 3
+            │ 
+            │ {1...5}
+            │   ^^^
+            ├╴= HINT: use '..' for an exclusive range or '..=' for an inclusive range
+            ╰╴= SUGGESTION: did you mean '..=' or '..'?
+        "}
+    );
+    assert_eq!(
+        render("{1.=.5}"),
+        indoc! {"
+            ╭╴ERROR: @ position 3 - Unexpected '='
+            │ --> line 1, column 3
+            │ 
+            │ {1.=.5}
+            │   ^^^
+            ├╴= HINT: the '=' of an inclusive range goes after the dots, eg. '{1..=5}'
+            ╰╴= SUGGESTION: did you mean '..='?
+        "}
+    );
+    assert_eq!(
+        render("{1=..5}"),
+        indoc! {"
+            ╭╴ERROR: @ position 3 - Unexpected '='
+            │ --> line 1, column 3
+            │ 
+            │ {1=..5}
+            │   ^^
+            ├╴= HINT: the '=' of an inclusive range goes after the dots, eg. '{1..=5}'
+            ╰╴= SUGGESTION: did you mean '..='?
+        "}
+    );
+    assert_eq!(
+        render("{1..5, step 2, m:*2}"),
+        indoc! {"
+            ╭╴ERROR: @ position 8-11 - Expected a trailing ':' after 'step'
+            │ --> line 1, column 8
+            │ 
+            │ {1..5, step 2, m:*2}
+            │        ^^^^
+            ├╴= HINT: range arguments are written as 'step:<STEP>'
+            ╰╴= SUGGESTION: did you mean 'step:2'?
+        "}
+    );
+
+    assert_eq!(
+        crate::parse("{1..5, s2}").unwrap_err().suggestion(),
+        Some("did you mean 's:2'?")
+    );
+    assert_eq!(crate::parse("{1.5}").unwrap_err().suggestion(), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_missing_operand_or_operator() {
+    let render = |input: &str| crate::parse(input).unwrap_err().render_plain();
+
+    assert_eq!(
+        render("(1 + )"),
+        indoc! {"
+            ╭╴ERROR: @ position 5 - Expected a number or a math expression
+            │ --> line 1, column 5
+            │ 
+            │ (1 + )
+            │     ^
+            ╰╴= HINT: every operator needs a value on both sides, eg. '(1 + 2)'
+        "}
+    );
+    assert_eq!(
+        render("(* 3)"),
+        indoc! {"
+            ╭╴ERROR: @ position 2 - Expected a number or a math expression
+            │ --> line 1, column 2
+            │ 
+            │ (* 3)
+            │  ^
+            ╰╴= HINT: every operator needs a value on both sides, eg. '(1 + 2)'
+        "}
+    );
+    assert_eq!(
+        render("(1 2)"),
+        indoc! {"
+            ╭╴ERROR: @ position 3 - Expected a math operator between the two values
+            │ --> line 1, column 3
+            │ 
+            │ (1 2)
+            │   ^
+            ╰╴= HINT: put an operator between the two values, eg. '(2 * 3)'
+        "}
+    );
+    assert_eq!(
+        render("(1 + + )"),
+        indoc! {"
+            ╭╴ERROR: @ position 7 - Expected a number or a math expression
+            │ --> line 1, column 7
+            │ 
+            │ (1 + + )
+            │       ^
+            ╰╴= HINT: every operator needs a value on both sides, eg. '(1 + 2)'
+        "}
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_long_line_excerpt() {
+    let numbers = (0..1000).map(|n| n.to_string()).collect::<Vec<_>>();
+    let excerpt = |input: &str| {
+        let rendered = crate::parse(input).unwrap_err().render_plain();
+        println!("{rendered}");
+        rendered
+            .lines()
+            .skip(3)
+            .take(2)
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let with_error_at = |idx: usize| {
+        let mut numbers = numbers.clone();
+        numbers[idx] = "$".to_string();
+        numbers.join(", ")
+    };
+
+    assert_eq!(
+        excerpt(&with_error_at(0)),
+        indoc! {"
+            │ $, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22…
+            │ ^"}
+    );
+    assert_eq!(
+        excerpt(&with_error_at(500)),
+        indoc! {"
+            │ …92, 493, 494, 495, 496, 497, 498, 499, $, 501, 502, 503, 504, 505, 506, 507, 508…
+            │                                         ^"}
+    );
+    assert_eq!(
+        excerpt(&with_error_at(999)),
+        indoc! {"
+            │ …83, 984, 985, 986, 987, 988, 989, 990, 991, 992, 993, 994, 995, 996, 997, 998, $
+            │                                                                                 ^"}
+    );
+
+    // errors wider than the window are cut at the end
+    let input = format!("1, ({})", numbers.join(", "));
+    assert_eq!(
+        excerpt(&input),
+        indoc! {"
+            │ …(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 2…
+            │  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^"}
+    );
+}
@@ -0,0 +1,1347 @@
+use pretty_assertions::assert_eq;
+
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+#[cfg(feature = "std")]
+use crate::errors::Seq2Error;
+
+use crate::{
+    errors::{Bound, EvalError},
+    eval::{Cardinality, EvalOptions, Evaluator, Explanation, RangeStep, MAX_STEPS},
+    parser::{Node, Parser},
+    tokens::{SortOrder, Span},
+};
+
+fn eval_math_expr(input: &str) -> Result<i64, EvalError> {
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    match &nodes[..] {
+        [Node::MathExpr { rpn, .. }] => Evaluator::new(input.into()).eval_rpn(rpn),
+        _ => panic!("Expected a single math expression, found {:?}", nodes),
+    }
+}
+
+#[test]
+fn test_math_expr() {
+    assert_eq!(eval_math_expr("(1 + 2 - 3)").unwrap(), 0);
+    assert_eq!(eval_math_expr("(1 + 2 * 3)").unwrap(), 7);
+    assert_eq!(eval_math_expr("((1 + 2) * 3)").unwrap(), 9);
+    assert_eq!(eval_math_expr("(10 - 4 - 3)").unwrap(), 3);
+    assert_eq!(eval_math_expr("(100 / 10 / 5)").unwrap(), 2);
+    assert_eq!(eval_math_expr("(7 / 2)").unwrap(), 3);
+    assert_eq!(eval_math_expr("(-2^3 - (3 * 100 / 20))").unwrap(), -23);
+    assert_eq!(eval_math_expr("(200 ^ 2 + 1)").unwrap(), 40001);
+    assert_eq!(eval_math_expr("(2 ^ -1)").unwrap(), 0);
+    assert_eq!(eval_math_expr("(0 ^ MAX)").unwrap(), 0);
+    assert_eq!(eval_math_expr("(0 ^ 0)").unwrap(), 1);
+    assert_eq!(eval_math_expr("(MIN % -1)").unwrap(), 0);
+    assert_eq!(eval_math_expr("(MAX - 1 + 1)").unwrap(), i64::MAX);
+}
+
+#[test]
+fn test_remainder() {
+    // truncated like the division, the remainder takes the sign of the left operand
+    assert_eq!(eval_math_expr("(-7 % 3)").unwrap(), -1);
+    assert_eq!(eval_math_expr("(7 % -3)").unwrap(), 1);
+    assert_eq!(eval_math_expr("(-7 % -3)").unwrap(), -1);
+    assert_eq!(eval_math_expr("(7 % 3)").unwrap(), 1);
+    assert_eq!(eval_math_expr("(-7 / 3 * 3 + -7 % 3)").unwrap(), -7);
+    // same precedence as `*` and `/`, from left to right
+    assert_eq!(eval_math_expr("(1 + 7 % 3 * 2)").unwrap(), 3);
+    assert_eq!(eval_math_expr("(7 * 3 % 4)").unwrap(), 1);
+    assert_eq!(eval_math_expr("(2 ^ 3 % 3)").unwrap(), 2);
+    assert_eq!(eval_math_expr("(MAX % MIN)").unwrap(), i64::MAX);
+
+    assert_eq!(eval("{1..=6, m:%3}").unwrap(), vec![1, 2, 0, 1, 2, 0]);
+    assert_eq!(eval("{-3..=3, m:%2}").unwrap(), vec![-1, 0, -1, 0, 1, 0, 1]);
+    assert_eq!(eval("{1..=4, m:10 % @}").unwrap(), vec![0, 0, 1, 2]);
+
+    if let Err(EvalError::DivisionByZero(ctx)) = eval_math_expr("(5 % (2 - 2))") {
+        assert_eq!(ctx.span, Span::new(3, 4));
+    } else {
+        panic!("Expected DivisionByZero error");
+    }
+    let result = eval("{0..=3, m:6 % @}");
+    if let Err(EvalError::DivisionByZero(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(12, 13));
+    } else {
+        panic!("Expected DivisionByZero error, found {result:?}");
+    }
+}
+
+#[test]
+fn test_unary_ops() {
+    assert_eq!(eval_math_expr("(-(2+3))").unwrap(), -5);
+    assert_eq!(eval_math_expr("(3*-2)").unwrap(), -6);
+    assert_eq!(eval_math_expr("(3 * -(4))").unwrap(), -12);
+    assert_eq!(eval_math_expr("(-+-5)").unwrap(), 5);
+    assert_eq!(eval_math_expr("(-+-(5))").unwrap(), 5);
+    assert_eq!(eval_math_expr("(-abs(-3) - -max(1, 2))").unwrap(), -1);
+    assert_eq!(eval_math_expr("(-~3)").unwrap(), 4);
+    assert_eq!(eval_math_expr("(-(2) * 3)").unwrap(), -6);
+    assert_eq!(eval_math_expr("(-(2)^2)").unwrap(), -4);
+    assert_eq!(eval_math_expr("(-(3)!)").unwrap(), -6);
+    // negative exponents truncate towards zero
+    assert_eq!(eval_math_expr("(2^-(1+1))").unwrap(), 0);
+    assert_eq!(eval_math_expr("(-1^-(1+2))").unwrap(), -1);
+
+    assert_eq!(eval("-(2 + 3), +(4), --(5)").unwrap(), vec![-5, 4, 5]);
+    assert_eq!(eval("{-(1)..=-(-1)}").unwrap(), vec![-1, 0, 1]);
+    assert_eq!(eval("{1..=3, m:(-@^2)}").unwrap(), vec![-1, -4, -9]);
+    assert_eq!(eval("{1..=3, m:2*-@}").unwrap(), vec![-2, -4, -6]);
+
+    for input in ["(-(MIN))", "-(MIN)", "{-(MIN)..=0}"] {
+        assert!(
+            matches!(eval(input), Err(EvalError::Overflow(_))),
+            "Expected Overflow error for {input:?}"
+        );
+    }
+    if let Err(EvalError::Overflow(ctx)) = eval("1, -(MIN)") {
+        assert_eq!(ctx.span, Span::new(3, 9));
+    } else {
+        panic!("Expected Overflow error");
+    }
+}
+
+#[test]
+fn test_pow_associativity() {
+    // right associative, `2^(3^2)`
+    assert_eq!(eval_math_expr("(2^3^2)").unwrap(), 512);
+    assert_eq!(eval_math_expr("((2^3)^2)").unwrap(), 64);
+    assert_eq!(eval_math_expr("(2 ^ 2 ^ 2 ^ 2)").unwrap(), 65536);
+    // the sign of a number binds looser than `^`
+    assert_eq!(eval_math_expr("(-2^2)").unwrap(), -4);
+    assert_eq!(eval_math_expr("(-2^3)").unwrap(), -8);
+    assert_eq!(eval_math_expr("(--2^2)").unwrap(), 4);
+    assert_eq!(eval_math_expr("(1 - -2^2)").unwrap(), 5);
+    assert_eq!(eval_math_expr("(3 * -2^2)").unwrap(), -12);
+    assert_eq!(eval_math_expr("(~2^2)").unwrap(), -5);
+    assert_eq!(eval_math_expr("(-2^2!)").unwrap(), -4);
+    // but tighter than `*`
+    assert_eq!(eval_math_expr("(-2 * 3)").unwrap(), -6);
+    // negative exponents, truncated towards zero
+    assert_eq!(eval_math_expr("(2^-3)").unwrap(), 0);
+    assert_eq!(eval_math_expr("(-1^-3)").unwrap(), -1);
+    assert_eq!(eval_math_expr("(2^-1^2)").unwrap(), 0);
+    assert_eq!(eval_math_expr("(-2^-2)").unwrap(), 0);
+    assert_eq!(eval_math_expr("(2^-0)").unwrap(), 1);
+    assert_eq!(eval("{1..=3, m:2^@^2}").unwrap(), vec![2, 16, 512]);
+
+    if let Err(EvalError::DivisionByZero(ctx)) = eval_math_expr("(0^-1^2)") {
+        assert_eq!(ctx.span, Span::new(2, 3));
+    } else {
+        panic!("Expected DivisionByZero error");
+    }
+}
+
+#[test]
+fn test_math_expr_errors() {
+    if let Err(EvalError::DivisionByZero(ctx)) = eval_math_expr("(1 + 5 / (2 - 2))") {
+        assert_eq!(ctx.span.start, 7);
+    } else {
+        panic!("Expected DivisionByZero error");
+    }
+
+    let result = eval_math_expr("(MAX + 1)");
+    if let Err(EvalError::Overflow(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 5);
+    } else {
+        panic!("Expected Overflow error");
+    }
+}
+
+#[test]
+fn test_overflow_bound() {
+    let cases = [
+        ("(MAX + 1)", Bound::Max),
+        ("(MIN + -1)", Bound::Min),
+        ("(MIN - 1)", Bound::Min),
+        ("(MAX - -1)", Bound::Max),
+        ("(MAX * 2)", Bound::Max),
+        ("(MIN * -2)", Bound::Max),
+        ("(MAX * -2)", Bound::Min),
+        ("(MIN / -1)", Bound::Max),
+        ("(-2 ^ 63)", Bound::Max),
+        ("((0 - 2) ^ 65)", Bound::Min),
+        ("((0 - 3) ^ 64)", Bound::Max),
+        ("(21!)", Bound::Max),
+        ("(abs(MIN))", Bound::Max),
+        ("(gcd(MIN, 0))", Bound::Max),
+        ("(-(MIN))", Bound::Max),
+        ("(1e18 * 10)", Bound::Max),
+        ("(-1e18 * 10)", Bound::Min),
+    ];
+    for (input, bound) in cases {
+        match eval_math_expr(input) {
+            Err(EvalError::Overflow(ctx)) => assert_eq!(ctx.extra, Some(bound), "{input}"),
+            result => panic!("Expected Overflow error for {input:?}, found {result:?}"),
+        }
+    }
+
+    // a folded constant of a range, and the numbers of a mutation
+    let error = eval("{0..(MIN - 1)}").unwrap_err();
+    assert_eq!(
+        error.message(),
+        "@ position 10-10 - Result is smaller than MIN (-9_223_372_036_854_775_808)"
+    );
+    let error = eval("{MAX - 1..=MAX, m:+1}").unwrap_err();
+    assert_eq!(
+        error.message(),
+        "@ position 19-19 - Result is larger than MAX (9_223_372_036_854_775_807)"
+    );
+}
+
+#[test]
+fn test_functions() {
+    assert_eq!(eval_math_expr("(abs(-5) * 2)").unwrap(), 10);
+    assert_eq!(eval_math_expr("(min(3, -10))").unwrap(), -10);
+    assert_eq!(eval_math_expr("(max(3, -10) + 1)").unwrap(), 4);
+    assert_eq!(eval_math_expr("(gcd(12, 18))").unwrap(), 6);
+    assert_eq!(eval_math_expr("(gcd(-12, 0))").unwrap(), 12);
+    assert_eq!(eval_math_expr("(gcd(12, max(8, 18 / 2)))").unwrap(), 3);
+    assert_eq!(eval_math_expr("(ABS(2 - 5 * 2) ^ 2)").unwrap(), 64);
+    assert_eq!(eval_math_expr("(abs (-3))").unwrap(), 3);
+    // a `(` after the name makes it the function rather than the constant
+    assert_eq!(eval_math_expr("(max (1, 2))").unwrap(), 2);
+    assert_eq!(
+        eval_math_expr("(min(MAX, 3) + max(MIN, -MAX))").unwrap(),
+        3 - i64::MAX
+    );
+
+    let result = eval_math_expr("(1 + abs(MIN))");
+    if let Err(EvalError::Overflow(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 5);
+        assert_eq!(ctx.span.end, 13);
+    } else {
+        panic!("Expected Overflow error");
+    }
+}
+
+fn eval(input: &str) -> Result<Vec<i64>, EvalError> {
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    Evaluator::new(input.into()).eval(&nodes)
+}
+
+#[test]
+fn test_ranges() {
+    assert_eq!(eval("{1..=5}").unwrap(), vec![1, 2, 3, 4, 5]);
+    assert_eq!(eval("{1..5}").unwrap(), vec![1, 2, 3, 4]);
+    assert_eq!(eval("{3..=1}").unwrap(), vec![3, 2, 1]);
+    assert_eq!(eval("{-3..=-6}").unwrap(), vec![-3, -4, -5, -6]);
+    assert_eq!(eval("{1..=5, s:2}").unwrap(), vec![1, 3, 5]);
+    assert_eq!(eval("{5..=0, s:-2}").unwrap(), vec![5, 3, 1]);
+    assert_eq!(eval("{5..=1, s:-2, m:-2}").unwrap(), vec![3, 1, -1]);
+    assert_eq!(eval("{1..=3, m:*10-(2 ^ 2)}").unwrap(), vec![6, 16, 26]);
+    assert_eq!(eval("{1..=3, m:(@ + 1) * @}").unwrap(), vec![2, 6, 12]);
+    assert_eq!(eval("{0..=1e4, s:1e3}").unwrap().len(), 11);
+    assert_eq!(
+        eval("{MAX-2..=MAX}").unwrap(),
+        vec![i64::MAX - 2, i64::MAX - 1, i64::MAX]
+    );
+    assert_eq!(
+        eval("{(2 * 3)..(max(2, 9)), s:(1 + 1)}").unwrap(),
+        vec![6, 8]
+    );
+}
+
+#[test]
+fn test_relative_end() {
+    assert_eq!(eval("{100..+5}").unwrap(), [100, 101, 102, 103, 104]);
+    assert_eq!(eval("{100..=+5, s:2}").unwrap(), [100, 102, 104]);
+    assert_eq!(eval("{100..=+-2}").unwrap(), [100, 99, 98]);
+    assert_eq!(eval("{..=+2}, {-1..+2}").unwrap(), [0, 1, 2, -1, 0]);
+    assert_eq!(eval("{MAX - 1..=+1}").unwrap(), [i64::MAX - 1, i64::MAX]);
+    assert_eq!(eval("{100..-98}").unwrap().len(), 198);
+
+    assert!(matches!(eval("{MAX..=+1}"), Err(EvalError::Overflow(..))));
+    assert!(matches!(
+        eval("{(1 / 0)..+1}"),
+        Err(EvalError::DivisionByZero(..))
+    ));
+}
+
+#[test]
+fn test_range_bounds() {
+    // (input, expected numbers), `..` never produces the end, `..=` does when a step lands on it
+    let cases: &[(&str, &[i64])] = &[
+        ("{1..4}", &[1, 2, 3]),
+        ("{1..=4}", &[1, 2, 3, 4]),
+        ("{4..1}", &[4, 3, 2]),
+        ("{4..=1}", &[4, 3, 2, 1]),
+        ("{-2..2}", &[-2, -1, 0, 1]),
+        ("{2..-2}", &[2, 1, 0, -1]),
+        ("{..3}", &[0, 1, 2]),
+        ("{..-3}", &[0, -1, -2]),
+        // with steps
+        ("{1..10, s:3}", &[1, 4, 7]),
+        ("{1..=10, s:3}", &[1, 4, 7, 10]),
+        ("{1..11, s:3}", &[1, 4, 7, 10]),
+        ("{1..=11, s:3}", &[1, 4, 7, 10]),
+        ("{10..1, s:-3}", &[10, 7, 4]),
+        ("{10..=1, s:-3}", &[10, 7, 4, 1]),
+        ("{1..2, s:5}", &[1]),
+        // the bounds apply to the numbers before they are mutated
+        ("{1..4, m:*10}", &[10, 20, 30]),
+        ("{1..4, m:+3}", &[4, 5, 6]),
+        ("{4..1, m:-1}", &[3, 2, 1]),
+        ("{1..10, s:3, m:@ ^ 2}", &[1, 16, 49]),
+        // single value ranges
+        ("{5..5}", &[]),
+        ("{5..=5}", &[5]),
+        ("{5..5, s:-1}", &[]),
+        ("{5..=5, s:-1}", &[5]),
+        ("{5..5, m:*2}", &[]),
+        ("{5..=5, m:*2}", &[10]),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(eval(input).unwrap(), *expected, "input: {input:?}");
+    }
+}
+
+#[test]
+fn test_chained() {
+    assert_eq!(
+        eval("-1, -2, -3, {1..=3, s:2, m:+2}, (200 ^ 2 + 1)").unwrap(),
+        vec![-1, -2, -3, 3, 5, 40001]
+    );
+}
+
+#[test]
+fn test_ordering() {
+    // the items one after the other, repeated numbers and all
+    let cases: [(&str, &[i64]); 7] = [
+        ("{1..=3}, 2, {3..=1}", &[1, 2, 3, 2, 3, 2, 1]),
+        ("3, 1, 2, 1, 3", &[3, 1, 2, 1, 3]),
+        // mutations going up and down are kept in the order of the step
+        (
+            "{1..=4, m:*-1}, 0, {-2..=2, m:*-1}",
+            &[-1, -2, -3, -4, 0, 2, 1, 0, -1, -2],
+        ),
+        (
+            "{-2..=2, m:@^2}, {2..=-2, s:-2, m:@^2}",
+            &[4, 1, 0, 1, 4, 4, 0, 4],
+        ),
+        // `u:` only drops the numbers repeated within its range
+        ("1, {1..=3, u:}, 1", &[1, 1, 2, 3, 1]),
+        ("{1..=2} ~ {2..=1}, 2 x 2", &[1, 2, 2, 1, 2, 2]),
+        ("(2 * 2), {4..=2, s:-2, m:*-1}, (-4 * -1)", &[4, -4, -2, 4]),
+    ];
+    for (input, expected) in cases {
+        let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+        let evaluator = Evaluator::new(input.into());
+        assert_eq!(
+            evaluator.eval(&nodes).unwrap(),
+            expected,
+            "input: {input:?}"
+        );
+
+        // every item alone makes the same numbers as it does in the chain
+        let items = nodes
+            .iter()
+            .flat_map(|node| evaluator.eval(core::slice::from_ref(node)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(items, expected, "input: {input:?}");
+
+        // and each number is found at its position
+        for (idx, number) in expected.iter().enumerate() {
+            let value = nth(input, idx as u128, EvalOptions::default()).unwrap();
+            assert_eq!(value, Some(*number), "input: {input:?}");
+        }
+    }
+}
+
+#[test]
+fn test_range_errors() {
+    // the parser rejects a zero step, but nodes can be built by hand
+    let input = "{1..=5, s:(2 - 2)}";
+    let nodes = [Node::RangeExpr {
+        span: Span::new(0, 18),
+        start: Box::new(Node::Int {
+            span: Span::new(1, 2),
+            value: 1,
+        }),
+        end: Box::new(Node::Int {
+            span: Span::new(5, 6),
+            value: 5,
+        }),
+        inclusive: true,
+        step: Some(Box::new(Node::Int {
+            span: Span::new(10, 17),
+            value: 0,
+        })),
+        mutation: None,
+        low: None,
+        high: None,
+        unique: false,
+        reverse: false,
+        order: None,
+    }];
+    let result = Evaluator::new(input.into()).eval(&nodes);
+    if let Err(EvalError::ZeroStep(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 10);
+        assert_eq!(ctx.span.end, 17);
+    } else {
+        panic!("Expected ZeroStep error");
+    }
+
+    let result = eval("{MAX-1..=MAX, m:+1}");
+    if let Err(EvalError::Overflow(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 16);
+    } else {
+        panic!("Expected Overflow error");
+    }
+}
+
+#[cfg(feature = "aggregate")]
+#[test]
+fn test_aggregates() {
+    assert_eq!(eval_math_expr("(sum{1..=10} * 2)").unwrap(), 110);
+    assert_eq!(eval_math_expr("(count{0..100, s:7})").unwrap(), 15);
+    assert_eq!(eval_math_expr("(prod{1..=5})").unwrap(), 120);
+    assert_eq!(
+        eval_math_expr("(sum{1..=3, m:*2} + count{5..5})").unwrap(),
+        12
+    );
+    assert_eq!(
+        eval("{0..count{0..100, s:7}, s:5}").unwrap(),
+        vec![0, 5, 10]
+    );
+    assert_eq!(
+        eval("{1..=2, m:+sum{1..=3}}, (count{1..=2})").unwrap(),
+        vec![7, 8, 2]
+    );
+
+    // the numbers aren't made to be counted, unless `u:` drops some of them
+    assert_eq!(eval_math_expr("(count{1..MAX})").unwrap(), i64::MAX - 1);
+    assert_eq!(eval_math_expr("(count{MIN..=MAX, s:MAX})").unwrap(), 3);
+    assert_eq!(eval_math_expr("(count{1..=10, lo:3, hi:5})").unwrap(), 10);
+    assert_eq!(
+        eval_math_expr("(count{1..=10, lo:3, hi:5, u:})").unwrap(),
+        3
+    );
+    assert_eq!(eval_math_expr("(count{1..=10, m:%3, u:})").unwrap(), 3);
+}
+
+#[test]
+fn test_bitwise() {
+    assert_eq!(eval_math_expr("(6 & 3)").unwrap(), 2);
+    assert_eq!(eval_math_expr("(6 | 3)").unwrap(), 7);
+    assert_eq!(eval_math_expr("(1 << 4)").unwrap(), 16);
+    assert_eq!(eval_math_expr("(-16 >> 2)").unwrap(), -4);
+    assert_eq!(eval_math_expr("(~0)").unwrap(), -1);
+    assert_eq!(eval_math_expr("(~(1 | 2) & 15)").unwrap(), 12);
+    // shifts bind looser than arithmetic, `&` looser than shifts and `|` looser than `&`
+    assert_eq!(eval_math_expr("(1 << 2 + 1)").unwrap(), 8);
+    assert_eq!(eval_math_expr("(1 | 2 & 3 << 1)").unwrap(), 3);
+    assert_eq!(eval("{0..=3, m:<<4}").unwrap(), vec![0, 16, 32, 48]);
+    assert_eq!(eval("{0..=2, m:~@}").unwrap(), vec![-1, -2, -3]);
+    assert_eq!(eval("{1..=3, m:|8}").unwrap(), vec![9, 10, 11]);
+
+    for input in ["(1 << 64)", "(1 >> -1)"] {
+        let result = eval_math_expr(input);
+        if let Err(EvalError::InvalidShift(ctx)) = &result {
+            println!("{}", result.as_ref().unwrap_err());
+            assert_eq!(ctx.span.start, 3);
+            assert_eq!(ctx.span.end, 5);
+        } else {
+            panic!("Expected InvalidShift error for {input:?}");
+        }
+    }
+}
+
+#[test]
+fn test_factorial() {
+    assert_eq!(eval_math_expr("(5!)").unwrap(), 120);
+    assert_eq!(eval_math_expr("(0!)").unwrap(), 1);
+    assert_eq!(eval_math_expr("(20!)").unwrap(), 2_432_902_008_176_640_000);
+    // binds tighter than any other operator
+    assert_eq!(eval_math_expr("(2 * 3! ^ 2)").unwrap(), 72);
+    assert_eq!(eval_math_expr("((1 + 2)!!)").unwrap(), 720);
+    assert_eq!(eval("{1..=5, m:!}").unwrap(), vec![1, 2, 6, 24, 120]);
+
+    let result = eval_math_expr("(21!)");
+    if let Err(EvalError::Overflow(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(3, 4));
+    } else {
+        panic!("Expected Overflow error");
+    }
+
+    let result = eval_math_expr("((0 - 3)!)");
+    if let Err(EvalError::InvalidFactorial(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(8, 9));
+    } else {
+        panic!("Expected InvalidFactorial error");
+    }
+}
+
+#[test]
+fn test_open_ended_range() {
+    assert_eq!(eval("{..5}").unwrap(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(eval("{..=3}").unwrap(), vec![0, 1, 2, 3]);
+    assert_eq!(eval("{..-5}").unwrap(), vec![0, -1, -2, -3, -4]);
+    assert_eq!(eval("{..=10, s:5, m:+1}").unwrap(), vec![1, 6, 11]);
+}
+
+#[test]
+fn test_unique() {
+    assert_eq!(eval("{1..=20, m:/5, u:}").unwrap(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(eval("{1..=6, m:/5, u:0}").unwrap(), vec![0, 0, 0, 0, 1, 1]);
+    assert_eq!(eval("{-3..=3, m:abs(@), u:}").unwrap(), vec![3, 2, 1, 0]);
+    // only duplicates within the range are dropped
+    assert_eq!(eval("1, {0..=3, m:/2, u:}, 1").unwrap(), vec![1, 0, 1, 1]);
+
+    let input = "5, {1..=6, m:/2}, 3, 5";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        dedup_all: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        Evaluator::with_options(input.into(), options)
+            .eval(&nodes)
+            .unwrap(),
+        vec![5, 0, 1, 2, 3]
+    );
+}
+
+#[test]
+fn test_reverse() {
+    assert_eq!(eval("{1..=5, r:}").unwrap(), vec![5, 4, 3, 2, 1]);
+    assert_eq!(eval("{1..=5, r:0}").unwrap(), vec![1, 2, 3, 4, 5]);
+    // the numbers the range would generate, from the last to the first
+    assert_eq!(eval("{1..10, s:3, r:}").unwrap(), vec![7, 4, 1]);
+    assert_eq!(eval("{1..=10, s:3, rev:}").unwrap(), vec![10, 7, 4, 1]);
+    assert_eq!(eval("{5..=0, s:-2, r:}").unwrap(), vec![1, 3, 5]);
+    assert_eq!(eval("{5..5, r:}, {5..=5, r:}").unwrap(), vec![5]);
+    assert_eq!(eval("{1..=4, m:*10, r:}").unwrap(), vec![40, 30, 20, 10]);
+    // `u:` keeps the first of the repeated numbers in the reversed order
+    let mutation = "m:@ % 2 + @ / 4 * 2"; // 1, 0, 1, 2
+    assert_eq!(
+        eval(&format!("{{1..=4, {mutation}, u:}}")).unwrap(),
+        vec![1, 0, 2]
+    );
+    assert_eq!(
+        eval(&format!("{{1..=4, {mutation}, u:, r:}}")).unwrap(),
+        vec![2, 1, 0]
+    );
+    assert_eq!(eval("{1..=4, m:/2, u:, r:}").unwrap(), vec![2, 1, 0]);
+    // sorting doesn't care about the order the numbers were generated in
+    assert_eq!(eval("{1..=3, r:, o:desc}").unwrap(), vec![3, 2, 1]);
+    assert_eq!(eval("{1..=3, r:, o:asc}").unwrap(), vec![1, 2, 3]);
+    assert_eq!(
+        eval("{MAX-2..=MAX, r:}").unwrap(),
+        vec![i64::MAX, i64::MAX - 1, i64::MAX - 2]
+    );
+}
+
+#[test]
+fn test_clamp() {
+    assert_eq!(
+        eval("{0..=10, m:*3, hi:20}").unwrap(),
+        vec![0, 3, 6, 9, 12, 15, 18, 20, 20, 20, 20]
+    );
+    assert_eq!(eval("{0..=5, lo:3}").unwrap(), vec![3, 3, 3, 3, 4, 5]);
+    assert_eq!(eval("{0..=5, lo:2, hi:3}").unwrap(), vec![2, 2, 2, 3, 3, 3]);
+    assert_eq!(eval("{0..=5, lo:2, hi:2}").unwrap(), vec![2; 6]);
+    // the clamp comes after the mutation
+    assert_eq!(eval("{1..=4, m:*-1, lo:-2}").unwrap(), vec![-1, -2, -2, -2]);
+    // wherever the arguments are written
+    assert_eq!(eval("{1..=4, lo:3, m:*-1}").unwrap(), vec![3, 3, 3, 3]);
+    // `u:`, `r:` and `o:` see the clamped numbers
+    assert_eq!(eval("{0..=5, lo:2, hi:3, u:}").unwrap(), vec![2, 3]);
+    assert_eq!(eval("{0..=5, hi:2, r:}").unwrap(), vec![2, 2, 2, 2, 1, 0]);
+    assert_eq!(
+        eval("{0..=4, m:@ * 7 % 5, hi:2, o:desc}").unwrap(),
+        vec![2, 2, 2, 1, 0]
+    );
+    // repeats and zips take the clamped numbers
+    assert_eq!(eval("{1..=3, hi:2} x 2").unwrap(), vec![1, 2, 2, 1, 2, 2]);
+    assert_eq!(eval("{1..=3, lo:2} ~ 0").unwrap(), vec![2, 0, 2, 3]);
+    // only the clamp repeats the numbers
+    let result = eval("{0..MAX, hi:5, u:}");
+    if let Err(EvalError::NonTerminatingRange(ctx)) = &result {
+        assert_eq!(ctx.span, Span::new(0, 18));
+    } else {
+        panic!("Expected NonTerminatingRange error, found {result:?}");
+    }
+}
+
+#[test]
+fn test_clamp_queries() {
+    let input = "{0..=1e12, s:3, lo:10, hi:1e9}, {5..=0, m:*2, hi:7}";
+    let numbers = |idx: u128| nth(input, idx, EvalOptions::default()).unwrap();
+    assert_eq!(numbers(0), Some(10));
+    assert_eq!(numbers(4), Some(12));
+    assert_eq!(numbers(400_000_000), Some(1_000_000_000));
+    assert_eq!(numbers(333_333_333_334), Some(7));
+    assert_eq!(numbers(333_333_333_338), Some(2));
+    assert_eq!(
+        len(input, EvalOptions::default()).unwrap(),
+        Cardinality::Exact(333_333_333_340)
+    );
+    assert_eq!(
+        len("{0..=5, hi:3, u:}", EvalOptions::default()).unwrap(),
+        Cardinality::AtMost(6)
+    );
+    assert_eq!(
+        nth("{0..=5, hi:3, u:}", 4, EvalOptions::default()).unwrap(),
+        None
+    );
+
+    for (value, expected) in [
+        (9, false),
+        (10, true),
+        (11, false),
+        (12, true),
+        (999_999_999, true),
+        (1_000_000_000, true),
+        (1_000_000_001, false),
+        (7, true),
+        (8, false),
+        (6, true),
+        (5, false),
+    ] {
+        assert_eq!(contains(input, value).unwrap(), expected, "{value}");
+    }
+    // the bounds only come out when a number is clamped to them
+    assert!(!contains("{5..=8, lo:2, hi:10}", 2).unwrap());
+    assert!(!contains("{5..=8, lo:2, hi:10}", 10).unwrap());
+    assert!(contains("{5..=8, lo:6, hi:7}", 6).unwrap());
+    assert!(!contains("{5..5, lo:6}", 6).unwrap());
+}
+
+#[test]
+fn test_inverted_clamp() {
+    // caught by the parser when both values are known, see `test_inverted_clamp` there
+    let input = "{1..5, lo:1, hi:2}";
+    let mut nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    if let [Node::RangeExpr { low, high, .. }] = nodes.as_mut_slice() {
+        core::mem::swap(low, high);
+    }
+    let result = Evaluator::new(input.into()).eval(&nodes);
+    if let Err(EvalError::InvertedClamp(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(10, 17));
+    } else {
+        panic!("Expected InvertedClamp error, found {result:?}");
+    }
+}
+
+#[test]
+fn test_range_iter_double_ended() {
+    let input = "{1..10, s:3, m:*2}, {1..=3, r:}, {1..=6, m:/2, u:}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let evaluator = Evaluator::new(input.into());
+    let numbers = |node| {
+        let range = evaluator.range_iter(node).unwrap();
+        range.rev().collect::<Result<Vec<_>, _>>().unwrap()
+    };
+
+    assert_eq!(numbers(&nodes[0]), [14, 8, 2]);
+    assert_eq!(numbers(&nodes[1]), [1, 2, 3]);
+    assert_eq!(numbers(&nodes[2]), [3, 2, 1, 0]);
+
+    // from both ends, meeting in the middle
+    let mut range = evaluator.range_iter(&nodes[0]).unwrap();
+    assert_eq!(range.next().unwrap().unwrap(), 2);
+    assert_eq!(range.next_back().unwrap().unwrap(), 14);
+    assert_eq!(range.next_back().unwrap().unwrap(), 8);
+    assert!(range.next().is_none());
+    assert!(range.next_back().is_none());
+
+    // `u:` skips the numbers already emitted from the other end
+    let mut range = evaluator.range_iter(&nodes[2]).unwrap();
+    assert_eq!(range.next_back().unwrap().unwrap(), 3);
+    let rest = range.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(rest, [0, 1, 2]);
+}
+
+#[test]
+fn test_zip() {
+    assert_eq!(
+        eval("{1..=3} ~ {10..=12}").unwrap(),
+        vec![1, 10, 2, 11, 3, 12]
+    );
+    // the rest of the longer side comes after the pairs
+    assert_eq!(
+        eval("{1..=5} ~ {10..=11}").unwrap(),
+        vec![1, 10, 2, 11, 3, 4, 5]
+    );
+    assert_eq!(
+        eval("{1..=2} ~ {10..=13}").unwrap(),
+        vec![1, 10, 2, 11, 12, 13]
+    );
+    assert_eq!(eval("{1..1} ~ {10..=12}").unwrap(), vec![10, 11, 12]);
+    // numbers and math expressions are a single number
+    assert_eq!(eval("0 ~ {1..=3}").unwrap(), vec![0, 1, 2, 3]);
+    assert_eq!(eval("{1..=3} ~ (2 * 5)").unwrap(), vec![1, 10, 2, 3]);
+    assert_eq!(eval("1 ~ 2, 3").unwrap(), vec![1, 2, 3]);
+    assert_eq!(eval("-1 ~ -4").unwrap(), vec![-1, -4]);
+    // chained zips interleave the earlier zip with the next item
+    assert_eq!(
+        eval("{1..=2} ~ 5 ~ {7..=9}").unwrap(),
+        vec![1, 7, 5, 8, 2, 9]
+    );
+    assert_eq!(
+        eval("{1..=3, m:*2, o:desc} ~ {1..=4, m:/2, u:, r:}").unwrap(),
+        vec![6, 2, 4, 1, 2, 0]
+    );
+
+    // the errors of either side
+    assert!(matches!(
+        eval("{1..=3} ~ {1..=3, m:+MAX}"),
+        Err(EvalError::Overflow(..))
+    ));
+}
+
+#[test]
+fn test_zip_queries() {
+    let input = "{1..=3} ~ {10..=14}, 7 ~ {1..=4, m:/2, u:}";
+    let numbers = eval(input).unwrap();
+    assert_eq!(numbers, [1, 10, 2, 11, 3, 12, 13, 14, 7, 0, 1, 2]);
+
+    assert_eq!(
+        len(input, EvalOptions::default()).unwrap(),
+        Cardinality::AtMost(13)
+    );
+    for value in -5..=20 {
+        assert_eq!(
+            contains(input, value).unwrap(),
+            numbers.contains(&value),
+            "{value}"
+        );
+    }
+    for (idx, number) in numbers.iter().enumerate() {
+        let value = nth(input, idx as u128, EvalOptions::default()).unwrap();
+        assert_eq!(value, Some(*number), "{idx}");
+    }
+    assert_eq!(
+        nth(input, numbers.len() as u128, EvalOptions::default()).unwrap(),
+        None
+    );
+
+    // without generating either side
+    let input = "{0..1e12} ~ {0..=-1e12, s:-2}";
+    assert_eq!(
+        nth(input, 1_000_001, EvalOptions::default()).unwrap(),
+        Some(-1_000_000)
+    );
+    assert_eq!(
+        nth(input, 1_000_000_000_000, EvalOptions::default()).unwrap(),
+        Some(500_000_000_000)
+    );
+}
+
+#[test]
+fn test_repeat() {
+    assert_eq!(eval("0 x 5").unwrap(), vec![0, 0, 0, 0, 0]);
+    assert_eq!(eval("{1..=3} x 2").unwrap(), vec![1, 2, 3, 1, 2, 3]);
+    assert_eq!(eval("(2 * 3) x 2, 1").unwrap(), vec![6, 6, 1]);
+    assert_eq!(eval("{1..1} x 3").unwrap(), Vec::<i64>::new());
+    assert_eq!(
+        eval("{1..=2} x 2 x 2").unwrap(),
+        vec![1, 2, 1, 2, 1, 2, 1, 2]
+    );
+    // the numbers of the item are repeated, not generated again
+    assert_eq!(
+        eval("{1..=4, m:/2, u:} x 2").unwrap(),
+        vec![0, 1, 2, 0, 1, 2]
+    );
+    assert_eq!(eval("1 x 3 ~ {5..=6}").unwrap(), vec![1, 5, 1, 6, 1]);
+
+    let len = |input| len(input, EvalOptions::default()).unwrap();
+    assert_eq!(len("{1..=3} x 4, 0 x 2"), Cardinality::Exact(14));
+    assert_eq!(len("{1..=4, m:/2, u:} x 2"), Cardinality::AtMost(8));
+
+    let input = "{1..=3} x 1e12, {1..=4, m:/2, u:} x 3";
+    assert!(contains(input, 3).unwrap());
+    assert!(!contains(input, 4).unwrap());
+    for (index, number) in [
+        (0, Some(1)),
+        (4, Some(2)),
+        (2_999_999_999_999, Some(3)),
+        (3_000_000_000_000, Some(0)),
+        (3_000_000_000_008, Some(2)),
+        (3_000_000_000_009, None),
+    ] {
+        assert_eq!(nth(input, index, EvalOptions::default()).unwrap(), number);
+    }
+}
+
+#[test]
+fn test_repeat_budget() {
+    // every copy after the first costs a step per number
+    assert_eq!(eval_with_steps("{1..=5} x 3", 15).unwrap().len(), 15);
+    assert!(eval_with_steps("{1..=5} x 3", 14).is_err());
+    assert_eq!(eval_with_steps("0 x 10", 9).unwrap().len(), 10);
+
+    let result = eval("1, 0 x 1e18");
+    if let Err(EvalError::BudgetExceeded(ctx)) = &result {
+        assert_eq!(ctx.span, Span::new(3, 11));
+    } else {
+        panic!("Expected BudgetExceeded error, found {result:?}");
+    }
+}
+
+#[test]
+fn test_sort() {
+    assert_eq!(eval("{1..=4, m:*-1, o:asc}").unwrap(), vec![-4, -3, -2, -1]);
+    assert_eq!(eval("{-2..=2, m:@^2, u:, o:desc}").unwrap(), vec![4, 1, 0]);
+    // only the range itself is sorted
+    assert_eq!(eval("9, {3..=1, o:asc}, 0").unwrap(), vec![9, 1, 2, 3, 0]);
+
+    let input = "9, {3..=1, o:asc}, 0, 2";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        dedup_all: true,
+        sort: Some(SortOrder::Desc),
+        ..Default::default()
+    };
+    assert_eq!(
+        Evaluator::with_options(input.into(), options)
+            .eval(&nodes)
+            .unwrap(),
+        vec![9, 3, 2, 1, 0]
+    );
+}
+
+#[test]
+fn test_lazy_eval() {
+    let input = "1, {MAX-1..=MAX, m:+1}, 2";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let evaluator = Evaluator::new(input.into());
+    let mut numbers = evaluator.iter(&nodes);
+    assert_eq!(numbers.next().unwrap().unwrap(), 1);
+    assert_eq!(numbers.next().unwrap().unwrap(), i64::MAX);
+    assert!(matches!(numbers.next(), Some(Err(EvalError::Overflow(..)))));
+    assert!(numbers.next().is_none());
+
+    // only as much of a range is generated as is taken
+    let input = "{0..MAX}, 5";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let numbers = evaluator.iter(&nodes).take(3);
+    assert_eq!(numbers.map(Result::unwrap).collect::<Vec<_>>(), [0, 1, 2]);
+
+    let input = "3, {1..=5, o:desc}, 4, 0";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        dedup_all: true,
+        sort: None,
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(input.into(), options);
+    let numbers = evaluator.iter(&nodes).map(Result::unwrap);
+    assert_eq!(numbers.collect::<Vec<_>>(), [3, 5, 4, 2, 1, 0]);
+}
+
+#[test]
+fn test_provenance() {
+    let input = "{1..=3, m:*3}, (2 ^ 4), 0, {2..0, o:asc}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let evaluator = Evaluator::new(input.into());
+    let numbers = evaluator.iter(&nodes).with_provenance();
+    let first = Span::new(0, 13);
+    let last = Span::new(27, 40);
+    assert_eq!(
+        numbers.collect::<Result<Vec<_>, _>>().unwrap(),
+        [
+            (3, first),
+            (6, first),
+            (9, first),
+            (16, Span::new(15, 22)),
+            (0, Span::new(24, 25)),
+            (1, last),
+            (2, last),
+        ]
+    );
+
+    // a number left out by `dedup_all` doesn't take the span of the item repeating it
+    let input = "9, {8..=10}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        dedup_all: true,
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(input.into(), options);
+    let numbers = evaluator.iter(&nodes).with_provenance().map(Result::unwrap);
+    let range = Span::new(3, 11);
+    assert_eq!(
+        numbers.collect::<Vec<_>>(),
+        [(9, Span::new(0, 1)), (8, range), (10, range)]
+    );
+
+    // it stops after the first error, like the numbers alone
+    let input = "1, {MAX..=MAX, m:+1}, 2";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let evaluator = Evaluator::new(input.into());
+    let mut numbers = evaluator.iter(&nodes).with_provenance();
+    assert_eq!(numbers.next().unwrap().unwrap(), (1, Span::new(0, 1)));
+    assert!(matches!(numbers.next(), Some(Err(EvalError::Overflow(..)))));
+    assert!(numbers.next().is_none());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_write_to() {
+    let mut out = vec![];
+    assert_eq!(
+        crate::write_to("-1, {1..=3}, (2 ^ 3)", &mut out, b", ").unwrap(),
+        5
+    );
+    assert_eq!(String::from_utf8(out).unwrap(), "-1, 1, 2, 3, 8");
+
+    let mut out = vec![];
+    assert_eq!(crate::write_to("  # nothing", &mut out, b" ").unwrap(), 0);
+    assert!(out.is_empty());
+
+    // the numbers before the error are written
+    let mut out = vec![];
+    let error = crate::write_to("1, 2, (MAX + 1)", &mut out, b" ").unwrap_err();
+    assert!(matches!(error, Seq2Error::Eval(EvalError::Overflow(..))));
+    assert_eq!(out, b"1 2");
+
+    // never holds on to more than the buffer
+    let count = crate::write_to("{0..1e7}", &mut io::sink(), b"\n").unwrap();
+    assert_eq!(count, 10_000_000);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_write_to_io_error() {
+    struct Full;
+
+    impl Write for Full {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let error = crate::write_to("{1..=3}", &mut Full, b" ").unwrap_err();
+    assert!(matches!(&error, Seq2Error::Io(e) if e.kind() == io::ErrorKind::StorageFull));
+    assert_eq!(error.code(), "E0301");
+    assert_eq!(error.span(), None);
+    assert_eq!(error.render_plain(), "disk full");
+}
+
+#[test]
+fn test_non_terminating_range() {
+    // mutations don't feed back into the range, so these all stop at the end bound
+    assert_eq!(
+        eval("{1..=10, m:-5}").unwrap(),
+        vec![-4, -3, -2, -1, 0, 1, 2, 3, 4, 5]
+    );
+    // the `-` of the `MIN` literal still subtracts, like for any other number
+    assert_eq!(
+        eval("{1..=3, m:-9223372036854775808}").unwrap(),
+        vec![
+            -9223372036854775807,
+            -9223372036854775806,
+            -9223372036854775805
+        ]
+    );
+    assert_eq!(
+        eval("{1..=3, m:-9223372036854775807}").unwrap(),
+        vec![
+            -9223372036854775806,
+            -9223372036854775805,
+            -9223372036854775804
+        ]
+    );
+    assert_eq!(eval("{1..=4, m:*0}").unwrap(), vec![0, 0, 0, 0]);
+    assert_eq!(eval("{1..=4, m:*-1}").unwrap(), vec![-1, -2, -3, -4]);
+    assert_eq!(eval("{1..=4, m:%2, u:}").unwrap(), vec![1, 0]);
+
+    // a `u:` range that only repeats itself would spin through the whole range
+    let input = "{1..MAX, m:*0, u:}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        max_skipped: 1000,
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(input.into(), options);
+    let mut numbers = evaluator.iter(&nodes);
+    assert_eq!(numbers.next().unwrap().unwrap(), 0);
+    let result = numbers.next().unwrap();
+    if let Err(EvalError::NonTerminatingRange(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(11, 13));
+    } else {
+        panic!("Expected NonTerminatingRange error, found {result:?}");
+    }
+    assert!(numbers.next().is_none());
+
+    // oscillating values are skipped too, but the budget is per run of skips
+    let input = "{1..=5000, m:%2 * -1, u:}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    assert!(matches!(
+        Evaluator::with_options(input.into(), options).eval(&nodes),
+        Err(EvalError::NonTerminatingRange(..))
+    ));
+    assert_eq!(
+        Evaluator::new(input.into()).eval(&nodes).unwrap(),
+        vec![-1, 0]
+    );
+}
+
+fn eval_with_steps(input: &str, max_steps: u64) -> Result<Vec<i64>, EvalError> {
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        max_steps,
+        ..Default::default()
+    };
+    Evaluator::with_options(input.into(), options).eval(&nodes)
+}
+
+#[test]
+fn test_step_budget() {
+    // a step for every number of a range, and for every operation of a math expression
+    assert_eq!(eval_with_steps("{1..=10}", 10).unwrap().len(), 10);
+    assert!(eval_with_steps("{1..=10}", 9).is_err());
+    assert_eq!(eval_with_steps("(1 + 2)", 3).unwrap(), [3]);
+    assert!(eval_with_steps("(1 + 2)", 2).is_err());
+    // mutations cost their operations on every number
+    assert_eq!(eval_with_steps("{1..=4, m:*2}", 16).unwrap(), [2, 4, 6, 8]);
+    assert!(eval_with_steps("{1..=4, m:*2}", 15).is_err());
+    // numbers are free
+    assert_eq!(eval_with_steps("1, 2, 3", 0).unwrap(), [1, 2, 3]);
+
+    // the budget is shared by all the items
+    let result = eval_with_steps("{1..=5}, (2 * 3), {1..=5}", 10);
+    if let Err(EvalError::BudgetExceeded(ctx)) = &result {
+        println!("{}", result.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(18, 25));
+        assert_eq!(ctx.extra, 10);
+    } else {
+        panic!("Expected BudgetExceeded error, found {result:?}");
+    }
+
+    // hostile inputs are cut off, keeping the numbers that came out before
+    let input = "1, {1..MAX, m:@ ^ 2 % 7 + 1, u:}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        max_steps: 10_000,
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(input.into(), options);
+    let numbers = evaluator.iter(&nodes).collect::<Vec<_>>();
+    let (last, numbers) = numbers.split_last().unwrap();
+    assert_eq!(
+        numbers
+            .iter()
+            .map(|n| *n.as_ref().unwrap())
+            .collect::<Vec<_>>(),
+        [1, 2, 5, 3, 1]
+    );
+    assert!(matches!(
+        last,
+        Err(EvalError::BudgetExceeded(ctx)) if ctx.span == Span::new(3, 32)
+    ));
+}
+
+#[test]
+fn test_default_step_budget() {
+    let inputs = [
+        "{1..=12}, (2 * 3), {-10..10, s:3, m:@ ^ 2, u:, o:desc}",
+        "{(1 - (10 ^ 2))..-108, s:-3, m:*-1}",
+        "{1..=20, m:/5, u:}",
+        "{0..1e6, m:(@ * 7) % 1000 + gcd(@, 12)}",
+    ];
+    for input in inputs {
+        assert!(eval_with_steps(input, MAX_STEPS).is_ok(), "{input}");
+    }
+
+    // a few million numbers are well within the default
+    let input = "{0..1e7}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let evaluator = Evaluator::new(input.into());
+    assert_eq!(
+        evaluator.iter(&nodes).filter(Result::is_ok).count(),
+        10_000_000
+    );
+}
+
+fn len(input: &str, options: EvalOptions) -> Result<Cardinality, EvalError> {
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    Evaluator::with_options(input.into(), options).len(&nodes)
+}
+
+#[test]
+fn test_len() {
+    let inputs = [
+        "1, -2, (3 * 4)",
+        "{1..=5}",
+        "{1..5}",
+        "{5..5}",
+        "{5..=5}",
+        "{3..=1}",
+        "{-3..=-6}",
+        "{1..=5, s:2}",
+        "{1..5, s:2}",
+        "{1..6, s:2}",
+        "{5..=0, s:-2}",
+        "{5..=1, s:-2, m:-2}",
+        "{1..10, s:3}",
+        "{1..5, s:10}",
+        "{0..=1e4, s:1e3}",
+        "{..3}, {..=-2}",
+        "{MAX-2..=MAX}, {MIN..=MIN+2}",
+        "{MIN..MAX, s:MAX}",
+        "{(2 * 3)..(max(2, 9)), s:(1 + 1)}",
+        "{(1 - (10 ^ 2))..-108, s:-3, m:*-1}",
+        "{1..=5, u:}, {1..=3, m:*0, o:desc}",
+        "-1, -2, -3, {1..=3, s:2, m:+2}, (200 ^ 2 + 1)",
+    ];
+    for input in inputs {
+        let numbers = eval(input).unwrap().len() as u128;
+        let len = len(input, EvalOptions::default()).unwrap();
+        assert_eq!(len, Cardinality::Exact(numbers), "{input}");
+    }
+
+    // the whole range of i64, without generating it
+    assert_eq!(
+        len("{MIN..=MAX}", EvalOptions::default()).unwrap(),
+        Cardinality::Exact(1 << 64)
+    );
+    assert_eq!(
+        len("{0..=1e9, s:7}", EvalOptions::default()).unwrap(),
+        Cardinality::Exact(142_857_143)
+    );
+}
+
+#[test]
+fn test_len_upper_bound() {
+    // numbers repeated by a mutation are dropped
+    let inputs = [
+        "{1..=20, m:/5, u:}",
+        "{-10..10, s:3, m:@ ^ 2, u:}",
+        "{1..=5, m:*2, u:}",
+    ];
+    for input in inputs {
+        let numbers = eval(input).unwrap().len() as u128;
+        let len = len(input, EvalOptions::default()).unwrap();
+        assert!(matches!(len, Cardinality::AtMost(_)), "{input}");
+        assert!(len.upper_bound() >= numbers, "{input}");
+        assert_eq!(len.exact(), None);
+    }
+    assert_eq!(
+        len("1, {1..=20, m:/5, u:}", EvalOptions::default()).unwrap(),
+        Cardinality::AtMost(21)
+    );
+
+    // or by any item at all
+    let options = EvalOptions {
+        dedup_all: true,
+        ..Default::default()
+    };
+    assert_eq!(len("1, {1..=3}", options).unwrap(), Cardinality::AtMost(4));
+    assert_eq!(len("7", options).unwrap(), Cardinality::Exact(1));
+
+    // the bounds and steps are still evaluated
+    assert!(matches!(
+        len("{1..(1 / 0)}", EvalOptions::default()),
+        Err(EvalError::DivisionByZero(..))
+    ));
+}
+
+fn contains(input: &str, value: i64) -> Result<bool, EvalError> {
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    Evaluator::new(input.into()).contains(&nodes, value)
+}
+
+#[test]
+fn test_contains() {
+    let inputs = [
+        "1, -2, (3 * 4)",
+        "{1..=5}, {1..5}, {5..5}, {5..=5}",
+        "{3..=1}, {-3..=-6}",
+        "{1..=5, s:2}, {1..6, s:2}, {5..=0, s:-2}",
+        "{1..10, s:3}, {1..5, s:10}, {-7..=7, s:4}",
+        "{..3}, {..=-2}, {10..=-10, s:-3}",
+        "{5..=1, s:-2, m:-2}, {1..=3, m:(@ + 1) * @}",
+        "{-10..10, s:3, m:@ ^ 2, u:, o:desc}",
+    ];
+    for input in inputs {
+        let numbers = eval(input).unwrap();
+        for value in -20..=20 {
+            let expected = numbers.contains(&value);
+            assert_eq!(contains(input, value).unwrap(), expected, "{input} {value}");
+        }
+    }
+
+    // near the bounds of i64
+    assert!(contains("{MIN..MAX, s:MAX}", 9223372036854775806).unwrap());
+    assert!(!contains("{MIN..MAX, s:MAX}", i64::MAX).unwrap());
+    assert!(contains("{MAX..=MIN, s:MIN}", -1).unwrap());
+    assert!(!contains("{MAX-2..MAX}", i64::MAX).unwrap());
+}
+
+#[test]
+fn test_contains_without_generating() {
+    // a billion numbers with the default step budget would take a while to generate
+    assert!(contains("{0..=1_000_000_000, s:7}", 4_900_007).unwrap());
+    assert!(!contains("{0..=1_000_000_000, s:7}", 4_900_003).unwrap());
+    assert!(contains("{MIN..=MAX}", 42).unwrap());
+
+    // ranges with a mutation are generated, keeping to the step budget
+    assert!(contains("{1..MAX, m:*2}", 20).unwrap());
+    let input = "{1..MAX, m:*0}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let options = EvalOptions {
+        max_steps: 1000,
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(input.into(), options);
+    assert!(matches!(
+        evaluator.contains(&nodes, 1),
+        Err(EvalError::BudgetExceeded(..))
+    ));
+}
+
+fn nth(input: &str, index: u128, options: EvalOptions) -> Result<Option<i64>, EvalError> {
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    Evaluator::with_options(input.into(), options).nth(&nodes, index)
+}
+
+#[test]
+fn test_nth() {
+    let inputs = [
+        "1, -2, (3 * 4)",
+        "{1..=5}, {1..5}, {5..5}, {5..=5}, 9",
+        "{3..=1}, (2 ^ 3), {-3..=-6}",
+        // the last number is trimmed to stay before the end
+        "{1..=6, s:2}, {5..=0, s:-2}, {1..10, s:4}",
+        "{5..=1, s:-2, m:-2}, {1..=3, m:(@ + 1) * @}",
+        "{1..=5, o:desc}, {5..=1, s:-2, o:asc}, {1..=3, o:asc}",
+        "{-10..10, s:3, m:@ ^ 2, u:, o:desc}, {1..=20, m:/5, u:}, 0",
+        "{-3..=3, m:@ ^ 2, o:asc}, {1..=4, u:}",
+        "{1..10, s:3, r:}, {1..=4, m:/2, u:, r:}, {5..=0, s:-2, r:, o:asc}",
+    ];
+    for input in inputs {
+        let numbers = eval(input).unwrap();
+        for (idx, number) in numbers.iter().enumerate() {
+            let value = nth(input, idx as u128, EvalOptions::default()).unwrap();
+            assert_eq!(value, Some(*number), "{input} {idx}");
+        }
+        let past_end = nth(input, numbers.len() as u128, EvalOptions::default());
+        assert_eq!(past_end.unwrap(), None, "{input}");
+    }
+
+    // the whole output is generated when it's post-processed
+    let options = EvalOptions {
+        dedup_all: true,
+        sort: Some(SortOrder::Desc),
+        ..Default::default()
+    };
+    assert_eq!(nth("1, {1..=3}, 5", 1, options).unwrap(), Some(3));
+    assert_eq!(nth("1, {1..=3}, 5", 4, options).unwrap(), None);
+}
+
+#[test]
+fn test_nth_without_generating() {
+    let input = "{MIN..=MAX}, 7";
+    assert_eq!(
+        nth(input, 0, EvalOptions::default()).unwrap(),
+        Some(i64::MIN)
+    );
+    assert_eq!(
+        nth(input, u64::MAX as u128, EvalOptions::default()).unwrap(),
+        Some(i64::MAX)
+    );
+    assert_eq!(
+        nth(input, 1 << 64, EvalOptions::default()).unwrap(),
+        Some(7)
+    );
+    assert_eq!(
+        nth(input, (1 << 64) + 1, EvalOptions::default()).unwrap(),
+        None
+    );
+
+    // only the mutation of the number itself is evaluated
+    let input = "{0..=1e12, s:3, m:10 / (@ - 999), r:}";
+    assert_eq!(nth(input, 0, EvalOptions::default()).unwrap(), Some(0));
+    assert_eq!(nth(input, 2, EvalOptions::default()).unwrap(), Some(0));
+
+    let input = "{0..=1e12, s:3, m:10 / (@ - 999)}";
+    assert_eq!(nth(input, 0, EvalOptions::default()).unwrap(), Some(0));
+    assert!(matches!(
+        nth(input, 333, EvalOptions::default()),
+        Err(EvalError::DivisionByZero(..))
+    ));
+    assert_eq!(nth(input, 334, EvalOptions::default()).unwrap(), Some(3));
+}
+
+#[test]
+fn test_explain() {
+    let input = "{1..=5, s:2, m:*3}, 100, {4..=1, hi:2, r:}, {1..=3, o:desc} x2";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let evaluator = Evaluator::new(input.into());
+    let explain = |index| evaluator.explain(&nodes, index).unwrap();
+
+    let range = Span::new(0, 18);
+    assert_eq!(
+        explain(1),
+        Some(Explanation {
+            value: 9,
+            item: 0,
+            span: range,
+            range: Some(RangeStep {
+                index: 1,
+                base: 3,
+                mutation: Some((Span::new(15, 17), 9)),
+            }),
+        })
+    );
+    assert_eq!(
+        explain(3),
+        Some(Explanation {
+            value: 100,
+            item: 1,
+            span: Span::new(20, 23),
+            range: None,
+        })
+    );
+    // `r:` counts from the end, the clamp comes after the base
+    assert_eq!(
+        explain(7),
+        Some(Explanation {
+            value: 2,
+            item: 2,
+            span: Span::new(25, 42),
+            range: Some(RangeStep {
+                index: 3,
+                base: 4,
+                mutation: None,
+            }),
+        })
+    );
+    // the numbers of a repeat are told apart by their item only
+    let repeat = explain(13).unwrap();
+    assert_eq!((repeat.value, repeat.item, repeat.range), (1, 3, None));
+    assert_eq!(repeat.span.text(input), Some("{1..=3, o:desc} x2"));
+    assert_eq!(explain(14), None);
+
+    // the items of an output made whole are looked up by their spans
+    let options = EvalOptions {
+        dedup_all: true,
+        sort: Some(SortOrder::Asc),
+        ..Default::default()
+    };
+    let evaluator = Evaluator::with_options(input.into(), options);
+    let first = evaluator.explain(&nodes, 0).unwrap().unwrap();
+    assert_eq!((first.value, first.item, first.range), (1, 2, None));
+    let last = evaluator.explain(&nodes, 5).unwrap().unwrap();
+    assert_eq!((last.value, last.item), (100, 1));
+    assert_eq!(evaluator.explain(&nodes, 6).unwrap(), None);
+}
@@ -0,0 +1,102 @@
+use pretty_assertions::assert_eq;
+
+use super::rng::Rng;
+use crate::{errors::Seq2Error, fast::parse_int_list, parse_slow, tokens::Span};
+
+fn summary(result: &Result<Vec<i64>, Seq2Error>) -> Result<&[i64], (&str, Option<Span>, String)> {
+    match result {
+        Ok(numbers) => Ok(numbers),
+        Err(e) => Err((e.code(), e.span(), e.message())),
+    }
+}
+
+/// `crate::parse` has to give the exact result (or error) of the full pipeline.
+fn assert_same(input: &str) {
+    let fast = crate::parse(input);
+    let slow = parse_slow(input, Default::default());
+    assert_eq!(summary(&fast), summary(&slow), "input: {input:?}");
+    // the fast path only ever takes valid lists
+    if let Some(numbers) = parse_int_list(input) {
+        assert_eq!(Ok(numbers.as_slice()), summary(&slow), "input: {input:?}");
+    }
+}
+
+#[test]
+fn test_fast_path() {
+    let cases = [
+        ("-3, 17, 42, 9", vec![-3, 17, 42, 9]),
+        ("1_000,\n\t2_000 , 3,", vec![1_000, 2_000, 3]),
+        ("+3, --3, - 3, +-+5", vec![3, 3, -3, -5]),
+        (
+            "9223372036854775807, -9223372036854775807",
+            vec![i64::MAX, -i64::MAX],
+        ),
+        ("", vec![]),
+        (" \n ", vec![]),
+    ];
+    for (input, numbers) in cases {
+        assert_eq!(
+            parse_int_list(input),
+            Some(numbers.clone()),
+            "input: {input:?}"
+        );
+        assert_eq!(crate::parse(input).unwrap(), numbers, "input: {input:?}");
+    }
+
+    // anything else is left to the full pipeline
+    for input in ["{1..3}", "(1 + 2)", "1e3", "MAX", "1, # two", "1, 2\u{a0}"] {
+        assert_eq!(parse_int_list(input), None, "input: {input:?}");
+    }
+}
+
+#[test]
+fn test_fast_path_errors() {
+    // all of them (but `MIN`) are errors, reported by the full pipeline
+    let cases = [
+        "1,,2",
+        ",1",
+        "1 2",
+        "1-2",
+        "1, -",
+        "1, +",
+        "1_",
+        "1__0",
+        "_1",
+        "1,_",
+        "99999999999999999999",
+        "-9223372036854775808",
+        "--9223372036854775808",
+        "18446744073709551616",
+        ",",
+        "- , 1",
+    ];
+    for input in cases {
+        assert_eq!(parse_int_list(input), None, "input: {input:?}");
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_fast_path_matches_slow_path() {
+    let alphabet = b"0123456789-+_, \n";
+    let mut rng = Rng(0xfa57_2024);
+    for _ in 0..20_000 {
+        let len = rng.below(16) as usize;
+        let input = (0..len)
+            .map(|_| alphabet[rng.below(alphabet.len() as u64) as usize] as char)
+            .collect::<String>();
+        assert_same(&input);
+    }
+
+    // long valid lists, with the odd big number in there
+    for _ in 0..200 {
+        let input = (0..rng.below(50))
+            .map(|_| match rng.chance(10) {
+                true => rng.next().to_string(),
+                false => (rng.below(2_000) - 1_000).to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        assert_same(&input);
+    }
+}
@@ -0,0 +1,103 @@
+use pretty_assertions::assert_eq;
+
+use std::{
+    ffi::{c_char, CStr},
+    ptr, slice,
+};
+
+use crate::ffi::{
+    seq2_free, seq2_last_error_message, seq2_parse, SEQ2_ERR_INVALID_UTF8, SEQ2_ERR_NULL_POINTER,
+    SEQ2_OK,
+};
+
+fn parse(input: &[u8]) -> (i32, *mut i64, usize) {
+    let mut out = ptr::null_mut();
+    let mut len = 0;
+    let code = unsafe { seq2_parse(input.as_ptr() as *const c_char, &mut out, &mut len) };
+    (code, out, len)
+}
+
+fn last_error() -> Option<String> {
+    let message = seq2_last_error_message();
+    match message.is_null() {
+        true => None,
+        false => Some(
+            unsafe { CStr::from_ptr(message) }
+                .to_str()
+                .unwrap()
+                .to_string(),
+        ),
+    }
+}
+
+#[test]
+fn test_ffi_parse() {
+    let (code, out, len) = parse(b"-1, {1..=3, s:2, m:+2}\0");
+    assert_eq!(code, SEQ2_OK);
+    assert_eq!(unsafe { slice::from_raw_parts(out, len) }, [-1, 3, 5]);
+    assert_eq!(last_error(), None);
+    unsafe { seq2_free(out, len) };
+
+    // nothing to allocate for an empty sequence
+    let (code, out, len) = parse(b"  \0");
+    assert_eq!((code, out, len), (SEQ2_OK, ptr::null_mut(), 0));
+    unsafe { seq2_free(out, len) };
+}
+
+#[test]
+fn test_ffi_errors() {
+    // the negated stable code
+    let (code, out, len) = parse(b"1, 2 3\0");
+    assert_eq!(code, -112);
+    assert_eq!((out, len), (ptr::null_mut(), 0));
+    let message = last_error().unwrap();
+    assert!(message.contains("Missing ',' between items"), "{message}");
+    assert!(!message.contains('\x1b'), "{message}");
+
+    assert_eq!(parse(b"(MAX + 1)\0").0, -204);
+    assert_eq!(parse(b"$\0").0, -1);
+
+    // a success clears the error
+    let (code, out, len) = parse(b"1\0");
+    assert_eq!(code, SEQ2_OK);
+    assert_eq!(last_error(), None);
+    unsafe { seq2_free(out, len) };
+}
+
+#[test]
+fn test_ffi_nul_and_utf8() {
+    // the input ends at the first NUL
+    let (code, out, len) = parse(b"1, 2\0, 3\0");
+    assert_eq!(code, SEQ2_OK);
+    assert_eq!(unsafe { slice::from_raw_parts(out, len) }, [1, 2]);
+    unsafe { seq2_free(out, len) };
+
+    let (code, ..) = parse(b"1, \xff\0");
+    assert_eq!(code, SEQ2_ERR_INVALID_UTF8);
+    assert!(last_error().unwrap().contains("not valid UTF-8"));
+
+    let mut out = ptr::null_mut();
+    let mut len = 0;
+    let code = unsafe { seq2_parse(ptr::null(), &mut out, &mut len) };
+    assert_eq!(code, SEQ2_ERR_NULL_POINTER);
+    let code = unsafe { seq2_parse(c"1".as_ptr(), ptr::null_mut(), &mut len) };
+    assert_eq!(code, SEQ2_ERR_NULL_POINTER);
+    let code = unsafe { seq2_parse(c"1".as_ptr(), &mut out, ptr::null_mut()) };
+    assert_eq!(code, SEQ2_ERR_NULL_POINTER);
+}
+
+#[test]
+fn test_ffi_double_free() {
+    let (code, out, len) = parse(b"{1..=100}\0");
+    assert_eq!(code, SEQ2_OK);
+    unsafe {
+        seq2_free(out, len);
+        seq2_free(out, len);
+        seq2_free(ptr::null_mut(), 0);
+    }
+
+    // pointers seq2 didn't hand out are left alone
+    let mut numbers = vec![1i64, 2, 3];
+    unsafe { seq2_free(numbers.as_mut_ptr(), numbers.len()) };
+    assert_eq!(numbers, [1, 2, 3]);
+}
@@ -0,0 +1,119 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    errors::LexicalError,
+    highlight,
+    lexer::Lexer,
+    tokens::{Span, TokenKind},
+    TokenClass,
+};
+
+fn classes(input: &str) -> Vec<(&str, TokenClass)> {
+    highlight(input)
+        .into_iter()
+        .map(|(span, class)| (&input[span.range()], class))
+        .collect()
+}
+
+#[test]
+fn test_highlight() {
+    use TokenClass::*;
+
+    let input = "(max(1, 2) + 3), {-3..=10, s:2, m:@*2, o:desc} $ 7, # comment\n{..5}";
+    assert_eq!(
+        classes(input),
+        [
+            ("(", Paren),
+            ("max", Operator),
+            ("(", Paren),
+            ("1", Number),
+            (",", Comma),
+            ("2", Number),
+            (")", Paren),
+            ("+", Operator),
+            ("3", Number),
+            (")", Paren),
+            (",", Comma),
+            ("{", Brace),
+            ("-", Operator),
+            ("3", Number),
+            ("..=", RangeOperator),
+            ("10", Number),
+            (",", Comma),
+            ("s:", RangeArgKeyword),
+            ("2", Number),
+            (",", Comma),
+            ("m:", RangeArgKeyword),
+            ("@", Number),
+            ("*", Operator),
+            ("2", Number),
+            (",", Comma),
+            ("o:desc", RangeArgKeyword),
+            ("}", Brace),
+            // the scan carries on after the invalid char
+            ("$", Error),
+            ("7", Number),
+            (",", Comma),
+            ("{", Brace),
+            ("..", RangeOperator),
+            ("5", Number),
+            ("}", Brace),
+        ]
+    );
+}
+
+#[test]
+fn test_highlight_errors() {
+    use TokenClass::*;
+
+    assert_eq!(highlight(""), []);
+    // the error covers what the lexer choked on, eg. the `_` without a digit after it
+    assert_eq!(
+        classes("1_, é, 99999999999999999999, 2"),
+        [
+            ("_", Error),
+            (",", Comma),
+            ("é", Error),
+            (",", Comma),
+            ("99999999999999999999", Error),
+            (",", Comma),
+            ("2", Number),
+        ]
+    );
+    assert_eq!(
+        classes("{1...5, foo:2}"),
+        [
+            ("{", Brace),
+            ("1", Number),
+            ("...", Error),
+            ("5", Number),
+            (",", Comma),
+            ("foo", Error),
+            (":", Error),
+            ("2", Number),
+            ("}", Brace),
+        ]
+    );
+}
+
+#[test]
+fn test_lex_all_errors() {
+    let input = "1, $, 2 $$ 3";
+    let (tokens, errors) = Lexer::new(input).lex_all_errors();
+    assert_eq!(tokens.len(), 5);
+    assert!(matches!(tokens[4].kind, TokenKind::Int { value: 3 }));
+    let spans = errors
+        .iter()
+        .map(|e| match e {
+            LexicalError::InvalidToken(ctx) => ctx.span,
+            _ => panic!("Expected InvalidToken error, found {e:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(spans, [Span::new(3, 4), Span::new(8, 9), Span::new(9, 10)]);
+
+    // same tokens as `lex` without errors
+    let input = "{1..=5, s:2}, (3 * 4)";
+    let (tokens, errors) = Lexer::new(input).lex_all_errors();
+    assert_eq!(tokens, Lexer::new(input).lex().unwrap());
+    assert!(errors.is_empty());
+}
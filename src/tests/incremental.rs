@@ -0,0 +1,132 @@
+use pretty_assertions::assert_eq;
+
+use super::rng::Rng;
+use crate::{
+    parser::{Node, Parser},
+    tokens::{Span, Token},
+    Seq2Document,
+};
+
+type Summary<'a> = (
+    &'a str,
+    &'a [Token],
+    Vec<&'a Node>,
+    Vec<(&'static str, Option<Span>)>,
+);
+
+fn summary(doc: &Seq2Document) -> Summary<'_> {
+    let errors = doc.errors().iter().map(|e| (e.code(), e.span())).collect();
+    (doc.source(), doc.tokens(), doc.nodes().collect(), errors)
+}
+
+/// The document after an edit has to be the one made from scratch out of the new source.
+fn assert_same(doc: &Seq2Document) {
+    let fresh = Seq2Document::new(doc.source());
+    assert_eq!(summary(doc), summary(&fresh));
+
+    // and when the source parses, the nodes are the parser's
+    if doc.errors().is_empty() {
+        let nodes = Parser::from_source(doc.source()).unwrap().parse().unwrap();
+        assert_eq!(
+            doc.nodes().collect::<Vec<_>>(),
+            nodes.iter().collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn test_document() {
+    let mut doc = Seq2Document::new("1, {2..=4}, (3 * 4) ~ 5");
+    assert_eq!(doc.nodes().count(), 3);
+    assert!(doc.errors().is_empty());
+
+    // the items after the edit are moved along
+    doc.apply_edit(Span::new(4, 5), "20");
+    assert_eq!(doc.source(), "1, {20..=4}, (3 * 4) ~ 5");
+    assert_eq!(doc.nodes().last().unwrap().span(), Span::new(13, 24));
+    assert_same(&doc);
+
+    // a broken item doesn't keep the others from being parsed
+    doc.apply_edit(Span::new(4, 10), "20..");
+    assert_eq!(doc.source(), "1, {20..}, (3 * 4) ~ 5");
+    assert_eq!(doc.nodes().count(), 2);
+    assert_eq!(doc.errors().len(), 1);
+    assert_same(&doc);
+
+    // an opened parenthesis or comment takes the items after it along
+    doc.apply_edit(Span::new(0, 0), "(");
+    assert_same(&doc);
+    doc.apply_edit(Span::new(0, 1), "# ");
+    assert_eq!(doc.nodes().count(), 0);
+    assert_same(&doc);
+    doc.apply_edit(Span::new(0, 2), "");
+    assert_same(&doc);
+
+    // lexical errors are reported for the whole source
+    doc.apply_edit(Span::new(1, 1), "$");
+    assert_eq!(doc.errors().len(), 1);
+    assert_eq!(doc.tokens(), []);
+    doc.apply_edit(Span::new(1, 2), "");
+    assert_eq!(doc.errors().len(), 1);
+    assert_same(&doc);
+}
+
+#[test]
+fn test_random_edits() {
+    let pieces = [
+        "1",
+        "-2",
+        "MAX",
+        "{",
+        "}",
+        "(",
+        ")",
+        "..",
+        "..=",
+        ",",
+        ", ",
+        " ",
+        "\n",
+        "# note",
+        "s:",
+        "m:",
+        "@",
+        "*",
+        "+ 3",
+        "-",
+        "~",
+        "x 2",
+        "abs(",
+        "{1..=5}",
+        "(2 + 3)",
+        "{1..10, s:2}",
+        "{..3, m:@ * 2}",
+        "é",
+    ];
+    let mut rng = Rng(0x5eed_2024);
+
+    for _ in 0..50 {
+        let source = (0..rng.below(12))
+            .map(|_| pieces[rng.below(pieces.len() as u64) as usize])
+            .collect::<String>();
+        let mut doc = Seq2Document::new(&source);
+        assert_same(&doc);
+
+        for _ in 0..40 {
+            let source = doc.source();
+            let mut offset = || loop {
+                let offset = rng.below(source.len() as u64 + 1) as usize;
+                if source.is_char_boundary(offset) {
+                    break offset;
+                }
+            };
+            let (a, b) = (offset(), offset());
+            let replacement = match rng.chance(30) {
+                true => "",
+                false => pieces[rng.below(pieces.len() as u64) as usize],
+            };
+            doc.apply_edit(Span::new(a.min(b), a.max(b)), replacement);
+            assert_same(&doc);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    invariants::{check_lexed, check_parsed},
+    lexer::Lexer,
+    parser::{Node, Parser},
+    tokens::{Span, Token, TokenKind},
+};
+
+fn int(start: usize, end: usize) -> Token {
+    Token::new(TokenKind::Int { value: 1 }, Span::new(start, end))
+}
+
+#[test]
+fn test_valid_spans() {
+    let input = "{1..=3, m:@ * 2} ~ (abs(-4)), é x 2, {..5}";
+    let (tokens, errors) = Lexer::new(input).with_trivia(true).lex_all_errors();
+    let spans = errors.iter().map(|e| e.span()).collect::<Vec<_>>();
+    check_lexed(input, &tokens, &spans);
+
+    let input = "{1..=3, m:@ * 2} ~ (abs(-4)), -(2 + 3) x 2, {..5, lo:1}";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let nodes = Parser::new(input.into(), &tokens).parse().unwrap();
+    check_parsed(&tokens, &nodes);
+    check_parsed(&[], &[]);
+}
+
+#[test]
+#[should_panic(expected = "invariant broken")]
+fn test_reversed_token() {
+    check_lexed("12", &[int(2, 1)], &[]);
+}
+
+#[test]
+#[should_panic(expected = "invariant broken")]
+fn test_overlapping_tokens() {
+    check_lexed("12, 3", &[int(0, 2), int(1, 2)], &[]);
+}
+
+#[test]
+#[should_panic(expected = "invariant broken")]
+fn test_token_past_the_end() {
+    check_lexed("12", &[int(0, 3)], &[]);
+}
+
+#[test]
+#[should_panic(expected = "invariant broken")]
+fn test_token_inside_a_char() {
+    check_lexed("é", &[int(0, 1)], &[]);
+}
+
+#[test]
+#[should_panic(expected = "invariant broken")]
+fn test_error_past_the_end() {
+    check_lexed("12", &[], &[Span::new(2, 4)]);
+}
+
+#[test]
+#[should_panic(expected = "invariant broken")]
+fn test_node_outside_its_tokens() {
+    let node = Node::Int {
+        span: Span::new(0, 4),
+        value: 1,
+    };
+    check_parsed(&[int(0, 2)], &[node]);
+}
+
+#[test]
+#[should_panic(expected = "invariant broken")]
+fn test_child_outside_its_parent() {
+    let node = Node::Repeat {
+        span: Span::new(0, 3),
+        item: Box::new(Node::Int {
+            span: Span::new(2, 5),
+            value: 1,
+        }),
+        count: 2,
+    };
+    check_parsed(&[int(0, 5)], &[node]);
+}
@@ -1,9 +1,10 @@
+use indoc::indoc;
 use pretty_assertions::assert_eq;
 
 use crate::{
     errors::LexicalError,
-    lexer::Lexer,
-    tokens::{Op, Span, Token, TokenKind},
+    lexer::{Lexer, MAX_INPUT_LEN},
+    tokens::{Func, Op, SortOrder, Span, Token, TokenKind},
 };
 
 #[test]
@@ -15,23 +16,23 @@ fn test_numbers() {
         vec![
             Token {
                 kind: TokenKind::Int { value: 1 },
-                span: Span { start: 1, end: 1 }
+                span: Span { start: 0, end: 1 }
             },
             Token {
                 kind: TokenKind::Comma,
-                span: Span { start: 2, end: 2 }
+                span: Span { start: 1, end: 2 }
             },
             Token {
                 kind: TokenKind::Int { value: 2 },
-                span: Span { start: 3, end: 3 }
+                span: Span { start: 2, end: 3 }
             },
             Token {
                 kind: TokenKind::Comma,
-                span: Span { start: 4, end: 4 }
+                span: Span { start: 3, end: 4 }
             },
             Token {
                 kind: TokenKind::Int { value: 3 },
-                span: Span { start: 5, end: 5 }
+                span: Span { start: 4, end: 5 }
             },
         ]
     );
@@ -41,21 +42,137 @@ fn test_numbers() {
 fn test_number_too_large() {
     let mut lexer = Lexer::new("1, 2, 9_223_372_036_854_775_808");
     let tokens = lexer.lex();
-    if let Err(LexicalError::NumberTooLarge(_, span)) = tokens {
-        println!("{}", tokens.err().unwrap());
-        assert_eq!(span, Span { start: 7, end: 31 });
+    if let Err(LexicalError::NumberTooLarge(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 6, end: 31 });
     } else {
         panic!("Expected NumberTooLarge error");
     }
 }
 
+#[test]
+fn test_number_too_small() {
+    // the span starts at the negating '-'
+    for (input, expected) in [
+        ("-9_223_372_036_854_775_809", Span { start: 0, end: 26 }),
+        ("1, -99999999999999999999999", Span { start: 3, end: 27 }),
+        ("(2 * -9223372036854775809)", Span { start: 5, end: 25 }),
+        ("{-1e19..0}", Span { start: 1, end: 6 }),
+        ("+-9223372036854775809e0", Span { start: 1, end: 23 }),
+        ("-92233720368547758080e0", Span { start: 0, end: 23 }),
+    ] {
+        let tokens = Lexer::new(input).lex();
+        if let Err(LexicalError::NumberTooSmall(ctx)) = &tokens {
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected NumberTooSmall error for {input:?}, found {tokens:?}");
+        }
+    }
+
+    let error = Lexer::new("-1e19").lex().unwrap_err();
+    assert_eq!(error.code(), "E0013");
+    assert_eq!(
+        error.message(),
+        "@ position 1-5 - Number too small. Smallest possible number is -9_223_372_036_854_775_808"
+    );
+    assert_eq!(Lexer::new("1e19").lex().unwrap_err().code(), "E0007");
+}
+
+#[test]
+fn test_input_too_large() {
+    let input = "1, 2, 3";
+    let tokens = Lexer::new(input).with_max_input_len(6).lex();
+    if let Err(LexicalError::InputTooLarge(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 6, end: 6 });
+        assert!(tokens
+            .as_ref()
+            .unwrap_err()
+            .message()
+            .contains("at most 6 bytes"));
+    } else {
+        panic!("Expected InputTooLarge error");
+    }
+
+    assert_eq!(
+        Lexer::new(input).with_max_input_len(7).lex().unwrap().len(),
+        5
+    );
+    // the limit can't go past what fits a span
+    assert_eq!(MAX_INPUT_LEN, u32::MAX as usize);
+    let mut lexer = Lexer::new(input).with_max_input_len(usize::MAX);
+    assert_eq!(lexer.lex().unwrap().len(), 5);
+}
+
+#[test]
+fn test_span_offsets() {
+    assert_eq!(Span::new(3, 5).range(), 3..5);
+    // offsets that don't fit are clamped
+    let span = Span::new(1, usize::MAX);
+    assert_eq!(
+        span,
+        Span {
+            start: 1,
+            end: u32::MAX
+        }
+    );
+    assert_eq!(span.len(), u32::MAX as usize - 1);
+}
+
+#[test]
+fn test_min_literal() {
+    // the '-' is folded into the number, as `9_223_372_036_854_775_808` doesn't fit i64
+    let tokens = Lexer::new("-9_223_372_036_854_775_808").lex().unwrap();
+    assert_eq!(
+        tokens,
+        vec![Token {
+            kind: TokenKind::Int { value: i64::MIN },
+            span: Span { start: 0, end: 26 }
+        }]
+    );
+
+    let tokens = Lexer::new("(1 * -9223372036854775808)").lex().unwrap();
+    assert_eq!(
+        tokens[3],
+        Token {
+            kind: TokenKind::Int { value: i64::MIN },
+            span: Span { start: 5, end: 25 }
+        }
+    );
+
+    // like in scientific notation
+    let tokens = Lexer::new("-9_223_372_036_854_775_808e0, 1").lex().unwrap();
+    assert_eq!(
+        tokens[0],
+        Token {
+            kind: TokenKind::Int { value: i64::MIN },
+            span: Span { start: 0, end: 28 }
+        }
+    );
+
+    // anything but a negating '-' is still too large
+    for (input, expected) in [
+        ("+9_223_372_036_854_775_808", Span { start: 1, end: 26 }),
+        ("- 9223372036854775808", Span { start: 2, end: 21 }),
+        ("(5-9223372036854775808)", Span { start: 3, end: 22 }),
+        ("92233720368547758080e0", Span { start: 0, end: 22 }),
+    ] {
+        let tokens = Lexer::new(input).lex();
+        if let Err(LexicalError::NumberTooLarge(ctx)) = &tokens {
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected NumberTooLarge error for {input:?}, found {tokens:?}");
+        }
+    }
+}
+
 #[test]
 fn test_invalid_token() {
-    let mut lexer = Lexer::new("1,2,#3");
+    let mut lexer = Lexer::new("1,2,$3");
     let tokens = lexer.lex();
-    if let Err(LexicalError::InvalidToken(_, span)) = tokens {
-        println!("{}", tokens.err().unwrap());
-        assert_eq!(span, Span { start: 5, end: 5 });
+    if let Err(LexicalError::InvalidToken(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 4, end: 5 });
     } else {
         panic!("Expected InvalidToken error");
     }
@@ -70,23 +187,23 @@ fn test_range() {
         vec![
             Token {
                 kind: TokenKind::LSquiggly,
-                span: Span { start: 1, end: 1 }
+                span: Span { start: 0, end: 1 }
             },
             Token {
                 kind: TokenKind::Int { value: 1 },
-                span: Span { start: 2, end: 2 }
+                span: Span { start: 1, end: 2 }
             },
             Token {
                 kind: TokenKind::RngExclusive,
-                span: Span { start: 3, end: 4 }
+                span: Span { start: 2, end: 4 }
             },
             Token {
                 kind: TokenKind::Int { value: 5 },
-                span: Span { start: 5, end: 5 }
+                span: Span { start: 4, end: 5 }
             },
             Token {
                 kind: TokenKind::RSquiggly,
-                span: Span { start: 6, end: 6 }
+                span: Span { start: 5, end: 6 }
             },
         ]
     );
@@ -98,23 +215,23 @@ fn test_range() {
         vec![
             Token {
                 kind: TokenKind::LSquiggly,
-                span: Span { start: 1, end: 1 }
+                span: Span { start: 0, end: 1 }
             },
             Token {
                 kind: TokenKind::Int { value: 1 },
-                span: Span { start: 2, end: 2 }
+                span: Span { start: 1, end: 2 }
             },
             Token {
                 kind: TokenKind::RngInclusive,
-                span: Span { start: 3, end: 5 }
+                span: Span { start: 2, end: 5 }
             },
             Token {
                 kind: TokenKind::Int { value: 5 },
-                span: Span { start: 6, end: 6 }
+                span: Span { start: 5, end: 6 }
             },
             Token {
                 kind: TokenKind::RSquiggly,
-                span: Span { start: 7, end: 7 }
+                span: Span { start: 6, end: 7 }
             },
         ]
     );
@@ -124,8 +241,8 @@ fn test_range() {
 fn test_invalid_range() {
     let mut lexer = Lexer::new("{1.=.5}");
     let tokens = lexer.lex();
-    if let Err(LexicalError::UnexpectedEqual(_, span)) = tokens {
-        assert_eq!(span, Span { start: 3, end: 5 });
+    if let Err(LexicalError::UnexpectedEqual(ctx)) = &tokens {
+        assert_eq!(ctx.span, Span { start: 2, end: 5 });
         println!("{}", tokens.err().unwrap());
     } else {
         panic!("Expected UnexpectedEqual error");
@@ -133,8 +250,8 @@ fn test_invalid_range() {
 
     let mut lexer = Lexer::new("{1.=5}");
     let tokens = lexer.lex();
-    if let Err(LexicalError::InvalidRange(_, span)) = tokens {
-        assert_eq!(span, Span { start: 3, end: 4 });
+    if let Err(LexicalError::InvalidRange(ctx)) = &tokens {
+        assert_eq!(ctx.span, Span { start: 2, end: 4 });
         println!("{}", tokens.err().unwrap());
     } else {
         panic!("Expected InvalidRange error");
@@ -142,8 +259,8 @@ fn test_invalid_range() {
 
     let mut lexer = Lexer::new("{1...5}");
     let tokens = lexer.lex();
-    if let Err(LexicalError::InvalidRange(_, span)) = tokens {
-        assert_eq!(span, Span { start: 3, end: 5 });
+    if let Err(LexicalError::InvalidRange(ctx)) = &tokens {
+        assert_eq!(ctx.span, Span { start: 2, end: 5 });
         println!("{}", tokens.err().unwrap());
     } else {
         panic!("Expected InvalidRange error");
@@ -159,35 +276,35 @@ fn test_range_arg() {
         vec![
             Token {
                 kind: TokenKind::LSquiggly,
-                span: Span { start: 1, end: 1 }
+                span: Span { start: 0, end: 1 }
             },
             Token {
                 kind: TokenKind::RngStep,
-                span: Span { start: 2, end: 3 }
+                span: Span { start: 1, end: 3 }
             },
             Token {
                 kind: TokenKind::Int { value: 1 },
-                span: Span { start: 4, end: 4 }
+                span: Span { start: 3, end: 4 }
             },
             Token {
                 kind: TokenKind::Comma,
-                span: Span { start: 5, end: 5 }
+                span: Span { start: 4, end: 5 }
             },
             Token {
                 kind: TokenKind::RngMutation,
-                span: Span { start: 6, end: 7 }
+                span: Span { start: 5, end: 7 }
             },
             Token {
                 kind: TokenKind::Math(Op::Add),
-                span: Span { start: 8, end: 8 }
+                span: Span { start: 7, end: 8 }
             },
             Token {
                 kind: TokenKind::Int { value: 20000000 },
-                span: Span { start: 9, end: 18 }
+                span: Span { start: 8, end: 18 }
             },
             Token {
                 kind: TokenKind::RSquiggly,
-                span: Span { start: 19, end: 19 }
+                span: Span { start: 18, end: 19 }
             },
         ]
     );
@@ -197,8 +314,8 @@ fn test_range_arg() {
 fn test_invalid_range_arg() {
     let mut lexer = Lexer::new("{1..=5, s2}");
     let tokens = lexer.lex();
-    if let Err(LexicalError::MissingColon(_, span)) = tokens {
-        assert_eq!(span, Span { start: 9, end: 9 });
+    if let Err(LexicalError::MissingColon(ctx)) = &tokens {
+        assert_eq!(ctx.span, Span { start: 8, end: 9 });
         println!("{}", tokens.err().unwrap());
     } else {
         panic!("Expected MissingColon error");
@@ -209,19 +326,707 @@ fn test_invalid_range_arg() {
 fn test_invalid_range_syntax() {
     let mut lexer = Lexer::new("s:1");
     let tokens = lexer.lex();
-    if let Err(LexicalError::MisplacedRngSyntax(_, span)) = tokens {
-        println!("{}", tokens.err().unwrap());
-        assert_eq!(span, Span { start: 1, end: 1 });
+    if let Err(LexicalError::MisplacedRngSyntax(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 0, end: 1 });
     } else {
         panic!("Expected MisplacedRngSyntax error");
     }
 
-    let mut lexer = Lexer::new("1, 3, 2__000, @");
+    let mut lexer = Lexer::new("1, 3, 2_000, @");
     let tokens = lexer.lex();
-    if let Err(LexicalError::MisplacedRngSyntax(_, span)) = tokens {
-        println!("{}", tokens.err().unwrap());
-        assert_eq!(span, Span { start: 15, end: 15 });
+    if let Err(LexicalError::MisplacedRngSyntax(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 13, end: 14 });
     } else {
         panic!("Expected MisplacedRngSyntax error");
     }
 }
+
+#[test]
+fn test_scientific_notation() {
+    let mut lexer = Lexer::new("1e6, 2E3, 1_0e1_0");
+    let tokens = lexer.lex().unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token {
+                kind: TokenKind::Int { value: 1_000_000 },
+                span: Span { start: 0, end: 3 }
+            },
+            Token {
+                kind: TokenKind::Comma,
+                span: Span { start: 3, end: 4 }
+            },
+            Token {
+                kind: TokenKind::Int { value: 2_000 },
+                span: Span { start: 5, end: 8 }
+            },
+            Token {
+                kind: TokenKind::Comma,
+                span: Span { start: 8, end: 9 }
+            },
+            Token {
+                kind: TokenKind::Int {
+                    value: 100_000_000_000
+                },
+                span: Span { start: 10, end: 17 }
+            },
+        ]
+    );
+
+    let mut lexer = Lexer::new("{0..=1e4, s:1e3}");
+    let tokens = lexer.lex().unwrap();
+    assert_eq!(tokens[3].kind, TokenKind::Int { value: 10_000 });
+    assert_eq!(tokens[3].span, Span { start: 5, end: 8 });
+    assert_eq!(tokens[6].kind, TokenKind::Int { value: 1_000 });
+    assert_eq!(tokens[6].span, Span { start: 12, end: 15 });
+}
+
+#[test]
+fn test_invalid_scientific_notation() {
+    let mut lexer = Lexer::new("1, 1e");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::MalformedNumber(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 3, end: 5 });
+    } else {
+        panic!("Expected MalformedNumber error");
+    }
+
+    let mut lexer = Lexer::new("1, 10e18");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::NumberTooLarge(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 3, end: 8 });
+    } else {
+        panic!("Expected NumberTooLarge error");
+    }
+}
+
+#[test]
+fn test_misplaced_underscore() {
+    // (input, span of the offending underscore)
+    let cases = [
+        ("1, 3, 2__000, @", 9),
+        ("_", 1),
+        ("1, _1", 4),
+        ("1_", 2),
+        ("10_, 2", 3),
+        ("1_e3", 2),
+        ("1e_3", 3),
+        ("{1..=5, s:2__0}", 13),
+    ];
+
+    for (input, pos) in cases {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+        if let Err(LexicalError::MalformedNumber(ctx)) = &tokens {
+            println!("{}", tokens.as_ref().unwrap_err());
+            assert_eq!(
+                ctx.span,
+                Span {
+                    start: pos - 1,
+                    end: pos
+                },
+                "input: {input:?}"
+            );
+        } else {
+            panic!("Expected MalformedNumber error for {input:?}");
+        }
+    }
+
+    let mut lexer = Lexer::new("1_000_000, 1_2_3");
+    let tokens = lexer.lex().unwrap();
+    assert_eq!(tokens[0].kind, TokenKind::Int { value: 1_000_000 });
+    assert_eq!(tokens[2].kind, TokenKind::Int { value: 123 });
+}
+
+#[test]
+fn test_min_max_keywords() {
+    let mut lexer = Lexer::new("MAX, min, {Max-5..=MAX}");
+    let tokens = lexer.lex().unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token {
+                kind: TokenKind::Int { value: i64::MAX },
+                span: Span { start: 0, end: 3 }
+            },
+            Token {
+                kind: TokenKind::Comma,
+                span: Span { start: 3, end: 4 }
+            },
+            Token {
+                kind: TokenKind::Int { value: i64::MIN },
+                span: Span { start: 5, end: 8 }
+            },
+            Token {
+                kind: TokenKind::Comma,
+                span: Span { start: 8, end: 9 }
+            },
+            Token {
+                kind: TokenKind::LSquiggly,
+                span: Span { start: 10, end: 11 }
+            },
+            Token {
+                kind: TokenKind::Int { value: i64::MAX },
+                span: Span { start: 11, end: 14 }
+            },
+            Token {
+                kind: TokenKind::Math(Op::Sub),
+                span: Span { start: 14, end: 15 }
+            },
+            Token {
+                kind: TokenKind::Int { value: 5 },
+                span: Span { start: 15, end: 16 }
+            },
+            Token {
+                kind: TokenKind::RngInclusive,
+                span: Span { start: 16, end: 19 }
+            },
+            Token {
+                kind: TokenKind::Int { value: i64::MAX },
+                span: Span { start: 19, end: 22 }
+            },
+            Token {
+                kind: TokenKind::RSquiggly,
+                span: Span { start: 22, end: 23 }
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_unknown_keyword() {
+    let mut lexer = Lexer::new("1, maximum, 3");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::UnknownKeyword(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 3, end: 10 });
+    } else {
+        panic!("Expected UnknownKeyword error");
+    }
+
+    let mut lexer = Lexer::new("{1..=5, S:2}");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::UnknownRangeArg(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 8, end: 9 });
+    } else {
+        panic!("Expected UnknownRangeArg error");
+    }
+}
+
+#[test]
+fn test_long_range_args() {
+    let short = Lexer::new("{1..9, s:2, m:+1}").lex().unwrap();
+    let long = Lexer::new("{1..9, step:2, mut:+1}").lex().unwrap();
+    let kinds = |tokens: &[Token]| tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+    assert_eq!(kinds(&short), kinds(&long));
+    assert_eq!(long[5].span, Span { start: 7, end: 12 });
+    assert_eq!(long[8].span, Span { start: 15, end: 19 });
+
+    let mixed = Lexer::new("{1..9, step:2, m:+1}").lex().unwrap();
+    assert_eq!(kinds(&short), kinds(&mixed));
+
+    // (input, span of the error)
+    let cases = [
+        ("{1..9, step 2}", Span::new(7, 11)),
+        ("{1..9, mut}", Span::new(7, 10)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex();
+        if let Err(LexicalError::MissingColon(ctx)) = &tokens {
+            println!("{}", tokens.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected MissingColon error for {input:?}");
+        }
+    }
+
+    let tokens = Lexer::new("{1..9, foo:2}").lex();
+    if let Err(LexicalError::UnknownRangeArg(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 7, end: 10 });
+    } else {
+        panic!("Expected UnknownRangeArg error");
+    }
+}
+
+#[test]
+fn test_clamp_args() {
+    let tokens = Lexer::new("{1..9, lo:2, hi : 5}").lex().unwrap();
+    let args = tokens
+        .iter()
+        .filter(|token| matches!(token.kind, TokenKind::RngLow | TokenKind::RngHigh))
+        .map(|token| (token.kind, token.span))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        vec![
+            (TokenKind::RngLow, Span::new(7, 10)),
+            (TokenKind::RngHigh, Span::new(13, 17)),
+        ]
+    );
+
+    let kinds = |tokens: &[Token]| tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+    let long = Lexer::new("{1..9, low:2, high:5}").lex().unwrap();
+    assert_eq!(kinds(&tokens), kinds(&long));
+
+    let tokens = Lexer::new("{1..9, hi 5}").lex();
+    if let Err(LexicalError::MissingColon(ctx)) = &tokens {
+        assert_eq!(ctx.span, Span::new(7, 9));
+    } else {
+        panic!("Expected MissingColon error, found {tokens:?}");
+    }
+}
+
+#[test]
+fn test_functions() {
+    let mut lexer = Lexer::new("(abs(-1) + MIN)");
+    let tokens = lexer.lex().unwrap();
+    assert_eq!(
+        tokens[1],
+        Token {
+            kind: TokenKind::Func(Func::Abs),
+            span: Span { start: 1, end: 4 }
+        }
+    );
+    assert_eq!(
+        tokens[7],
+        Token {
+            kind: TokenKind::Int { value: i64::MIN },
+            span: Span { start: 11, end: 14 }
+        }
+    );
+
+    let mut lexer = Lexer::new("(sqrt(4))");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::UnknownFunc(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 1, end: 5 });
+    } else {
+        panic!("Expected UnknownFunc error");
+    }
+
+    // the `(` of a call can be spaced out from the name
+    let tokens = Lexer::new("(abs (-3))").lex().unwrap();
+    assert_eq!(tokens[1].kind, TokenKind::Func(Func::Abs));
+    let error = Lexer::new("(sqrt (4))").lex().unwrap_err();
+    assert!(matches!(error, LexicalError::UnknownFunc(ctx) if ctx.span == Span::new(1, 5)));
+
+    // a name without its `(` isn't a call
+    let error = Lexer::new("(abs 3)").lex().unwrap_err();
+    assert!(matches!(&error, LexicalError::UnknownKeyword(ctx) if ctx.span == Span::new(1, 4)));
+    assert!(error.hint().unwrap().contains("'abs' is a function"));
+}
+
+#[test]
+fn test_min_max_names() {
+    // `MIN` and `MAX` are the constants, unless a `(` follows them, spaces or not
+    let kinds = |input: &str| {
+        Lexer::new(input)
+            .lex()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(kinds("max")[0], TokenKind::Int { value: i64::MAX });
+    assert_eq!(kinds("MIN")[0], TokenKind::Int { value: i64::MIN });
+    assert_eq!(kinds("(max(1, 2))")[1], TokenKind::Func(Func::Max));
+    assert_eq!(kinds("(MIN (1, 2))")[1], TokenKind::Func(Func::Min));
+    assert_eq!(kinds("(max - 1)")[1], TokenKind::Int { value: i64::MAX });
+}
+
+#[test]
+fn test_bitwise_operators() {
+    let mut lexer = Lexer::new("(1 << 2 >> 1 & ~3 | 4)");
+    let tokens = lexer.lex().unwrap();
+    let operators = tokens
+        .iter()
+        .filter(|token| matches!(token.kind, TokenKind::Math(_)))
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        operators,
+        vec![
+            Token {
+                kind: TokenKind::Math(Op::Shl),
+                span: Span { start: 3, end: 5 }
+            },
+            Token {
+                kind: TokenKind::Math(Op::Shr),
+                span: Span { start: 8, end: 10 }
+            },
+            Token {
+                kind: TokenKind::Math(Op::BitAnd),
+                span: Span { start: 13, end: 14 }
+            },
+            Token {
+                kind: TokenKind::Math(Op::BitNot),
+                span: Span { start: 15, end: 16 }
+            },
+            Token {
+                kind: TokenKind::Math(Op::BitOr),
+                span: Span { start: 18, end: 19 }
+            },
+        ]
+    );
+
+    let mut lexer = Lexer::new("(1 < 2)");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::InvalidToken(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 3, end: 4 });
+    } else {
+        panic!("Expected InvalidToken error");
+    }
+}
+
+#[test]
+fn test_factorial_operator() {
+    let mut lexer = Lexer::new("(5!), {1..=3, m:!}");
+    let tokens = lexer.lex().unwrap();
+    let factorials = tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Math(Op::Factorial))
+        .map(|token| token.span)
+        .collect::<Vec<_>>();
+    assert_eq!(factorials, vec![Span::new(2, 3), Span::new(16, 17)]);
+
+    let mut lexer = Lexer::new("1, 5!");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::InvalidToken(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 4, end: 5 });
+    } else {
+        panic!("Expected InvalidToken error");
+    }
+}
+
+#[test]
+fn test_zip_operator() {
+    // `~` between items zips them, inside a math expression or a range it is a bitwise NOT
+    let mut lexer = Lexer::new("{1..=3, m:~@} ~ (~2) ~ -4");
+    let tokens = lexer.lex().unwrap();
+    let tildes = tokens
+        .iter()
+        .filter(|token| matches!(token.kind, TokenKind::Zip | TokenKind::Math(Op::BitNot)))
+        .map(|token| (token.kind, token.span))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tildes,
+        vec![
+            (TokenKind::Math(Op::BitNot), Span::new(10, 11)),
+            (TokenKind::Zip, Span::new(14, 15)),
+            (TokenKind::Math(Op::BitNot), Span::new(17, 18)),
+            (TokenKind::Zip, Span::new(21, 22)),
+        ]
+    );
+}
+
+#[test]
+fn test_repeat_operator() {
+    let mut lexer = Lexer::new("{1..=3} x 2, 0X5");
+    let tokens = lexer.lex().unwrap();
+    let repeats = tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Repeat)
+        .map(|token| token.span)
+        .collect::<Vec<_>>();
+    assert_eq!(repeats, vec![Span::new(8, 9), Span::new(14, 15)]);
+
+    // `x` is only special between items, and only on its own
+    for (input, span) in [
+        ("(2 x 3)", Span::new(3, 4)),
+        ("{1..5, m:@ x 2}", Span::new(11, 12)),
+        ("0 xx 5", Span::new(2, 4)),
+    ] {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+        if let Err(LexicalError::UnknownKeyword(ctx)) = &tokens {
+            assert_eq!(ctx.span, span, "input: {input:?}");
+        } else {
+            panic!("Expected UnknownKeyword error for {input:?}, found {tokens:?}");
+        }
+    }
+}
+
+#[test]
+fn test_whitespace() {
+    let mut lexer = Lexer::new("1,\n2,\t3\n");
+    let tokens = lexer.lex().unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token {
+                kind: TokenKind::Int { value: 1 },
+                span: Span { start: 0, end: 1 }
+            },
+            Token {
+                kind: TokenKind::Comma,
+                span: Span { start: 1, end: 2 }
+            },
+            Token {
+                kind: TokenKind::Int { value: 2 },
+                span: Span { start: 3, end: 4 }
+            },
+            Token {
+                kind: TokenKind::Comma,
+                span: Span { start: 4, end: 5 }
+            },
+            Token {
+                kind: TokenKind::Int { value: 3 },
+                span: Span { start: 6, end: 7 }
+            },
+        ]
+    );
+
+    let mut lexer = Lexer::new("1,\n2,\t$\n3");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::InvalidToken(ctx)) = &tokens {
+        let rendered = tokens.as_ref().unwrap_err().to_string();
+        println!("{rendered}");
+        assert_eq!(ctx.span, Span { start: 6, end: 7 });
+        // only the offending line is shown
+        #[cfg(feature = "std")]
+        assert!(rendered.contains("│ 2,\t"));
+        assert!(!rendered.contains("1,"));
+        assert!(!rendered.contains("│ 3"));
+    } else {
+        panic!("Expected InvalidToken error");
+    }
+}
+
+#[test]
+fn test_comments() {
+    let input = indoc! {"
+        # months of the year
+        {1..=12}  # months
+        (2 * 3), # math
+        {1..10, # step below
+         s:2, m:abs(@)} # mutation
+        7 #"};
+    let tokens = Lexer::new(input).lex().unwrap();
+    // blanking out the comments must produce the exact same tokens and spans
+    let blanked = input
+        .lines()
+        .map(|line| {
+            let code = line.split('#').next().unwrap();
+            format!("{code:<width$}", width = line.chars().count())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(tokens, Lexer::new(&blanked).lex().unwrap());
+    assert_eq!(tokens.len(), 26);
+    assert_eq!(
+        tokens.last().unwrap().span,
+        Span {
+            start: 104,
+            end: 105
+        }
+    );
+
+    let mut lexer = Lexer::new("1, # comment $\n2, $");
+    let tokens = lexer.lex();
+    if let Err(LexicalError::InvalidToken(ctx)) = &tokens {
+        println!("{}", tokens.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span { start: 18, end: 19 });
+    } else {
+        panic!("Expected InvalidToken error");
+    }
+}
+
+#[test]
+fn test_sort_order() {
+    let tokens = Lexer::new("{1..9, o:asc}, {1..9, order: DESC}")
+        .lex()
+        .unwrap();
+    let orders = tokens
+        .iter()
+        .filter(|token| matches!(token.kind, TokenKind::RngOrder(_)))
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        orders,
+        vec![
+            Token {
+                kind: TokenKind::RngOrder(SortOrder::Asc),
+                span: Span { start: 7, end: 12 }
+            },
+            Token {
+                kind: TokenKind::RngOrder(SortOrder::Desc),
+                span: Span { start: 22, end: 33 }
+            },
+        ]
+    );
+
+    // (input, span of the error)
+    let cases = [
+        ("{1..9, o:up}", Span::new(9, 11)),
+        ("{1..9, o:}", Span::new(9, 10)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex();
+        if let Err(LexicalError::InvalidSortOrder(ctx)) = &tokens {
+            println!("{}", tokens.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected InvalidSortOrder error for {input:?}");
+        }
+    }
+}
+
+#[test]
+fn test_byte_offsets() {
+    let input = "{1..=3}, # ünïcödé\n(2 * 3), é";
+    let tokens = Lexer::new(input).lex();
+    if let Err(LexicalError::InvalidToken(ctx)) = &tokens {
+        let rendered = tokens.as_ref().unwrap_err().to_string();
+        println!("{rendered}");
+        assert_eq!(ctx.span, Span { start: 32, end: 34 });
+        assert_eq!(&input[ctx.span.range()], "é");
+        assert_eq!(ctx.span.to_char_range(input), 28..29);
+        assert!(rendered.contains("@ position 29"));
+    } else {
+        panic!("Expected InvalidToken error");
+    }
+}
+
+#[test]
+fn test_whitespace_in_ranges() {
+    let kinds = |input: &str| {
+        let tokens = Lexer::new(input).lex().unwrap();
+        tokens
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>()
+    };
+
+    // (pretty-formatted, compact)
+    let cases = [
+        ("{ 1 .. 5 }", "{1..5}"),
+        ("{ 1 ..= 5 }", "{1..=5}"),
+        ("{ ..= 5 }", "{..=5}"),
+        ("{1..5, s : 2}", "{1..5, s:2}"),
+        ("{1..5, s :2}", "{1..5, s:2}"),
+        ("{1..5, m : * 2}", "{1..5, m:*2}"),
+        ("{1..5, step\n  : 2}", "{1..5, s:2}"),
+        ("{1..5, u : , o : desc}", "{1..5, u:, o:desc}"),
+        (
+            "{\n  1 ..= 10 ,\n  s : 2 ,\n  m : @ ^ 2 ,\n}",
+            "{1..=10,s:2,m:@^2,}",
+        ),
+    ];
+    for (pretty, compact) in cases {
+        assert_eq!(kinds(pretty), kinds(compact), "input: {pretty:?}");
+    }
+
+    // the argument's span covers the whitespace up to the colon
+    let tokens = Lexer::new("{1..5, s  : 2}").lex().unwrap();
+    assert_eq!(
+        tokens[5],
+        Token {
+            kind: TokenKind::RngStep,
+            span: Span { start: 7, end: 11 }
+        }
+    );
+
+    // a missing colon is still reported
+    let tokens = Lexer::new("{1..5, s 2}").lex();
+    if let Err(LexicalError::MissingColon(ctx)) = &tokens {
+        assert_eq!(ctx.span, Span { start: 7, end: 8 });
+    } else {
+        panic!("Expected MissingColon error, found {tokens:?}");
+    }
+}
+
+#[test]
+fn test_trivia() {
+    let input = "1,  2 # two\n\t{1..5, s : 2}";
+    let tokens = Lexer::new(input).with_trivia(true).lex().unwrap();
+    let trivia = tokens
+        .iter()
+        .filter(|token| token.kind.is_trivia())
+        .map(|token| (token.kind, token.text(input).unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        trivia,
+        [
+            (TokenKind::Whitespace, "  "),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::Comment, "# two"),
+            (TokenKind::Whitespace, "\n\t"),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::Whitespace, " "),
+        ]
+    );
+
+    // off by default
+    let tokens = Lexer::new(input).lex().unwrap();
+    assert!(!tokens.iter().any(|token| token.kind.is_trivia()));
+}
+
+#[test]
+fn test_trivia_round_trip() {
+    let inputs = [
+        "",
+        "   ",
+        "# only a comment",
+        "1,\n2,\t3\n",
+        "  -3, - 3, -9_223_372_036_854_775_808, (1 -9_223_372_036_854_775_807)",
+        "{1..=12}  # months\n(2 * 3), # math\n{1..10, # step below\n s:2, m:abs(@)} # mutation\n7 #",
+        "{\n  1 ..= 10 ,\n  step : 2 ,\n  m : @ ^ 2 , o : desc\n}\u{a0}\u{2003}",
+        "(max( 1 , 2 )! << 3)\r\n,{..5, u :}",
+    ];
+    for input in inputs {
+        let tokens = Lexer::new(input).with_trivia(true).lex().unwrap();
+        let text = tokens
+            .iter()
+            .map(|token| token.text(input).unwrap())
+            .collect::<String>();
+        assert_eq!(text, input);
+
+        // the rest of the tokens are the same as without trivia
+        let significant = tokens
+            .into_iter()
+            .filter(|token| !token.kind.is_trivia())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            significant,
+            Lexer::new(input).lex().unwrap(),
+            "input: {input:?}"
+        );
+    }
+}
+
+#[test]
+fn test_dump() {
+    let mut lexer = Lexer::new("{1..=MAX, m:* 2}, # é\n(abs(-3))");
+    let tokens = lexer.lex().unwrap();
+    assert_eq!(
+        lexer.dump(&tokens),
+        indoc! {r#"
+            0..1    LSquiggly                           "{"
+            1..2    Int { value: 1 }                    "1"
+            2..5    RngInclusive                        "..="
+            5..8    Int { value: 9223372036854775807 }  "MAX"
+            8..9    Comma                               ","
+            10..12  RngMutation                         "m:"
+            12..13  Math(Mul)                           "*"
+            14..15  Int { value: 2 }                    "2"
+            15..16  RSquiggly                           "}"
+            16..17  Comma                               ","
+            23..24  LParen                              "("
+            24..27  Func(Abs)                           "abs"
+            27..28  LParen                              "("
+            28..29  Math(Sub)                           "-"
+            29..30  Int { value: 3 }                    "3"
+            30..31  RParen                              ")"
+            31..32  RParen                              ")"
+        "#}
+    );
+    assert_eq!(lexer.dump(&[]), "");
+}
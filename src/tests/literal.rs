@@ -0,0 +1,107 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+use crate::literal::{to_literal, Language, LiteralOptions};
+
+fn literal(numbers: &[i64], language: Language, rust_vec: bool, max_width: usize) -> String {
+    let options = LiteralOptions {
+        language,
+        rust_vec,
+        max_width: Some(max_width),
+    };
+    to_literal(numbers, &options)
+}
+
+#[test]
+fn test_languages() {
+    let numbers = [1, -3, 5];
+    let cases = [
+        (Language::Rust, true, "vec![1, -3, 5]"),
+        (Language::Rust, false, "[1, -3, 5]"),
+        (Language::Python, true, "[1, -3, 5]"),
+        (Language::Json, true, "[1,-3,5]"),
+        (Language::Csv, true, "1,-3,5"),
+    ];
+    for (language, rust_vec, expected) in cases {
+        assert_eq!(literal(&numbers, language, rust_vec, 100), expected);
+    }
+
+    // nothing to escape in the numbers, they are written as they are
+    assert_eq!(
+        literal(&[i64::MIN, 0, i64::MAX], Language::Python, true, 100),
+        "[-9223372036854775808, 0, 9223372036854775807]"
+    );
+
+    assert_eq!(literal(&[], Language::Rust, true, 100), "vec![]");
+    assert_eq!(literal(&[], Language::Rust, true, 0), "vec![]");
+    assert_eq!(literal(&[], Language::Csv, true, 100), "");
+}
+
+#[test]
+fn test_wrap() {
+    let numbers = (1..=12).map(|n| n * 10).collect::<Vec<_>>();
+
+    // the widest that fits on a single line
+    let one_line = "vec![10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, 120]";
+    assert_eq!(
+        literal(&numbers, Language::Rust, true, one_line.len()),
+        one_line
+    );
+
+    // a line is filled up to the width, trailing comma included
+    assert_eq!(
+        literal(&numbers, Language::Rust, true, one_line.len() - 1),
+        indoc! {"
+            vec![
+                10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, 120,
+            ]"}
+    );
+    assert_eq!(
+        literal(&numbers, Language::Python, true, 20),
+        indoc! {"
+            [
+                10, 20, 30, 40,
+                50, 60, 70, 80,
+                90, 100, 110,
+                120,
+            ]"}
+    );
+    assert_eq!(
+        literal(&numbers, Language::Rust, false, 19),
+        indoc! {"
+            [
+                10, 20, 30, 40,
+                50, 60, 70, 80,
+                90, 100, 110,
+                120,
+            ]"}
+    );
+
+    // a number too wide for the line still gets one to itself
+    assert_eq!(
+        literal(&[123456, 7, 8], Language::Rust, true, 6),
+        indoc! {"
+            vec![
+                123456,
+                7,
+                8,
+            ]"}
+    );
+
+    // JSON and CSV stay on a single line whatever the width
+    assert_eq!(
+        literal(&numbers, Language::Json, true, 10).lines().count(),
+        1
+    );
+    assert_eq!(
+        literal(&numbers, Language::Csv, true, 10).lines().count(),
+        1
+    );
+
+    // without a width, nothing is wrapped
+    let options = LiteralOptions {
+        max_width: None,
+        ..Default::default()
+    };
+    assert_eq!(to_literal(&numbers, &options), one_line);
+}
@@ -1,2 +1,34 @@
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod builder;
+#[cfg(feature = "clap")]
+mod clap;
+#[cfg(feature = "proptest")]
+mod differential;
+mod document;
+mod errors;
+mod eval;
+mod fast;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod highlight;
+mod incremental;
+// the checks are left out of release builds without the feature
+#[cfg(any(debug_assertions, feature = "strict-invariants"))]
+mod invariants;
 mod lexer;
+mod literal;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod parser;
+#[cfg(feature = "proptest")]
+mod props;
+// the snapshots are taken without the `fun` hints
+#[cfg(all(feature = "std", not(feature = "fun"), not(target_arch = "wasm32")))]
+mod rendered;
+mod rng;
+#[cfg(feature = "serde")]
+mod serialize;
+mod tokens;
+mod validate;
+mod visit;
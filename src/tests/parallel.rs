@@ -0,0 +1,107 @@
+use pretty_assertions::assert_eq;
+
+use std::time::{Duration, Instant};
+
+use super::rng::Rng;
+use crate::{
+    errors::EvalError,
+    eval::{EvalOptions, Evaluator, MAX_STEPS},
+    parser::Parser,
+    tokens::{SortOrder, Span},
+};
+
+fn random_item(rng: &mut Rng) -> String {
+    match rng.below(10) {
+        0 => format!("{}", rng.below(2_000) - 1_000),
+        // fails every now and then, to check the first error is the one reported
+        1 => format!("({} / {})", rng.below(100), rng.below(10)),
+        2 => format!(
+            "({} * {} + {})",
+            rng.below(100),
+            rng.below(100),
+            rng.below(10)
+        ),
+        _ => {
+            let start = rng.below(10_000) - 5_000;
+            let end = rng.below(10_000) - 5_000;
+            let dots = if rng.chance(50) { "..=" } else { ".." };
+            let mut range = format!("{{{start}{dots}{end}");
+            if rng.chance(30) {
+                let step = rng.below(5) + 1;
+                let step = if end < start { -step } else { step };
+                range.push_str(&format!(", s:{step}"));
+            }
+            if rng.chance(30) {
+                let op = ["+", "-", "*", "/", "%"][rng.below(5) as usize];
+                range.push_str(&format!(", m:{op}{}", rng.below(10)));
+            }
+            if rng.chance(20) {
+                range.push_str(", u:");
+            }
+            if rng.chance(20) {
+                range.push_str([", o:asc", ", o:desc"][rng.below(2) as usize]);
+            }
+            range.push('}');
+            range
+        }
+    }
+}
+
+fn random_options(rng: &mut Rng) -> EvalOptions {
+    EvalOptions {
+        dedup_all: rng.chance(30),
+        sort: match rng.below(4) {
+            0 => Some(SortOrder::Asc),
+            1 => Some(SortOrder::Desc),
+            _ => None,
+        },
+        // runs out of steps somewhere in the middle every now and then
+        max_steps: match rng.chance(25) {
+            true => rng.below(50_000) as u64,
+            false => MAX_STEPS,
+        },
+        ..Default::default()
+    }
+}
+
+fn summary(result: &Result<Vec<i64>, EvalError>) -> Result<&[i64], (&str, Span)> {
+    match result {
+        Ok(numbers) => Ok(numbers),
+        Err(e) => Err((e.code(), e.span())),
+    }
+}
+
+#[test]
+fn test_par_eval_matches_eval() {
+    let mut rng = Rng(0x5eed_2024);
+    for _ in 0..100 {
+        let count = rng.below(12) + 1;
+        let input = (0..count)
+            .map(|_| random_item(&mut rng))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let nodes = Parser::from_source(&input).unwrap().parse().unwrap();
+        let evaluator = Evaluator::with_options(input.as_str().into(), random_options(&mut rng));
+
+        let sequential = evaluator.eval(&nodes);
+        // a threshold of 0 always takes the parallel path
+        let parallel = evaluator.par_eval_with_threshold(&nodes, 0);
+        assert_eq!(summary(&parallel), summary(&sequential), "input: {input}");
+        assert_eq!(summary(&evaluator.par_eval(&nodes)), summary(&sequential));
+    }
+}
+
+#[test]
+fn test_par_eval_stops_after_error() {
+    // the range after the error would take a few quintillion numbers to finish
+    let input = "{1..=3}, (1 / 0), {0..MAX}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+
+    let started = Instant::now();
+    let error = Evaluator::new(input.into()).par_eval(&nodes).unwrap_err();
+    assert!(matches!(
+        &error,
+        EvalError::DivisionByZero(ctx) if ctx.span == Span::new(12, 13)
+    ));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
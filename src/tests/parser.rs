@@ -1,24 +1,43 @@
+use indoc::indoc;
 use pretty_assertions::assert_eq;
 
+#[cfg(feature = "aggregate")]
+use crate::errors::Bound;
 use crate::{
-    errors::ParserError,
+    errors::{render_all, LexicalError, ParserError},
     lexer::Lexer,
-    parser::{Node, Parser, MAX_PAREN_DEPTH},
-    tokens::Span,
+    parser::{Node, Parser, ParserOptions, MAX_PAREN_DEPTH},
+    tokens::{Func, Op, SortOrder, Span, Token, TokenKind},
 };
 
+/// Compares the nodes parsed from `source`, showing both as trees when they differ.
+#[track_caller]
+pub(super) fn assert_nodes_eq(source: &str, left: &[Node], right: &[Node]) {
+    if left != right {
+        let tree = |nodes: &[Node]| {
+            let tree = nodes.iter().map(|node| node.tree_string(source));
+            tree.collect::<String>()
+        };
+        panic!(
+            "nodes of {source:?} differ\n\nleft:\n{}\nright:\n{}",
+            tree(left),
+            tree(right)
+        );
+    }
+}
+
 #[test]
 fn test_unexpectd_comma() {
     // comma at the start
     let input = ",1,2,3";
     let mut lexer = Lexer::new(input);
     let tokens = lexer.lex().unwrap();
-    let mut parser = Parser::new(lexer.input_chars, &tokens);
+    let mut parser = Parser::new(lexer.source, &tokens);
     let nodes = parser.parse();
 
-    if let Err(ParserError::UnexpectedComma(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 1);
+    if let Err(ParserError::UnexpectedComma(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 0);
     } else {
         panic!();
     }
@@ -26,12 +45,12 @@ fn test_unexpectd_comma() {
     // comma in the middle
     let input = "1,,2,3";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
 
-    if let Err(ParserError::UnexpectedComma(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 3);
+    if let Err(ParserError::UnexpectedComma(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 2);
     } else {
         panic!();
     }
@@ -41,24 +60,24 @@ fn test_unexpectd_comma() {
 fn test_unexpectd_math_operator() {
     let input = "1 * 10,2,3";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
 
-    if let Err(ParserError::UnexpectedMathOp(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 3);
+    if let Err(ParserError::UnexpectedMathOp(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 2);
     } else {
         panic!();
     }
 
     let input = "1, 10,  2  ^ 10,3";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
 
-    if let Err(ParserError::UnexpectedMathOp(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 12);
+    if let Err(ParserError::UnexpectedMathOp(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 11);
     } else {
         panic!();
     }
@@ -68,12 +87,12 @@ fn test_unexpectd_math_operator() {
 fn test_incomplete_number() {
     let input = "1, 10, -";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
-    if let Err(ParserError::IncompleteInt(_, span)) = nodes {
+    if let Err(ParserError::IncompleteInt(ctx)) = &nodes {
         dbg!(u16::MAX);
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 8);
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 7);
     } else {
         dbg!(&nodes);
         panic!();
@@ -82,25 +101,13 @@ fn test_incomplete_number() {
 
 #[test]
 fn test_invalid_number() {
-    let input = "1, 10, (-+-),3";
-    let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
-    let nodes = parser.parse();
-    if let Err(ParserError::InvalidInt(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 12);
-    } else {
-        dbg!(&nodes);
-        panic!();
-    }
-
     let input = "1, -+%, 10, 3";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
-    if let Err(ParserError::InvalidInt(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 6);
+    if let Err(ParserError::InvalidInt(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 5);
     } else {
         dbg!(&nodes);
         panic!();
@@ -112,12 +119,12 @@ fn test_neg_pos_int() {
     // double minus
     let input = "--10";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse().unwrap();
     assert_eq!(
         nodes,
         vec![Node::Int {
-            span: Span::new(1, 4),
+            span: Span::new(0, 4),
             value: 10
         }]
     );
@@ -125,86 +132,141 @@ fn test_neg_pos_int() {
     // minus and plus
     let input = "-+10";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse().unwrap();
     assert_eq!(
         nodes,
         vec![Node::Int {
-            span: Span::new(1, 4),
+            span: Span::new(0, 4),
             value: -10
         }]
     );
+
+    // the smallest i64 can be written out
+    let input = "-9_223_372_036_854_775_808, {-9223372036854775808..=MIN}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    assert_eq!(
+        nodes[0],
+        Node::Int {
+            span: Span::new(0, 26),
+            value: i64::MIN
+        }
+    );
+    assert!(matches!(
+        &nodes[1],
+        Node::RangeExpr { start, .. } if **start == Node::Int { span: Span::new(29, 49), value: i64::MIN }
+    ));
+}
+
+#[test]
+fn test_negated_min() {
+    // (input, span of the error), the span starts at the first sign
+    let cases = [
+        ("-MIN", Span::new(0, 4)),
+        ("1, +-MIN", Span::new(3, 8)),
+        ("--9223372036854775808", Span::new(0, 21)),
+        ("(1 + -MIN)", Span::new(5, 9)),
+        ("{+-MIN..=1}", Span::new(1, 6)),
+    ];
+
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::NumberTooLarge(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected NumberTooLarge error for {input:?}, found {nodes:?}");
+        }
+    }
+
+    // an even number of '-' leaves MIN as is
+    let input = "--MIN";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    assert_eq!(
+        nodes,
+        vec![Node::Int {
+            span: Span::new(0, 5),
+            value: i64::MIN
+        }]
+    );
 }
 
 #[test]
 fn test_unmatched_paren() {
     let input = "1, (10 + 3) + (5 * 3))) , 3";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
-    if let Err(ParserError::UnmatchedParen(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 22);
+    if let Err(ParserError::UnmatchedParen(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 21);
     } else {
         panic!();
     }
 
     let input = "1, (";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
-    if let Err(ParserError::UnmatchedParen(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 4);
+    if let Err(ParserError::UnmatchedParen(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 3);
     } else {
         panic!();
     }
 }
 
-// #[test]
-// fn test_math_expr() {
-//     let input = "(1 - 5), ((10 + 3) + (5 * 3)) , 3";
-//     let tokens = Lexer::new(input).lex().unwrap();
-//     let mut parser = Parser::new(input.chars().collect(), &tokens);
-//     let nodes = parser.parse();
-
-//     if let Ok(nodes) = nodes {
-//         assert_eq!(
-//             nodes,
-//             vec![
-//                 Node::Int {
-//                     span: Span::new(0, 7),
-//                     value: -4
-//                 },
-//                 Node::Int {
-//                     span: Span::new(9, 26),
-//                     value: 28
-//                 },
-//                 Node::Int {
-//                     span: Span::new(28, 29),
-//                     value: 3
-//                 }
-//             ]
-//         );
-//     } else {
-//         println!("{}", nodes.err().unwrap());
-//     }
-// }
-
 #[test]
 fn test_empty_maths_expr() {
     let input = "1, 2, -3, ()";
     let tokens = Lexer::new(input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
-    if let Err(ParserError::EmptyParen(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 11);
+    if let Err(ParserError::EmptyParen(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span.start, 10);
     } else {
         panic!();
     }
 }
 
+#[test]
+fn test_unmatched_paren_around_ranges() {
+    #[allow(unused_mut)]
+    let mut inputs = vec![
+        // before a range
+        ("(1 + 2, {1..3}", 0),
+        ("(1 + 2)), {1..3}", 7),
+        ("(1+2, 3))", 8),
+        // inside a range
+        ("{(1 + 2..5}", 1),
+        ("{1..(5 + 2))}", 11),
+        ("{1..5, s:(2}", 9),
+        ("{1..5, m:*(2 + 3}", 10),
+        ("{1..5, m:*2)}", 11),
+        // after a range
+        ("{1..3}, ((2)", 8),
+        ("{1..3}, (2))", 11),
+        // after an expression, without a comma
+        ("(1 + 2) * (3", 10),
+        ("(1 + 2) (3, 4", 8),
+        ("(1 + 2) 3)", 9),
+    ];
+    #[cfg(feature = "aggregate")]
+    inputs.push(("(sum{1..(3} + 1)", 8));
+
+    for (input, start) in inputs {
+        let tokens = Lexer::new(input).lex().unwrap();
+        match Parser::new(input.into(), &tokens).parse() {
+            Err(ParserError::UnmatchedParen(ctx)) => {
+                assert_eq!(ctx.span, Span::new(start, start + 1), "{input}")
+            }
+            result => panic!("Expected an unmatched parenthesis in {input}, found {result:?}"),
+        }
+    }
+}
+
 #[test]
 fn test_too_many_parenthesis() {
     let input = format!(
@@ -213,12 +275,1395 @@ fn test_too_many_parenthesis() {
         vec![")"; MAX_PAREN_DEPTH + 2].join("")
     );
     let tokens = Lexer::new(&input).lex().unwrap();
-    let mut parser = Parser::new(input.chars().collect(), &tokens);
+    let mut parser = Parser::new(input.into(), &tokens);
     let nodes = parser.parse();
-    if let Err(ParserError::TooManyParen(_, span)) = nodes {
-        println!("{}", nodes.err().unwrap());
-        assert_eq!(span.start, 1);
+    if let Err(ParserError::TooManyParen(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(MAX_PAREN_DEPTH, MAX_PAREN_DEPTH + 1));
     } else {
         panic!();
     }
+
+    // the limit can be lowered
+    let input = "((1)), (((2)))";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let error = Parser::new(input.into(), &tokens)
+        .with_max_paren_depth(2)
+        .parse()
+        .unwrap_err();
+    assert!(matches!(&error, ParserError::TooManyParen(ctx) if ctx.extra == 2));
+    assert_eq!(error.span(), Span::new(9, 10));
+    assert!(error.message().contains("at most 2 levels deep"));
+}
+
+#[test]
+fn test_parser_options() {
+    let options = ParserOptions {
+        max_paren_depth: 2,
+        max_tokens: 12,
+        max_items: 3,
+        ..Default::default()
+    };
+    let parse = |input: &str| {
+        let tokens = Lexer::new(input).lex().unwrap();
+        Parser::with_options(input.into(), &tokens, options).parse()
+    };
+
+    assert_eq!(parse("1, ((2)), 3 x 2").unwrap().len(), 3);
+    assert!(matches!(parse("(((1)))"), Err(ParserError::TooManyParen(ctx)) if ctx.extra == 2));
+
+    // the 13th token is the `3`
+    let error = parse("(1 + 1) ~ (2 * 2), 3").unwrap_err();
+    assert!(matches!(&error, ParserError::TooManyTokens(ctx) if ctx.extra == 12));
+    assert_eq!(error.span(), Span::new(19, 20));
+    assert!(error.message().contains("at most 12 tokens"));
+
+    // zipped or repeated items count as one
+    assert_eq!(parse("1 ~ 2, 3 x 2, 4").unwrap().len(), 3);
+    let error = parse("1, 2, 3, {4..6}").unwrap_err();
+    assert!(matches!(&error, ParserError::TooManyItems(ctx) if ctx.extra == 3));
+    assert_eq!(error.span(), Span::new(9, 10));
+
+    // the items after the limit are not looked at
+    let input = "1, (2 +), 3, 4";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let errors = Parser::with_options(input.into(), &tokens, options)
+        .parse_all_errors()
+        .unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], ParserError::ExpectedOperand(..)));
+    assert!(matches!(errors[1], ParserError::TooManyItems(..)));
+    assert_eq!(errors[1].span(), Span::new(13, 14));
+
+    // the defaults don't limit anything but the depth
+    let input = "1, ".repeat(10_000) + "2";
+    let tokens = Lexer::new(&input).lex().unwrap();
+    let nodes = Parser::with_options(input.as_str().into(), &tokens, ParserOptions::default())
+        .parse()
+        .unwrap();
+    assert_eq!(nodes.len(), 10_001);
+    assert_eq!(ParserOptions::default().max_paren_depth, MAX_PAREN_DEPTH);
+}
+
+#[test]
+fn test_deeply_nested_parenthesis() {
+    // nesting doesn't recurse, so it is only limited by the configured depth
+    let depth = 10_000;
+    let input = format!(
+        "{}1 + max(2, 3){}",
+        "(".repeat(depth),
+        " * 2)".repeat(depth - 1) + ")"
+    );
+    let tokens = Lexer::new(&input).lex().unwrap();
+    let nodes = Parser::new(input.as_str().into(), &tokens)
+        .with_max_paren_depth(depth + 1) // `max(..)` is one more level
+        .parse()
+        .unwrap();
+
+    let [Node::MathExpr { rpn, .. }] = &nodes[..] else {
+        panic!("Expected a single math expression, found {:?}", nodes);
+    };
+    // `1`, `2`, `3`, `max`, `+` and a `2` and `*` per level
+    assert_eq!(rpn.len(), 5 + 2 * (depth - 1));
+}
+
+#[test]
+fn test_negated_math_expr() {
+    let input = "-(2 + 3), +-(4), --(5)";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+
+    let negated = nodes
+        .iter()
+        .map(|node| match node {
+            Node::MathExpr { negated, span, .. } => (*negated, *span),
+            _ => panic!("Expected MathExpr, found {:?}", node),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        negated,
+        vec![
+            (true, Span::new(0, 8)),
+            (true, Span::new(10, 15)),
+            (false, Span::new(17, 22)),
+        ]
+    );
+}
+
+#[test]
+fn test_math_expr_rpn() {
+    let input = "(1 + 2 * 3), (max(1, 2) - 4)";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let mut parser = Parser::new(input.into(), &tokens);
+    let nodes = parser.parse().unwrap();
+
+    let rpn_kinds = |node: &Node| match node {
+        Node::MathExpr { rpn, .. } => rpn.iter().map(|token| token.kind).collect::<Vec<_>>(),
+        _ => panic!("Expected MathExpr, found {:?}", node),
+    };
+
+    assert_eq!(
+        rpn_kinds(&nodes[0]),
+        vec![
+            TokenKind::Int { value: 1 },
+            TokenKind::Int { value: 2 },
+            TokenKind::Int { value: 3 },
+            TokenKind::Math(Op::Mul),
+            TokenKind::Math(Op::Add),
+        ]
+    );
+    assert_eq!(
+        rpn_kinds(&nodes[1]),
+        vec![
+            TokenKind::Int { value: 1 },
+            TokenKind::Int { value: 2 },
+            TokenKind::Func(Func::Max),
+            TokenKind::Int { value: 4 },
+            TokenKind::Math(Op::Sub),
+        ]
+    );
+
+    if let Node::MathExpr { span, .. } = &nodes[1] {
+        assert_eq!(*span, Span::new(13, 28));
+    }
+
+    // `%` ranks with `*` and `/`, applied from left to right
+    let input = "(1 + 7 % 3 * 2)";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    assert_eq!(
+        rpn_kinds(&nodes[0]),
+        vec![
+            TokenKind::Int { value: 1 },
+            TokenKind::Int { value: 7 },
+            TokenKind::Int { value: 3 },
+            TokenKind::Math(Op::Mod),
+            TokenKind::Int { value: 2 },
+            TokenKind::Math(Op::Mul),
+            TokenKind::Math(Op::Add),
+        ]
+    );
+
+    // signs before a group are unary operators
+    let input = "(3 * -(1 + 2))";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    assert_eq!(
+        rpn_kinds(&nodes[0]),
+        vec![
+            TokenKind::Int { value: 3 },
+            TokenKind::Int { value: 1 },
+            TokenKind::Int { value: 2 },
+            TokenKind::Math(Op::Add),
+            TokenKind::Math(Op::UnarySub),
+            TokenKind::Math(Op::Mul),
+        ]
+    );
+
+    // `^` is right associative, and the sign of `-2` is applied after it
+    let input = "(-2 ^ 3 ^ 2)";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    assert_eq!(
+        rpn_kinds(&nodes[0]),
+        vec![
+            TokenKind::Int { value: 2 },
+            TokenKind::Int { value: 3 },
+            TokenKind::Int { value: 2 },
+            TokenKind::Math(Op::Pow),
+            TokenKind::Math(Op::Pow),
+            TokenKind::Math(Op::UnarySub),
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_arg_count() {
+    let input = "1, (2 * max(1, 2, 3))";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let mut parser = Parser::new(input.into(), &tokens);
+    let nodes = parser.parse();
+    if let Err(ParserError::InvalidArgCount(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(8, 20));
+    } else {
+        panic!();
+    }
+
+    let input = "(abs(1, 2))";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let mut parser = Parser::new(input.into(), &tokens);
+    let nodes = parser.parse();
+    if let Err(ParserError::InvalidArgCount(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(1, 10));
+    } else {
+        panic!();
+    }
+}
+
+#[test]
+fn test_range_expr() {
+    let input = "{1..=-5, s:-2, m:*2}";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let mut parser = Parser::new(input.into(), &tokens);
+    let nodes = parser.parse().unwrap();
+    assert_nodes_eq(
+        input,
+        &nodes,
+        &[Node::RangeExpr {
+            span: Span::new(0, 20),
+            start: Box::new(Node::Int {
+                span: Span::new(1, 2),
+                value: 1,
+            }),
+            end: Box::new(Node::Int {
+                span: Span::new(5, 7),
+                value: -5,
+            }),
+            inclusive: true,
+            step: Some(Box::new(Node::Int {
+                span: Span::new(11, 13),
+                value: -2,
+            })),
+            mutation: Some(Box::new(Node::MathExpr {
+                negated: false,
+                span: Span::new(17, 19),
+                rpn: vec![
+                    Token::new(TokenKind::RngMutArg, Span::new(17, 18)),
+                    Token::new(TokenKind::Int { value: 2 }, Span::new(18, 19)),
+                    Token::new(TokenKind::Math(Op::Mul), Span::new(17, 18)),
+                ],
+            })),
+            low: None,
+            high: None,
+            unique: false,
+            reverse: false,
+            order: None,
+        }],
+    );
+}
+
+#[test]
+fn test_invalid_range_expr() {
+    // (input, span of the error)
+    let cases = [("{1..5, s:2 m:+1}", Span::new(0, 13))];
+
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let mut parser = Parser::new(input.into(), &tokens);
+        let nodes = parser.parse();
+        if let Err(ParserError::InvalidRangeExpr(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected InvalidRangeExpr error for {input:?}, found {nodes:?}");
+        }
+    }
+
+    // anything that isn't a range argument is pointed at directly
+    let cases = [
+        ("{1..5, 3}", Span::new(7, 8)),
+        ("{1..5, s:2, (2)}", Span::new(12, 13)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::ExpectedRangeArg(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected ExpectedRangeArg error for {input:?}, found {nodes:?}");
+        }
+    }
+}
+
+#[test]
+fn test_trailing_comma() {
+    // (input, number of nodes)
+    let cases = [
+        ("1, 2, 3,", 3),
+        ("(1 + 2),", 1),
+        ("{1..=5},", 1),
+        ("{1..=5,}", 1),
+        ("{1..=5, s:2,}", 1),
+        ("{1..=5, m:*2,}", 1),
+        ("{1..=5, s:2, m:*2,}, 6,", 2),
+    ];
+
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        match nodes {
+            Ok(nodes) => assert_eq!(nodes.len(), expected, "input: {input:?}"),
+            Err(e) => panic!("Unexpected error for {input:?}\n{e}"),
+        }
+    }
+
+    let cases = [",1", "1,,2", "1, 2,,"];
+    for input in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::UnexpectedComma(..)) = nodes {
+            println!("{}", nodes.err().unwrap());
+        } else {
+            panic!("Expected UnexpectedComma error for {input:?}, found {nodes:?}");
+        }
+    }
+
+    let cases = [
+        ("{1..=5,,}", Span::new(7, 8)),
+        ("{1..=5, s:2,,}", Span::new(12, 13)),
+        ("{1..=5, m:*2,,}", Span::new(13, 14)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::ExpectedRangeArg(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected ExpectedRangeArg error for {input:?}, found {nodes:?}");
+        }
+    }
+}
+
+#[test]
+fn test_missing_comma() {
+    // (input, span of the error)
+    let cases = [
+        ("1 2", Span::new(1, 2)),
+        ("1, 2 3", Span::new(4, 5)),
+        ("1 -2", Span::new(1, 2)),
+        ("(1+2)(3+4)", Span::new(5, 6)),
+        ("{1..2}{3..4}", Span::new(6, 7)),
+        ("{1..2} 5", Span::new(6, 7)),
+        // right after a closing brace or parenthesis, the error points at the next token
+        ("{1..3}5", Span::new(6, 7)),
+        ("(1+2)7", Span::new(5, 6)),
+        ("{1..3}(2)", Span::new(6, 7)),
+        ("(abs(-1))-2", Span::new(9, 10)),
+        ("1, {1..=3, r:}{4..5}, 6", Span::new(14, 15)),
+        ("(1) x 2 (3)", Span::new(7, 8)),
+    ];
+
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::MissingComma(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected MissingComma error for {input:?}, found {nodes:?}");
+        }
+    }
+}
+
+#[test]
+fn test_open_ended_range() {
+    let input = "{..=3}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    assert_nodes_eq(
+        input,
+        &nodes,
+        &[Node::RangeExpr {
+            span: Span::new(0, 6),
+            start: Box::new(Node::Int {
+                span: Span::new(1, 4),
+                value: 0,
+            }),
+            end: Box::new(Node::Int {
+                span: Span::new(4, 5),
+                value: 3,
+            }),
+            inclusive: true,
+            step: None,
+            mutation: None,
+            low: None,
+            high: None,
+            unique: false,
+            reverse: false,
+            order: None,
+        }],
+    );
+
+    // (input, span of the dots)
+    let cases = [
+        ("{1..}", Span::new(2, 4)),
+        ("{1..=}", Span::new(2, 5)),
+        ("{(2 * 3)..=, s:2}", Span::new(8, 11)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::MissingRangeEnd(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected MissingRangeEnd error for {input:?}, found {nodes:?}");
+        }
+    }
+}
+
+#[test]
+fn test_relative_end() {
+    // (input, expected end, span of the end)
+    let cases = [
+        ("{100..+5}", 105, Span::new(6, 8)),
+        ("{100..=+5}", 105, Span::new(7, 9)),
+        ("{100.. + 5, s:2}", 105, Span::new(7, 10)),
+        ("{..+3}", 3, Span::new(3, 5)),
+        ("{-3..+2}", -1, Span::new(5, 7)),
+        ("{10..+0}", 10, Span::new(5, 7)),
+        // the offset can be negative or a math expression, the start a math expression
+        ("{100..+-5}", 95, Span::new(6, 9)),
+        ("{100..++5}", 105, Span::new(6, 9)),
+        ("{100..+(2 * 3)}", 106, Span::new(6, 14)),
+        ("{MAX - 10..+5}", i64::MAX - 5, Span::new(11, 13)),
+        ("{(2 ^ 4)..=+1e3}", 1016, Span::new(11, 15)),
+        // a `-` is the sign of an absolute end
+        ("{100..-5}", -5, Span::new(6, 8)),
+        ("{100..(+5)}", 5, Span::new(6, 10)),
+    ];
+    for (input, expected, span) in cases {
+        let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+        match nodes.as_slice() {
+            [Node::RangeExpr { end, .. }] => assert_eq!(
+                **end,
+                Node::Int {
+                    span,
+                    value: expected
+                },
+                "input: {input:?}"
+            ),
+            _ => panic!("Expected a single range expression for {input:?}, found {nodes:?}"),
+        }
+    }
+
+    // the step is checked against the relative end
+    let error = Parser::from_source("{5..+-5, s:2}")
+        .unwrap()
+        .parse()
+        .unwrap_err();
+    assert!(matches!(error, ParserError::StepDirectionMismatch(..)));
+
+    // a sum that overflows, or a start that fails, is left to the evaluator
+    let input = "{MAX..=+1}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    match nodes.as_slice() {
+        [Node::RangeExpr { end, .. }] => {
+            assert!(matches!(**end, Node::MathExpr { .. }));
+            assert_eq!(end.span(), Span::new(1, 9));
+        }
+        _ => panic!("Expected a single range expression for {input:?}, found {nodes:?}"),
+    }
+    assert!(Parser::from_source("{(1 / 0)..+1}")
+        .unwrap()
+        .parse()
+        .is_ok());
+
+    // an offset is still needed
+    let error = Parser::from_source("{1..+}").unwrap().parse().unwrap_err();
+    assert!(matches!(error, ParserError::InvalidRangeExpr(..)));
+}
+
+#[test]
+fn test_unique_flag() {
+    // (input, expected flag)
+    let cases = [
+        ("{1..5}", false),
+        ("{1..5, u:}", true),
+        ("{1..5, u:1}", true),
+        ("{1..5, u:0}", false),
+        ("{1..5, unique:,}", true),
+        ("{1..5, s:2, m:/2, u:}", true),
+        ("{1..5, u:, s:2}", true),
+    ];
+
+    for (input, expected) in cases {
+        let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+        if let [Node::RangeExpr { unique, .. }] = nodes.as_slice() {
+            assert_eq!(*unique, expected, "input: {input:?}");
+        } else {
+            panic!("Expected a single range expression for {input:?}, found {nodes:?}");
+        }
+    }
+
+    let input = "{1..5, u:2}";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let nodes = Parser::new(input.into(), &tokens).parse();
+    if let Err(ParserError::InvalidFlagValue(ctx)) = &nodes {
+        println!("{}", nodes.as_ref().unwrap_err());
+        assert_eq!(ctx.span, Span::new(7, 10));
+    } else {
+        panic!("Expected InvalidFlagValue error, found {nodes:?}");
+    }
+}
+
+#[test]
+fn test_reverse_flag() {
+    // (input, expected flag)
+    let cases = [
+        ("{1..5}", false),
+        ("{1..5, r:}", true),
+        ("{1..5, r:1}", true),
+        ("{1..5, r:0}", false),
+        ("{1..5, rev:,}", true),
+        ("{1..5, r:, u:, s:2}", true),
+    ];
+
+    for (input, expected) in cases {
+        let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+        if let [Node::RangeExpr { reverse, .. }] = nodes.as_slice() {
+            assert_eq!(*reverse, expected, "input: {input:?}");
+        } else {
+            panic!("Expected a single range expression for {input:?}, found {nodes:?}");
+        }
+    }
+}
+
+#[test]
+fn test_clamp_args() {
+    let input = "{1..5, hi:(2 * 5), m:*3, lo:-1}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    match nodes.as_slice() {
+        [Node::RangeExpr {
+            low: Some(low),
+            high: Some(high),
+            ..
+        }] => {
+            assert!(matches!(**low, Node::Int { value: -1, .. }));
+            assert_eq!(low.span(), Span::new(28, 30));
+            assert!(matches!(**high, Node::MathExpr { .. }));
+            assert_eq!(high.span(), Span::new(10, 17));
+        }
+        _ => panic!("Expected a single clamped range, found {nodes:?}"),
+    }
+
+    for input in ["{1..5, lo:2}", "{1..5, high:2}", "{1..5, lo:2, hi:2}"] {
+        let tokens = Lexer::new(input).lex().unwrap();
+        assert!(
+            Parser::new(input.into(), &tokens).parse().is_ok(),
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn test_inverted_clamp() {
+    // (input, span of `lo:`, span of `hi:`)
+    let cases = [
+        ("{1..5, lo:3, hi:2}", Span::new(10, 11), Span::new(16, 17)),
+        (
+            "{1..5, hi:-1, lo:1 - 1}",
+            Span::new(17, 22),
+            Span::new(10, 12),
+        ),
+        (
+            "{1..5, lo:MAX, hi:(2 ^ 62)}",
+            Span::new(10, 13),
+            Span::new(18, 26),
+        ),
+    ];
+    for (input, low, high) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let result = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::InvertedClamp(ctx)) = &result {
+            println!("{}", result.as_ref().unwrap_err());
+            assert_eq!((ctx.span, ctx.extra), (low, high), "input: {input:?}");
+        } else {
+            panic!("Expected InvertedClamp error for {input:?}, found {result:?}");
+        }
+    }
+}
+
+#[test]
+fn test_parse_all_errors() {
+    let input = "1 2, (3 +), {-1..=2}, {1..}, 4";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let errors = Parser::new(input.into(), &tokens)
+        .parse_all_errors()
+        .unwrap_err();
+    println!("{}", render_all(&errors));
+
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(errors[0], ParserError::MissingComma(..)));
+    assert_eq!(errors[0].span(), Span::new(1, 2));
+    assert!(matches!(errors[1], ParserError::ExpectedOperand(..)));
+    assert_eq!(errors[1].span(), Span::new(9, 10));
+    assert!(matches!(errors[2], ParserError::MissingRangeEnd(..)));
+    assert_eq!(errors[2].span(), Span::new(24, 26));
+
+    // doubled commas are only reported once
+    let input = "1,,2, (1 + (2 *)), 3";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let errors = Parser::new(input.into(), &tokens)
+        .parse_all_errors()
+        .unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], ParserError::UnexpectedComma(..)));
+    assert_eq!(errors[0].span(), Span::new(2, 3));
+    assert!(matches!(errors[1], ParserError::ExpectedOperand(..)));
+
+    let input = "1, {2..=4}, (5 * 2)";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let nodes = Parser::new(input.into(), &tokens).parse_all_errors();
+    assert_eq!(nodes.unwrap().len(), 3);
+}
+
+#[test]
+fn test_token_cursor() {
+    let input = "1, {2..3}";
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.lex().unwrap();
+    let mut parser = Parser::new(lexer.source, &tokens);
+
+    assert_eq!(parser.prev_token(), None);
+    assert_eq!(parser.peek_nth(0), Some(tokens[0]));
+    assert_eq!(parser.peek_nth(2), Some(tokens[2]));
+
+    for idx in 0..tokens.len() {
+        parser.advance();
+        assert_eq!(parser.prev_token(), Some(tokens[idx]));
+        assert_eq!(parser.peek_nth(0), tokens.get(idx + 1).copied());
+        assert_eq!(parser.peek_nth(1), tokens.get(idx + 2).copied());
+    }
+
+    // moving past the end keeps the last token as the previous one
+    parser.advance();
+    assert_eq!(parser.prev_token(), tokens.last().copied());
+    assert_eq!(parser.peek_nth(0), None);
+}
+
+#[test]
+fn test_token_cursor_after_recovery() {
+    // the spans after a recovered error still line up with the source
+    let input = "{1..}, 2 3";
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.lex().unwrap();
+    let errors = Parser::new(lexer.source, &tokens)
+        .parse_all_errors()
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].span(), Span::new(8, 9));
+}
+
+#[test]
+fn test_unexpected_top_level_tokens() {
+    let error = |input: &str| {
+        let tokens = Lexer::new(input).lex().unwrap();
+        Parser::new(input.into(), &tokens).parse().unwrap_err()
+    };
+
+    assert!(matches!(error("}"), ParserError::UnmatchedBrace(ctx) if ctx.span == Span::new(0, 1)));
+    assert!(
+        matches!(error("1, )"), ParserError::UnmatchedParen(ctx) if ctx.span == Span::new(3, 4))
+    );
+    assert!(
+        matches!(error("..5"), ParserError::RangeOutsideBraces(ctx) if ctx.span == Span::new(0, 2))
+    );
+    assert!(
+        matches!(error("1, ..=5"), ParserError::RangeOutsideBraces(ctx) if ctx.span == Span::new(3, 6))
+    );
+    assert!(
+        matches!(error("1..5"), ParserError::RangeOutsideBraces(ctx) if ctx.span == Span::new(1, 3))
+    );
+    assert!(
+        matches!(error("max(1, 2)"), ParserError::MathOutsideParen(ctx) if ctx.span == Span::new(0, 3))
+    );
+    #[cfg(feature = "aggregate")]
+    assert!(
+        matches!(error("sum{1..3}"), ParserError::MathOutsideParen(ctx) if ctx.span == Span::new(0, 3))
+    );
+
+    // the lexer rejects range arguments outside of braces, but the parser can be handed any tokens
+    for kind in [
+        TokenKind::RngStep,
+        TokenKind::RngMutation,
+        TokenKind::RngUnique,
+        TokenKind::RngOrder(SortOrder::Asc),
+        TokenKind::RngMutArg,
+    ] {
+        let tokens = [Token::new(kind, Span::new(0, 2))];
+        let error = Parser::new("s:".into(), &tokens).parse().unwrap_err();
+        assert!(
+            matches!(error, ParserError::MisplacedRngSyntax(ctx) if ctx.span == Span::new(0, 2))
+        );
+    }
+}
+
+#[test]
+fn test_invalid_step() {
+    let error = |input: &str| {
+        let tokens = Lexer::new(input).lex().unwrap();
+        Parser::new(input.into(), &tokens).parse().unwrap_err()
+    };
+
+    let err = error("{1..=10, s:0}");
+    println!("{}", err);
+    assert!(matches!(err, ParserError::ZeroStep(ctx) if ctx.span == Span::new(11, 12)));
+    assert!(
+        matches!(error("{1..=5, s:(2 - 2)}"), ParserError::ZeroStep(ctx) if ctx.span == Span::new(10, 17))
+    );
+
+    let err = error("{1..=10, s:-2}");
+    println!("{}", err);
+    assert!(matches!(
+        &err,
+        ParserError::StepDirectionMismatch(ctx)
+            if ctx.span == Span::new(11, 13) && ctx.extra == Span::new(1, 7)
+    ));
+    assert!(err.hint().unwrap().contains("'s:2'"));
+
+    let err = error("{10..=1, s:2}");
+    println!("{}", err);
+    assert!(matches!(
+        &err,
+        ParserError::StepDirectionMismatch(ctx)
+            if ctx.span == Span::new(11, 12) && ctx.extra == Span::new(1, 7)
+    ));
+    assert!(err.hint().unwrap().contains("'s:-2'"));
+
+    // steps matching the direction, and single value ranges, are fine
+    for input in [
+        "{5..=5, s:-1}",
+        "{5..5, s:3}",
+        "{..=-4, s:-2}",
+        "{10..=1, s:(0 - 3)}",
+    ] {
+        let tokens = Lexer::new(input).lex().unwrap();
+        assert!(
+            Parser::new(input.into(), &tokens).parse().is_ok(),
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn test_duplicate_range_arg() {
+    let error = |input: &str| {
+        let tokens = Lexer::new(input).lex().unwrap();
+        Parser::new(input.into(), &tokens).parse().unwrap_err()
+    };
+
+    let err = error("{1..5, s:2, s:3}");
+    println!("{}", err);
+    assert!(matches!(
+        &err,
+        ParserError::DuplicateRangeArg(ctx)
+            if ctx.span == Span::new(12, 14) && ctx.extra == Span::new(7, 9)
+    ));
+    assert!(err
+        .to_string()
+        .contains("'s:' was already given at position 8-9"));
+
+    assert!(matches!(
+        error("{1..5, m:+1, m:*2}"),
+        ParserError::DuplicateRangeArg(ctx)
+            if ctx.span == Span::new(13, 15) && ctx.extra == Span::new(7, 9)
+    ));
+    assert!(matches!(
+        error("{1..5, s:2, m:+1, s:3}"),
+        ParserError::DuplicateRangeArg(ctx)
+            if ctx.span == Span::new(18, 20) && ctx.extra == Span::new(7, 9)
+    ));
+    assert!(matches!(
+        error("{1..5, u:, unique:}"),
+        ParserError::DuplicateRangeArg(ctx)
+            if ctx.span == Span::new(11, 18) && ctx.extra == Span::new(7, 9)
+    ));
+    assert!(matches!(
+        error("{1..5, rev:, r:}"),
+        ParserError::DuplicateRangeArg(ctx)
+            if ctx.span == Span::new(13, 15) && ctx.extra == Span::new(7, 11)
+    ));
+    assert!(matches!(
+        error("{1..5, hi:1, high:2}"),
+        ParserError::DuplicateRangeArg(ctx)
+            if ctx.span == Span::new(13, 18) && ctx.extra == Span::new(7, 10)
+    ));
+}
+
+#[test]
+fn test_zip() {
+    let input = "{1..=3} ~ 7, (1 + 2)";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    match nodes.as_slice() {
+        [Node::Zip { span, left, right }, Node::MathExpr { .. }] => {
+            assert_eq!(*span, Span::new(0, 11));
+            assert!(matches!(**left, Node::RangeExpr { .. }));
+            assert!(matches!(**right, Node::Int { value: 7, .. }));
+        }
+        _ => panic!("Expected a zip and a math expression, found {nodes:?}"),
+    }
+
+    // zips chain from left to right
+    let input = "1 ~ (2) ~ {3..4}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    match nodes.as_slice() {
+        [Node::Zip { left, right, .. }] => {
+            assert_eq!(left.span(), Span::new(0, 7));
+            assert!(matches!(**left, Node::Zip { .. }));
+            assert_eq!(right.span(), Span::new(10, 16));
+        }
+        _ => panic!("Expected a single zip, found {nodes:?}"),
+    }
+}
+
+#[test]
+fn test_zip_errors() {
+    // (input, span of the '~' missing an item)
+    let cases = [
+        ("~ 1", Span::new(0, 1)),
+        ("1, ~ 2", Span::new(3, 4)),
+        ("1 ~, 2", Span::new(2, 3)),
+        ("1 ~", Span::new(2, 3)),
+        ("1 ~ ~ 2", Span::new(2, 3)),
+    ];
+    for (input, span) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::MissingZipOperand(ctx)) = &nodes {
+            assert_eq!(ctx.span, span, "input: {input:?}");
+        } else {
+            panic!("Expected MissingZipOperand error for {input:?}, found {nodes:?}");
+        }
+    }
+
+    // the items around the `~` still need their commas
+    let input = "1 ~ 2 3";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let nodes = Parser::new(input.into(), &tokens).parse();
+    assert!(matches!(nodes, Err(ParserError::MissingComma(..))));
+}
+
+#[test]
+fn test_repeat() {
+    let input = "{1..=3} x 2, 0 x 5 ~ 7";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    match nodes.as_slice() {
+        [Node::Repeat {
+            span,
+            item,
+            count: 2,
+        }, Node::Zip { left, right, .. }] => {
+            assert_eq!(*span, Span::new(0, 11));
+            assert!(matches!(**item, Node::RangeExpr { .. }));
+            // `x` binds tighter than `~`
+            assert!(matches!(**left, Node::Repeat { count: 5, .. }));
+            assert_eq!(left.span(), Span::new(13, 18));
+            assert!(matches!(**right, Node::Int { value: 7, .. }));
+        }
+        _ => panic!("Expected a repeat and a zip, found {nodes:?}"),
+    }
+
+    // repeats of repeats
+    let input = "(1) x 2 x +3";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    match nodes.as_slice() {
+        [Node::Repeat {
+            span, item, count, ..
+        }] => {
+            assert_eq!((*span, *count), (Span::new(0, 12), 3));
+            assert!(matches!(**item, Node::Repeat { count: 2, .. }));
+        }
+        _ => panic!("Expected a single repeat, found {nodes:?}"),
+    }
+}
+
+#[test]
+fn test_repeat_errors() {
+    // (input, error code, span)
+    let cases = [
+        ("0 x 0", "E0130", Span::new(4, 5)),
+        ("0 x -2", "E0130", Span::new(4, 6)),
+        ("0 x (2)", "E0129", Span::new(4, 5)),
+        ("0 x {1..3}", "E0129", Span::new(4, 5)),
+        ("0 x -(2)", "E0129", Span::new(5, 6)),
+        ("0 x", "E0129", Span::new(2, 3)),
+        ("0 x, 1", "E0129", Span::new(2, 3)),
+        ("0 x ~ 1", "E0129", Span::new(2, 3)),
+        ("x 2", "E0131", Span::new(0, 1)),
+        ("1, x 2", "E0131", Span::new(3, 4)),
+    ];
+    for (input, code, span) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let error = Parser::new(input.into(), &tokens).parse().unwrap_err();
+        assert_eq!(
+            (error.code(), error.span()),
+            (code, span),
+            "input: {input:?}"
+        );
+    }
+}
+
+#[test]
+fn test_range_arg_order() {
+    // the spans differ between the two orders, so zero them before comparing
+    fn strip_spans(node: &mut Node) {
+        match node {
+            Node::Int { span, .. } => *span = Span::new(0, 0),
+            Node::MathExpr { span, rpn, .. } => {
+                *span = Span::new(0, 0);
+                rpn.iter_mut()
+                    .for_each(|token| token.span = Span::new(0, 0));
+            }
+            Node::RangeExpr {
+                span,
+                start,
+                end,
+                step,
+                mutation,
+                ..
+            } => {
+                *span = Span::new(0, 0);
+                strip_spans(start);
+                strip_spans(end);
+                step.iter_mut()
+                    .chain(mutation)
+                    .for_each(|node| strip_spans(node));
+            }
+            Node::Zip { .. } | Node::Repeat { .. } => unreachable!(),
+        }
+    }
+    let parse = |input: &str| {
+        let mut nodes = Parser::from_source(input).unwrap().parse().unwrap();
+        nodes.iter_mut().for_each(strip_spans);
+        nodes
+    };
+
+    assert_eq!(parse("{1..5, s:2, m:+2}"), parse("{1..5, m:+2, s:2}"));
+    assert_eq!(
+        parse("{1..=9, s:2, m:*3, u:, o:desc}"),
+        parse("{1..=9, o:desc, u:, m:*3, s:2}")
+    );
+    assert_eq!(
+        parse("{1..=3, o:desc, m:*2}"),
+        parse("{1..=3, m:*2, o:desc}")
+    );
+}
+
+#[test]
+fn test_nested_and_stray_braces() {
+    let error = |input: &str| {
+        let tokens = Lexer::new(input).lex().unwrap();
+        Parser::new(input.into(), &tokens).parse().unwrap_err()
+    };
+
+    for (input, expected) in [
+        ("{1..{2..3}}", Span::new(4, 5)),
+        ("{{1..2}}", Span::new(1, 2)),
+        ("{1..5, m:{1..2}}", Span::new(9, 10)),
+        ("{1..(2 * {1..2})}", Span::new(9, 10)),
+    ] {
+        let err = error(input);
+        println!("{}", err);
+        assert!(
+            matches!(&err, ParserError::NestedRange(ctx) if ctx.span == expected),
+            "input: {input:?}, found {err:?}"
+        );
+    }
+
+    // the braces of aggregates aren't ranges of their own
+    #[cfg(feature = "aggregate")]
+    {
+        let input = "{1..sum{1..3}, m:+prod{1..=2}}";
+        let tokens = Lexer::new(input).lex().unwrap();
+        assert!(Parser::new(input.into(), &tokens).parse().is_ok());
+        assert!(matches!(
+            error("{1..sum{1..{2..3}}}"),
+            ParserError::NestedRange(ctx) if ctx.span == Span::new(11, 12)
+        ));
+    }
+
+    // (input, span of the brace without a partner)
+    for (input, expected) in [
+        ("{1..5", Span::new(0, 1)),
+        ("1, {1..5, 2", Span::new(3, 4)),
+        ("1, }", Span::new(3, 4)),
+        ("{1..5}}", Span::new(6, 7)),
+        ("{1..5}, 2}", Span::new(9, 10)),
+    ] {
+        let err = error(input);
+        println!("{}", err);
+        assert!(
+            matches!(&err, ParserError::UnmatchedBrace(ctx) if ctx.span == expected),
+            "input: {input:?}, found {err:?}"
+        );
+    }
+}
+
+#[test]
+fn test_missing_range_bounds() {
+    // (input, span of the error), braces without bounds are underlined as a whole
+    let cases = [
+        ("{}", Span::new(0, 2)),
+        ("{s:2}", Span::new(0, 5)),
+        ("{, s:2}", Span::new(0, 7)),
+        ("1, {m:+1, u:}", Span::new(3, 13)),
+        // only the dots, they are underlined instead
+        ("{..}", Span::new(1, 3)),
+        ("{..=}", Span::new(1, 4)),
+        ("{.., s:2}", Span::new(1, 3)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::MissingRangeBounds(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected MissingRangeBounds error for {input:?}, found {nodes:?}");
+        }
+    }
+}
+
+#[test]
+fn test_empty_input() {
+    for input in ["", "   ", "\n\t"] {
+        let tokens = Lexer::new(input).lex().unwrap();
+        assert_eq!(Parser::new(input.into(), &tokens).parse().unwrap(), []);
+        assert_eq!(
+            Parser::new(input.into(), &tokens)
+                .parse_all_errors()
+                .unwrap(),
+            []
+        );
+    }
+}
+
+#[test]
+fn test_missing_operand_or_operator() {
+    // (input, the char where the operand is missing)
+    let cases = [
+        ("(1 + )", Span::new(4, 5)),
+        ("(1+)", Span::new(3, 4)),
+        ("(* 3)", Span::new(1, 2)),
+        ("(1 + * 3)", Span::new(4, 5)),
+        ("(1 + + )", Span::new(6, 7)),
+        ("1, 10, (-+-),3", Span::new(11, 12)),
+        ("(max(1, 2 *))", Span::new(11, 12)),
+        ("{1..3 +}", Span::new(7, 8)),
+        ("{1..3, m:@ -, u:}", Span::new(12, 13)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::ExpectedOperand(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected ExpectedOperand error for {input:?}, found {nodes:?}");
+        }
+    }
+
+    // (input, the char where the operator is missing)
+    let cases = [
+        ("(1 2)", Span::new(2, 3)),
+        ("(1+2 3)", Span::new(4, 5)),
+        ("(abs(1) + 2 3)", Span::new(11, 12)),
+        ("{1..=3, m:@ 2}", Span::new(11, 12)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::ExpectedOperator(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected ExpectedOperator error for {input:?}, found {nodes:?}");
+        }
+    }
+}
+
+#[test]
+fn test_implicit_multiplication() {
+    // (input, span of both operands, hint)
+    let cases = [
+        ("(2(3+4))", Span::new(1, 7), "'2 * (3+4)'"),
+        ("((1+2)(3+4))", Span::new(1, 11), "'(1+2) * (3+4)'"),
+        ("((1 + 2) 3)", Span::new(1, 10), "'(1 + 2) * 3'"),
+        ("(2 abs(-1) + 1)", Span::new(1, 10), "'2 * abs(-1)'"),
+        ("(3!(2))", Span::new(1, 6), "'3! * (2)'"),
+        ("{1..(2)(3)}", Span::new(4, 10), "'(2) * (3)'"),
+        ("{1..=3, m:2@}", Span::new(10, 12), "'2 * @'"),
+    ];
+    for (input, expected, hint) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(e @ ParserError::ImplicitMultiplication(ctx)) = &nodes {
+            println!("{e}");
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+            assert!(
+                e.hint().is_some_and(|h| h.contains(hint)),
+                "input: {input:?}"
+            );
+        } else {
+            panic!("Expected ImplicitMultiplication error for {input:?}, found {nodes:?}");
+        }
+    }
+
+    // two numbers in a row are not taken for a multiplication
+    let input = "(1 2)";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let nodes = Parser::new(input.into(), &tokens).parse();
+    assert!(matches!(nodes, Err(ParserError::ExpectedOperator(_))));
+}
+
+#[test]
+fn test_prefix_op_between_operands() {
+    let cases = [
+        ("(6 ~ 9)", Span::new(3, 4)),
+        ("{1..6~9*3, s:3}", Span::new(5, 6)),
+        ("{1..2, m:+6~9}", Span::new(11, 12)),
+        ("(max(1, 2) ~ 3)", Span::new(11, 12)),
+    ];
+    for (input, expected) in cases {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let nodes = Parser::new(input.into(), &tokens).parse();
+        if let Err(ParserError::InvalidMathOp(ctx)) = &nodes {
+            println!("{}", nodes.as_ref().unwrap_err());
+            assert_eq!(ctx.span, expected, "input: {input:?}");
+        } else {
+            panic!("Expected InvalidMathOp error for {input:?}, found {nodes:?}");
+        }
+    }
+
+    // still fine as a prefix
+    for input in ["(6 + ~9)", "{1..~9}", "{1..2, m:~@}"] {
+        let tokens = Lexer::new(input).lex().unwrap();
+        assert!(
+            Parser::new(input.into(), &tokens).parse().is_ok(),
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn test_parse_skips_trivia() {
+    let inputs = [
+        "1, 2 # two\n, (3 * -4)",
+        "{1..=10, # range\n s : 2, m : @ * 2}",
+        "1, (2 * 3", // errors point at the same place
+        "1 2",
+    ];
+    let parse = |input: &str, tokens: &[Token]| {
+        Parser::new(input.into(), tokens)
+            .parse()
+            .map_err(|e| (e.code(), e.span()))
+    };
+    for input in inputs {
+        let tokens = Lexer::new(input).lex().unwrap();
+        let trivia = Lexer::new(input).with_trivia(true).lex().unwrap();
+        assert!(trivia.len() > tokens.len());
+        assert_eq!(
+            parse(input, &trivia),
+            parse(input, &tokens),
+            "input: {input:?}"
+        );
+    }
+}
+
+fn tree(input: &str) -> String {
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    nodes.iter().map(|node| node.tree_string(input)).collect()
+}
+
+#[test]
+fn test_tree_string() {
+    assert_eq!(tree("MAX"), "Int(9223372036854775807) [0..3]\n");
+    assert_eq!(tree("(2 ^ 3 - 10)"), "MathExpr(2 ^ 3 - 10 → -2) [0..12]\n");
+    assert_eq!(tree("((1) + (2))"), "MathExpr((1) + (2) → 3) [0..11]\n");
+    assert_eq!(tree("(1 / 0)"), "MathExpr(1 / 0 → error E0201) [0..7]\n");
+    assert_eq!(
+        tree("{1..=10 ^ 2, s:3}"),
+        indoc! {"
+            RangeExpr [0..17]
+            ├─ start: Int(1) [1..2]
+            ├─ end: MathExpr(10 ^ 2 → 100) [5..11]
+            ├─ inclusive: true
+            └─ step: Int(3) [15..16]
+        "}
+    );
+    assert_eq!(
+        tree("{..5, m:*2, u:, r:, o:asc}"),
+        indoc! {"
+            RangeExpr [0..26]
+            ├─ start: Int(0) [1..3]
+            ├─ end: Int(5) [3..4]
+            ├─ inclusive: false
+            ├─ mutation: MathExpr(*2) [8..10]
+            ├─ unique: true
+            ├─ reverse: true
+            └─ order: asc
+        "}
+    );
+}
+
+#[test]
+fn test_tree_string_zip() {
+    assert_eq!(
+        tree("{1..=3} ~ 7"),
+        indoc! {"
+            Zip [0..11]
+            ├─ left: RangeExpr [0..7]
+            │  ├─ start: Int(1) [1..2]
+            │  ├─ end: Int(3) [5..6]
+            │  └─ inclusive: true
+            └─ right: Int(7) [10..11]
+        "}
+    );
+}
+
+#[test]
+fn test_tree_string_repeat() {
+    assert_eq!(
+        tree("(1 + 1) x 3"),
+        indoc! {"
+            Repeat [0..11]
+            ├─ item: MathExpr(1 + 1 → 2) [0..7]
+            └─ count: 3
+        "}
+    );
+}
+
+#[test]
+fn test_tree_string_clamp() {
+    assert_eq!(
+        tree("{0..=10, m:*3, lo:1, hi:20}"),
+        indoc! {"
+            RangeExpr [0..27]
+            ├─ start: Int(0) [1..2]
+            ├─ end: Int(10) [5..7]
+            ├─ inclusive: true
+            ├─ mutation: MathExpr(*3) [11..13]
+            ├─ low: Int(1) [18..19]
+            └─ high: Int(20) [24..26]
+        "}
+    );
+}
+
+#[test]
+fn test_tree_string_nested() {
+    assert_eq!(
+        tree("1, {(1) + (2)..=max(3, 4) * 2, s:(3 - 2), m:(@ % 3)}, (5!)"),
+        indoc! {"
+            Int(1) [0..1]
+            RangeExpr [3..52]
+            ├─ start: MathExpr((1) + (2) → 3) [4..13]
+            ├─ end: MathExpr(max(3, 4) * 2 → 8) [16..29]
+            ├─ inclusive: true
+            ├─ step: MathExpr(3 - 2 → 1) [33..40]
+            └─ mutation: MathExpr(@ % 3) [44..51]
+            MathExpr(5! → 120) [54..58]
+        "}
+    );
+}
+
+#[test]
+fn test_display() {
+    let cases = [
+        ("MAX, -3", "9223372036854775807, -3"),
+        ("-(2 ^ 3 - 10)", "-((2 ^ 3) - 10)"),
+        ("(-2^2), (-5), (-(5)!)", "(-(2 ^ 2)), (-5), (-(5!))"),
+        ("(max(1, 2 * 3) - -4)", "(max(1, (2 * 3)) - (-4))"),
+        (
+            "{..5, m:*2, u:, r:, o:asc}",
+            "{0..5, m:(@ * 2), u:, r:, o:asc}",
+        ),
+        ("{1..=10^2, s:3, lo:2}", "{1..=(10 ^ 2), s:3, lo:2}"),
+        ("{1..=3} ~ 7 x 2", "{1..=3} ~ 7 x 2"),
+    ];
+    for (input, expected) in cases {
+        let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+        let source = nodes.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(source.join(", "), expected, "input: {input:?}");
+
+        // and parses back to the same nodes
+        let tokens = Lexer::new(expected).lex().unwrap();
+        let reparsed = Parser::new(expected.into(), &tokens).parse().unwrap();
+        let reparsed_source = reparsed.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(reparsed_source, source, "input: {input:?}");
+    }
+}
+
+#[test]
+fn test_from_source() {
+    let input = "1, {2..=4, s:2}, # two\n(3 * 4)";
+    let tokens = Lexer::new(input).with_trivia(true).lex().unwrap();
+    let nodes = Parser::new(input.into(), &tokens).parse().unwrap();
+    let mut parser = Parser::from_source(input).unwrap();
+    assert_eq!(&**parser.source(), input);
+    assert_nodes_eq(input, &parser.parse().unwrap(), &nodes);
+
+    // the options are the parser's, the errors the lexer's
+    let options = ParserOptions {
+        max_items: 2,
+        ..Default::default()
+    };
+    let error = Parser::from_source_with_options(input, options)
+        .unwrap()
+        .parse()
+        .unwrap_err();
+    assert!(matches!(error, ParserError::TooManyItems(..)));
+    assert!(matches!(
+        Parser::from_source("1, $"),
+        Err(LexicalError::InvalidToken(..))
+    ));
+
+    // nothing to parse
+    assert_eq!(Parser::from_source("# none").unwrap().parse().unwrap(), []);
+}
+
+#[cfg(feature = "aggregate")]
+#[test]
+fn test_aggregate_overflow() {
+    // the aggregates are folded by the parser, the error names the bound they went past
+    let cases = [
+        ("(sum{MAX - 1..=MAX})", Bound::Max),
+        ("(sum{MIN..=MIN + 1})", Bound::Min),
+        ("(prod{MIN..=MIN + 1})", Bound::Max),
+        ("(prod{-2..=-1, m:*MAX})", Bound::Min),
+    ];
+    for (input, bound) in cases {
+        match Parser::from_source(input).unwrap().parse() {
+            Err(ParserError::AggregateOverflow(ctx)) => {
+                assert_eq!(ctx.extra, Some(bound), "{input}");
+                assert_eq!(ctx.span.text(input), Some(&input[1..input.len() - 1]));
+            }
+            nodes => panic!("Expected AggregateOverflow error for {input:?}, found {nodes:?}"),
+        }
+    }
+
+    let error = Parser::from_source("(1 + sum{MIN..=MIN + 1})")
+        .unwrap()
+        .parse()
+        .unwrap_err();
+    assert_eq!(
+        error.message(),
+        "@ position 6-23 - Aggregated range is smaller than MIN (-9_223_372_036_854_775_808)"
+    );
+}
+
+#[cfg(feature = "aggregate")]
+#[test]
+fn test_aggregate_max_steps() {
+    let options = ParserOptions {
+        max_steps: 1000,
+        ..Default::default()
+    };
+    let parse = |input: &str| {
+        Parser::from_source_with_options(input, options)
+            .unwrap()
+            .parse()
+    };
+
+    assert!(matches!(
+        parse("(sum{0..=MAX})"),
+        Err(ParserError::InvalidAggregate(ctx)) if ctx.span == Span::new(4, 13)
+    ));
+    assert!(matches!(
+        parse("(count{0..=1_000_000, m:*2, u:})"),
+        Err(ParserError::InvalidAggregate(..))
+    ));
+    assert!(parse("(sum{1..=100})").is_ok());
+    // counting a range doesn't go through its numbers
+    assert!(parse("(count{0..=10_000_000_000})").is_ok());
 }
@@ -0,0 +1,202 @@
+use proptest::prelude::*;
+
+use crate::{
+    eval::Evaluator,
+    lexer::Lexer,
+    parser::{Node, Parser, MAX_PAREN_DEPTH},
+    tokens::Span,
+    visit::{walk_children, walk_node, Visit},
+};
+
+/// How many numbers of a generated input are evaluated, ranges like `{MIN..MAX}` never end.
+const MAX_NUMBERS: usize = 1_000;
+
+pub(super) fn int() -> impl Strategy<Value = String> {
+    prop_oneof![
+        8 => (-1_000i64..1_000).prop_map(|value| value.to_string()),
+        1 => any::<i64>().prop_map(|value| value.to_string()),
+        1 => Just("MAX".to_string()),
+        1 => Just("MIN".to_string()),
+        1 => (1u32..10, 0u32..7).prop_map(|(mantissa, exponent)| format!("{mantissa}e{exponent}")),
+    ]
+}
+
+pub(super) fn math_expr() -> impl Strategy<Value = String> {
+    let op = prop::sample::select(vec!["+", "-", "*", "/", "%", "^", "&", "|", "<<", ">>"]);
+    // each level adds one pair of parenthesis, stay well below the limit
+    let depth = 8.min(MAX_PAREN_DEPTH as u32 - 1);
+    int()
+        .prop_recursive(depth, 32, 2, move |inner| {
+            prop_oneof![
+                (inner.clone(), op.clone(), inner.clone())
+                    .prop_map(|(lhs, op, rhs)| format!("({lhs} {op} {rhs})")),
+                inner.clone().prop_map(|value| format!("(0 - {value})")),
+                (inner.clone(), inner).prop_map(|(lhs, rhs)| format!("(max({lhs}, {rhs}))")),
+            ]
+        })
+        .prop_map(|expr| match expr.starts_with('(') {
+            true => expr,
+            false => format!("({expr})"),
+        })
+}
+
+pub(super) fn range() -> impl Strategy<Value = String> {
+    let mutation = (prop::sample::select(vec!["+", "-", "*", "/"]), 1i64..100)
+        .prop_map(|(op, value)| format!("m:{op}{value}"));
+    let order = prop::sample::select(vec!["o:asc", "o:desc"]);
+    (
+        -1_000i64..1_000,
+        -1_000i64..1_000,
+        any::<bool>(),
+        prop::option::of(1i64..50),
+        prop::option::of(mutation),
+        any::<bool>(),
+        any::<bool>(),
+        prop::option::of(order),
+        prop::option::of(-2_000i64..1_000),
+        prop::option::of(0i64..2_000),
+    )
+        .prop_map(
+            |(start, end, inclusive, step, mutation, unique, reverse, order, low, width)| {
+                let dots = if inclusive { "..=" } else { ".." };
+                let mut range = format!("{{{start}{dots}{end}");
+                // the step has to go the same way as the range
+                if let Some(step) = step {
+                    let step = if end < start { -step } else { step };
+                    range.push_str(&format!(", s:{step}"));
+                }
+                if let Some(mutation) = mutation {
+                    range.push_str(&format!(", {mutation}"));
+                }
+                if unique {
+                    range.push_str(", u:");
+                }
+                if reverse {
+                    range.push_str(", r:");
+                }
+                if let Some(order) = order {
+                    range.push_str(&format!(", {order}"));
+                }
+                // `hi:` can't be below `lo:`
+                if let Some(low) = low {
+                    range.push_str(&format!(", lo:{low}"));
+                }
+                if let Some(width) = width {
+                    range.push_str(&format!(", hi:{}", low.unwrap_or(-2_000) + width));
+                }
+                range.push('}');
+                range
+            },
+        )
+}
+
+fn seq2_input() -> impl Strategy<Value = String> {
+    let item = prop_oneof![int(), math_expr(), range()];
+    prop::collection::vec(item, 1..8).prop_map(|items| items.join(", "))
+}
+
+/// Spans of all the nodes, nested ones included.
+#[derive(Default)]
+struct Spans(Vec<Span>);
+
+impl Visit for Spans {
+    fn visit_int(&mut self, node: &Node) {
+        self.0.push(node.span());
+    }
+
+    fn visit_math(&mut self, node: &Node) {
+        self.0.push(node.span());
+    }
+
+    fn visit_range(&mut self, node: &Node) {
+        self.0.push(node.span());
+        walk_children(node, self);
+    }
+}
+
+fn assert_in_bounds(input: &str, span: Span) {
+    assert!(span.start <= span.end, "{span} is reversed in {input:?}");
+    assert!(
+        span.end as usize <= input.len(),
+        "{span} is out of bounds in {input:?}"
+    );
+}
+
+/// Runs the whole pipeline on any input, rendering every error along the way.
+fn run(input: &str) {
+    let tokens = match Lexer::new(input).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let _ = (e.to_string(), e.render_plain());
+            return;
+        }
+    };
+    for token in &tokens {
+        assert_in_bounds(input, token.span);
+    }
+    let nodes = match Parser::new(input.into(), &tokens).parse() {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            let _ = (e.to_string(), e.render_plain());
+            let _ = Parser::new(input.into(), &tokens).parse_all_errors();
+            return;
+        }
+    };
+    let evaluator = Evaluator::new(input.into());
+    for number in evaluator.iter(&nodes).take(MAX_NUMBERS) {
+        if let Err(e) = number {
+            let _ = (e.to_string(), e.render_plain());
+            return;
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn valid_inputs_parse(input in seq2_input()) {
+        let tokens = Lexer::new(&input).lex();
+        prop_assert!(tokens.is_ok(), "{input:?}: {}", tokens.unwrap_err().render_plain());
+        let tokens = tokens.unwrap();
+        for token in &tokens {
+            assert_in_bounds(&input, token.span);
+        }
+
+        let nodes = Parser::new(input.as_str().into(), &tokens).parse();
+        prop_assert!(nodes.is_ok(), "{input:?}: {}", nodes.unwrap_err().render_plain());
+        let mut spans = Spans::default();
+        for node in &nodes.unwrap() {
+            walk_node(node, &mut spans);
+        }
+        for span in spans.0 {
+            assert_in_bounds(&input, span);
+        }
+    }
+
+    // the evaluator never reorders the items, see the `Ordering` of `Evaluator`
+    #[test]
+    fn chain_is_its_items_in_order(items in prop::collection::vec(prop_oneof![int(), range()], 1..6)) {
+        let input = items.join(", ");
+        let nodes = Parser::from_source(&input).unwrap().parse().unwrap();
+        let evaluator = Evaluator::new(input.as_str().into());
+        let alone = nodes
+            .iter()
+            .map(|node| evaluator.eval(core::slice::from_ref(node)))
+            .collect::<Result<Vec<_>, _>>();
+        match (evaluator.eval(&nodes), alone) {
+            (Ok(numbers), Ok(alone)) => prop_assert_eq!(numbers, alone.concat(), "{:?}", input),
+            (numbers, alone) => prop_assert!(numbers.is_err() && alone.is_err(), "{:?}", input),
+        }
+    }
+
+    #[test]
+    fn arbitrary_strings_never_panic(input in any::<String>()) {
+        run(&input);
+    }
+
+    // mostly the characters of the syntax, to get past the lexer more often; 'o' and 'u'
+    // are left out so `o:` ranges and `sum{..}` can't ask for a range of a few quintillion
+    #[test]
+    fn arbitrary_syntax_never_panics(input in "[0-9{}().,=:+*/%^!<>@&|~ \\-_seMAXIN]{0,40}") {
+        run(&input);
+    }
+}
@@ -0,0 +1,129 @@
+use insta::assert_snapshot;
+use std::sync::Arc;
+
+use crate::{
+    errors::{ErrorContext, ParserError, Seq2Error},
+    lexer::Lexer,
+    parser::{Parser, ParserOptions},
+    tokens::Span,
+};
+
+/// The first error of lexing and parsing `input`, rendered without colors so it's the same
+/// whatever the terminal.
+fn render(input: &str, options: ParserOptions) -> (&'static str, String) {
+    let error = match Lexer::new(input).lex() {
+        Ok(tokens) => Seq2Error::from(
+            Parser::with_options(input.into(), &tokens, options)
+                .parse()
+                .expect_err(input),
+        ),
+        Err(e) => e.into(),
+    };
+    (error.code(), error.render_plain())
+}
+
+#[test]
+fn test_rendered_lexical_errors() {
+    let cases = [
+        ("E0001", "1, $"),
+        ("E0002", "{1..5, s2}"),
+        ("E0003", "{1...5}"),
+        ("E0004", "{1.=.5}"),
+        ("E0005", "1, 1e"),
+        ("E0006", "1, @"),
+        ("E0007", "99999999999999999999"),
+        ("E0008", "foo"),
+        ("E0009", "(foo(1))"),
+        ("E0010", "{1..5, foo:2}"),
+        ("E0011", "{1..5, o:sideways}"),
+        ("E0013", "{-1e19..0}"),
+        // the caret and the column count chars, not bytes
+        ("E0001_multibyte", "1, 2, ü"),
+    ];
+    for (name, input) in cases {
+        let (code, rendered) = render(input, ParserOptions::default());
+        assert!(name.starts_with(code), "input: {input:?}");
+        assert_snapshot!(name, rendered, input);
+    }
+
+    let error = Lexer::new("1, 2, 3")
+        .with_max_input_len(4)
+        .lex()
+        .unwrap_err();
+    assert_snapshot!("E0012", error.render_plain(), "1, 2, 3");
+}
+
+#[test]
+fn test_rendered_parser_errors() {
+    let few_tokens = ParserOptions {
+        max_tokens: 4,
+        ..Default::default()
+    };
+    let few_items = ParserOptions {
+        max_items: 2,
+        ..Default::default()
+    };
+    let nested = format!("{}1{}", "(".repeat(70), ")".repeat(70));
+    let cases = [
+        #[cfg(feature = "aggregate")]
+        ("E0101", "(sum{MAX-1..=MAX})", ParserOptions::default()),
+        ("E0102", "1, 2, -3, ()", ParserOptions::default()),
+        ("E0103", "1, 10, -", ParserOptions::default()),
+        ("E0104", "(1 + {1..2})", ParserOptions::default()),
+        #[cfg(feature = "aggregate")]
+        ("E0105", "(sum{1..=3, m:@/0})", ParserOptions::default()),
+        ("E0106", "(max(1))", ParserOptions::default()),
+        ("E0107", "{1..5, u:2}", ParserOptions::default()),
+        ("E0108", "1, -+%, 10, 3", ParserOptions::default()),
+        ("E0109", "(6 ~ 9)", ParserOptions::default()),
+        ("E0110", "(1, 2)", ParserOptions::default()),
+        ("E0111", "{1..5, s:2 m:+1}", ParserOptions::default()),
+        ("E0112", "1 2", ParserOptions::default()),
+        ("E0113", "{1..}", ParserOptions::default()),
+        ("E0114", &nested, ParserOptions::default()),
+        ("E0115", "(1 + 2", ParserOptions::default()),
+        ("E0116", "1,,2", ParserOptions::default()),
+        ("E0117", "1 * 10,2,3", ParserOptions::default()),
+        ("E0118", "..5", ParserOptions::default()),
+        ("E0120", "max(1, 2)", ParserOptions::default()),
+        ("E0121", "{1..5, s:0}", ParserOptions::default()),
+        ("E0122", "{5..1, s:1}", ParserOptions::default()),
+        ("E0123", "{1..5, s:1, s:2}", ParserOptions::default()),
+        ("E0124", "{1..5, 3}", ParserOptions::default()),
+        ("E0125", "-MIN", ParserOptions::default()),
+        ("E0126", "{1..{2..3}}", ParserOptions::default()),
+        ("E0127", "{}", ParserOptions::default()),
+        ("E0128", "1 ~", ParserOptions::default()),
+        ("E0129", "1 x", ParserOptions::default()),
+        ("E0130", "1 x 0", ParserOptions::default()),
+        ("E0131", "x 2", ParserOptions::default()),
+        ("E0132", "{1..5, lo:3, hi:1}", ParserOptions::default()),
+        ("E0133", "(2(3))", ParserOptions::default()),
+        ("E0134", "(1 + * 2)", ParserOptions::default()),
+        ("E0135", "(1 2)", ParserOptions::default()),
+        ("E0136", "1, 2, 3", few_tokens),
+        ("E0137", "1, 2, 3", few_items),
+        ("E0138", "{1..5", ParserOptions::default()),
+        // the lines are counted past the comments
+        (
+            "E0112_multibyte",
+            "# café\n1, 2 3",
+            ParserOptions::default(),
+        ),
+        (
+            "E0121_multibyte",
+            "# ☕\n\t{1..5, s:0}",
+            ParserOptions::default(),
+        ),
+    ];
+    for (name, input, options) in cases {
+        let (code, rendered) = render(input, options);
+        assert!(name.starts_with(code), "input: {input:?}");
+        assert_snapshot!(name, rendered, input);
+    }
+
+    // the range arguments are taken by the lexer before the parser gets to see them
+    let error =
+        ParserError::MisplacedRngSyntax(ErrorContext::new(Arc::from("1, s:2"), Span::new(3, 5)));
+    assert_snapshot!("E0119", error.render_plain(), "1, s:2");
+}
@@ -0,0 +1,116 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    lexer::Lexer,
+    parser::Parser,
+    tokens::{Span, Token},
+};
+
+#[test]
+fn test_serialize_tokens() {
+    let input = "-1, {..=4, s:2, o:desc}";
+    let tokens = Lexer::new(input).lex().unwrap();
+    let json = serde_json::to_string(&tokens).unwrap();
+    assert_eq!(
+        json,
+        concat!(
+            r#"[{"kind":{"math":"sub"},"span":{"start":0,"end":1}},"#,
+            r#"{"kind":{"int":{"value":1}},"span":{"start":1,"end":2}},"#,
+            r#"{"kind":"comma","span":{"start":2,"end":3}},"#,
+            r#"{"kind":"l_squiggly","span":{"start":4,"end":5}},"#,
+            r#"{"kind":"rng_inclusive","span":{"start":5,"end":8}},"#,
+            r#"{"kind":{"int":{"value":4}},"span":{"start":8,"end":9}},"#,
+            r#"{"kind":"comma","span":{"start":9,"end":10}},"#,
+            r#"{"kind":"rng_step","span":{"start":11,"end":13}},"#,
+            r#"{"kind":{"int":{"value":2}},"span":{"start":13,"end":14}},"#,
+            r#"{"kind":"comma","span":{"start":14,"end":15}},"#,
+            r#"{"kind":{"rng_order":"desc"},"span":{"start":16,"end":22}},"#,
+            r#"{"kind":"r_squiggly","span":{"start":22,"end":23}}]"#,
+        )
+    );
+
+    let tokens_back: Vec<Token> = serde_json::from_str(&json).unwrap();
+    assert_eq!(tokens_back, tokens);
+    assert_eq!(
+        serde_json::from_str::<Span>(r#"{"start":3,"end":7}"#).unwrap(),
+        Span::new(3, 7)
+    );
+}
+
+#[test]
+fn test_serialize_nodes() {
+    let input = "7, (2 * 3), {1..5, s:2, m:+1, u:}";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let json = serde_json::to_string_pretty(&nodes).unwrap();
+    println!("{json}");
+    assert_eq!(
+        serde_json::to_value(&nodes).unwrap(),
+        serde_json::json!([
+            {"type": "int", "span": {"start": 0, "end": 1}, "value": 7},
+            {
+                "type": "math_expr",
+                "negated": false,
+                "span": {"start": 3, "end": 10},
+                "rpn": [
+                    {"kind": {"int": {"value": 2}}, "span": {"start": 4, "end": 5}},
+                    {"kind": {"int": {"value": 3}}, "span": {"start": 8, "end": 9}},
+                    {"kind": {"math": "mul"}, "span": {"start": 6, "end": 7}},
+                ],
+            },
+            {
+                "type": "range_expr",
+                "span": {"start": 12, "end": 33},
+                "start": {"type": "int", "span": {"start": 13, "end": 14}, "value": 1},
+                "end": {"type": "int", "span": {"start": 16, "end": 17}, "value": 5},
+                "inclusive": false,
+                "step": {"type": "int", "span": {"start": 21, "end": 22}, "value": 2},
+                "mutation": {
+                    "type": "math_expr",
+                    "negated": false,
+                    "span": {"start": 26, "end": 28},
+                    "rpn": [
+                        {"kind": "rng_mut_arg", "span": {"start": 26, "end": 27}},
+                        {"kind": {"int": {"value": 1}}, "span": {"start": 27, "end": 28}},
+                        {"kind": {"math": "add"}, "span": {"start": 26, "end": 27}},
+                    ],
+                },
+                "low": null,
+                "high": null,
+                "unique": true,
+                "reverse": false,
+                "order": null,
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_serialize_errors() {
+    let error = crate::parse("1, 2 3").unwrap_err();
+    assert_eq!(
+        serde_json::to_string(&error).unwrap(),
+        r#"{"code":"E0112","message":"@ position 5 - Missing ',' between items","span":{"start":4,"end":5}}"#
+    );
+
+    let error = Lexer::new("{1...5}").lex().unwrap_err();
+    assert_eq!(
+        serde_json::to_string(&error).unwrap(),
+        r#"{"code":"E0003","message":"@ position 3-5 - Invalid range syntax","span":{"start":2,"end":5}}"#
+    );
+}
+
+#[test]
+fn test_dump_json() {
+    let mut lexer = Lexer::new("{..3}");
+    let tokens = lexer.lex().unwrap();
+    let dump: serde_json::Value = serde_json::from_str(&lexer.dump_json(&tokens)).unwrap();
+    assert_eq!(
+        dump,
+        serde_json::json!([
+            {"kind": "l_squiggly", "span": {"start": 0, "end": 1}, "text": "{"},
+            {"kind": "rng_exclusive", "span": {"start": 1, "end": 3}, "text": ".."},
+            {"kind": {"int": {"value": 3}}, "span": {"start": 3, "end": 4}, "text": "3"},
+            {"kind": "r_squiggly", "span": {"start": 4, "end": 5}, "text": "}"},
+        ])
+    );
+}
@@ -0,0 +1,135 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    lexer::Lexer,
+    parser::Parser,
+    tokens::{Span, Token, TokenKind},
+};
+
+#[test]
+fn test_span_merge() {
+    assert_eq!(Span::new(2, 4).merge(Span::new(6, 9)), Span::new(2, 9));
+    // the order of the spans doesn't matter
+    assert_eq!(Span::new(6, 9).merge(Span::new(2, 4)), Span::new(2, 9));
+    // overlapping and contained spans
+    assert_eq!(Span::new(2, 7).merge(Span::new(5, 9)), Span::new(2, 9));
+    assert_eq!(Span::new(1, 10).merge(Span::new(3, 4)), Span::new(1, 10));
+    // zero-length spans still stretch the result
+    assert_eq!(Span::new(3, 3).merge(Span::new(5, 6)), Span::new(3, 6));
+    assert_eq!(Span::new(5, 6).merge(Span::new(8, 8)), Span::new(5, 8));
+    assert_eq!(Span::new(4, 4).merge(Span::new(4, 4)), Span::new(4, 4));
+}
+
+#[test]
+fn test_span_contains() {
+    let span = Span::new(3, 6);
+    assert!(!span.contains(2));
+    assert!(span.contains(3));
+    assert!(span.contains(5));
+    // `end` is exclusive
+    assert!(!span.contains(6));
+
+    assert!(!Span::new(3, 3).contains(3));
+}
+
+#[test]
+fn test_span_len() {
+    assert_eq!(Span::new(3, 7).len(), 4);
+    assert!(!Span::new(3, 7).is_empty());
+    assert_eq!(Span::new(5, 5).len(), 0);
+    assert!(Span::new(5, 5).is_empty());
+    // a reversed span has no length rather than underflowing
+    assert_eq!(Span::new(7, 3).len(), 0);
+    assert!(Span::new(7, 3).is_empty());
+}
+
+#[test]
+fn test_span_display() {
+    assert_eq!(Span::new(3, 7).to_string(), "3..7");
+    assert_eq!(Span::new(0, 0).to_string(), "0..0");
+}
+
+#[test]
+fn test_span_text() {
+    let input = "{1..=5, s:2}é";
+    assert_eq!(Span::new(0, 1).text(input), Some("{"));
+    assert_eq!(Span::new(11, 14).text(input), Some("}é"));
+    assert_eq!(Span::new(14, 14).text(input), Some(""));
+    // out of bounds, backwards and inside a char
+    assert_eq!(Span::new(11, 15).text(input), None);
+    assert_eq!(Span::new(20, 25).text(input), None);
+    assert_eq!(Span::new(5, 2).text(input), None);
+    assert_eq!(Span::new(12, 13).text(input), None);
+}
+
+#[test]
+fn test_span_clamp() {
+    let input = "{1..=5, s:2}é";
+    assert_eq!(Span::new(2, 5).clamp(input), Span::new(2, 5));
+    // out of bounds, backwards and inside a char
+    assert_eq!(Span::new(11, 20).clamp(input), Span::new(11, 14));
+    assert_eq!(Span::new(20, 25).clamp(input), Span::new(14, 14));
+    assert_eq!(Span::new(5, 2).clamp(input), Span::new(5, 5));
+    assert_eq!(Span::new(13, 14).clamp(input), Span::new(12, 14));
+}
+
+#[test]
+fn test_span_line_col() {
+    let input = "1,\n{2..é}";
+    assert_eq!(Span::new(3, 10).line_col(input), ((2, 1), (2, 6)));
+    assert_eq!(Span::new(3, 3).line_col(input), ((2, 1), (2, 1)));
+    // spans that don't fit the source don't panic
+    assert_eq!(Span::new(8, 50).line_col(input), ((2, 5), (2, 6)));
+    assert_eq!(Span::new(60, 70).line_col(input), ((2, 7), (2, 7)));
+    assert_eq!(Span::new(5, 1).line_col(input), ((2, 3), (2, 3)));
+    assert_eq!(Span::new(8, 9).line_col(input), ((2, 5), (2, 5)));
+}
+
+#[test]
+fn test_span_to_char_range() {
+    let input = "é, {1..2}";
+    assert_eq!(Span::new(4, 10).to_char_range(input), 3..9);
+    // spans that don't fit the source don't panic
+    assert_eq!(Span::new(4, 50).to_char_range(input), 3..9);
+    assert_eq!(Span::new(1, 3).to_char_range(input), 0..2);
+    assert_eq!(Span::new(6, 4).to_char_range(input), 5..5);
+    assert_eq!(Span::new(40, 50).to_char_range(input), 9..9);
+}
+
+#[test]
+fn test_token_text() {
+    let input = "{1..=5, s:2}";
+    let texts = Lexer::new(input)
+        .lex()
+        .unwrap()
+        .iter()
+        .map(|token| token.text(input))
+        .collect::<Option<Vec<_>>>()
+        .unwrap();
+    assert_eq!(texts, ["{", "1", "..=", "5", ",", "s:", "2", "}"]);
+
+    let token = Token::new(TokenKind::Comma, Span::new(12, 13));
+    assert_eq!(token.text(input), None);
+}
+
+#[test]
+fn test_node_text() {
+    let input = "{1..=5, s:2, m:(@ * 2)}, (3 ^ 2), 7";
+    let nodes = Parser::from_source(input).unwrap().parse().unwrap();
+    let texts = nodes
+        .iter()
+        .map(|node| node.text(input))
+        .collect::<Option<Vec<_>>>()
+        .unwrap();
+    assert_eq!(texts, ["{1..=5, s:2, m:(@ * 2)}", "(3 ^ 2)", "7"]);
+
+    let args = nodes[0]
+        .children()
+        .map(|node| node.text(input))
+        .collect::<Option<Vec<_>>>()
+        .unwrap();
+    assert_eq!(args, ["1", "5", "2", "(@ * 2)"]);
+
+    // the source has to be the one the nodes were parsed from
+    assert_eq!(nodes[2].text("1, 2"), None);
+}
@@ -0,0 +1,150 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    tokens::Span,
+    validate::{validate, Severity},
+};
+
+/// Codes and spans of the diagnostics of `input`.
+fn codes(input: &str) -> Vec<(&'static str, Span)> {
+    validate(input)
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.span))
+        .collect()
+}
+
+#[test]
+fn test_step_too_large() {
+    assert_eq!(codes("{1..5, s:10}"), [("W0001", Span::new(9, 11))]);
+    assert_eq!(codes("{1..5, s:4}"), [("W0001", Span::new(9, 10))]);
+    assert_eq!(codes("{1..=5, s:5}"), [("W0001", Span::new(10, 11))]);
+    assert_eq!(codes("{5..1, s:(0 - 8)}"), [("W0001", Span::new(9, 16))]);
+
+    // more than one number
+    assert_eq!(codes("{1..=5, s:4}"), []);
+    assert_eq!(codes("{1..5, s:3}"), []);
+    assert_eq!(codes("{5..1, s:-2}"), []);
+    // a single number either way
+    assert_eq!(codes("{5..=5, s:3}"), []);
+}
+
+#[test]
+fn test_empty_exclusive_range() {
+    assert_eq!(codes("{5..5}"), [("W0002", Span::new(0, 6))]);
+    assert_eq!(
+        codes("1, {(2 * 2)..4, m:*2}"),
+        [("W0002", Span::new(3, 21))]
+    );
+
+    assert_eq!(codes("{5..=5}"), []);
+    assert_eq!(codes("{5..6}"), []);
+}
+
+#[test]
+fn test_noop_mutation() {
+    for mutation in [
+        "+0", "-0", "*1", "/1", "^1", "|0", "<<0", ">>0", "@", "@ + 0",
+    ] {
+        let input = format!("{{1..5, m:{mutation}}}");
+        let span = Span::new(9, 9 + mutation.len());
+        assert_eq!(codes(&input), [("W0003", span)], "{input}");
+    }
+
+    for mutation in ["+1", "*0", "-(0 - 1)", "@ * 2", "@!", "(0 + @)"] {
+        let input = format!("{{1..5, m:{mutation}}}");
+        assert_eq!(codes(&input), [], "{input}");
+    }
+}
+
+#[test]
+fn test_redundant_unary_plus() {
+    assert_eq!(codes("+5"), [("W0004", Span::new(0, 1))]);
+    assert_eq!(codes("-+5"), [("W0004", Span::new(1, 2))]);
+    assert_eq!(codes("(2 * +3)"), [("W0004", Span::new(5, 6))]);
+    assert_eq!(codes("{+1..3}"), [("W0004", Span::new(1, 2))]);
+    assert_eq!(codes("{1..=9, m:* +2}"), [("W0004", Span::new(12, 13))]);
+    assert_eq!(codes("(2 * +(3))"), [("W0004", Span::new(5, 6))]);
+    assert_eq!(codes("{1..=9, m:-+@}"), [("W0004", Span::new(11, 12))]);
+
+    // binary and implicit pluses are fine
+    assert_eq!(codes("(2 + 3), -5, {1..3, m:+2}, (-2)"), []);
+}
+
+#[test]
+fn test_warnings_in_order() {
+    let diagnostics = validate("{1..=3, m:*1}, +5, {2..2}");
+    let warnings = diagnostics
+        .iter()
+        .map(|d| (d.severity, d.code, d.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            (
+                Severity::Warning,
+                "W0003",
+                "@ position 11-12 - Mutation leaves the numbers unchanged"
+            ),
+            (
+                Severity::Warning,
+                "W0004",
+                "@ position 16 - Redundant unary plus"
+            ),
+            (
+                Severity::Warning,
+                "W0002",
+                "@ position 20-25 - Exclusive range with equal bounds produces no numbers"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_errors() {
+    // every syntax error, and no warnings next to them
+    let diagnostics = validate("{5..5}, {1..}, (2 +)");
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    assert_eq!(
+        codes("{5..5}, {1..}, (2 +)"),
+        [("E0113", Span::new(10, 12)), ("E0134", Span::new(19, 20))]
+    );
+
+    let diagnostics = validate("1, $");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "E0001");
+    assert_eq!(diagnostics[0].message, "@ position 4 - Invalid token");
+}
+
+#[test]
+fn test_valid_inputs() {
+    let inputs = [
+        "",
+        "# nothing",
+        "1, 2, 3",
+        "{1..=10, s:2, m:*3, u:, o:desc}",
+        "{(1 - (10 ^ 2))..-108, s:-3, m:*-1}",
+        "-1, -2, -3, {1..=3, s:2, m:+2}, (200 ^ 2 + 1)",
+        "{..5}, {MIN..=MAX, s:MAX}",
+    ];
+    for input in inputs {
+        assert_eq!(codes(input), [], "{input}");
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_render_warning() {
+    let input = "{1..5, s:10}";
+    let diagnostics = validate(input);
+    assert_eq!(
+        diagnostics[0].render_plain(input),
+        indoc::indoc! {"
+            ╭╴WARNING: @ position 10-11 - Step is larger than the range, only one number will be produced
+            │ --> line 1, column 10
+            │ 
+            │ {1..5, s:10}
+            │          ^^
+            ╰╴= HINT: use a smaller step, or a single number instead of the range
+        "}
+    );
+}
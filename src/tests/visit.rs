@@ -0,0 +1,140 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    lexer::Lexer,
+    parser::{Node, Parser},
+    visit::{walk_children, walk_node, Visit},
+};
+
+fn parse(input: &str) -> Vec<Node> {
+    let tokens = Lexer::new(input).lex().unwrap();
+    Parser::new(input.into(), &tokens).parse().unwrap()
+}
+
+/// Records the kind and text of every node visited.
+struct Trace<'a> {
+    input: &'a str,
+    visited: Vec<String>,
+    skip_ranges: bool,
+}
+
+impl Trace<'_> {
+    fn push(&mut self, kind: &str, node: &Node) {
+        let text = node.text(self.input).unwrap();
+        self.visited.push(format!("{kind} {text}"));
+    }
+}
+
+impl Visit for Trace<'_> {
+    fn visit_int(&mut self, node: &Node) {
+        self.push("int", node);
+    }
+
+    fn visit_math(&mut self, node: &Node) {
+        self.push("math", node);
+    }
+
+    fn visit_range(&mut self, node: &Node) {
+        self.push("range", node);
+        if !self.skip_ranges {
+            walk_children(node, self);
+        }
+    }
+}
+
+fn trace(input: &str, skip_ranges: bool) -> Vec<String> {
+    let mut trace = Trace {
+        input,
+        visited: vec![],
+        skip_ranges,
+    };
+    for node in &parse(input) {
+        walk_node(node, &mut trace);
+    }
+    trace.visited
+}
+
+#[test]
+fn test_walk_order() {
+    let input = "1, {2..=(3 * 4), s:2, m:@ + 1}, (5), {..6}";
+    assert_eq!(
+        trace(input, false),
+        [
+            "int 1",
+            "range {2..=(3 * 4), s:2, m:@ + 1}",
+            "int 2",
+            "math (3 * 4)",
+            "int 2",
+            "math @ + 1",
+            "math (5)",
+            "range {..6}",
+            "int ..",
+            "int 6",
+        ]
+    );
+
+    // the children of ranges are left out without `walk_children`
+    assert_eq!(
+        trace(input, true),
+        [
+            "int 1",
+            "range {2..=(3 * 4), s:2, m:@ + 1}",
+            "math (5)",
+            "range {..6}",
+        ]
+    );
+}
+
+#[test]
+fn test_default_visit_descends() {
+    struct Count(usize);
+    impl Visit for Count {
+        fn visit_int(&mut self, _node: &Node) {
+            self.0 += 1;
+        }
+    }
+
+    let mut count = Count(0);
+    for node in &parse("1, {2..5, s:1}, (3), {4..=7, m:*2}") {
+        walk_node(node, &mut count);
+    }
+    assert_eq!(count.0, 6);
+
+    // both sides of a zip are walked
+    let mut count = Count(0);
+    for node in &parse("{1..2} ~ 3 ~ (4)") {
+        walk_node(node, &mut count);
+    }
+    assert_eq!(count.0, 3);
+
+    // and the item of a repeat
+    let mut count = Count(0);
+    for node in &parse("{1..2} x 3, 4 x 5") {
+        walk_node(node, &mut count);
+    }
+    assert_eq!(count.0, 3);
+}
+
+#[test]
+fn test_children() {
+    let nodes = parse("1, (2 + 3), {4..8, s:2, m:*3}, {..9}");
+    let children = |node: &Node| node.children().map(Node::span).collect::<Vec<_>>();
+
+    assert_eq!(children(&nodes[0]), []);
+    assert_eq!(children(&nodes[1]), []);
+    let Node::RangeExpr {
+        start,
+        end,
+        step: Some(step),
+        mutation: Some(mutation),
+        ..
+    } = &nodes[2]
+    else {
+        panic!("Expected a range with a step and a mutation");
+    };
+    assert_eq!(
+        children(&nodes[2]),
+        [start.span(), end.span(), step.span(), mutation.span()]
+    );
+    assert_eq!(nodes[3].children().count(), 2);
+}
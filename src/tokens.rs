@@ -1,6 +1,9 @@
-// use std::fmt;
+use core::{fmt, ops::Range};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Op {
     Add,
     Sub,
@@ -8,6 +11,12 @@ pub enum Op {
     Div,
     Pow,
     Mod,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    BitNot,
+    Factorial,
     UnarySub,
     UnaryAdd,
 }
@@ -18,32 +27,176 @@ impl Op {
 
     pub fn precedence(&self) -> u8 {
         match self {
-            Op::Add | Op::Sub => 1,
-            Op::Mul | Op::Div | Op::Mod => 2,
-            Op::Pow => 3,
-            Op::UnaryAdd | Op::UnarySub => 4,
+            Op::BitOr => 1,
+            Op::BitAnd => 2,
+            Op::Shl | Op::Shr => 3,
+            Op::Add | Op::Sub => 4,
+            Op::Mul | Op::Div | Op::Mod => 5,
+            // `-2^2` is `-(2^2)`, like in math notation
+            Op::UnaryAdd | Op::UnarySub | Op::BitNot => 6,
+            Op::Pow => 7,
+            Op::Factorial => 8,
         }
     }
 
     pub fn associativity(&self) -> u8 {
         match self {
-            Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod | Op::Pow => Self::LEFT_ASSOC,
-            Op::UnaryAdd | Op::UnarySub => Self::RIGHT_ASSOC,
+            Op::Add
+            | Op::Sub
+            | Op::Mul
+            | Op::Div
+            | Op::Mod
+            | Op::BitAnd
+            | Op::BitOr
+            | Op::Shl
+            | Op::Shr => Self::LEFT_ASSOC,
+            // `2^3^2` is `2^(3^2)`
+            Op::Pow | Op::UnaryAdd | Op::UnarySub | Op::BitNot => Self::RIGHT_ASSOC,
+            Op::Factorial => Self::LEFT_ASSOC,
+        }
+    }
+
+    /// Whether the operator takes a single operand.
+    pub fn is_unary(&self) -> bool {
+        self.is_prefix() || self.is_postfix()
+    }
+
+    /// Whether the operator is written before its operand, eg. `~5`
+    pub fn is_prefix(&self) -> bool {
+        matches!(self, Op::UnaryAdd | Op::UnarySub | Op::BitNot)
+    }
+
+    /// Whether the operator is written after its operand, eg. `5!`
+    pub fn is_postfix(&self) -> bool {
+        matches!(self, Op::Factorial)
+    }
+
+    /// How the operator is written, eg. `<<`. The unary `-` and `+` are written like the
+    /// binary ones.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Op::Add | Op::UnaryAdd => "+",
+            Op::Sub | Op::UnarySub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Pow => "^",
+            Op::Mod => "%",
+            Op::BitAnd => "&",
+            Op::BitOr => "|",
+            Op::Shl => "<<",
+            Op::Shr => ">>",
+            Op::BitNot => "~",
+            Op::Factorial => "!",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Func {
+    Abs,
+    Min,
+    Max,
+    Gcd,
+}
+
+impl Func {
+    pub const NAMES: [&'static str; 4] = ["abs", "min", "max", "gcd"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "abs" => Some(Func::Abs),
+            "min" => Some(Func::Min),
+            "max" => Some(Func::Max),
+            "gcd" => Some(Func::Gcd),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Func::Abs => "abs",
+            Func::Min => "min",
+            Func::Max => "max",
+            Func::Gcd => "gcd",
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        match self {
+            Func::Abs => 1,
+            Func::Min | Func::Max | Func::Gcd => 2,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Aggregate {
+    Sum,
+    Count,
+    Prod,
+}
+
+impl Aggregate {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sum" => Some(Aggregate::Sum),
+            "count" => Some(Aggregate::Count),
+            "prod" => Some(Aggregate::Prod),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "asc" => Some(SortOrder::Asc),
+            "desc" => Some(SortOrder::Desc),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 #[rustfmt::skip]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum TokenKind {
     // Misc
     Comma,
+    Zip,       // ~ between two items
+    Repeat,    // x between an item and its count
 
     // Numbers
     Int { value: i64 },
 
     // Math operations
     Math(Op),
+    Func(Func),
+    Aggregate(Aggregate), // sum{..}, count{..}, prod{..}
 
     // Parentheses
     LParen,    // (
@@ -56,10 +209,28 @@ pub enum TokenKind {
     RngExclusive, // ..
     RngStep,      // s:
     RngMutation,  // m:
+    RngUnique,    // u:
+    RngReverse,   // r:
+    RngOrder(SortOrder), // o:asc, o:desc
+    RngLow,       // lo:
+    RngHigh,      // hi:
     RngMutArg,    // @
+
+    // Trivia, only emitted by `Lexer::with_trivia`
+    Whitespace,
+    Comment,      // # up to the end of the line
+}
+
+impl TokenKind {
+    /// Whether the token is whitespace or a comment, which don't change what the input means.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenKind::Whitespace | TokenKind::Comment)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
@@ -69,16 +240,115 @@ impl Token {
     pub fn new(kind: TokenKind, span: Span) -> Self {
         Self { kind, span }
     }
+
+    /// Text of `source` the token covers, see [`Span::text`].
+    pub fn text<'s>(&self, source: &'s str) -> Option<&'s str> {
+        self.span.text(source)
+    }
 }
 
+/// Location of a token in the source, as byte offsets (`start` inclusive, `end` exclusive).
+///
+/// Offsets are kept as `u32` to keep tokens small, the lexer refuses inputs longer than
+/// [`MAX_INPUT_LEN`](crate::lexer::MAX_INPUT_LEN) so they always fit.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
-    pub start: usize,
-    pub end: usize,
+    pub start: u32,
+    pub end: u32,
 }
 
+const _: () = assert!(core::mem::size_of::<Span>() <= 8);
+const _: () = assert!(core::mem::size_of::<Token>() <= 24);
+
 impl Span {
+    /// Offsets past `u32::MAX` are clamped to it.
     pub fn new(start: usize, end: usize) -> Self {
-        Self { start, end }
+        let offset = |offset: usize| u32::try_from(offset).unwrap_or(u32::MAX);
+        Self {
+            start: offset(start),
+            end: offset(end),
+        }
+    }
+
+    /// The span as a range of byte offsets, to slice the source with.
+    pub fn range(self) -> Range<usize> {
+        self.start as usize..self.end as usize
+    }
+
+    /// Text of `source` the span covers, `None` if the span is out of bounds of `source`
+    /// or doesn't fall on char boundaries.
+    pub fn text(self, source: &str) -> Option<&str> {
+        source.get(self.range())
+    }
+
+    /// Smallest span covering both spans, in whichever order they are given.
+    pub fn merge(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Whether the byte offset `pos` lies within the span.
+    pub fn contains(self, pos: usize) -> bool {
+        self.range().contains(&pos)
+    }
+
+    /// Length of the span in bytes.
+    pub fn len(self) -> usize {
+        self.end.saturating_sub(self.start) as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    /// 1-based line and column (in chars) of the first and the last char of the span.
+    /// The span is clamped to `source` first, see [`Span::clamp`].
+    pub fn line_col(&self, source: &str) -> ((usize, usize), (usize, usize)) {
+        let line_col = |offset: usize| {
+            let before = &source[..offset];
+            let line = before.matches('\n').count() + 1;
+            let column = before.chars().rev().take_while(|ch| *ch != '\n').count() + 1;
+            (line, column)
+        };
+        let Range { start, end } = self.clamp(source).range();
+        let last_char = source[start..end]
+            .char_indices()
+            .next_back()
+            .map_or(start, |(idx, _)| start + idx);
+
+        (line_col(start), line_col(last_char))
+    }
+
+    /// Converts the byte offsets into char offsets of `source` (`start` inclusive, `end` exclusive).
+    /// The span is clamped to `source` first, see [`Span::clamp`].
+    pub fn to_char_range(&self, source: &str) -> Range<usize> {
+        let span = self.clamp(source);
+        let start = source[..span.start as usize].chars().count();
+        let end = start + source[span.range()].chars().count();
+        start..end
+    }
+
+    /// The span cut down to one that can slice `source`: offsets past its end are moved back
+    /// to it, offsets inside a char back to the start of the char, and a reversed span is
+    /// made empty at its start.
+    pub fn clamp(self, source: &str) -> Span {
+        let floor = |offset: u32| {
+            let mut offset = (offset as usize).min(source.len());
+            while !source.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            offset
+        };
+        let start = floor(self.start);
+        Span::new(start, floor(self.end).max(start))
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
     }
 }
@@ -0,0 +1,312 @@
+//! Checks of an input without generating its numbers, for editors and forms that want to
+//! point out mistakes as they are typed.
+//!
+//! Besides the syntax errors, inputs that are valid but most likely not what was meant get
+//! a warning:
+//!
+//! | Code  | Warning |
+//! |-------|---------|
+//! | W0001 | The step is larger than the range, only one number is produced, eg. `{1..5, s:10}` |
+//! | W0002 | An exclusive range with equal bounds produces nothing, eg. `{5..5}` |
+//! | W0003 | The mutation leaves the numbers unchanged, eg. `m:+0` |
+//! | W0004 | A redundant unary plus, eg. `+5` |
+//!
+//! ```
+//! use seq2::{validate, Severity};
+//!
+//! let diagnostics = validate("{1..5, s:10}, +3");
+//! let codes = diagnostics.iter().map(|d| (d.severity, d.code)).collect::<Vec<_>>();
+//! assert_eq!(codes, [(Severity::Warning, "W0001"), (Severity::Warning, "W0004")]);
+//!
+//! let diagnostics = validate("1, 2 3");
+//! assert_eq!(diagnostics[0].severity, Severity::Error);
+//! assert_eq!(diagnostics[0].code, "E0112");
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+#[cfg(feature = "std")]
+use crate::errors::{use_color, Report, RED, YELLOW};
+use crate::{
+    errors::{LexicalError, ParserError},
+    eval::Evaluator,
+    lexer::Lexer,
+    parser::{Node, Parser, ParserOptions},
+    tokens::{Op, Span, TokenKind},
+    visit::{walk_children, walk_node, Visit},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Severity {
+    /// The input can't be evaluated
+    Error,
+    /// The input is valid, but most likely a mistake
+    Warning,
+}
+
+/// An error or a warning about the input, see the [module docs](self) for the warnings.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Code of the error (eg. `E0112`) or of the warning (eg. `W0001`).
+    pub code: &'static str,
+    pub span: Span,
+    /// Description without colors or the source excerpt, eg.
+    /// `@ position 7-10 - Step is larger than the range, only one number will be produced`.
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    fn warning(source: &str, code: &'static str, span: Span, msg: &str, hint: &str) -> Self {
+        let range = span.to_char_range(source);
+        let position = match range.len() {
+            0 | 1 => format!("{}", range.start + 1),
+            _ => format!("{}-{}", range.start + 1, range.end),
+        };
+        Diagnostic {
+            severity: Severity::Warning,
+            code,
+            span,
+            message: format!("@ position {position} - {msg}"),
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    /// Renders the diagnostic like the errors are rendered, with an excerpt of `source`.
+    /// Colors are left out when `NO_COLOR` is set or stderr is not a terminal.
+    #[cfg(feature = "std")]
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(source, use_color())
+    }
+
+    /// Renders the diagnostic without any colors, the span is underlined with `^` instead.
+    #[cfg(feature = "std")]
+    pub fn render_plain(&self, source: &str) -> String {
+        self.render_with(source, false)
+    }
+
+    #[cfg(feature = "std")]
+    fn render_with(&self, source: &str, color: bool) -> String {
+        let (label, label_color) = match self.severity {
+            Severity::Error => ("ERROR", RED),
+            Severity::Warning => ("WARNING", YELLOW),
+        };
+        let report = Report {
+            label,
+            label_color,
+            msg: self.message.clone(),
+            hint: self.hint.clone(),
+            suggestion: None,
+        };
+        report.render(source, self.span, color)
+    }
+}
+
+impl From<&LexicalError> for Diagnostic {
+    fn from(e: &LexicalError) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code: e.code(),
+            span: e.span(),
+            message: e.message(),
+            hint: e.hint(),
+        }
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(e: &ParserError) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code: e.code(),
+            span: e.span(),
+            message: e.message(),
+            hint: e.hint(),
+        }
+    }
+}
+
+/// Lexes and parses `input`, returning every syntax error, or the warnings about the parsed
+/// input when there are none. Nothing is evaluated, apart from the bounds and steps of the
+/// ranges.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    validate_with(input, ParserOptions::default())
+}
+
+/// [`validate`] with limits on the size of the input and on the steps its aggregates take.
+pub fn validate_with(input: &str, options: ParserOptions) -> Vec<Diagnostic> {
+    let mut lexer = Lexer::new(input);
+    let tokens = match lexer.lex() {
+        Ok(tokens) => tokens,
+        Err(e) => return vec![Diagnostic::from(&e)],
+    };
+    if tokens.is_empty() {
+        return vec![];
+    }
+    match Parser::with_options(lexer.source, &tokens, options).parse_all_errors() {
+        Ok(nodes) => warnings(input, &nodes),
+        Err(errors) => errors.iter().map(Diagnostic::from).collect(),
+    }
+}
+
+/// Warnings about nodes parsed from `source`, in the order they appear.
+pub fn warnings(source: &str, nodes: &[Node]) -> Vec<Diagnostic> {
+    let mut lints = Lints {
+        source,
+        evaluator: Evaluator::new(source.into()),
+        warnings: vec![],
+    };
+    for node in nodes {
+        walk_node(node, &mut lints);
+    }
+    lints.warnings.sort_by_key(|warning| warning.span.start);
+    lints.warnings
+}
+
+struct Lints<'a> {
+    source: &'a str,
+    evaluator: Evaluator,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Lints<'_> {
+    fn warn(&mut self, code: &'static str, span: Span, msg: &str, hint: &str) {
+        let warning = Diagnostic::warning(self.source, code, span, msg, hint);
+        self.warnings.push(warning);
+    }
+
+    /// Signs are folded into the number they come before, eg. `-+5` is a single token.
+    fn check_unary_plus(&mut self, span: Span) {
+        let Some(text) = span.text(self.source) else {
+            return;
+        };
+        let signs = text.trim_start_matches(['+', '-', ' ', '\t']);
+        let signs = &text[..text.len() - signs.len()];
+        if let Some(idx) = signs.find('+') {
+            let start = span.start as usize + idx;
+            let span = Span::new(start, start + 1);
+            self.warn("W0004", span, "Redundant unary plus", "remove the '+'");
+        }
+    }
+
+    fn check_bounds(&mut self, node: &Node) {
+        let Node::RangeExpr {
+            span,
+            start,
+            end,
+            inclusive,
+            step,
+            ..
+        } = node
+        else {
+            return;
+        };
+        let (Ok(start), Ok(end)) = (
+            self.evaluator.eval_scalar(start),
+            self.evaluator.eval_scalar(end),
+        ) else {
+            return;
+        };
+
+        if start == end && !inclusive {
+            self.warn(
+                "W0002",
+                *span,
+                "Exclusive range with equal bounds produces no numbers",
+                "use '..=' to produce the bound, or remove the range",
+            );
+            return;
+        }
+
+        let Some(step_node) = step else {
+            return;
+        };
+        let Ok(step) = self.evaluator.eval_scalar(step_node) else {
+            return;
+        };
+        let (len, step) = (start.abs_diff(end), step.unsigned_abs());
+        let produced_one = match inclusive {
+            true => step > len,
+            false => step >= len,
+        };
+        if len > 0 && produced_one {
+            self.warn(
+                "W0001",
+                step_node.span(),
+                "Step is larger than the range, only one number will be produced",
+                "use a smaller step, or a single number instead of the range",
+            );
+        }
+    }
+
+    fn check_mutation(&mut self, node: &Node) {
+        let Node::RangeExpr {
+            mutation: Some(mutation),
+            ..
+        } = node
+        else {
+            return;
+        };
+        let Node::MathExpr {
+            negated: false,
+            rpn,
+            ..
+        } = mutation.as_ref()
+        else {
+            return;
+        };
+
+        let kinds = rpn.iter().map(|token| token.kind).collect::<Vec<_>>();
+        let identity = match kinds.as_slice() {
+            [TokenKind::RngMutArg] => true,
+            [TokenKind::RngMutArg, TokenKind::Int { value }, TokenKind::Math(op)] => matches!(
+                (op, value),
+                (Op::Add | Op::Sub | Op::BitOr | Op::Shl | Op::Shr, 0)
+                    | (Op::Mul | Op::Div | Op::Pow, 1)
+            ),
+            _ => false,
+        };
+        if identity {
+            self.warn(
+                "W0003",
+                mutation.span(),
+                "Mutation leaves the numbers unchanged",
+                "remove the mutation",
+            );
+        }
+    }
+}
+
+impl Visit for Lints<'_> {
+    fn visit_int(&mut self, node: &Node) {
+        self.check_unary_plus(node.span());
+    }
+
+    fn visit_math(&mut self, node: &Node) {
+        if let Node::MathExpr { rpn, .. } = node {
+            for token in rpn {
+                match token.kind {
+                    TokenKind::Int { .. } | TokenKind::Math(Op::UnaryAdd) => {
+                        self.check_unary_plus(token.span)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn visit_range(&mut self, node: &Node) {
+        self.check_bounds(node);
+        self.check_mutation(node);
+        walk_children(node, self);
+    }
+}
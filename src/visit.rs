@@ -0,0 +1,72 @@
+//! Traversal of the syntax tree, so tools don't have to recurse into the children of
+//! ranges by hand.
+//!
+//! ```
+//! use seq2::{parser::{Node, Parser}, tokens::Span, visit::{walk_node, Visit}};
+//!
+//! /// Spans of every number in the input, including range bounds.
+//! struct Ints(Vec<Span>);
+//!
+//! impl Visit for Ints {
+//!     fn visit_int(&mut self, node: &Node) {
+//!         self.0.push(node.span());
+//!     }
+//! }
+//!
+//! let mut ints = Ints(vec![]);
+//! for node in &Parser::from_source("1, {2..=5, s:(1 + 1)}").unwrap().parse().unwrap() {
+//!     walk_node(node, &mut ints);
+//! }
+//! assert_eq!(ints.0, [Span::new(0, 1), Span::new(4, 5), Span::new(8, 9)]);
+//! ```
+
+use crate::parser::Node;
+
+/// Callbacks for each kind of [`Node`], called by [`walk_node`] in source order, a range,
+/// a zip or a repeat before its children. All of them do nothing by default, apart from
+/// [`Visit::visit_range`], [`Visit::visit_zip`] and [`Visit::visit_repeat`] descending
+/// into their children.
+pub trait Visit {
+    /// Called for every [`Node::Int`].
+    fn visit_int(&mut self, _node: &Node) {}
+
+    /// Called for every [`Node::MathExpr`].
+    fn visit_math(&mut self, _node: &Node) {}
+
+    /// Called for every [`Node::RangeExpr`]. Overriding it without calling [`walk_children`]
+    /// skips the bounds, step and mutation of the range.
+    fn visit_range(&mut self, node: &Node) {
+        walk_children(node, self);
+    }
+
+    /// Called for every [`Node::Zip`]. Overriding it without calling [`walk_children`]
+    /// skips the two sides of the zip.
+    fn visit_zip(&mut self, node: &Node) {
+        walk_children(node, self);
+    }
+
+    /// Called for every [`Node::Repeat`]. Overriding it without calling [`walk_children`]
+    /// skips the repeated item.
+    fn visit_repeat(&mut self, node: &Node) {
+        walk_children(node, self);
+    }
+}
+
+/// Visits `node`, and through [`Visit::visit_range`], [`Visit::visit_zip`] and
+/// [`Visit::visit_repeat`] everything inside it.
+pub fn walk_node<V: Visit + ?Sized>(node: &Node, visitor: &mut V) {
+    match node {
+        Node::Int { .. } => visitor.visit_int(node),
+        Node::MathExpr { .. } => visitor.visit_math(node),
+        Node::RangeExpr { .. } => visitor.visit_range(node),
+        Node::Zip { .. } => visitor.visit_zip(node),
+        Node::Repeat { .. } => visitor.visit_repeat(node),
+    }
+}
+
+/// Visits the [children](Node::children) of `node`, in order.
+pub fn walk_children<V: Visit + ?Sized>(node: &Node, visitor: &mut V) {
+    for child in node.children() {
+        walk_node(child, visitor);
+    }
+}
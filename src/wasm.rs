@@ -0,0 +1,77 @@
+//! JavaScript bindings, built with `wasm-pack build --features wasm`.
+//!
+//! Errors are plain objects `{ code, message, start, end }`, with the message free of
+//! any colors and `start`/`end` counted in UTF-16 code units, the way JavaScript indexes
+//! strings, eg. `input.slice(error.start, error.end)` is the offending source.
+//! The diagnostics of [`validate`] are error objects with a `severity` too, either
+//! `"error"` or `"warning"`.
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    errors::Seq2Error,
+    tokens::Span,
+    validate::{Diagnostic, Severity},
+};
+
+/// Lexes, parses and evaluates `input`, throwing the error object if any stage fails.
+#[wasm_bindgen]
+pub fn parse(input: &str) -> Result<Vec<i64>, JsValue> {
+    crate::parse(input).map_err(|e| error_object(input, &e))
+}
+
+/// Lexes and parses `input` without evaluating it, returning an array with an object for
+/// every error found, or for every warning when there are no errors, see
+/// [`crate::validate`]. Empty when the input is valid.
+#[wasm_bindgen]
+pub fn validate(input: &str) -> JsValue {
+    let diagnostics = Array::new();
+    for diagnostic in crate::validate(input) {
+        diagnostics.push(&diagnostic_object(input, &diagnostic));
+    }
+    diagnostics.into()
+}
+
+fn error_object(input: &str, e: &Seq2Error) -> JsValue {
+    // only I/O errors have no span, and nothing is written out here
+    let span = e.span().unwrap_or(Span::new(0, 0));
+    object(input, e.code(), &e.message(), span).into()
+}
+
+fn diagnostic_object(input: &str, diagnostic: &Diagnostic) -> JsValue {
+    let object = object(input, diagnostic.code, &diagnostic.message, diagnostic.span);
+    let severity = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let _ = Reflect::set(&object, &"severity".into(), &severity.into());
+    object.into()
+}
+
+fn object(input: &str, code: &str, message: &str, span: Span) -> Object {
+    let object = Object::new();
+    let fields = [
+        ("code", JsValue::from_str(code)),
+        ("message", JsValue::from_str(message)),
+        (
+            "start",
+            JsValue::from(utf16_offset(input, span.start as usize)),
+        ),
+        ("end", JsValue::from(utf16_offset(input, span.end as usize))),
+    ];
+    for (key, value) in fields {
+        // setting a property on a fresh plain object can't fail
+        let _ = Reflect::set(&object, &JsValue::from_str(key), &value);
+    }
+    object
+}
+
+/// Converts a byte offset into `input` to the number of UTF-16 code units before it.
+fn utf16_offset(input: &str, offset: usize) -> u32 {
+    input
+        .char_indices()
+        .take_while(|(idx, _)| *idx < offset)
+        .map(|(_, ch)| ch.len_utf16() as u32)
+        .sum()
+}
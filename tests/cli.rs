@@ -0,0 +1,681 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    io::{BufRead, BufReader},
+    process::Stdio,
+};
+
+use assert_cmd::Command;
+use predicates::{prelude::PredicateBooleanExt, str::contains};
+
+fn seq2() -> Command {
+    let mut cmd = Command::cargo_bin("seq2").unwrap();
+    cmd.env("NO_COLOR", "1");
+    cmd
+}
+
+#[test]
+fn test_evaluates_expression() {
+    seq2()
+        .arg("{1..=10, s:2}, (3*4)")
+        .assert()
+        .success()
+        .stdout("1 3 5 7 9 12\n")
+        .stderr("");
+}
+
+#[test]
+fn test_joins_arguments() {
+    seq2()
+        .args(["--", "-1", "{1..=3}", "(2 ^ 3)"])
+        .assert()
+        .success()
+        .stdout("-1 1 2 3 8\n");
+}
+
+#[test]
+fn test_lexical_error() {
+    seq2()
+        .arg("1, $")
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(contains("ERROR: @ position 4 - Invalid token"));
+}
+
+#[test]
+fn test_parser_error() {
+    seq2()
+        .arg("1, 2 3")
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(contains("Missing ',' between items"));
+}
+
+#[test]
+fn test_eval_error() {
+    seq2()
+        .arg("(MAX + 1)")
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(contains("Result is larger than MAX"));
+}
+
+#[test]
+fn test_max_steps() {
+    seq2()
+        .args(["--max-steps", "5", "{1..MAX}"])
+        .assert()
+        .code(2)
+        .stdout("1 2 3 4 5")
+        .stderr(contains("Gave up after 5 evaluation steps"));
+
+    seq2()
+        .args(["--max-steps", "5", "{1..=5}"])
+        .assert()
+        .success()
+        .stdout("1 2 3 4 5\n");
+}
+
+#[cfg(feature = "aggregate")]
+#[test]
+fn test_aggregate_max_steps() {
+    // the aggregates are folded by the parser, with the same budget
+    seq2()
+        .args(["--max-steps", "1000", "(sum{0..=MAX})"])
+        .assert()
+        .code(1)
+        .stderr(contains("Range cannot be aggregated"));
+
+    seq2()
+        .args(["--check", "--max-steps", "1000", "(sum{0..=MAX})"])
+        .assert()
+        .code(1)
+        .stderr(contains("Range cannot be aggregated"));
+
+    seq2()
+        .args(["--check", "(count{0..=10_000_000_000})"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_missing_expression() {
+    seq2().assert().failure().stderr(contains("Usage"));
+}
+
+#[test]
+fn test_delimiter() {
+    seq2()
+        .args(["-d", ";", "{1..=4}"])
+        .assert()
+        .success()
+        .stdout("1;2;3;4\n");
+    seq2()
+        .args(["{1..=4}", "--delimiter", ", "])
+        .assert()
+        .success()
+        .stdout("1, 2, 3, 4\n");
+}
+
+#[test]
+fn test_one_per_line() {
+    seq2()
+        .args(["-1", "{1..=3}, -4"])
+        .assert()
+        .success()
+        .stdout("1\n2\n3\n-4\n");
+}
+
+#[test]
+fn test_json() {
+    seq2()
+        .args(["--json", "{1..=3}, -4"])
+        .assert()
+        .success()
+        .stdout("[1,2,3,-4]\n");
+    seq2()
+        .args(["--json", "# nothing"])
+        .assert()
+        .success()
+        .stdout("[]\n");
+}
+
+#[test]
+fn test_csv() {
+    seq2()
+        .args(["--csv", "{1..=3}, -4"])
+        .assert()
+        .success()
+        .stdout("1,2,3,-4\n");
+}
+
+#[test]
+fn test_conflicting_formats() {
+    for flags in [["--json", "--csv"], ["-1", "--json"], ["-d;", "--csv"]] {
+        seq2()
+            .args(flags)
+            .arg("1")
+            .assert()
+            .code(2)
+            .stderr(contains("cannot be used with"));
+    }
+}
+
+#[test]
+fn test_streams_output() {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_seq2"))
+        .args(["-1", "{0..1e7}"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut first_line = String::new();
+    BufReader::new(child.stdout.as_mut().unwrap())
+        .read_line(&mut first_line)
+        .unwrap();
+    assert_eq!(first_line, "0\n");
+    // the rest of the range hasn't been generated yet
+    assert!(child.try_wait().unwrap().is_none());
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_stdin() {
+    seq2()
+        .arg("-")
+        .write_stdin("{1..=3},\n(2 * 4)  # eight\n")
+        .assert()
+        .success()
+        .stdout("1 2 3 8\n");
+
+    seq2()
+        .arg("-")
+        .write_stdin("1,\n2,\n  3 4\n")
+        .assert()
+        .code(1)
+        .stderr(contains("--> line 3, column 4"))
+        .stderr(contains("│   3 4\n"));
+}
+
+#[test]
+fn test_file() {
+    let path = std::env::temp_dir().join(format!("seq2-test-{}.txt", std::process::id()));
+    std::fs::write(&path, "{1..=3}, # one to three\r\n(MAX + 1)\r\n").unwrap();
+
+    let assert = seq2().arg("--file").arg(&path).assert();
+    std::fs::remove_file(&path).unwrap();
+    assert.code(2).stderr(contains("--> line 2, column 6"));
+
+    seq2()
+        .args(["-f", "does/not/exist.txt"])
+        .assert()
+        .code(1)
+        .stderr(contains("seq2: cannot read 'does/not/exist.txt'"))
+        .stderr(contains("panicked").not());
+
+    seq2()
+        .args(["-f", "spec.txt", "1, 2"])
+        .assert()
+        .code(2)
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn test_summary() {
+    seq2()
+        .args(["--sum", "{1..=100}"])
+        .assert()
+        .success()
+        .stdout("5050\n");
+
+    seq2()
+        .args(["--sum", "--count", "--min", "--max", "--product", "--"])
+        .arg("{1..=5}, -3")
+        .assert()
+        .success()
+        .stdout("sum: 12\ncount: 6\nmin: -3\nmax: 5\nproduct: -360\n");
+
+    seq2()
+        .args(["--count", "--min", ""])
+        .assert()
+        .success()
+        .stdout("count: 0\nmin: \n");
+
+    // the summary is computed without holding on to the numbers
+    seq2()
+        .args(["--count", "{0..1e6}"])
+        .assert()
+        .success()
+        .stdout("1000000\n");
+}
+
+#[test]
+fn test_summary_overflow() {
+    seq2()
+        .args(["--product", "{1..=30}"])
+        .assert()
+        .code(2)
+        .stderr(contains("ERROR"));
+
+    seq2()
+        .args(["--sum", "--json", "1"])
+        .assert()
+        .code(2)
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn test_check() {
+    seq2()
+        .args(["--check", "{1..=10, s:2}, (3 * 4)"])
+        .assert()
+        .success()
+        .stdout("");
+
+    // every syntax error is reported, nothing is evaluated
+    seq2()
+        .args(["--check", "{1..}, (MAX + 1), (2 +)"])
+        .assert()
+        .code(1)
+        .stderr(contains("--> line 1, column 3\n"))
+        .stderr(contains("--> line 1, column 23\n"))
+        .stderr(contains("Result does not fit").not());
+
+    seq2()
+        .args(["--check", "--sum", "1"])
+        .assert()
+        .code(2)
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn test_check_warnings() {
+    // warnings don't fail the check
+    seq2()
+        .args(["--check", "{1..5, s:10}, {1..=3, m:*1}"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(contains(
+            "WARNING: @ position 10-11 - Step is larger than the range",
+        ))
+        .stderr(contains(
+            "WARNING: @ position 25-26 - Mutation leaves the numbers unchanged",
+        ));
+
+    // and there are none once there are errors
+    seq2()
+        .args(["--check", "{5..5}, (2 +)"])
+        .assert()
+        .code(1)
+        .stderr(contains("ERROR"))
+        .stderr(contains("WARNING").not());
+}
+
+#[test]
+fn test_check_verbose() {
+    seq2()
+        .args([
+            "--check",
+            "--verbose",
+            "--",
+            "-+5, {1..=(2 ^ 3), s:2, m:(@ * 2), o:desc}, (3 * 4)",
+        ])
+        .assert()
+        .success()
+        .stdout(indoc::indoc! {"
+            Int(-5) [0..3]
+            RangeExpr [5..42]
+            ├─ start: Int(1) [6..7]
+            ├─ end: MathExpr(2 ^ 3 → 8) [10..17]
+            ├─ inclusive: true
+            ├─ step: Int(2) [21..22]
+            ├─ mutation: MathExpr(@ * 2) [26..33]
+            └─ order: desc
+            MathExpr(3 * 4 → 12) [44..51]
+        "});
+}
+
+#[test]
+fn test_equal_width() {
+    seq2()
+        .args(["-w", "{8..=10}"])
+        .assert()
+        .success()
+        .stdout("08 09 10\n");
+
+    seq2()
+        .args(["-w", "-1", "--", "{-10..=10, s:5}, 3"])
+        .assert()
+        .success()
+        .stdout("-10\n-05\n000\n005\n010\n003\n");
+}
+
+#[test]
+fn test_number_format() {
+    seq2()
+        .args(["--format", "img_%03d.png", "-1", "{6..=8}"])
+        .assert()
+        .success()
+        .stdout("img_006.png\nimg_007.png\nimg_008.png\n");
+
+    seq2()
+        .args(["--format", "%x", "--", "255, 10, -255"])
+        .assert()
+        .success()
+        .stdout("ff a -ff\n");
+
+    seq2()
+        .args(["--format", "[%4o] 100%%", "--csv", "--", "8, -2"])
+        .assert()
+        .success()
+        .stdout("[  10] 100%,[  -2] 100%\n");
+
+    seq2()
+        .args(["--format", "%06b", "-d", "|", "--", "5, -5"])
+        .assert()
+        .success()
+        .stdout("000101|-00101\n");
+}
+
+#[test]
+fn test_literal_format() {
+    seq2()
+        .args(["--format", "rust", "{1..8, s:2}"])
+        .assert()
+        .success()
+        .stdout("vec![1, 3, 5, 7]\n");
+
+    seq2()
+        .args([
+            "--format",
+            "python",
+            "--line-width",
+            "12",
+            "--",
+            "-1, {10..=13}",
+        ])
+        .assert()
+        .success()
+        .stdout("[\n    -1, 10,\n    11, 12,\n    13,\n]\n");
+
+    // the same as --json and --csv
+    seq2()
+        .args(["--format", "json", "{1..=3}"])
+        .assert()
+        .success()
+        .stdout("[1,2,3]\n");
+    seq2()
+        .args(["--format", "csv", "{1..=3}"])
+        .assert()
+        .success()
+        .stdout("1,2,3\n");
+
+    seq2()
+        .args(["--format", "rust", "-1", "{1..=3}"])
+        .assert()
+        .code(2)
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn test_invalid_number_format() {
+    for (format, message) in [
+        ("%q", "unknown conversion '%q'"),
+        ("img_%0", "incomplete '%' conversion"),
+        ("img.png", "needs a '%d', '%x', '%o' or '%b' conversion"),
+        ("%d-%d", "only a single '%' conversion"),
+    ] {
+        seq2()
+            .args(["--format", format, "1"])
+            .assert()
+            .code(2)
+            .stderr(contains(message));
+    }
+
+    seq2()
+        .args(["--format", "%d", "--json", "1"])
+        .assert()
+        .code(2)
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn test_compat() {
+    // expected output taken from GNU seq 9.4
+    for (args, expected) in [
+        ("5", "1 2 3 4 5"),
+        ("0", ""),
+        ("-2", ""),
+        ("3 6", "3 4 5 6"),
+        ("6 3", ""),
+        ("-2 2", "-2 -1 0 1 2"),
+        ("1 2 10", "1 3 5 7 9"),
+        ("1 3 10", "1 4 7 10"),
+        ("10 -2 1", "10 8 6 4 2"),
+        ("10 -3 1", "10 7 4 1"),
+        ("-1 -1 -3", "-1 -2 -3"),
+        ("1 -1 5", ""),
+        ("5 1 1", ""),
+        ("4 4", "4"),
+        ("4 -1 4", "4"),
+    ] {
+        let expected = match expected {
+            "" => String::new(),
+            expected => expected.replace(' ', "\n") + "\n",
+        };
+        seq2()
+            .arg("--compat")
+            .args(args.split(' '))
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+}
+
+#[test]
+fn test_compat_options() {
+    seq2()
+        .args(["--compat", "1", "3", "-d", ", "])
+        .assert()
+        .success()
+        .stdout("1, 2, 3\n");
+
+    seq2()
+        .args(["-w", "--compat", "8", "10"])
+        .assert()
+        .success()
+        .stdout("08\n09\n10\n");
+
+    seq2()
+        .args(["--compat", "1", "0", "3"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("seq2: invalid zero INCR value: '0'\n");
+
+    // the errors don't quote the expression `--compat` is turned into
+    seq2()
+        .args(["--max-steps", "3", "--compat", "10"])
+        .assert()
+        .code(1)
+        .stdout("1\n2\n3")
+        .stderr(
+            "seq2: gave up after 3 steps generating the numbers from FIRST to LAST, allow more with '--max-steps'\n",
+        );
+
+    seq2()
+        .args(["--compat", "1", "x"])
+        .assert()
+        .code(2)
+        .stderr(contains("invalid value 'x'"));
+}
+
+#[test]
+fn test_get() {
+    let path = std::env::temp_dir().join(format!("seq2-test-{}.conf", std::process::id()));
+    std::fs::write(&path, "ports = {8000..=8002}\nretries = 1, 2, 4, 8\n").unwrap();
+
+    let ports = seq2()
+        .arg("--file")
+        .arg(&path)
+        .args(["--get", "ports"])
+        .assert();
+    let missing = seq2()
+        .arg("--file")
+        .arg(&path)
+        .args(["--get", "sizes"])
+        .assert();
+    std::fs::write(&path, "ports = {8000..=8002}\nports = 1").unwrap();
+    let duplicate = seq2()
+        .arg("--file")
+        .arg(&path)
+        .args(["--get", "ports"])
+        .assert();
+    std::fs::remove_file(&path).unwrap();
+
+    ports.success().stdout("8000 8001 8002\n");
+    missing
+        .code(1)
+        .stderr(contains("seq2: no sequence named 'sizes'"));
+    duplicate
+        .code(1)
+        .stderr(contains("'ports' is already defined on line 1"))
+        .stderr(contains("--> line 2, column 1"));
+
+    seq2()
+        .args(["--get", "ports", "1, 2"])
+        .assert()
+        .code(2)
+        .stderr(contains("--file"));
+}
+
+#[test]
+fn test_dump_tokens() {
+    seq2()
+        .args(["--dump-tokens", "{1..3}, 7"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "0..1  LSquiggly         \"{\"\n",
+            "1..2  Int { value: 1 }  \"1\"\n",
+            "2..4  RngExclusive      \"..\"\n",
+            "4..5  Int { value: 3 }  \"3\"\n",
+            "5..6  RSquiggly         \"}\"\n",
+            "6..7  Comma             \",\"\n",
+            "8..9  Int { value: 7 }  \"7\"\n",
+        ));
+
+    let json = seq2().args(["--dump-tokens", "--json", "7"]).assert();
+    #[cfg(feature = "serde")]
+    json.success().stdout(
+        "[{\"kind\":{\"int\":{\"value\":7}},\"span\":{\"start\":0,\"end\":1},\"text\":\"7\"}]\n",
+    );
+    #[cfg(not(feature = "serde"))]
+    json.code(1).stderr(contains("the `serde` feature"));
+
+    seq2()
+        .args(["--dump-tokens", "1, $"])
+        .assert()
+        .code(1)
+        .stderr(contains("Invalid token"));
+}
+
+#[test]
+fn test_explain() {
+    let input = "{1..=5, s:2, m:*3}, 100, {5..=1, lo:2}";
+    let cases = [
+        (
+            "2",
+            concat!(
+                "number 2 = 9\n",
+                "  item 1 @ position 1-18: {1..=5, s:2, m:*3}\n",
+                "  number 2 of the range: 3\n",
+                "  mutation *3: 3 -> 9\n",
+            ),
+        ),
+        (
+            "4",
+            concat!("number 4 = 100\n", "  item 2 @ position 21-23: 100\n"),
+        ),
+        (
+            "9",
+            concat!(
+                "number 9 = 2\n",
+                "  item 3 @ position 26-38: {5..=1, lo:2}\n",
+                "  number 5 of the range: 1\n",
+                "  clamp: 1 -> 2\n",
+            ),
+        ),
+    ];
+    for (number, expected) in cases {
+        seq2()
+            .args(["--explain", number, input])
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+
+    seq2()
+        .args(["--explain", "10", input])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("seq2: there is no number 10, the sequence has 9\n");
+    seq2()
+        .args(["--explain", "0", input])
+        .assert()
+        .code(2)
+        .stderr(contains("0 is not in 1.."));
+    seq2()
+        .args(["--explain", "2", "{1..=5, m:/0}"])
+        .assert()
+        .code(2)
+        .stderr(contains("Attempt to divide by zero"));
+}
+
+#[test]
+fn test_completions() {
+    let assert = seq2().args(["completions", "bash"]).assert().success();
+    let script = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    for flag in [
+        "--file",
+        "--get",
+        "--delimiter",
+        "--one-per-line",
+        "--json",
+        "--csv",
+        "--equal-width",
+        "--format",
+        "--line-width",
+        "--sum",
+        "--count",
+        "--min",
+        "--max",
+        "--product",
+        "--check",
+        "--compat",
+        "--dump-tokens",
+        "--explain",
+    ] {
+        assert!(
+            script.contains(flag),
+            "{flag} is missing from the completions"
+        );
+    }
+
+    for shell in ["zsh", "fish", "powershell"] {
+        seq2().args(["completions", shell]).assert().success();
+    }
+
+    seq2()
+        .args(["completions", "tcsh"])
+        .assert()
+        .code(2)
+        .stderr(contains("invalid value 'tcsh'"));
+}
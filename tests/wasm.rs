@@ -0,0 +1,52 @@
+//! Run with `wasm-pack test --node -- --no-default-features --features wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use js_sys::{Array, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn field(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_parse() {
+    assert_eq!(
+        seq2::wasm::parse("-1, {1..=3, s:2, m:+2}").unwrap(),
+        [-1, 3, 5]
+    );
+
+    let error = seq2::wasm::parse("1, 2 3").unwrap_err();
+    assert_eq!(field(&error, "code"), "E0112");
+    assert_eq!(
+        field(&error, "message"),
+        "@ position 5 - Missing ',' between items"
+    );
+    assert_eq!(field(&error, "start"), 4);
+    assert_eq!(field(&error, "end"), 5);
+}
+
+#[wasm_bindgen_test]
+fn test_validate() {
+    let valid: Array = seq2::wasm::validate("{1..MAX}, (2 * 3)").into();
+    assert_eq!(valid.length(), 0);
+
+    // the offsets count UTF-16 code units, '€' is 3 bytes but a single unit
+    let invalid: Array = seq2::wasm::validate("€").into();
+    assert_eq!(invalid.length(), 1);
+    assert_eq!(field(&invalid.get(0), "code"), "E0001");
+    assert_eq!(field(&invalid.get(0), "start"), 0);
+    assert_eq!(field(&invalid.get(0), "end"), 1);
+
+    let invalid: Array = seq2::wasm::validate("1 2, (1 +), 3").into();
+    assert_eq!(invalid.length(), 2);
+    assert_eq!(field(&invalid.get(0), "code"), "E0112");
+    assert_eq!(field(&invalid.get(0), "severity"), "error");
+    assert_eq!(field(&invalid.get(1), "code"), "E0104");
+
+    let warnings: Array = seq2::wasm::validate("{1..5, s:10}").into();
+    assert_eq!(warnings.length(), 1);
+    assert_eq!(field(&warnings.get(0), "code"), "W0001");
+    assert_eq!(field(&warnings.get(0), "severity"), "warning");
+    assert_eq!(field(&warnings.get(0), "start"), 9);
+}